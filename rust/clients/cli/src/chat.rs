@@ -57,7 +57,11 @@ impl Chat {
                     // get message string
                     if let Some(message) = command_string.strip_prefix(group_id_str) {
                         // send message
-                        Self::send_chat_message(group_id, message.to_string().trim().to_string());
+                        Self::send_chat_message(
+                            group_id,
+                            message.to_string().trim().to_string(),
+                            Vec::new(),
+                        );
                         println!("chat message sent [{}] {}", group_id_str, message);
                         return;
                     } else {
@@ -68,6 +72,164 @@ impl Chat {
                     log::error!("chat send command incorrectly formatted");
                 }
             }
+            // reply to a chat message
+            cmd if cmd.starts_with("reply ") => {
+                let command_string = cmd.strip_prefix("reply ").unwrap().to_string();
+                let mut iter = command_string.split_whitespace();
+
+                match (iter.next(), iter.next()) {
+                    (Some(group_id_str), Some(message_id_str)) => {
+                        let group_id;
+                        match Self::group_id_from_string(group_id_str) {
+                            Some(id) => group_id = id,
+                            None => {
+                                log::error!("invalid group id format");
+                                return;
+                            }
+                        }
+                        let message_id;
+                        match bs58::decode(message_id_str).into_vec() {
+                            Ok(id) => message_id = id,
+                            Err(_) => {
+                                log::error!("invalid message id format");
+                                return;
+                            }
+                        }
+
+                        // get message string
+                        let message = command_string
+                            .strip_prefix(group_id_str)
+                            .unwrap()
+                            .trim()
+                            .strip_prefix(message_id_str)
+                            .unwrap()
+                            .trim()
+                            .to_string();
+                        Self::send_chat_message(group_id, message.clone(), message_id);
+                        println!("chat reply sent [{}] {}", group_id_str, message);
+                    }
+                    _ => log::error!("chat reply command incorrectly formatted"),
+                }
+            }
+            // create a poll
+            cmd if cmd.starts_with("poll ") || cmd.starts_with("multipoll ") => {
+                let multiple_choice = cmd.starts_with("multipoll ");
+                let command_string = cmd.splitn(2, ' ').nth(1).unwrap().to_string();
+                let mut iter = command_string.split_whitespace();
+
+                match (iter.next(), iter.next()) {
+                    (Some(group_id_str), Some(seconds_str)) => {
+                        let group_id;
+                        match Self::group_id_from_string(group_id_str) {
+                            Some(id) => group_id = id,
+                            None => {
+                                log::error!("invalid group id format");
+                                return;
+                            }
+                        }
+                        let seconds;
+                        match seconds_str.parse::<u64>() {
+                            Ok(number) => seconds = number,
+                            Err(_) => {
+                                log::error!("poll duration needs to be a number of seconds");
+                                return;
+                            }
+                        }
+
+                        // get question and options
+                        let poll_string = command_string
+                            .strip_prefix(group_id_str)
+                            .unwrap()
+                            .trim()
+                            .strip_prefix(seconds_str)
+                            .unwrap()
+                            .trim()
+                            .to_string();
+                        let mut parts = poll_string.split('|').map(|part| part.trim().to_string());
+                        let question = parts.next().unwrap_or_default();
+                        let options: Vec<String> = parts.filter(|part| part.len() > 0).collect();
+
+                        let mut deadline = 0;
+                        if seconds > 0 {
+                            deadline = Self::timestamp() + seconds * 1000;
+                        }
+
+                        Self::create_poll(group_id, question, options, multiple_choice, deadline);
+                    }
+                    _ => log::error!("chat poll command incorrectly formatted"),
+                }
+            }
+            // vote on a poll
+            cmd if cmd.starts_with("vote ") => {
+                let command_string = cmd.strip_prefix("vote ").unwrap().to_string();
+                let mut iter = command_string.split_whitespace();
+
+                match (iter.next(), iter.next()) {
+                    (Some(group_id_str), Some(poll_id_str)) => {
+                        match (
+                            Self::group_id_from_string(group_id_str),
+                            bs58::decode(poll_id_str).into_vec(),
+                        ) {
+                            (Some(group_id), Ok(poll_id)) => {
+                                // options are numbered from 1
+                                let mut options: Vec<u32> = Vec::new();
+                                for option_str in iter {
+                                    match option_str.parse::<u32>() {
+                                        Ok(number) if number > 0 => options.push(number - 1),
+                                        _ => {
+                                            log::error!("invalid poll option number");
+                                            return;
+                                        }
+                                    }
+                                }
+                                Self::vote_poll(group_id, poll_id, options);
+                            }
+                            _ => log::error!("invalid group id or poll id"),
+                        }
+                    }
+                    _ => log::error!("chat vote command incorrectly formatted"),
+                }
+            }
+            // request poll results
+            cmd if cmd.starts_with("results ") => {
+                let command_string = cmd.strip_prefix("results ").unwrap().to_string();
+                let mut iter = command_string.split_whitespace();
+
+                match (iter.next(), iter.next()) {
+                    (Some(group_id_str), Some(poll_id_str)) => {
+                        match (
+                            Self::group_id_from_string(group_id_str),
+                            bs58::decode(poll_id_str).into_vec(),
+                        ) {
+                            (Some(group_id), Ok(poll_id)) => {
+                                Self::request_poll_result(group_id, poll_id);
+                            }
+                            _ => log::error!("invalid group id or poll id"),
+                        }
+                    }
+                    _ => log::error!("chat results command incorrectly formatted"),
+                }
+            }
+            // request message thread
+            cmd if cmd.starts_with("thread ") => {
+                let command_string = cmd.strip_prefix("thread ").unwrap().to_string();
+                let mut iter = command_string.split_whitespace();
+
+                match (iter.next(), iter.next()) {
+                    (Some(group_id_str), Some(message_id_str)) => {
+                        match (
+                            Self::group_id_from_string(group_id_str),
+                            bs58::decode(message_id_str).into_vec(),
+                        ) {
+                            (Some(group_id), Ok(message_id)) => {
+                                Self::request_chat_thread(group_id, message_id);
+                            }
+                            _ => log::error!("invalid group id or message id"),
+                        }
+                    }
+                    _ => log::error!("chat thread command incorrectly formatted"),
+                }
+            }
             // request chat conversation
             cmd if cmd.starts_with("conversation") => {
                 match cmd.strip_prefix("conversation ") {
@@ -117,6 +279,70 @@ impl Chat {
                     }
                 }
             }
+            // request chat configuration
+            cmd if cmd.starts_with("config") => {
+                Self::request_chat_config();
+            }
+            // enable or disable read receipts
+            cmd if cmd.starts_with("readreceipts ") => {
+                match cmd.strip_prefix("readreceipts ").unwrap().trim() {
+                    "enable" => Self::set_chat_config(true),
+                    "disable" => Self::set_chat_config(false),
+                    _ => log::error!("chat readreceipts command incorrectly formatted"),
+                }
+            }
+            // set default message retention time
+            cmd if cmd.starts_with("retention ") => {
+                match cmd
+                    .strip_prefix("retention ")
+                    .unwrap()
+                    .trim()
+                    .parse::<u64>()
+                {
+                    Ok(retention) => Self::set_chat_retention(retention),
+                    Err(_) => log::error!("retention time needs to be a number of seconds"),
+                }
+            }
+            // export chat conversation
+            cmd if cmd.starts_with("export ") => {
+                let command_string = cmd.strip_prefix("export ").unwrap().to_string();
+                let mut iter = command_string.split_whitespace();
+
+                // convert group id from string to binary version
+                let group_id;
+                match iter.next() {
+                    Some(group_id_str) => match Self::id_string_to_bin(group_id_str.to_string()) {
+                        Ok(id) => group_id = id,
+                        Err(_e) => match Self::uuid_string_to_bin(group_id_str.to_string()) {
+                            Ok(id) => group_id = id,
+                            _ => {
+                                log::error!("invalid group id");
+                                return;
+                            }
+                        },
+                    },
+                    None => {
+                        log::error!("chat export command incorrectly formatted");
+                        return;
+                    }
+                }
+
+                // export format
+                let format;
+                match iter.next() {
+                    Some("json") => format = proto::ChatExportFormat::Json,
+                    Some("markdown") => format = proto::ChatExportFormat::Markdown,
+                    _ => {
+                        log::error!("chat export format needs to be 'json' or 'markdown'");
+                        return;
+                    }
+                }
+
+                // bundle files option
+                let include_files = iter.next() == Some("files");
+
+                Self::export_chat_conversation(group_id, format, include_files);
+            }
             // unknown command
             _ => log::error!("unknown chat command"),
         }
@@ -147,13 +373,22 @@ impl Chat {
         }
     }
 
+    /// Convert a Group ID in bs58 or UUID format to Binary
+    fn group_id_from_string(id_str: &str) -> Option<Vec<u8>> {
+        if let Ok(id) = Self::id_string_to_bin(id_str.to_string()) {
+            return Some(id);
+        }
+        Self::uuid_string_to_bin(id_str.to_string()).ok()
+    }
+
     /// Create and send feed message via rpc
-    fn send_chat_message(group_id: Vec<u8>, message_text: String) {
+    fn send_chat_message(group_id: Vec<u8>, message_text: String, reply_to: Vec<u8>) {
         // create feed send message
         let proto_message = proto::Chat {
             message: Some(proto::chat::Message::Send(proto::ChatMessageSend {
                 group_id,
                 content: message_text,
+                reply_to,
             })),
         };
 
@@ -192,12 +427,186 @@ impl Chat {
         Rpc::send_message(buf, super::rpc::proto::Modules::Chat.into(), "".to_string());
     }
 
+    /// Request the chat configuration via rpc
+    fn request_chat_config() {
+        let proto_message = proto::Chat {
+            message: Some(proto::chat::Message::ConfigRequest(
+                proto::ChatConfigRequest {},
+            )),
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(buf, super::rpc::proto::Modules::Chat.into(), "".to_string());
+    }
+
+    /// Set the default message retention time via rpc
+    fn set_chat_retention(retention: u64) {
+        let proto_message = proto::Chat {
+            message: Some(proto::chat::Message::RetentionSet(
+                proto::ChatRetentionSet { retention },
+            )),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Chat.into(),
+            "".to_string(),
+        );
+    }
+
+    /// Request a message thread via rpc
+    fn request_chat_thread(group_id: Vec<u8>, message_id: Vec<u8>) {
+        let proto_message = proto::Chat {
+            message: Some(proto::chat::Message::ThreadRequest(
+                proto::ChatThreadRequest {
+                    group_id,
+                    message_id,
+                },
+            )),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Chat.into(),
+            "".to_string(),
+        );
+    }
+
+    /// current time in milliseconds
+    fn timestamp() -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|time| time.as_millis() as u64)
+            .unwrap_or(0)
+    }
+
+    /// Create a poll via rpc
+    fn create_poll(
+        group_id: Vec<u8>,
+        question: String,
+        options: Vec<String>,
+        multiple_choice: bool,
+        deadline: u64,
+    ) {
+        let proto_message = proto::Chat {
+            message: Some(proto::chat::Message::PollCreate(proto::ChatPollCreate {
+                group_id,
+                question,
+                options,
+                multiple_choice,
+                deadline,
+            })),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Chat.into(),
+            "".to_string(),
+        );
+    }
+
+    /// Vote on a poll via rpc
+    fn vote_poll(group_id: Vec<u8>, poll_id: Vec<u8>, options: Vec<u32>) {
+        let proto_message = proto::Chat {
+            message: Some(proto::chat::Message::PollVote(proto::ChatPollVote {
+                group_id,
+                poll_id,
+                options,
+            })),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Chat.into(),
+            "".to_string(),
+        );
+    }
+
+    /// Request the results of a poll via rpc
+    fn request_poll_result(group_id: Vec<u8>, poll_id: Vec<u8>) {
+        let proto_message = proto::Chat {
+            message: Some(proto::chat::Message::PollResultRequest(
+                proto::ChatPollResultRequest { group_id, poll_id },
+            )),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Chat.into(),
+            "".to_string(),
+        );
+    }
+
+    /// Request the export of a chat conversation via rpc
+    fn export_chat_conversation(
+        group_id: Vec<u8>,
+        format: proto::ChatExportFormat,
+        include_files: bool,
+    ) {
+        let proto_message = proto::Chat {
+            message: Some(proto::chat::Message::ExportRequest(
+                proto::ChatExportRequest {
+                    group_id,
+                    format: format as i32,
+                    include_files,
+                },
+            )),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Chat.into(),
+            "".to_string(),
+        );
+    }
+
+    /// Set the chat configuration via rpc
+    fn set_chat_config(read_receipts: bool) {
+        let proto_message = proto::Chat {
+            message: Some(proto::chat::Message::ConfigSet(proto::ChatConfig {
+                read_receipts,
+                retention: 0,
+            })),
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(buf, super::rpc::proto::Modules::Chat.into(), "".to_string());
+    }
+
     fn analyze_content(content: &Vec<u8>) -> Result<Vec<String>, String> {
         let mut res: Vec<String> = vec![];
 
         if let Ok(content_message) = proto::ChatContentMessage::decode(&content[..]) {
             match content_message.message {
                 Some(proto::chat_content_message::Message::ChatContent(chat_content)) => {
+                    if let Some(reply) = chat_content.reply {
+                        res.push(
+                            "reply to ".to_string()
+                                + bs58::encode(reply.message_id).into_string().as_str()
+                                + " from "
+                                + bs58::encode(reply.sender_id).into_string().as_str()
+                                + ": > "
+                                + reply.preview.as_str(),
+                        );
+                    }
                     res.push(chat_content.text);
                     return Ok(res);
                 }
@@ -214,6 +623,34 @@ impl Chat {
                     );
                     return Ok(res);
                 }
+                Some(proto::chat_content_message::Message::PollContent(poll_content)) => {
+                    let mut poll_type = "poll";
+                    if poll_content.multiple_choice {
+                        poll_type = "multiple choice poll";
+                    }
+                    res.push(poll_type.to_string() + ": " + poll_content.question.as_str());
+                    for (index, option) in poll_content.options.iter().enumerate() {
+                        res.push(format!("  {}. {}", index + 1, option));
+                    }
+                    if poll_content.deadline > 0 {
+                        res.push(format!("deadline: {}", poll_content.deadline));
+                    }
+                    return Ok(res);
+                }
+                Some(proto::chat_content_message::Message::PollVoteContent(vote_content)) => {
+                    let options: Vec<String> = vote_content
+                        .options
+                        .iter()
+                        .map(|index| (index + 1).to_string())
+                        .collect();
+                    res.push(
+                        "vote on poll ".to_string()
+                            + bs58::encode(vote_content.poll_id).into_string().as_str()
+                            + ": "
+                            + options.join(", ").as_str(),
+                    );
+                    return Ok(res);
+                }
                 Some(proto::chat_content_message::Message::GroupEvent(group_event)) => {
                     match proto::GroupEventType::from_i32(group_event.event_type).unwrap() {
                         proto::GroupEventType::Joined => {
@@ -252,6 +689,41 @@ impl Chat {
         Err("content decoding error".to_string())
     }
 
+    /// print a list of chat messages
+    fn print_message_list(message_list: Vec<proto::ChatMessage>) {
+        println!("No. | Status | Sent At | Sender ID");
+        println!("  [Message ID] Received At");
+        println!("  Message Content");
+        println!("");
+
+        for message in message_list {
+            if let Ok(ss) = Self::analyze_content(&message.content) {
+                print! {"{} | ", message.index};
+                match proto::MessageStatus::from_i32(message.status).unwrap() {
+                    proto::MessageStatus::Sending => print!(".. | "),
+                    proto::MessageStatus::Sent => print!("✓. | "),
+                    proto::MessageStatus::Confirmed => print!("✓✓ | "),
+                    proto::MessageStatus::ConfirmedByAll => print!("✓✓✓| "),
+                    proto::MessageStatus::Receiving => print!("🚚 | "),
+                    proto::MessageStatus::Received => print!("📨 | "),
+                }
+
+                print!("{} | ", message.sent_at);
+                println!("{}", bs58::encode(message.sender_id).into_string());
+                println!(
+                    " [{}] {}",
+                    bs58::encode(message.message_id).into_string(),
+                    message.received_at
+                );
+
+                for s in ss {
+                    println!("\t{}", s);
+                }
+                println!("");
+            }
+        }
+    }
+
     /// Process received RPC message
     ///
     /// Decodes received protobuf encoded binary RPC message
@@ -268,40 +740,82 @@ impl Chat {
 
                         println!("Conversation [ {} ]", group_id.to_string());
                         println!("");
-                        println!("No. | Status | Sent At | Sender ID");
-                        println!("  [Message ID] Received At");
-                        println!("  Message Content");
-                        println!("");
 
-                        // print all messages in the feed list
-                        for message in proto_conversation.message_list {
-                            if let Ok(ss) = Self::analyze_content(&message.content) {
-                                print! {"{} | ", message.index};
-                                match proto::MessageStatus::from_i32(message.status).unwrap() {
-                                    proto::MessageStatus::Sending => print!(".. | "),
-                                    proto::MessageStatus::Sent => print!("✓. | "),
-                                    proto::MessageStatus::Confirmed => print!("✓✓ | "),
-                                    proto::MessageStatus::ConfirmedByAll => print!("✓✓✓| "),
-                                    proto::MessageStatus::Receiving => print!("🚚 | "),
-                                    proto::MessageStatus::Received => print!("📨 | "),
-                                }
+                        // print all messages in the conversation
+                        Self::print_message_list(proto_conversation.message_list);
+                    }
+                    Some(proto::chat::Message::ThreadList(thread_list)) => {
+                        println!("");
+                        println!(
+                            "Thread [ {} ]",
+                            bs58::encode(thread_list.message_id).into_string()
+                        );
+                        println!("");
 
-                                print!("{} | ", message.sent_at);
-                                println!("{}", bs58::encode(message.sender_id).into_string());
+                        // print all messages of the thread
+                        Self::print_message_list(thread_list.message_list);
+                    }
+                    Some(proto::chat::Message::Config(config)) => {
+                        println!("");
+                        println!("Chat Configuration");
+                        println!("  read receipts: {}", config.read_receipts);
+                        println!("  default retention: {} seconds", config.retention);
+                        println!("");
+                    }
+                    Some(proto::chat::Message::PollResult(result)) => {
+                        println!("");
+                        println!(
+                            "Poll Results [ {} ]",
+                            bs58::encode(result.poll_id).into_string()
+                        );
+                        if result.error_message.len() > 0 {
+                            println!("  {}", result.error_message);
+                        } else if let Some(poll) = result.poll {
+                            println!("  {}", poll.question);
+                            for option in result.options {
                                 println!(
-                                    " [{}] {}",
-                                    bs58::encode(message.message_id).into_string(),
-                                    message.received_at
+                                    "  {}. {} : {} votes",
+                                    option.index + 1,
+                                    option.option,
+                                    option.votes
                                 );
-
-                                for s in ss {
-                                    println!("\t{}", s);
+                                for voter_id in option.voter_ids {
+                                    println!("       {}", bs58::encode(voter_id).into_string());
                                 }
-                                println!("");
+                            }
+                            println!("  voters: {}", result.voters);
+                            if result.closed {
+                                println!("  the poll is closed");
                             }
                         }
+                        println!("");
+                    }
+                    Some(proto::chat::Message::PollResponse(response)) => {
+                        println!("");
+                        if response.success {
+                            println!(
+                                "Poll [ {} ]: ok",
+                                bs58::encode(response.poll_id).into_string()
+                            );
+                        } else {
+                            println!("Poll request failed");
+                            println!("  {}", response.error_message);
+                        }
+                        println!("");
+                    }
+                    Some(proto::chat::Message::ExportResponse(export)) => {
+                        println!("");
+                        if export.success {
+                            println!("Chat conversation exported");
+                            println!("  messages: {}", export.message_count);
+                            println!("  files: {}", export.file_count);
+                            println!("  path: {}", export.path);
+                        } else {
+                            println!("Chat conversation export failed");
+                            println!("  {}", export.error_message);
+                        }
+                        println!("");
                     }
-
                     _ => {
                         log::error!("unprocessable RPC chat message");
                     }
@@ -16,6 +16,7 @@ use super::group::Group;
 use super::node::Node;
 use super::router::Router;
 use super::rtc::Rtc;
+use super::search::Search;
 use super::user_accounts::UserAccounts;
 use super::users::Users;
 
@@ -78,6 +79,10 @@ impl Cli {
             cmd if cmd.starts_with("dtn ") => {
                 Dtn::cli(cmd.strip_prefix("dtn ").unwrap());
             }
+            // search functions
+            cmd if cmd.starts_with("search ") => {
+                Search::cli(cmd.strip_prefix("search ").unwrap());
+            }
             // unknown command
             _ => log::error!("unknown command"),
         }
@@ -6,6 +6,7 @@
 //! * get list of statically defined internet peer nodes
 //! * add an internet peer node
 //! * remove an internet peer node
+//! * display the bandwidth statistics
 
 use super::rpc::Rpc;
 use prost::Message;
@@ -80,6 +81,14 @@ impl Connections {
 
                 Self::internet_node_deactivate(String::from(address));
             }
+            // display and reset bandwidth statistics
+            cmd if cmd.starts_with("bandwidth reset") => {
+                Self::bandwidth_statistics(true);
+            }
+            // display bandwidth statistics
+            cmd if cmd.starts_with("bandwidth") => {
+                Self::bandwidth_statistics(false);
+            }
             // unknown command
             _ => log::error!("unknown connections command"),
         }
@@ -107,6 +116,7 @@ impl Connections {
                     address,
                     name,
                     enabled: true,
+                    status: None,
                 },
             )),
         };
@@ -124,6 +134,7 @@ impl Connections {
                     address,
                     name,
                     enabled: true,
+                    status: None,
                 },
             )),
         };
@@ -143,6 +154,7 @@ impl Connections {
                     address,
                     name: String::from(""),
                     enabled: false,
+                    status: None,
                 },
             )),
         };
@@ -162,6 +174,7 @@ impl Connections {
                     address,
                     name: String::from(""),
                     enabled: true,
+                    status: None,
                 },
             )),
         };
@@ -180,6 +193,7 @@ impl Connections {
                     address,
                     name: String::from(""),
                     enabled: false,
+                    status: None,
                 },
             )),
         };
@@ -187,6 +201,44 @@ impl Connections {
         Self::send_message(proto_message);
     }
 
+    /// send an rpc request for the bandwidth statistics
+    fn bandwidth_statistics(reset: bool) {
+        // create request message
+        let proto_message = proto::Connections {
+            message: Some(proto::connections::Message::BandwidthRequest(
+                proto::BandwidthRequest { reset },
+            )),
+        };
+
+        // send message
+        Self::send_message(proto_message);
+    }
+
+    /// get the name of a bandwidth module
+    fn bandwidth_module_name(module: i32) -> &'static str {
+        match proto::BandwidthModule::from_i32(module) {
+            Some(proto::BandwidthModule::Lan) => "LAN",
+            Some(proto::BandwidthModule::Internet) => "Internet",
+            Some(proto::BandwidthModule::Ble) => "BLE",
+            Some(proto::BandwidthModule::Stream) => "Stream",
+            None => "unknown",
+        }
+    }
+
+    /// print a bandwidth counter line
+    fn print_bandwidth_counter(name: String, counter: Option<proto::BandwidthCounter>) {
+        let counter = counter.unwrap_or_default();
+        println!(
+            "{} | {} / {} | {} / {} | {}",
+            name,
+            counter.bytes_sent,
+            counter.bytes_received,
+            counter.messages_sent,
+            counter.messages_received,
+            counter.messages_dropped
+        );
+    }
+
     /// Encode and send a protobuf connections message to RPC
     fn send_message(message: proto::Connections) {
         // encode message
@@ -252,18 +304,84 @@ impl Connections {
                         };
 
                         println!("Internet Peer Nodes List");
-                        println!("No. | Address | Name | Enabled");
+                        println!("No. | Address | Name | Enabled | State | RTT | Sent | Received");
 
                         for node in proto_list.nodes {
+                            let status = node.status.unwrap_or_default();
+                            let state = match proto::InternetNodeState::from_i32(status.state) {
+                                Some(proto::InternetNodeState::Disconnected) => "disconnected",
+                                Some(proto::InternetNodeState::Connecting) => "connecting",
+                                Some(proto::InternetNodeState::Connected) => "connected",
+                                Some(proto::InternetNodeState::Failed) => "failed",
+                                Some(proto::InternetNodeState::Disabled) => "disabled",
+                                None => "unknown",
+                            };
                             println!(
-                                "{} | {} | {} | {}",
-                                line, node.address, node.name, node.enabled
+                                "{} | {} | {} | {} | {} | {} ms | {} | {}",
+                                line,
+                                node.address,
+                                node.name,
+                                node.enabled,
+                                state,
+                                status.rtt / 1000,
+                                status.bytes_sent,
+                                status.bytes_received
                             );
+                            if status.last_error.len() > 0 {
+                                println!("    last error: {}", status.last_error);
+                            }
+                            if status.attempts > 0 {
+                                println!("    connection attempts: {}", status.attempts);
+                            }
                             line += 1;
                         }
 
                         println!("");
                     }
+                    Some(proto::connections::Message::BandwidthStatistics(statistics)) => {
+                        println!("");
+                        println!("Bandwidth Statistics since {}", statistics.since);
+                        println!("");
+
+                        println!("Modules");
+                        println!("Module | Limit | Bytes sent / received | Messages sent / received | Dropped");
+                        for entry in statistics.modules {
+                            let limit = match entry.limit {
+                                0 => "unlimited".to_string(),
+                                limit => format!("{} B/s", limit),
+                            };
+                            Self::print_bandwidth_counter(
+                                format!("{} | {}", Self::bandwidth_module_name(entry.module), limit),
+                                entry.counter,
+                            );
+                        }
+                        println!("");
+
+                        println!("Neighbours");
+                        println!("Node ID | Module | Bytes sent / received | Messages sent / received | Dropped");
+                        for entry in statistics.neighbours {
+                            Self::print_bandwidth_counter(
+                                format!(
+                                    "{} | {}",
+                                    bs58::encode(entry.node_id).into_string(),
+                                    Self::bandwidth_module_name(entry.module)
+                                ),
+                                entry.counter,
+                            );
+                        }
+                        println!("");
+
+                        println!("Services");
+                        println!("Service | Bytes sent / received | Messages sent / received | Dropped");
+                        for entry in statistics.services {
+                            let service = match proto::BandwidthService::from_i32(entry.service) {
+                                Some(service) => service.as_str_name().to_lowercase(),
+                                None => "unknown".to_string(),
+                            };
+                            Self::print_bandwidth_counter(service, entry.counter);
+                        }
+                        println!("");
+                    }
                     _ => {
                         log::error!("unprocessable connections RPC message");
                     }
@@ -67,6 +67,139 @@ impl Group {
                     log::error!("group create command incorrectly formatted");
                 }
             }
+            // set message retention time
+            cmd if cmd.starts_with("retention ") => {
+                let command_string = cmd.strip_prefix("retention ").unwrap().to_string();
+                let mut iter = command_string.split_whitespace();
+
+                if let Some(group_id_str) = iter.next() {
+                    match Self::uuid_string_to_bin(group_id_str.to_string()) {
+                        Ok(group_id) => match iter.next().map(|s| s.parse::<u64>()) {
+                            Some(Ok(retention)) => {
+                                Self::set_retention(group_id, retention);
+                            }
+                            _ => {
+                                log::error!("retention time needs to be a number of seconds");
+                            }
+                        },
+                        Err(e) => {
+                            log::error!("{}", e);
+                            return;
+                        }
+                    }
+                } else {
+                    log::error!("group retention command incorrectly formatted");
+                }
+            }
+            // set group description
+            cmd if cmd.starts_with("description ") => {
+                let command_string = cmd.strip_prefix("description ").unwrap().to_string();
+                let mut iter = command_string.split_whitespace();
+
+                if let Some(group_id_str) = iter.next() {
+                    match Self::uuid_string_to_bin(group_id_str.to_string()) {
+                        Ok(group_id) => {
+                            let description = command_string
+                                .strip_prefix(group_id_str)
+                                .unwrap()
+                                .trim()
+                                .to_string();
+
+                            Self::update_group(
+                                group_id,
+                                proto::group_update_request::Update::Description(description),
+                            );
+                        }
+                        Err(e) => {
+                            log::error!("{}", e);
+                            return;
+                        }
+                    }
+                } else {
+                    log::error!("group description command incorrectly formatted");
+                }
+            }
+            // set group avatar
+            cmd if cmd.starts_with("avatar ") => {
+                let command_string = cmd.strip_prefix("avatar ").unwrap().to_string();
+                let mut iter = command_string.split_whitespace();
+
+                if let Some(group_id_str) = iter.next() {
+                    match Self::uuid_string_to_bin(group_id_str.to_string()) {
+                        Ok(group_id) => {
+                            let path_name = command_string
+                                .strip_prefix(group_id_str)
+                                .unwrap()
+                                .trim()
+                                .to_string();
+
+                            // an empty path removes the avatar
+                            let mut avatar = Vec::new();
+                            if path_name.len() > 0 {
+                                match std::fs::read(&path_name) {
+                                    Ok(data) => avatar = data,
+                                    Err(e) => {
+                                        log::error!("avatar file read error {}", e);
+                                        return;
+                                    }
+                                }
+                            }
+
+                            Self::update_group(
+                                group_id,
+                                proto::group_update_request::Update::Avatar(avatar),
+                            );
+                        }
+                        Err(e) => {
+                            log::error!("{}", e);
+                            return;
+                        }
+                    }
+                } else {
+                    log::error!("group avatar command incorrectly formatted");
+                }
+            }
+            // set group permission settings
+            cmd if cmd.starts_with("settings ") => {
+                let command_string = cmd.strip_prefix("settings ").unwrap().to_string();
+                let iter: Vec<&str> = command_string.split_whitespace().collect();
+
+                if iter.len() != 4 {
+                    log::error!("group settings command incorrectly formatted");
+                    return;
+                }
+
+                let group_id;
+                match Self::uuid_string_to_bin(iter[0].to_string()) {
+                    Ok(id) => group_id = id,
+                    Err(e) => {
+                        log::error!("{}", e);
+                        return;
+                    }
+                }
+
+                // parse permissions
+                let mut members_allowed: Vec<bool> = Vec::new();
+                for permission in &iter[1..] {
+                    match *permission {
+                        "admins" => members_allowed.push(false),
+                        "members" => members_allowed.push(true),
+                        _ => {
+                            log::error!("permission needs to be `admins` or `members`");
+                            return;
+                        }
+                    }
+                }
+
+                Self::update_group(
+                    group_id,
+                    proto::group_update_request::Update::Settings(proto::GroupSettings {
+                        members_can_invite: members_allowed[0],
+                        admins_only_post: !members_allowed[1],
+                        members_can_rename: members_allowed[2],
+                    }),
+                );
+            }
             // group info
             cmd if cmd.starts_with("info ") => {
                 let command_string = cmd.strip_prefix("info ").unwrap().to_string();
@@ -195,6 +328,114 @@ impl Group {
                     log::error!("group remove command incorrectly formatted");
                 }
             }
+            // leave group
+            cmd if cmd.starts_with("leave ") => {
+                let command_string = cmd.strip_prefix("leave ").unwrap().to_string();
+                let mut iter = command_string.split_whitespace();
+
+                if let Some(group_id_str) = iter.next() {
+                    match Self::uuid_string_to_bin(group_id_str.to_string()) {
+                        Ok(group_id) => {
+                            Self::leave_group(group_id);
+                        }
+                        Err(e) => {
+                            log::error!("{}", e);
+                            return;
+                        }
+                    }
+                } else {
+                    log::error!("group leave command incorrectly formatted");
+                }
+            }
+            // disband group
+            cmd if cmd.starts_with("disband ") => {
+                let command_string = cmd.strip_prefix("disband ").unwrap().to_string();
+                let mut iter = command_string.split_whitespace();
+
+                if let Some(group_id_str) = iter.next() {
+                    match Self::uuid_string_to_bin(group_id_str.to_string()) {
+                        Ok(group_id) => {
+                            Self::disband_group(group_id);
+                        }
+                        Err(e) => {
+                            log::error!("{}", e);
+                            return;
+                        }
+                    }
+                } else {
+                    log::error!("group disband command incorrectly formatted");
+                }
+            }
+            // promote member to administrator
+            cmd if cmd.starts_with("promote ") => {
+                let command_string = cmd.strip_prefix("promote ").unwrap().to_string();
+                Self::set_role_command(command_string, proto::GroupMemberRole::Admin);
+            }
+            // demote administrator to user
+            cmd if cmd.starts_with("demote ") => {
+                let command_string = cmd.strip_prefix("demote ").unwrap().to_string();
+                Self::set_role_command(command_string, proto::GroupMemberRole::User);
+            }
+            // create invite code
+            cmd if cmd.starts_with("code ") => {
+                let command_string = cmd.strip_prefix("code ").unwrap().to_string();
+                let mut iter = command_string.split_whitespace();
+
+                if let Some(group_id_str) = iter.next() {
+                    match Self::uuid_string_to_bin(group_id_str.to_string()) {
+                        Ok(group_id) => {
+                            let mut expires_in = 0;
+                            let mut max_uses = 0;
+                            if let Some(expires_str) = iter.next() {
+                                match expires_str.parse::<u64>() {
+                                    Ok(seconds) => expires_in = seconds,
+                                    Err(_) => {
+                                        log::error!("invalid expiry time");
+                                        return;
+                                    }
+                                }
+                            }
+                            if let Some(uses_str) = iter.next() {
+                                match uses_str.parse::<u32>() {
+                                    Ok(uses) => max_uses = uses,
+                                    Err(_) => {
+                                        log::error!("invalid maximal number of uses");
+                                        return;
+                                    }
+                                }
+                            }
+                            Self::create_invite_code(group_id, expires_in, max_uses);
+                        }
+                        Err(e) => {
+                            log::error!("{}", e);
+                            return;
+                        }
+                    }
+                } else {
+                    log::error!("group code command incorrectly formatted");
+                }
+            }
+            // redeem invite code
+            cmd if cmd.starts_with("redeem ") => {
+                let command_string = cmd.strip_prefix("redeem ").unwrap().to_string();
+                let mut iter = command_string.split_whitespace();
+
+                if let Some(code) = iter.next() {
+                    let mut admin_id = Vec::new();
+                    if let Some(admin_id_str) = iter.next() {
+                        match Self::id_string_to_bin(admin_id_str.to_string()) {
+                            Ok(id) => admin_id = id,
+                            Err(e) => {
+                                log::error!("{}", e);
+                                return;
+                            }
+                        }
+                    }
+                    Self::redeem_invite_code(code.to_string(), admin_id);
+                } else {
+                    log::error!("group redeem command incorrectly formatted");
+                }
+            }
             // unknown command
             _ => log::error!("unknown group command"),
         }
@@ -276,6 +517,70 @@ impl Group {
         );
     }
 
+    /// set message retention time of a group
+    fn set_retention(group_id: Vec<u8>, retention: u64) {
+        let proto_message = proto::Group {
+            message: Some(proto::group::Message::GroupRetentionRequest(
+                proto::GroupRetentionRequest {
+                    group_id,
+                    retention,
+                },
+            )),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Group.into(),
+            "".to_string(),
+        );
+    }
+
+    /// update the description, avatar or settings of a group
+    fn update_group(group_id: Vec<u8>, update: proto::group_update_request::Update) {
+        let proto_message = proto::Group {
+            message: Some(proto::group::Message::GroupUpdateRequest(
+                proto::GroupUpdateRequest {
+                    group_id,
+                    update: Some(update),
+                },
+            )),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Group.into(),
+            "".to_string(),
+        );
+    }
+
+    /// print group description, avatar and permission settings
+    fn print_group_details(
+        description: &String,
+        avatar: &Vec<u8>,
+        settings: &Option<proto::GroupSettings>,
+    ) {
+        if description.len() > 0 {
+            println!("	description: {}", description);
+        }
+        if avatar.len() > 0 {
+            println!("	avatar: {} bytes", avatar.len());
+        }
+        if let Some(settings) = settings {
+            let permission = |members_allowed: bool| match members_allowed {
+                true => "members",
+                false => "admins",
+            };
+            println!(
+                "	permissions: invite: {}, post: {}, rename: {}",
+                permission(settings.members_can_invite),
+                permission(!settings.admins_only_post),
+                permission(settings.members_can_rename)
+            );
+        }
+    }
+
     /// group info
     fn group_info(group_id: Vec<u8>) {
         // group info send message
@@ -419,6 +724,123 @@ impl Group {
         );
     }
 
+    /// interpret the promote & demote commands
+    fn set_role_command(command_string: String, role: proto::GroupMemberRole) {
+        let mut iter = command_string.split_whitespace();
+
+        if let Some(group_id_str) = iter.next() {
+            match Self::uuid_string_to_bin(group_id_str.to_string()) {
+                Ok(group_id) => {
+                    if let Some(user_id_str) = iter.next() {
+                        match Self::id_string_to_bin(user_id_str.to_string()) {
+                            Ok(user_id) => {
+                                Self::set_role(group_id, user_id, role);
+                            }
+                            Err(e) => {
+                                log::error!("{}", e);
+                            }
+                        }
+                    } else {
+                        log::error!("user id is not given");
+                    }
+                }
+                Err(e) => {
+                    log::error!("{}", e);
+                }
+            }
+        } else {
+            log::error!("group role command incorrectly formatted");
+        }
+    }
+
+    /// leave group
+    fn leave_group(group_id: Vec<u8>) {
+        let proto_message = proto::Group {
+            message: Some(proto::group::Message::GroupLeaveRequest(
+                proto::GroupLeaveRequest { group_id },
+            )),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Group.into(),
+            "".to_string(),
+        );
+    }
+
+    /// disband group
+    fn disband_group(group_id: Vec<u8>) {
+        let proto_message = proto::Group {
+            message: Some(proto::group::Message::GroupDisbandRequest(
+                proto::GroupDisbandRequest { group_id },
+            )),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Group.into(),
+            "".to_string(),
+        );
+    }
+
+    /// set role of a group member
+    fn set_role(group_id: Vec<u8>, user_id: Vec<u8>, role: proto::GroupMemberRole) {
+        let proto_message = proto::Group {
+            message: Some(proto::group::Message::GroupSetRoleRequest(
+                proto::GroupSetRoleRequest {
+                    group_id,
+                    user_id,
+                    role: role as i32,
+                },
+            )),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Group.into(),
+            "".to_string(),
+        );
+    }
+
+    /// create invite code
+    fn create_invite_code(group_id: Vec<u8>, expires_in: u64, max_uses: u32) {
+        let proto_message = proto::Group {
+            message: Some(proto::group::Message::GroupInviteCodeRequest(
+                proto::GroupInviteCodeRequest {
+                    group_id,
+                    expires_in,
+                    max_uses,
+                },
+            )),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Group.into(),
+            "".to_string(),
+        );
+    }
+
+    /// redeem invite code
+    fn redeem_invite_code(code: String, admin_id: Vec<u8>) {
+        let proto_message = proto::Group {
+            message: Some(proto::group::Message::GroupRedeemCodeRequest(
+                proto::GroupRedeemCodeRequest { code, admin_id },
+            )),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Group.into(),
+            "".to_string(),
+        );
+    }
+
     /// Process the last message & print it's content
     fn print_last_message(data: Vec<u8>) {
         if let Ok(content_message) = proto_chat::ChatContentMessage::decode(&data[..]) {
@@ -435,6 +857,12 @@ impl Group {
                     );
                     println!("\t\t{}", file_content.file_description);
                 }
+                Some(proto_chat::chat_content_message::Message::PollContent(poll_content)) => {
+                    println!("\t\tpoll: {}", poll_content.question);
+                }
+                Some(proto_chat::chat_content_message::Message::PollVoteContent(_)) => {
+                    println!("\t\tvoted on a poll");
+                }
                 Some(proto_chat::chat_content_message::Message::GroupEvent(group_event)) => {
                     match proto_chat::GroupEventType::from_i32(group_event.event_type).unwrap() {
                         proto_chat::GroupEventType::Joined => {
@@ -494,6 +922,29 @@ impl Group {
                             println!("\terror: {}", result.message);
                         }
                     }
+                    Some(proto::group::Message::GroupRetentionResponse(retention_response)) => {
+                        let result = retention_response.result.unwrap();
+                        println!("====================================");
+                        println!("Group Retention status: {}", result.status);
+                        let group_id =
+                            uuid::Uuid::from_bytes(retention_response.group_id.try_into().unwrap());
+                        println!("\tid: {}", group_id.to_string());
+                        println!("\tretention: {} seconds", retention_response.retention);
+                        if !result.status {
+                            println!("\terror: {}", result.message);
+                        }
+                    }
+                    Some(proto::group::Message::GroupUpdateResponse(update_response)) => {
+                        let result = update_response.result.unwrap();
+                        println!("====================================");
+                        println!("Group Update status: {}", result.status);
+                        let group_id =
+                            uuid::Uuid::from_bytes(update_response.group_id.try_into().unwrap());
+                        println!("\tid: {}", group_id.to_string());
+                        if !result.status {
+                            println!("\terror: {}", result.message);
+                        }
+                    }
                     Some(proto::group::Message::GroupInviteMemberResponse(
                         invite_group_response,
                     )) => {
@@ -534,6 +985,81 @@ impl Group {
                             println!("\terror: {}", result.message);
                         }
                     }
+                    Some(proto::group::Message::GroupLeaveResponse(leave_response)) => {
+                        let result = leave_response.result.unwrap();
+                        println!("====================================");
+                        println!("Group Leave status: {}", result.status);
+                        let group_id =
+                            uuid::Uuid::from_bytes(leave_response.group_id.try_into().unwrap());
+                        println!("\tid: {}", group_id.to_string());
+                        if !result.status {
+                            println!("\terror: {}", result.message);
+                        }
+                    }
+                    Some(proto::group::Message::GroupDisbandResponse(disband_response)) => {
+                        let result = disband_response.result.unwrap();
+                        println!("====================================");
+                        println!("Group Disband status: {}", result.status);
+                        let group_id =
+                            uuid::Uuid::from_bytes(disband_response.group_id.try_into().unwrap());
+                        println!("\tid: {}", group_id.to_string());
+                        if !result.status {
+                            println!("\terror: {}", result.message);
+                        }
+                    }
+                    Some(proto::group::Message::GroupSetRoleResponse(role_response)) => {
+                        let result = role_response.result.unwrap();
+                        println!("====================================");
+                        println!("Group Set Role status: {}", result.status);
+                        let group_id =
+                            uuid::Uuid::from_bytes(role_response.group_id.try_into().unwrap());
+                        println!("\tid: {}", group_id.to_string());
+                        println!(
+                            "\tuser: {}",
+                            bs58::encode(role_response.user_id).into_string()
+                        );
+                        match proto::GroupMemberRole::from_i32(role_response.role) {
+                            Some(proto::GroupMemberRole::Admin) => println!("\trole: admin"),
+                            _ => println!("\trole: user"),
+                        }
+                        if !result.status {
+                            println!("\terror: {}", result.message);
+                        }
+                    }
+                    Some(proto::group::Message::GroupInviteCodeResponse(code_response)) => {
+                        let result = code_response.result.unwrap();
+                        println!("====================================");
+                        println!("Group Invite Code status: {}", result.status);
+                        let group_id =
+                            uuid::Uuid::from_bytes(code_response.group_id.try_into().unwrap());
+                        println!("\tid: {}", group_id.to_string());
+                        if result.status {
+                            println!("\tcode: {}", code_response.code);
+                            if code_response.expires_at > 0 {
+                                println!("\texpires at: {}", code_response.expires_at);
+                            }
+                            if code_response.max_uses > 0 {
+                                println!("\tmax uses: {}", code_response.max_uses);
+                            }
+                        } else {
+                            println!("\terror: {}", result.message);
+                        }
+                    }
+                    Some(proto::group::Message::GroupRedeemCodeResponse(redeem_response)) => {
+                        let result = redeem_response.result.unwrap();
+                        println!("====================================");
+                        println!("Group Redeem Invite Code status: {}", result.status);
+                        if result.status {
+                            let group_id = uuid::Uuid::from_bytes(
+                                redeem_response.group_id.try_into().unwrap(),
+                            );
+                            println!("\tid: {}", group_id.to_string());
+                            println!("\tname: {}", redeem_response.group_name);
+                            println!("\tThe invitation will appear in `group invited`");
+                        } else {
+                            println!("\terror: {}", result.message);
+                        }
+                    }
                     Some(proto::group::Message::GroupInfoResponse(group_info_response)) => {
                         // group
                         println!("====================================");
@@ -545,6 +1071,11 @@ impl Group {
                         println!("\tname: {}", group_info_response.group_name.clone());
                         println!("\tcreated_at: {}", group_info_response.created_at);
                         println!("\tmembers: {}", group_info_response.members.len());
+                        Self::print_group_details(
+                            &group_info_response.description,
+                            &group_info_response.avatar,
+                            &group_info_response.settings,
+                        );
                     }
                     Some(proto::group::Message::GroupListResponse(group_list_response)) => {
                         // List groups
@@ -594,14 +1125,21 @@ impl Group {
 
                                 match proto::GroupMemberRole::from_i32(member.role).unwrap() {
                                     proto::GroupMemberRole::User => {
-                                        println!("user , sent: {}", member.last_message_index);
+                                        print!("user , sent: {}", member.last_message_index);
                                     }
                                     proto::GroupMemberRole::Admin => {
-                                        println!("admin , sent: {}", member.last_message_index);
+                                        print!("admin , sent: {}", member.last_message_index);
                                     }
                                 }
+                                println!(" , read: {}", member.last_read_index);
                             }
                             println!("\trevision: {}", group.revision);
+                            println!("\tretention: {} seconds", group.retention);
+                            Self::print_group_details(
+                                &group.description,
+                                &group.avatar,
+                                &group.settings,
+                            );
                             println!("\tunread messages: {}", group.unread_messages);
                             println!("\tlast message:");
                             println!(
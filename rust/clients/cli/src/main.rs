@@ -28,6 +28,7 @@ mod node;
 mod router;
 mod rpc;
 mod rtc;
+mod search;
 mod user_accounts;
 mod users;
 
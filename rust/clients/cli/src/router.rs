@@ -32,6 +32,10 @@ impl Router {
             cmd if cmd.starts_with("connections list") => {
                 Self::request_connections_list();
             },
+            // request routing information overhead per neighbour.
+            cmd if cmd.starts_with("overhead list") => {
+                Self::request_overhead_list();
+            },
             // unknown command
             _ => log::error!("unknown router command"),
         }
@@ -76,6 +80,19 @@ impl Router {
         Self::send_message(proto_message);
     }
 
+    /// create rpc request for routing overhead list
+    fn request_overhead_list() {
+        // create request message
+        let proto_message = proto::Router {
+            message: Some(proto::router::Message::RoutingOverheadRequest (
+                proto::RoutingOverheadRequest {}
+            )),
+        };
+
+        // send message
+        Self::send_message(proto_message);
+    }
+
     /// Encode and send protobuf message
     fn send_message(message: proto::Router) {
         // encode message
@@ -119,6 +136,7 @@ impl Router {
                                     Some(proto::ConnectionModule::Internet) => "Internet",
                                     Some(proto::ConnectionModule::Ble) => "Ble",
                                     Some(proto::ConnectionModule::Local) => "Local",
+                                    Some(proto::ConnectionModule::Stream) => "Stream",
                                     None => "Unknown",
                                 };
 
@@ -140,6 +158,9 @@ impl Router {
 
                         println!("Internet Neighbours");
                         Self::rpc_display_neighbours_list(proto_message.internet);
+
+                        println!("Stream Neighbours");
+                        Self::rpc_display_neighbours_list(proto_message.stream);
                     },
                     Some(proto::router::Message::ConnectionsList(proto_message)) => {
                         println!("");
@@ -151,6 +172,27 @@ impl Router {
 
                         println!("Internet Connections");
                         Self::rpc_display_connections_list(proto_message.internet);
+
+                        println!("Stream Connections");
+                        Self::rpc_display_connections_list(proto_message.stream);
+                    },
+                    Some(proto::router::Message::RoutingOverheadList(proto_message)) => {
+                        println!("");
+                        println!("Routing Overhead - Routing Information per Neighbour");
+                        println!("Neighbour Node Id | sent bytes / frames / full snapshots | received bytes / frames | since");
+
+                        for entry in proto_message.neighbours {
+                            println!("{} | {} / {} / {} | {} / {} | {}",
+                                bs58::encode(entry.node_id).into_string(),
+                                entry.bytes_sent,
+                                entry.frames_sent,
+                                entry.full_sent,
+                                entry.bytes_received,
+                                entry.frames_received,
+                                entry.since
+                            );
+                        }
+                        println!("");
                     },
                     _ => {
                         log::error!("unprocessable RPC router message");
@@ -166,7 +208,7 @@ impl Router {
     /// Display Neighbours list
     fn rpc_display_neighbours_list(neighbours_list: Vec<proto::NeighboursEntry>) {
         for entry in neighbours_list {
-            println!("{}, {} rtt", bs58::encode(entry.node_id).into_string(), entry.rtt);
+            println!("{}, {} rtt, {}.{:02} etx", bs58::encode(entry.node_id).into_string(), entry.rtt, entry.etx / 100, entry.etx % 100);
         }
         println!("");
     }
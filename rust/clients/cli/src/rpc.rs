@@ -112,6 +112,9 @@ impl Rpc {
                     Some(proto::Modules::Dtn) => {
                         super::dtn::Dtn::rpc(message.data);
                     }
+                    Some(proto::Modules::Search) => {
+                        super::search::Search::rpc(message.data);
+                    }
                     Some(proto::Modules::None) => {}
                     None => {}
                 }
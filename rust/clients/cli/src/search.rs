@@ -0,0 +1,153 @@
+// Copyright (c) 2022 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Search module functions
+
+use super::rpc::Rpc;
+use prost::Message;
+
+/// include generated protobuf RPC rust definition file
+mod proto {
+    include!("../../../libqaul/src/rpc/protobuf_generated/rust/qaul.rpc.search.rs");
+}
+
+/// Search module function handling
+pub struct Search {}
+
+impl Search {
+    /// CLI command interpretation
+    ///
+    /// The CLI commands of search module are processed here
+    pub fn cli(command: &str) {
+        match command {
+            // search chat messages & feed messages
+            cmd if cmd.starts_with("all ") => {
+                let query = cmd.strip_prefix("all ").unwrap().trim().to_string();
+                Self::search(query, proto::SearchSource::All, Vec::new());
+            }
+            // search chat messages only
+            cmd if cmd.starts_with("chat ") => {
+                let query = cmd.strip_prefix("chat ").unwrap().trim().to_string();
+                Self::search(query, proto::SearchSource::Chat, Vec::new());
+            }
+            // search feed messages only
+            cmd if cmd.starts_with("feed ") => {
+                let query = cmd.strip_prefix("feed ").unwrap().trim().to_string();
+                Self::search(query, proto::SearchSource::Feed, Vec::new());
+            }
+            // search the messages of a group
+            cmd if cmd.starts_with("group ") => {
+                let command_string = cmd.strip_prefix("group ").unwrap().to_string();
+                let mut iter = command_string.split_whitespace();
+
+                if let Some(group_id_str) = iter.next() {
+                    let group_id;
+                    match uuid::Uuid::parse_str(group_id_str) {
+                        Ok(id) => group_id = id.as_bytes().to_vec(),
+                        Err(_) => {
+                            log::error!("invalid group id format");
+                            return;
+                        }
+                    }
+
+                    let query = command_string
+                        .strip_prefix(group_id_str)
+                        .unwrap()
+                        .trim()
+                        .to_string();
+                    Self::search(query, proto::SearchSource::Chat, group_id);
+                } else {
+                    log::error!("search group command incorrectly formatted");
+                }
+            }
+            // unknown command
+            _ => log::error!("unknown search command"),
+        }
+    }
+
+    /// send search request
+    fn search(query: String, source: proto::SearchSource, group_id: Vec<u8>) {
+        if query.len() == 0 {
+            log::error!("search query is empty");
+            return;
+        }
+
+        // create search request message
+        let proto_message = proto::Search {
+            message: Some(proto::search::Message::SearchRequest(
+                proto::SearchRequest {
+                    query,
+                    source: source as i32,
+                    group_id,
+                    sender_id: Vec::new(),
+                    sent_after: 0,
+                    sent_before: 0,
+                    offset: 0,
+                    limit: 0,
+                },
+            )),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            super::rpc::proto::Modules::Search.into(),
+            "".to_string(),
+        );
+    }
+
+    /// Process received RPC message
+    ///
+    /// Decodes received protobuf encoded binary RPC message
+    /// of the search module.
+    pub fn rpc(data: Vec<u8>) {
+        match proto::Search::decode(&data[..]) {
+            Ok(search) => match search.message {
+                Some(proto::search::Message::SearchResponse(response)) => {
+                    println!("====================================");
+                    println!(
+                        "Search results for '{}': {} of {}",
+                        response.query,
+                        response.results.len(),
+                        response.total
+                    );
+
+                    for result in response.results {
+                        match proto::SearchResultType::from_i32(result.result_type) {
+                            Some(proto::SearchResultType::FeedMessage) => {
+                                println!("[feed {}] {}", result.index, result.sent_at);
+                            }
+                            Some(proto::SearchResultType::ChatFile) => {
+                                match uuid::Uuid::from_slice(&result.group_id) {
+                                    Ok(group_id) => println!(
+                                        "[file {} {}] {}",
+                                        group_id, result.index, result.sent_at
+                                    ),
+                                    Err(_) => {
+                                        println!("[file {}] {}", result.index, result.sent_at)
+                                    }
+                                }
+                            }
+                            _ => match uuid::Uuid::from_slice(&result.group_id) {
+                                Ok(group_id) => println!(
+                                    "[chat {} {}] {}",
+                                    group_id, result.index, result.sent_at
+                                ),
+                                Err(_) => println!("[chat {}] {}", result.index, result.sent_at),
+                            },
+                        }
+                        println!("\t{}", bs58::encode(result.sender_id).into_string());
+                        println!("\t{}", result.snippet);
+                        println!("");
+                    }
+                }
+                _ => {
+                    log::error!("unprocessable RPC search message");
+                }
+            },
+            Err(error) => {
+                log::error!("{:?}", error);
+            }
+        }
+    }
+}
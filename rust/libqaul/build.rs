@@ -87,6 +87,7 @@ fn main() {
                 "connections/ble/ble_rpc.proto",
                 "services/messaging/messaging.proto",
                 "services/dtn/dtn_rpc.proto",
+                "services/search/search_rpc.proto",
             ],
             &["src"],
         )
@@ -163,6 +164,11 @@ fn main() {
         to.join("qaul.rpc.rtc.rs"),
     )
     .unwrap();
+    fs::copy(
+        Path::new(&out_dir).join("qaul.rpc.search.rs"),
+        to.join("qaul.rpc.search.rs"),
+    )
+    .unwrap();
     fs::copy(
         Path::new(&out_dir).join("qaul.rpc.ble.rs"),
         to.join("qaul.rpc.ble.rs"),
@@ -278,6 +284,11 @@ fn main() {
         Path::new("src/services/rtc/qaul.rpc.rtc.rs"),
     )
     .unwrap();
+    fs::copy(
+        &Path::new(&out_dir).join("qaul.rpc.search.rs"),
+        Path::new("src/services/search/qaul.rpc.search.rs"),
+    )
+    .unwrap();
     fs::copy(
         &Path::new(&out_dir).join("qaul.rpc.ble.rs"),
         Path::new("src/connections/ble/qaul.rpc.ble.rs"),
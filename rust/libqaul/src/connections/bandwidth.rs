@@ -0,0 +1,484 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Bandwidth Accounting & Limits
+//!
+//! Counts the bytes and messages sent and received via
+//! the `qaul_info` and `qaul_messaging` channels, floodsub
+//! and BLE, per connection module, per neighbour and per
+//! service.
+//!
+//! The sending bandwidth of each connection module can be
+//! limited in the configuration, in bytes per second:
+//!
+//! ```yaml
+//! bandwidth:
+//!   lan: 0
+//!   internet: 0
+//!   ble: 2000
+//!   stream: 0
+//! ```
+//!
+//! The limit is applied as a token bucket, holding the
+//! tokens of one second. Messages sent while the bucket is
+//! empty are dropped and counted. Routing information is
+//! resent periodically and unconfirmed messages are
+//! retransmitted, dropped messages are therefore recovered
+//! once bandwidth is available again.
+//!
+//! The service of end-to-end encrypted messages is only known
+//! for the messages sent by this node. Received and forwarded
+//! messages are counted as `MESSAGING`.
+
+use libp2p::PeerId;
+use prost::Message;
+use state::Storage;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::RwLock;
+
+use super::{proto, ConnectionModule};
+use crate::router::router_net_proto;
+use crate::services::messaging::{proto as messaging_proto, MessagingServiceType};
+use crate::storage::configuration::Configuration;
+use crate::utilities::timestamp::Timestamp;
+
+/// mutable state of the bandwidth accounting
+static BANDWIDTH: Storage<RwLock<Bandwidth>> = Storage::new();
+
+/// number of sent message signatures kept for the
+/// service lookup
+const TAG_CACHE_SIZE: usize = 10_000;
+
+/// Bandwidth Counter
+#[derive(Clone, Default)]
+struct Counter {
+    bytes_sent: u64,
+    bytes_received: u64,
+    messages_sent: u64,
+    messages_received: u64,
+    messages_dropped: u64,
+}
+
+impl Counter {
+    fn sent(&mut self, len: usize) {
+        self.bytes_sent += len as u64;
+        self.messages_sent += 1;
+    }
+
+    fn received(&mut self, len: usize) {
+        self.bytes_received += len as u64;
+        self.messages_received += 1;
+    }
+
+    fn dropped(&mut self) {
+        self.messages_dropped += 1;
+    }
+
+    fn to_proto(&self) -> proto::BandwidthCounter {
+        proto::BandwidthCounter {
+            bytes_sent: self.bytes_sent,
+            bytes_received: self.bytes_received,
+            messages_sent: self.messages_sent,
+            messages_received: self.messages_received,
+            messages_dropped: self.messages_dropped,
+        }
+    }
+}
+
+/// Token bucket limiting the sending bandwidth of a module
+struct TokenBucket {
+    /// limit in bytes per second, 0 means unlimited
+    limit: u64,
+    /// available bytes, negative after sending
+    /// a message bigger than the available bytes
+    tokens: i64,
+    /// time of the last refill in milli seconds
+    last_refill: u64,
+}
+
+impl TokenBucket {
+    fn new(limit: u64) -> Self {
+        TokenBucket {
+            limit,
+            tokens: limit as i64,
+            last_refill: Timestamp::get_timestamp(),
+        }
+    }
+
+    /// take the tokens for sending a message at time `now`
+    ///
+    /// returns false if the message exceeds the limit
+    fn take(&mut self, len: usize, now: u64) -> bool {
+        if self.limit == 0 {
+            return true;
+        }
+
+        // refill the bucket
+        let elapsed = now.saturating_sub(self.last_refill);
+        let refill = (self.limit * elapsed / 1000) as i64;
+        if refill > 0 {
+            self.tokens = (self.tokens + refill).min(self.limit as i64);
+            self.last_refill = now;
+        }
+
+        if self.tokens <= 0 {
+            return false;
+        }
+        self.tokens -= len as i64;
+
+        true
+    }
+}
+
+/// Bandwidth Accounting
+pub struct Bandwidth {
+    /// counting since, timestamp in milli seconds
+    since: u64,
+    /// counters per connection module
+    modules: BTreeMap<ConnectionModule, Counter>,
+    /// counters per neighbour and connection module
+    neighbours: HashMap<(ConnectionModule, PeerId), Counter>,
+    /// counters per service
+    services: BTreeMap<proto::BandwidthService, Counter>,
+    /// sending limits per connection module
+    limits: HashMap<ConnectionModule, TokenBucket>,
+    /// service of the messages sent by this node,
+    /// by message signature
+    tags: HashMap<Vec<u8>, proto::BandwidthService>,
+    /// order of the tagged signatures, for eviction
+    tags_order: VecDeque<Vec<u8>>,
+}
+
+impl Bandwidth {
+    /// initialize bandwidth accounting
+    /// and read the limits from the configuration
+    pub fn init() {
+        let config = Configuration::get();
+
+        let mut limits = HashMap::new();
+        limits.insert(
+            ConnectionModule::Lan,
+            TokenBucket::new(config.bandwidth.lan),
+        );
+        limits.insert(
+            ConnectionModule::Internet,
+            TokenBucket::new(config.bandwidth.internet),
+        );
+        limits.insert(
+            ConnectionModule::Ble,
+            TokenBucket::new(config.bandwidth.ble),
+        );
+        limits.insert(
+            ConnectionModule::Stream,
+            TokenBucket::new(config.bandwidth.stream),
+        );
+
+        let bandwidth = Bandwidth {
+            since: Timestamp::get_timestamp(),
+            modules: BTreeMap::new(),
+            neighbours: HashMap::new(),
+            services: BTreeMap::new(),
+            limits,
+            tags: HashMap::new(),
+            tags_order: VecDeque::new(),
+        };
+        BANDWIDTH.set(RwLock::new(bandwidth));
+    }
+
+    /// check the sending limit of the module and
+    /// count the message to send
+    ///
+    /// Returns false if the message exceeds the limit
+    /// and shall not be sent.
+    pub fn sending(
+        module: ConnectionModule,
+        neighbour_id: &PeerId,
+        service: proto::BandwidthService,
+        len: usize,
+    ) -> bool {
+        let mut bandwidth = BANDWIDTH.get().write().unwrap();
+
+        let allowed = match bandwidth.limits.get_mut(&module) {
+            Some(bucket) => bucket.take(len, Timestamp::get_timestamp()),
+            None => true,
+        };
+
+        if allowed {
+            bandwidth.modules.entry(module).or_default().sent(len);
+            bandwidth
+                .neighbours
+                .entry((module, *neighbour_id))
+                .or_default()
+                .sent(len);
+            bandwidth.services.entry(service).or_default().sent(len);
+        } else {
+            log::debug!(
+                "bandwidth limit of {:?} exceeded, {:?} message to {} dropped",
+                module,
+                service,
+                neighbour_id.to_base58()
+            );
+            bandwidth.modules.entry(module).or_default().dropped();
+            bandwidth
+                .neighbours
+                .entry((module, *neighbour_id))
+                .or_default()
+                .dropped();
+            bandwidth.services.entry(service).or_default().dropped();
+        }
+
+        allowed
+    }
+
+    /// count a received message
+    pub fn received(
+        module: ConnectionModule,
+        neighbour_id: &PeerId,
+        service: proto::BandwidthService,
+        len: usize,
+    ) {
+        let mut bandwidth = BANDWIDTH.get().write().unwrap();
+
+        bandwidth.modules.entry(module).or_default().received(len);
+        bandwidth
+            .neighbours
+            .entry((module, *neighbour_id))
+            .or_default()
+            .received(len);
+        bandwidth.services.entry(service).or_default().received(len);
+    }
+
+    /// count a feed message received via floodsub
+    ///
+    /// Floodsub only reports the origin of a message,
+    /// the message is therefore not counted per neighbour.
+    pub fn received_floodsub(module: ConnectionModule, len: usize) {
+        let mut bandwidth = BANDWIDTH.get().write().unwrap();
+
+        bandwidth.modules.entry(module).or_default().received(len);
+        bandwidth
+            .services
+            .entry(proto::BandwidthService::Feed)
+            .or_default()
+            .received(len);
+    }
+
+    /// count a received router info message
+    pub fn received_info(module: ConnectionModule, neighbour_id: &PeerId, data: &Vec<u8>) {
+        Self::received(module, neighbour_id, Self::info_service(data), data.len());
+    }
+
+    /// count a received messaging message
+    pub fn received_messaging(module: ConnectionModule, neighbour_id: &PeerId, data: &Vec<u8>) {
+        Self::received(
+            module,
+            neighbour_id,
+            Self::messaging_service(data),
+            data.len(),
+        );
+    }
+
+    /// remember the service of a message sent by this node
+    pub fn tag_message(signature: &Vec<u8>, message_type: &MessagingServiceType) {
+        let service = match message_type {
+            MessagingServiceType::Chat => proto::BandwidthService::Chat,
+            MessagingServiceType::ChatFile => proto::BandwidthService::Files,
+            MessagingServiceType::Rtc => proto::BandwidthService::Rtc,
+            MessagingServiceType::Group => proto::BandwidthService::Group,
+            MessagingServiceType::Crypto => proto::BandwidthService::Crypto,
+            MessagingServiceType::DtnOrigin | MessagingServiceType::DtnStored => {
+                proto::BandwidthService::Dtn
+            }
+            MessagingServiceType::Unconfirmed => proto::BandwidthService::Messaging,
+        };
+
+        let mut bandwidth = BANDWIDTH.get().write().unwrap();
+        if bandwidth.tags.insert(signature.clone(), service).is_none() {
+            bandwidth.tags_order.push_back(signature.clone());
+        }
+        while bandwidth.tags_order.len() > TAG_CACHE_SIZE {
+            if let Some(old_signature) = bandwidth.tags_order.pop_front() {
+                bandwidth.tags.remove(&old_signature);
+            }
+        }
+    }
+
+    /// get the service of an encoded router info container
+    pub fn info_service(data: &Vec<u8>) -> proto::BandwidthService {
+        let module = router_net_proto::RouterInfoContainer::decode(&data[..])
+            .ok()
+            .and_then(|container| {
+                router_net_proto::RouterInfoContent::decode(&container.message[..]).ok()
+            })
+            .and_then(|content| {
+                router_net_proto::RouterInfoModule::from_i32(content.router_info_module)
+            });
+
+        match module {
+            Some(router_net_proto::RouterInfoModule::FeedRequest)
+            | Some(router_net_proto::RouterInfoModule::FeedResponse)
+            | Some(router_net_proto::RouterInfoModule::Broadcast)
+            | Some(router_net_proto::RouterInfoModule::BroadcastHave)
+            | Some(router_net_proto::RouterInfoModule::BroadcastWant) => {
+                proto::BandwidthService::Feed
+            }
+            _ => proto::BandwidthService::Routing,
+        }
+    }
+
+    /// get the service of an encoded messaging container
+    pub fn messaging_service(data: &Vec<u8>) -> proto::BandwidthService {
+        let container = match messaging_proto::Container::decode(&data[..]) {
+            Ok(container) => container,
+            Err(_) => return proto::BandwidthService::Messaging,
+        };
+
+        // messages sent by this node
+        {
+            let bandwidth = BANDWIDTH.get().read().unwrap();
+            if let Some(service) = bandwidth.tags.get(&container.signature) {
+                return *service;
+            }
+        }
+
+        // DTN messages are not end-to-end encrypted
+        if let Some(envelope) = container.envelope {
+            if let Ok(payload) = messaging_proto::EnvelopPayload::decode(&envelope.payload[..]) {
+                if let Some(messaging_proto::envelop_payload::Payload::Dtn(_)) = payload.payload {
+                    return proto::BandwidthService::Dtn;
+                }
+            }
+        }
+
+        proto::BandwidthService::Messaging
+    }
+
+    /// create the bandwidth statistics RPC message
+    ///
+    /// The counters are reset afterwards if requested.
+    pub fn rpc_statistics(reset: bool) -> proto::BandwidthStatistics {
+        let mut bandwidth = BANDWIDTH.get().write().unwrap();
+
+        let mut modules = Vec::new();
+        for module in ConnectionModule::TRANSPORTS {
+            let counter = bandwidth.modules.get(&module).cloned().unwrap_or_default();
+            let limit = match bandwidth.limits.get(&module) {
+                Some(bucket) => bucket.limit,
+                None => 0,
+            };
+            if let Some(rpc_module) = Self::rpc_module(module) {
+                modules.push(proto::BandwidthModuleEntry {
+                    module: rpc_module as i32,
+                    counter: Some(counter.to_proto()),
+                    limit,
+                });
+            }
+        }
+
+        let mut neighbours = Vec::new();
+        for ((module, node_id), counter) in &bandwidth.neighbours {
+            if let Some(rpc_module) = Self::rpc_module(*module) {
+                neighbours.push(proto::BandwidthNeighbourEntry {
+                    node_id: node_id.to_bytes(),
+                    module: rpc_module as i32,
+                    counter: Some(counter.to_proto()),
+                });
+            }
+        }
+
+        let services = bandwidth
+            .services
+            .iter()
+            .map(|(service, counter)| proto::BandwidthServiceEntry {
+                service: *service as i32,
+                counter: Some(counter.to_proto()),
+            })
+            .collect();
+
+        let statistics = proto::BandwidthStatistics {
+            since: bandwidth.since,
+            modules,
+            neighbours,
+            services,
+        };
+
+        if reset {
+            bandwidth.since = Timestamp::get_timestamp();
+            bandwidth.modules.clear();
+            bandwidth.neighbours.clear();
+            bandwidth.services.clear();
+        }
+
+        statistics
+    }
+
+    /// get the RPC enum of a connection module
+    fn rpc_module(module: ConnectionModule) -> Option<proto::BandwidthModule> {
+        match module {
+            ConnectionModule::Lan => Some(proto::BandwidthModule::Lan),
+            ConnectionModule::Internet => Some(proto::BandwidthModule::Internet),
+            ConnectionModule::Ble => Some(proto::BandwidthModule::Ble),
+            ConnectionModule::Stream => Some(proto::BandwidthModule::Stream),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unlimited_bucket_never_drops() {
+        let mut bucket = TokenBucket::new(0);
+        let now = bucket.last_refill;
+
+        for _ in 0..100 {
+            assert!(bucket.take(1_000_000, now));
+        }
+    }
+
+    #[test]
+    fn empty_bucket_drops() {
+        let mut bucket = TokenBucket::new(1000);
+        let now = bucket.last_refill;
+
+        assert!(bucket.take(600, now));
+        assert!(bucket.take(600, now));
+        assert_eq!(bucket.tokens, -200);
+        assert!(!bucket.take(1, now));
+    }
+
+    #[test]
+    fn bucket_refills_over_time() {
+        let mut bucket = TokenBucket::new(1000);
+        let now = bucket.last_refill;
+
+        assert!(bucket.take(1200, now));
+        assert!(!bucket.take(1, now + 100));
+        assert!(bucket.take(1, now + 300));
+        assert_eq!(bucket.tokens, 99);
+    }
+
+    #[test]
+    fn refill_is_capped_at_limit() {
+        let mut bucket = TokenBucket::new(1000);
+        let now = bucket.last_refill;
+
+        assert!(bucket.take(500, now));
+        assert!(bucket.take(0, now + 10_000));
+        assert_eq!(bucket.tokens, 1000);
+    }
+
+    #[test]
+    fn partial_milliseconds_are_not_lost() {
+        let mut bucket = TokenBucket::new(100);
+        let now = bucket.last_refill;
+
+        assert!(bucket.take(100, now));
+        // less than one byte accumulated, the refill time is kept
+        assert!(!bucket.take(1, now + 5));
+        assert!(bucket.take(1, now + 10));
+        assert_eq!(bucket.tokens, 0);
+    }
+}
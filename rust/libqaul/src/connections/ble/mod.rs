@@ -10,15 +10,15 @@
 //! * Android
 //! * iOS
 
-use libp2p::{floodsub::Topic, PeerId};
+use libp2p::PeerId;
 use prost::Message;
 use state::Storage;
 use std::{collections::BTreeMap, fmt, sync::RwLock};
 use uuid::Uuid;
 
-use crate::connections::ConnectionModule;
+use crate::connections::{bandwidth::Bandwidth, proto::BandwidthService, ConnectionModule};
 use crate::node::Node;
-use crate::router::neighbours::Neighbours;
+use crate::router::{flooder::Flooder, neighbours::Neighbours};
 use crate::rpc::{sys::Sys, Rpc};
 use crate::services::{feed, messaging};
 use crate::utilities::{qaul_id::QaulId, timestamp::Timestamp};
@@ -107,6 +107,12 @@ pub struct Ble {
     pub devices: Vec<proto::BleDeviceInfo>,
 }
 
+/// Transport handle of the BLE module
+///
+/// The BLE module keeps it's state in the module's static state,
+/// messages are sent via the BLE system module of the platform.
+pub struct BleTransport;
+
 impl Ble {
     /// initialize the BLE module
     pub fn init() {
@@ -190,6 +196,7 @@ impl Ble {
         let start_request = proto::BleStartRequest {
             qaul_id,
             power_setting: proto::BlePowerSetting::LowLatency.into(),
+            network_id: Node::get_network_id(),
         };
         let message = proto::Ble {
             message: Some(proto::ble::Message::StartRequest(start_request)),
@@ -409,9 +416,19 @@ impl Ble {
     /// Identification Received
     ///
     /// Received identity information from another node
+    ///
+    /// Nodes of private networks are only filtered by their
+    /// network id. The network id is public, BLE neighbours
+    /// are therefore not authenticated with the network key.
     fn identification_received(small_id: Vec<u8>, identification: proto_net::Identification) {
         // add node id
         if let Some(node) = identification.node {
+            // ignore nodes of other networks
+            if node.network_id != Node::get_network_id() {
+                log::warn!("BLE node of another network ignored");
+                return;
+            }
+
             // remove node from to_confirm
             {
                 let mut to_confirm = TO_CONFIRM.get().write().unwrap();
@@ -440,7 +457,10 @@ impl Ble {
             request,
             node: Some(proto_net::NodeIdentification {
                 id: node_id.to_bytes(),
+                network_id: Node::get_network_id(),
             }),
+            challenge: Vec::new(),
+            proof: Vec::new(),
         };
 
         // create unified message
@@ -504,17 +524,13 @@ impl Ble {
     }
 
     /// send feed message
-    pub fn send_feed_message(_topic: Topic, data: Vec<u8>) {
-        // find all nodes, that are only connected through BLE
-        let nodes = Neighbours::get_ble_only_nodes();
-
-        // create BLE message
+    ///
+    /// Feed messages are only sent to nodes not
+    /// supporting the broadcast messages.
+    pub fn send_feed_message(node_id: PeerId, data: Vec<u8>) {
         let message = proto_net::ble_message::Message::Feed(data);
 
-        // send it nodes
-        for node_id in nodes {
-            Self::create_send_message(QaulId::to_small(node_id), message.clone());
-        }
+        Self::create_send_message(QaulId::to_small(node_id), message);
     }
 
     /// create the message
@@ -555,6 +571,8 @@ impl Ble {
         match proto_net::BleMessage::decode(&message.data[..]) {
             Ok(ble_message) => match ble_message.message {
                 Some(proto_net::ble_message::Message::Info(data)) => {
+                    Bandwidth::received_info(ConnectionModule::Ble, &node_id, &data);
+
                     let received = qaul_info::QaulInfoReceived {
                         received_from: node_id,
                         data,
@@ -562,9 +580,16 @@ impl Ble {
                     crate::router::info::RouterInfo::received(received);
                 }
                 Some(proto_net::ble_message::Message::Feed(data)) => {
+                    Bandwidth::received(
+                        ConnectionModule::Ble,
+                        &node_id,
+                        BandwidthService::Feed,
+                        data.len(),
+                    );
+
                     match feed::proto_net::FeedContainer::decode(&data[..]) {
                         Ok(feed_container) => {
-                            feed::Feed::received(ConnectionModule::Ble, node_id, feed_container);
+                            Flooder::received_feed(node_id, feed_container);
                         }
                         Err(e) => {
                             log::error!("BleMessage feed decoding error: {}", e);
@@ -572,6 +597,8 @@ impl Ble {
                     }
                 }
                 Some(proto_net::ble_message::Message::Messaging(data)) => {
+                    Bandwidth::received_messaging(ConnectionModule::Ble, &node_id, &data);
+
                     let received = qaul_messaging::QaulMessagingReceived {
                         received_from: node_id,
                         data,
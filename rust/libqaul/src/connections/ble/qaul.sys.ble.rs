@@ -173,6 +173,15 @@ pub struct BleStartRequest {
     /// power settings 
     #[prost(enumeration="BlePowerSetting", tag="2")]
     pub power_setting: i32,
+    /// network id
+    ///
+    /// The 16 byte id of the private network
+    /// this node is part of, empty for the public
+    /// qaul network.
+    /// Only nodes advertising the same network id
+    /// shall be connected.
+    #[prost(bytes="vec", tag="3")]
+    pub network_id: ::prost::alloc::vec::Vec<u8>,
 }
 /// Start device result message
 ///
@@ -9,12 +9,12 @@ use std::convert::TryFrom;
 use qaul_info::QaulInfoEvent;
 use qaul_messaging::QaulMessagingEvent;
 
-use crate::connections::ConnectionModule;
+use crate::connections::{bandwidth::Bandwidth, ConnectionModule};
 use crate::router::{info::RouterInfo, neighbours::Neighbours};
 use crate::services::messaging::Messaging;
 
 /// Handle incoming QaulInfo behaviour events
-pub fn qaul_info_event(event: QaulInfoEvent, _module: ConnectionModule) {
+pub fn qaul_info_event(event: QaulInfoEvent, module: ConnectionModule) {
     match event {
         // received a RoutingInfo message
         QaulInfoEvent::Message(message) => {
@@ -23,6 +23,8 @@ pub fn qaul_info_event(event: QaulInfoEvent, _module: ConnectionModule) {
                 message.received_from
             );
 
+            Bandwidth::received_info(module, &message.received_from, &message.data);
+
             // forward to router
             RouterInfo::received(message);
         }
@@ -30,7 +32,7 @@ pub fn qaul_info_event(event: QaulInfoEvent, _module: ConnectionModule) {
 }
 
 /// Handle incoming QaulMessaging behaviour events
-pub fn qaul_messaging_event(event: QaulMessagingEvent, _module: ConnectionModule) {
+pub fn qaul_messaging_event(event: QaulMessagingEvent, module: ConnectionModule) {
     match event {
         // received a messaging message
         QaulMessagingEvent::Message(message) => {
@@ -39,6 +41,8 @@ pub fn qaul_messaging_event(event: QaulMessagingEvent, _module: ConnectionModule
                 message.received_from
             );
 
+            Bandwidth::received_messaging(module, &message.received_from, &message.data);
+
             // forward to messaging module
             Messaging::received(message);
         }
@@ -62,6 +66,7 @@ pub fn ping_event(event: Event, module: ConnectionModule) {
                 Ok(micros) => Neighbours::update_node(module, peer, micros),
                 Err(_) => Neighbours::update_node(module, peer, 4294967295),
             }
+            Neighbours::record_delivery(module, peer, true);
         }
         Event {
             peer,
@@ -74,12 +79,14 @@ pub fn ping_event(event: Event, module: ConnectionModule) {
             result: Result::Err(Failure::Timeout),
         } => {
             log::debug!("PingFailure::Timeout to {}", peer);
+            Neighbours::record_delivery(module, peer, false);
         }
         Event {
             peer,
             result: Result::Err(Failure::Other { error }),
         } => {
             log::debug!("PingFailure::Other {} error: {}", peer, error);
+            Neighbours::record_delivery(module, peer, false);
         }
         Event {
             peer,
@@ -14,19 +14,60 @@
 //! active: true
 //! peers:
 //!   - /ip4/144.91.74.192/tcp/9229
+//!   - /dns4/qaul.example.org/tcp/443/wss
+//!   - /ip4/144.91.74.192/udp/9229/quic
 //! do_listen: false
 //! listen: /ip4/0.0.0.0/tcp/9229
+//! listen_extra:
+//!   - /ip4/0.0.0.0/tcp/9230/ws
+//!   - /ip4/0.0.0.0/udp/9229/quic
 //! ```
+//!
+//! The transport is selected by the multiaddress:
+//! TCP, WebSocket (`/ws` & `/wss`) or QUIC (`/quic`).
+//! WebSocket connections can be served via ordinary
+//! HTTP reverse proxies.
+//!
+//! In a private network, the TCP & WebSocket connections are
+//! protected with the pre-shared network key, QUIC is disabled.
+//!
+//! All connections can be dialed via an outbound SOCKS5 or
+//! HTTP proxy, configured in `internet.proxy`.
+//! See the `proxy` module for details.
+//!
+//! Nodes behind a NAT can listen via a relay node, configured
+//! in `internet.relay.listen_via`. When the relay listener is
+//! closed or the reservation fails, the node listens again
+//! via the relay with an exponential backoff. Nodes with a public address
+//! can act as relays for others by setting `internet.relay.active`.
+//! Relayed connections are upgraded to direct connections via
+//! hole punching (DCUtR) whenever possible.
+//!
+//! Disconnected peers are redialed with an exponential backoff
+//! with jitter. The backoff is only reset, when a connection
+//! was stable for a while. The live state of each configured
+//! peer is reported in the `InternetNodesList` RPC message.
 
 use libp2p::swarm::keep_alive;
 use libp2p::{
-    core::upgrade,
+    core::{
+        either::{EitherOutput, EitherTransport},
+        multiaddr::Protocol,
+        muxing::StreamMuxerBox,
+        transport::{ListenerId, OrTransport},
+        upgrade,
+    },
+    dcutr,
     floodsub::{Floodsub, FloodsubEvent},
     identify, mplex,
     noise::{AuthenticKeypair, NoiseConfig, X25519Spec},
     ping,
-    swarm::{NetworkBehaviour, Swarm},
+    pnet::{PnetConfig, PreSharedKey},
+    quic,
+    relay::v2::{client, relay},
+    swarm::{behaviour::toggle::Toggle, NetworkBehaviour, Swarm},
     tcp::{async_io::Transport as TcpTransport, Config as GenTcpConfig},
+    websocket::WsConfig,
     yamux, Multiaddr, PeerId,
 };
 // DNS is excluded on mobile, as it is not working there
@@ -36,12 +77,17 @@ use libp2p::Transport;
 use prost::Message;
 
 use crate::node::Node;
-use crate::services::feed::Feed;
+use crate::router::flooder::Flooder;
 
+use super::bandwidth::Bandwidth;
+use super::proto;
+use super::proxy::{ListenOnly, ProxyTransport};
 use crate::connections::{events, ConnectionModule};
+use crate::router::neighbours::Neighbours;
 use crate::utilities::timestamp::Timestamp;
 use qaul_info::{QaulInfo, QaulInfoEvent};
 use qaul_messaging::{QaulMessaging, QaulMessagingEvent};
+use rand::Rng;
 use state::Storage;
 use std::{
     collections::{BTreeMap, HashMap},
@@ -61,6 +107,9 @@ pub struct QaulInternetBehaviour {
     pub ping: ping::Behaviour,
     pub qaul_info: QaulInfo,
     pub qaul_messaging: QaulMessaging,
+    pub relay: Toggle<relay::Relay>,
+    pub relay_client: client::Client,
+    pub dcutr: Toggle<dcutr::behaviour::Behaviour>,
 }
 
 impl QaulInternetBehaviour {
@@ -84,13 +133,26 @@ impl QaulInternetBehaviour {
             QaulInternetEvent::Floodsub(ev) => {
                 self.floodsub_event(ev);
             }
+            QaulInternetEvent::Relay(ev) => {
+                self.relay_event(ev);
+            }
+            QaulInternetEvent::RelayClient(ev) => {
+                self.relay_client_event(ev);
+            }
+            QaulInternetEvent::Dcutr(ev) => {
+                self.dcutr_event(ev);
+            }
         }
     }
 
     fn qaul_info_event(&mut self, event: QaulInfoEvent) {
+        let QaulInfoEvent::Message(ref message) = event;
+        Internet::count_received(&message.received_from, message.data.len());
         events::qaul_info_event(event, ConnectionModule::Internet);
     }
     fn qaul_messaging_event(&mut self, event: QaulMessagingEvent) {
+        let QaulMessagingEvent::Message(ref message) = event;
+        Internet::count_received(&message.received_from, message.data.len());
         events::qaul_messaging_event(event, ConnectionModule::Internet);
     }
     fn keep_alive_event(&mut self, event: void::Void) {
@@ -131,20 +193,80 @@ impl QaulInternetBehaviour {
     fn floodsub_event(&mut self, event: FloodsubEvent) {
         match event {
             FloodsubEvent::Message(msg) => {
+                Bandwidth::received_floodsub(ConnectionModule::Internet, msg.data.len());
+
                 // feed Message
                 if let Ok(resp) = proto_net::FeedContainer::decode(&msg.data[..]) {
-                    Feed::received(ConnectionModule::Internet, msg.source, resp);
+                    Flooder::received_feed(msg.source, resp);
                 }
             }
             _ => (),
         }
     }
+
+    fn relay_event(&mut self, event: relay::Event) {
+        log::debug!("Internet relay event: {:?}", event);
+    }
+
+    fn relay_client_event(&mut self, event: client::Event) {
+        match event {
+            client::Event::ReservationReqAccepted {
+                relay_peer_id,
+                renewal,
+                ..
+            } => {
+                if !renewal {
+                    log::info!("listening via relay {}", relay_peer_id);
+                }
+                Internet::relay_reservation_result(&relay_peer_id, true);
+            }
+            client::Event::ReservationReqFailed {
+                relay_peer_id,
+                error,
+                ..
+            } => {
+                log::warn!("relay {} reservation failed: {:?}", relay_peer_id, error);
+                Internet::relay_reservation_result(&relay_peer_id, false);
+            }
+            _ => log::debug!("Internet relay client event: {:?}", event),
+        }
+    }
+
+    fn dcutr_event(&mut self, event: dcutr::behaviour::Event) {
+        match event {
+            dcutr::behaviour::Event::DirectConnectionUpgradeSucceeded { remote_peer_id } => {
+                log::info!("direct connection to {} established", remote_peer_id);
+            }
+            dcutr::behaviour::Event::DirectConnectionUpgradeFailed {
+                remote_peer_id,
+                error,
+            } => {
+                log::debug!("hole punching to {} failed: {:?}", remote_peer_id, error);
+            }
+            _ => log::trace!("Internet DCUtR event: {:?}", event),
+        }
+    }
 }
 
+/// redial backoff base delay in milli seconds
+const REDIAL_DELAY_BASE: u64 = 5_000;
+/// maximal redial backoff delay in milli seconds
+const REDIAL_DELAY_MAX: u64 = 300_000;
+/// time in milli seconds after which a connection is stable
+/// and the redial backoff is reset
+const STABLE_CONNECTION_TIME: u64 = 60_000;
+/// time in milli seconds after which a dial without result failed
+const DIAL_TIMEOUT: u64 = 60_000;
+
 pub struct InternetReConnection {
     pub address: Multiaddr,
     pub attempt: u32,
     pub last_try: u64,
+    /// timestamp of the next redial attempt
+    pub next_try: u64,
+    /// timestamp since when the peer is connected,
+    /// 0 if it is not connected
+    pub connected_since: u64,
 }
 pub struct InternetReConnections {
     peers: HashMap<Multiaddr, InternetReConnection>,
@@ -152,6 +274,65 @@ pub struct InternetReConnections {
 static INTERNETRECONNECTIONS: Storage<RwLock<InternetReConnections>> = Storage::new();
 static INTERNETCONNECTIONS: Storage<RwLock<BTreeMap<String, PeerId>>> = Storage::new();
 
+/// Connection state of a configured internet peer
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InternetPeerState {
+    Disconnected,
+    Connecting,
+    Connected,
+    Failed,
+}
+
+/// Live status of a configured internet peer
+#[derive(Clone, Debug)]
+pub struct InternetPeerStatus {
+    pub state: InternetPeerState,
+    /// last connection error
+    pub last_error: String,
+    /// node id of the connected peer
+    pub node_id: Option<PeerId>,
+    /// timestamp since when the peer is connected
+    pub connected_since: u64,
+    /// timestamp of the last dial
+    pub dialed_at: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+impl Default for InternetPeerStatus {
+    fn default() -> Self {
+        InternetPeerStatus {
+            state: InternetPeerState::Disconnected,
+            last_error: String::new(),
+            node_id: None,
+            connected_since: 0,
+            dialed_at: 0,
+            bytes_sent: 0,
+            bytes_received: 0,
+        }
+    }
+}
+
+/// status of the internet peers by address
+static INTERNETPEERSTATUS: Storage<RwLock<BTreeMap<String, InternetPeerStatus>>> = Storage::new();
+
+/// Listener via a configured relay node
+pub struct InternetRelayListener {
+    /// address of the relay node
+    pub address: Multiaddr,
+    /// node id of the relay node
+    pub relay_peer_id: Option<PeerId>,
+    /// id of the active listener
+    pub listener: Option<ListenerId>,
+    /// the reservation failed, the listener needs to be replaced
+    pub failed: bool,
+    pub attempt: u32,
+    /// timestamp of the next listen attempt
+    pub next_try: u64,
+}
+/// listeners via the configured relay nodes
+static INTERNETRELAYLISTENERS: Storage<RwLock<Vec<InternetRelayListener>>> = Storage::new();
+
 #[derive(Debug)]
 pub enum QaulInternetEvent {
     Floodsub(FloodsubEvent),
@@ -160,6 +341,9 @@ pub enum QaulInternetEvent {
     Ping(ping::Event),
     QaulInfo(QaulInfoEvent),
     QaulMessaging(QaulMessagingEvent),
+    Relay(relay::Event),
+    RelayClient(client::Event),
+    Dcutr(dcutr::behaviour::Event),
 }
 
 impl From<FloodsubEvent> for QaulInternetEvent {
@@ -198,6 +382,24 @@ impl From<QaulMessagingEvent> for QaulInternetEvent {
     }
 }
 
+impl From<relay::Event> for QaulInternetEvent {
+    fn from(event: relay::Event) -> Self {
+        Self::Relay(event)
+    }
+}
+
+impl From<client::Event> for QaulInternetEvent {
+    fn from(event: client::Event) -> Self {
+        Self::RelayClient(event)
+    }
+}
+
+impl From<dcutr::behaviour::Event> for QaulInternetEvent {
+    fn from(event: dcutr::behaviour::Event) -> Self {
+        Self::Dcutr(event)
+    }
+}
+
 /// Internet Connection Module of libqaul
 ///
 /// it creates a libp2p swarm
@@ -206,6 +408,19 @@ pub struct Internet {
 }
 
 impl Internet {
+    /// create the tcp & WebSocket transport with DNS resolution
+    ///
+    /// Both transports share a single DNS resolver.
+    /// WebSocket addresses are dialed and listened on via the
+    /// WebSocket transport, all other addresses via tcp.
+    #[cfg(not(any(target_os = "android", target_os = "ios")))]
+    async fn create_dns_transport() -> DnsConfig<OrTransport<WsConfig<TcpTransport>, TcpTransport>>
+    {
+        let tcp = TcpTransport::new(GenTcpConfig::new().nodelay(true));
+        let ws_tcp = WsConfig::new(TcpTransport::new(GenTcpConfig::new().nodelay(true)));
+        DnsConfig::system(ws_tcp.or_transport(tcp)).await.unwrap()
+    }
+
     /// Initialize swarm for Internet overlay connection module
     pub async fn init(auth_keys: AuthenticKeypair<X25519Spec>) -> Self {
         log::trace!("Internet.init() start");
@@ -214,34 +429,92 @@ impl Internet {
             peers: HashMap::new(),
         }));
         INTERNETCONNECTIONS.set(RwLock::new(BTreeMap::<String, PeerId>::new()));
+        INTERNETPEERSTATUS.set(RwLock::new(BTreeMap::new()));
+        INTERNETRELAYLISTENERS.set(RwLock::new(Vec::new()));
 
-        // TCP transport for android without DNS resolution
+        let proxy = ProxyTransport::from_config(&Configuration::get().internet.proxy);
+
+        // TCP & WebSocket transport for android without DNS resolution
         // as the DNS module crashes on android due to a file system access
         #[cfg(any(target_os = "android", target_os = "ios"))]
         let transport = {
             let tcp = TcpTransport::new(GenTcpConfig::new().nodelay(true));
-            tcp
+            let ws_tcp = WsConfig::new(TcpTransport::new(GenTcpConfig::new().nodelay(true)));
+            tcp.or_transport(ws_tcp)
         };
-        // create tcp transport with DNS for all other devices
+        // create tcp & WebSocket transport with DNS for all other devices
         #[cfg(not(any(target_os = "android", target_os = "ios")))]
-        let transport = async {
-            let tcp = TcpTransport::new(GenTcpConfig::new().nodelay(true));
-            let dns_tcp = DnsConfig::system(tcp).await.unwrap();
-            dns_tcp
-        }
-        .await;
+        let transport = Self::create_dns_transport().await;
+
+        // dial all connections via the outbound proxy,
+        // the direct transport is only used to listen
+        let transport = match proxy.clone() {
+            Some(proxy) => {
+                log::info!("internet connections are dialed via proxy");
+                let proxy_ws = WsConfig::new(proxy.clone());
+                EitherTransport::Left(
+                    proxy
+                        .or_transport(proxy_ws)
+                        .or_transport(ListenOnly::new(transport)),
+                )
+            }
+            None => EitherTransport::Right(transport),
+        };
+
+        // relay client transport, to listen and dial via relay nodes
+        let (relay_transport, relay_client) =
+            client::Client::new_transport_and_behaviour(Node::get_id());
+        let transport = OrTransport::new(relay_transport, transport);
 
         log::trace!("Internet.init() transport created");
 
+        // protect the transport with the pre-shared key,
+        // when this node is part of a private network
+        let transport = match Node::get_network_key() {
+            Some(key) => EitherTransport::Left(transport.and_then(move |socket, _| {
+                PnetConfig::new(PreSharedKey::new(key)).handshake(socket)
+            })),
+            None => EitherTransport::Right(transport),
+        };
+
         let transport_upgraded = transport
             .upgrade(upgrade::Version::V1)
             .authenticate(NoiseConfig::xx(auth_keys).into_authenticated())
             .multiplex(upgrade::SelectUpgrade::new(
                 yamux::YamuxConfig::default(),
                 mplex::MplexConfig::default(),
-            ))
-            //.timeout(std::time::Duration::from_secs(100 * 365 * 24 * 3600)) // 100 years
-            .boxed();
+            ));
+        //.timeout(std::time::Duration::from_secs(100 * 365 * 24 * 3600)) // 100 years
+
+        // QUIC transport, it is encrypted and multiplexed by itself.
+        // QUIC can't be protected by the pre-shared key,
+        // and can't be dialed via a proxy.
+        // It is therefore not available in private networks
+        // and when a proxy is configured.
+        let transport_upgraded = match (Node::get_network_key(), &proxy) {
+            (Some(_), _) => {
+                log::info!("private network, QUIC transport disabled");
+                transport_upgraded.boxed()
+            }
+            (None, Some(_)) => {
+                log::info!("proxy configured, QUIC transport disabled");
+                transport_upgraded.boxed()
+            }
+            (None, None) => {
+                let quic_transport =
+                    quic::async_std::Transport::new(quic::Config::new(&Node::get_keys()));
+                OrTransport::new(quic_transport, transport_upgraded)
+                    .map(|output, _| match output {
+                        EitherOutput::First((peer_id, muxer)) => {
+                            (peer_id, StreamMuxerBox::new(muxer))
+                        }
+                        EitherOutput::Second((peer_id, muxer)) => {
+                            (peer_id, StreamMuxerBox::new(muxer))
+                        }
+                    })
+                    .boxed()
+            }
+        };
 
         log::trace!("Internet.init() transport_upgraded");
 
@@ -260,6 +533,29 @@ impl Internet {
 
         log::trace!("Internet.init() ping_config");
 
+        // relay service, when this node acts as a relay
+        let relay = match config.internet.relay.active {
+            true => {
+                let relay_config = relay::Config {
+                    max_reservations: config.internet.relay.max_reservations,
+                    max_circuits: config.internet.relay.max_circuits,
+                    max_circuit_duration: Duration::from_secs(
+                        config.internet.relay.max_circuit_duration,
+                    ),
+                    max_circuit_bytes: config.internet.relay.max_circuit_bytes,
+                    ..Default::default()
+                };
+                Some(relay::Relay::new(Node::get_id(), relay_config))
+            }
+            false => None,
+        };
+
+        // hole punching of relayed connections
+        let dcutr = match config.internet.relay.hole_punching {
+            true => Some(dcutr::behaviour::Behaviour::new()),
+            false => None,
+        };
+
         // create behaviour
         let mut swarm = {
             let mut behaviour = QaulInternetBehaviour {
@@ -272,6 +568,9 @@ impl Internet {
                 ping: ping::Behaviour::new(ping_config),
                 qaul_info: QaulInfo::new(Node::get_id()),
                 qaul_messaging: QaulMessaging::new(Node::get_id()),
+                relay: Toggle::from(relay),
+                relay_client,
+                dcutr: Toggle::from(dcutr),
             };
             behaviour.floodsub.subscribe(Node::get_topic());
             Swarm::with_threadpool_executor(transport_upgraded, behaviour, Node::get_id())
@@ -292,6 +591,42 @@ impl Internet {
         )
         .expect("swarm can be started");
 
+        // listen on the additional addresses,
+        // e.g. for WebSocket and QUIC connections
+        for address in &config.internet.listen_extra {
+            match address.parse::<Multiaddr>() {
+                Ok(multiaddr) => {
+                    if let Err(e) = Swarm::listen_on(&mut swarm, multiaddr) {
+                        log::error!("internet can't listen on {}: {}", address, e);
+                    }
+                }
+                Err(e) => log::error!("listen address {} parse error: {}", address, e),
+            }
+        }
+
+        // listen via the configured relay nodes
+        for address in &config.internet.relay.listen_via {
+            match address.parse::<Multiaddr>() {
+                Ok(multiaddr) => {
+                    let relay_peer_id = multiaddr.iter().find_map(|protocol| match protocol {
+                        Protocol::P2p(hash) => PeerId::from_multihash(hash).ok(),
+                        _ => None,
+                    });
+                    let mut listeners = INTERNETRELAYLISTENERS.get().write().unwrap();
+                    listeners.push(InternetRelayListener {
+                        address: multiaddr,
+                        relay_peer_id,
+                        listener: None,
+                        failed: false,
+                        attempt: 0,
+                        next_try: 0,
+                    });
+                }
+                Err(e) => log::error!("relay address {} parse error: {}", address, e),
+            }
+        }
+        Self::check_relay_listeners(&mut swarm);
+
         log::trace!("Internet.init() Swarm::listen_on");
 
         // connect to remote peers that are specified in
@@ -338,19 +673,47 @@ impl Internet {
     /// dial a remote peer
     pub fn peer_dial(addresse: Multiaddr, swarm: &mut Swarm<QaulInternetBehaviour>) {
         match swarm.dial(addresse.clone()) {
-            Ok(_) => log::trace!("peer {:?} dialed", addresse),
-            Err(error) => log::trace!("peer {} swarm dial error: {:?}", addresse, error),
+            Ok(_) => {
+                log::trace!("peer {:?} dialed", addresse);
+                Self::update_status(&addresse.to_string(), |status| {
+                    if status.state != InternetPeerState::Connected {
+                        status.state = InternetPeerState::Connecting;
+                        status.dialed_at = Timestamp::get_timestamp();
+                    }
+                });
+            }
+            Err(error) => {
+                log::trace!("peer {} swarm dial error: {:?}", addresse, error);
+                Self::set_failed(&addresse, error.to_string());
+            }
         }
     }
 
-    /// set tried time
+    /// set tried time and schedule the next attempt
     pub fn set_redialed(addresse: &Multiaddr) {
         let mut reconnections = INTERNETRECONNECTIONS.get().write().unwrap();
         if let Some(peer) = reconnections.peers.get_mut(addresse) {
-            peer.last_try = Timestamp::get_timestamp();
+            let now = Timestamp::get_timestamp();
+            peer.attempt += 1;
+            peer.last_try = now;
+            peer.next_try = now + Self::redial_delay(peer.attempt);
         }
     }
 
+    /// calculate the redial delay for an attempt
+    ///
+    /// The delay doubles with every attempt up to
+    /// `REDIAL_DELAY_MAX` and is randomized by +/- 25%,
+    /// so that nodes do not redial in lock step.
+    fn redial_delay(attempt: u32) -> u64 {
+        let delay = REDIAL_DELAY_BASE
+            .saturating_mul(1 << attempt.min(16))
+            .min(REDIAL_DELAY_MAX);
+        let jitter = rand::thread_rng().gen_range(0..=delay / 2);
+
+        delay - delay / 4 + jitter
+    }
+
     /// redial a remote peer
     pub async fn peer_redial(addresse: &Multiaddr, swarm: &mut Swarm<QaulInternetBehaviour>) {
         Self::peer_dial(addresse.clone(), swarm);
@@ -358,8 +721,17 @@ impl Internet {
 
     /// add connection entry
     pub fn add_connection(address: String, peer_id: &PeerId) {
-        let mut connections = INTERNETCONNECTIONS.get().write().unwrap();
-        connections.insert(address.clone(), peer_id.clone());
+        {
+            let mut connections = INTERNETCONNECTIONS.get().write().unwrap();
+            connections.insert(address.clone(), peer_id.clone());
+        }
+
+        Self::update_status(&address, |status| {
+            status.state = InternetPeerState::Connected;
+            status.last_error = String::new();
+            status.node_id = Some(peer_id.clone());
+            status.connected_since = Timestamp::get_timestamp();
+        });
     }
 
     /// peerid from mutiaddr uri
@@ -372,36 +744,289 @@ impl Internet {
     }
 
     ///add reconnection
+    ///
+    /// An existing entry keeps it's backoff schedule,
+    /// unless it's connection was stable.
     pub fn add_reconnection(address: Multiaddr) {
         let mut reconnections = INTERNETRECONNECTIONS.get().write().unwrap();
-        if let Some(peer) = reconnections.peers.get_mut(&address) {
-            peer.last_try = Timestamp::get_timestamp();
-        } else {
-            reconnections.peers.insert(
-                address.clone(),
-                InternetReConnection {
-                    address: address.clone(),
-                    attempt: 0,
-                    last_try: Timestamp::get_timestamp(),
-                },
-            );
+        let now = Timestamp::get_timestamp();
+        match reconnections.peers.get_mut(&address) {
+            Some(peer) => {
+                if peer.connected_since > 0 {
+                    if now.saturating_sub(peer.connected_since) >= STABLE_CONNECTION_TIME {
+                        peer.attempt = 0;
+                    }
+                    peer.connected_since = 0;
+                    peer.next_try = now + Self::redial_delay(peer.attempt);
+                }
+            }
+            None => {
+                reconnections.peers.insert(
+                    address.clone(),
+                    InternetReConnection {
+                        address: address.clone(),
+                        attempt: 0,
+                        last_try: now,
+                        next_try: now + Self::redial_delay(0),
+                        connected_since: 0,
+                    },
+                );
+            }
         }
     }
 
-    pub fn remove_reconnection(address: Multiaddr) {
+    /// a redialed peer is connected
+    ///
+    /// The peer is not redialed while it is connected.
+    /// It's backoff is kept until the connection was stable.
+    pub fn set_reconnected(address: &Multiaddr) {
         let mut reconnections = INTERNETRECONNECTIONS.get().write().unwrap();
-        reconnections.peers.remove(&address);
+        if let Some(peer) = reconnections.peers.get_mut(address) {
+            peer.connected_since = Timestamp::get_timestamp();
+        }
     }
 
     /// check redial
+    ///
+    /// returns the first peer that is due to be redialed
     pub fn check_reconnection() -> Option<Multiaddr> {
         let reconnections = INTERNETRECONNECTIONS.get().read().unwrap();
         let now_ts = Timestamp::get_timestamp();
         for (addr, peer) in reconnections.peers.iter() {
-            if (now_ts - peer.last_try) > 10000 {
+            if peer.connected_since == 0 && now_ts >= peer.next_try {
                 return Some(addr.clone());
             }
         }
         None
     }
+
+    /// fail the dials that didn't get a result
+    ///
+    /// Dial errors without an address can't be assigned
+    /// to a peer, such peers are failed after a timeout.
+    pub fn check_dial_timeouts() {
+        let now = Timestamp::get_timestamp();
+        let mut timed_out: Vec<Multiaddr> = Vec::new();
+        {
+            let peers = INTERNETPEERSTATUS.get().read().unwrap();
+            for (address, status) in peers.iter() {
+                if status.state == InternetPeerState::Connecting
+                    && now.saturating_sub(status.dialed_at) >= DIAL_TIMEOUT
+                {
+                    if let Ok(address) = address.parse() {
+                        timed_out.push(address);
+                    }
+                }
+            }
+        }
+
+        for address in timed_out {
+            Self::set_failed(&address, "dial timed out".to_string());
+            if Self::is_active_connection(&address) {
+                Self::add_reconnection(address);
+            }
+        }
+    }
+
+    /// listen via the relay nodes that are due
+    ///
+    /// Listeners with a failed reservation are removed
+    /// and replaced after a backoff.
+    pub fn check_relay_listeners(swarm: &mut Swarm<QaulInternetBehaviour>) {
+        let mut listeners = INTERNETRELAYLISTENERS.get().write().unwrap();
+        let now = Timestamp::get_timestamp();
+
+        for relay in listeners.iter_mut() {
+            if relay.failed {
+                relay.failed = false;
+                if let Some(listener) = relay.listener.take() {
+                    swarm.remove_listener(listener);
+                }
+                Self::schedule_relay_listener(relay, now);
+            }
+
+            if relay.listener.is_some() || now < relay.next_try {
+                continue;
+            }
+
+            match swarm.listen_on(relay.address.clone().with(Protocol::P2pCircuit)) {
+                Ok(listener) => relay.listener = Some(listener),
+                Err(e) => {
+                    log::error!("internet can't listen via relay {}: {}", relay.address, e);
+                    Self::schedule_relay_listener(relay, now);
+                }
+            }
+        }
+    }
+
+    /// schedule the next listen attempt via a relay
+    fn schedule_relay_listener(relay: &mut InternetRelayListener, now: u64) {
+        relay.attempt += 1;
+        relay.next_try = now + Self::redial_delay(relay.attempt);
+    }
+
+    /// a listener of the swarm was closed
+    ///
+    /// A closed relay listener is replaced after a backoff.
+    pub fn listener_closed(listener: ListenerId) {
+        let mut listeners = INTERNETRELAYLISTENERS.get().write().unwrap();
+        let now = Timestamp::get_timestamp();
+        if let Some(relay) = listeners
+            .iter_mut()
+            .find(|relay| relay.listener == Some(listener))
+        {
+            log::warn!("listener via relay {} closed", relay.address);
+            relay.listener = None;
+            Self::schedule_relay_listener(relay, now);
+        }
+    }
+
+    /// result of a reservation at a relay node
+    fn relay_reservation_result(relay_peer_id: &PeerId, accepted: bool) {
+        let mut listeners = INTERNETRELAYLISTENERS.get().write().unwrap();
+        for relay in listeners.iter_mut() {
+            if relay.relay_peer_id.as_ref() == Some(relay_peer_id) {
+                if accepted {
+                    relay.attempt = 0;
+                } else {
+                    relay.failed = true;
+                }
+            }
+        }
+    }
+
+    /// update the status of a peer
+    fn update_status<F>(address: &String, update: F)
+    where
+        F: FnOnce(&mut InternetPeerStatus),
+    {
+        let mut peers = INTERNETPEERSTATUS.get().write().unwrap();
+        update(peers.entry(address.clone()).or_default());
+    }
+
+    /// dialing a peer failed
+    pub fn set_failed(address: &Multiaddr, error: String) {
+        log::debug!("internet peer {} failed: {}", address, error);
+        Self::update_status(&address.to_string(), |status| {
+            status.state = InternetPeerState::Failed;
+            status.last_error = error;
+            status.node_id = None;
+            status.connected_since = 0;
+        });
+    }
+
+    /// connection to a dialed peer was closed
+    pub fn set_disconnected(address: &Multiaddr, error: Option<String>) {
+        Self::update_status(&address.to_string(), |status| {
+            status.state = InternetPeerState::Disconnected;
+            if let Some(error) = error {
+                status.last_error = error;
+            }
+            status.node_id = None;
+            status.connected_since = 0;
+        });
+    }
+
+    /// count bytes sent to a neighbour
+    pub fn count_sent(node_id: &PeerId, bytes: usize) {
+        let mut peers = INTERNETPEERSTATUS.get().write().unwrap();
+        for status in peers.values_mut() {
+            if status.node_id.as_ref() == Some(node_id) {
+                status.bytes_sent += bytes as u64;
+            }
+        }
+    }
+
+    /// count bytes received from a neighbour
+    pub fn count_received(node_id: &PeerId, bytes: usize) {
+        let mut peers = INTERNETPEERSTATUS.get().write().unwrap();
+        for status in peers.values_mut() {
+            if status.node_id.as_ref() == Some(node_id) {
+                status.bytes_received += bytes as u64;
+            }
+        }
+    }
+
+    /// create the RPC status message of a configured peer
+    pub fn rpc_peer_status(address: &String, enabled: bool) -> proto::InternetNodeStatus {
+        let status = {
+            let peers = INTERNETPEERSTATUS.get().read().unwrap();
+            peers.get(address).cloned().unwrap_or_default()
+        };
+
+        let state = match (enabled, status.state) {
+            (false, _) => proto::InternetNodeState::Disabled,
+            (true, InternetPeerState::Disconnected) => proto::InternetNodeState::Disconnected,
+            (true, InternetPeerState::Connecting) => proto::InternetNodeState::Connecting,
+            (true, InternetPeerState::Connected) => proto::InternetNodeState::Connected,
+            (true, InternetPeerState::Failed) => proto::InternetNodeState::Failed,
+        };
+
+        // current round trip time of the connected node
+        let mut rtt = 0;
+        let mut node_id = Vec::new();
+        if let Some(id) = status.node_id {
+            rtt = Neighbours::get_rtt(&id, &ConnectionModule::Internet).unwrap_or(0);
+            node_id = id.to_bytes();
+        }
+
+        // redial schedule
+        let mut attempts = 0;
+        let mut next_attempt = 0;
+        if let Ok(address) = address.parse::<Multiaddr>() {
+            let reconnections = INTERNETRECONNECTIONS.get().read().unwrap();
+            if let Some(peer) = reconnections.peers.get(&address) {
+                attempts = peer.attempt;
+                next_attempt = peer.next_try;
+            }
+        }
+
+        proto::InternetNodeStatus {
+            state: state as i32,
+            last_error: status.last_error,
+            rtt,
+            connected_since: status.connected_since,
+            bytes_sent: status.bytes_sent,
+            bytes_received: status.bytes_received,
+            node_id,
+            attempts,
+            next_attempt,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use libp2p::core::transport::TransportEvent;
+
+    #[cfg(not(any(target_os = "android", target_os = "ios")))]
+    #[async_std::test]
+    async fn dial_local_websocket_listener() {
+        let mut listener = Internet::create_dns_transport().await.boxed();
+        listener
+            .listen_on("/ip4/127.0.0.1/tcp/0/ws".parse().unwrap())
+            .unwrap();
+        let listen_address = match listener.select_next_some().await {
+            TransportEvent::NewAddress { listen_addr, .. } => listen_addr,
+            _ => panic!("listener not started"),
+        };
+        assert_eq!(listen_address.iter().last(), Some(Protocol::Ws("/".into())));
+
+        let mut dialer = Internet::create_dns_transport().await.boxed();
+        let outbound = dialer.dial(listen_address).unwrap();
+        let inbound = async {
+            loop {
+                if let TransportEvent::Incoming { upgrade, .. } = listener.select_next_some().await
+                {
+                    break upgrade.await;
+                }
+            }
+        };
+
+        let (outbound, inbound) = futures::join!(outbound, inbound);
+        assert!(outbound.is_ok());
+        assert!(inbound.is_ok());
+    }
 }
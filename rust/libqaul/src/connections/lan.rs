@@ -11,36 +11,61 @@
 //! The module is configured in the configuration file:
 //!
 //! ```yaml
-//! [lan]
-//! active = true
-//! listen = "/ip4/0.0.0.0/tcp/0"
+//! lan:
+//!   active: true
+//!   listen: /ip4/0.0.0.0/tcp/0
+//!   listen_extra:
+//!     - /ip6/::/tcp/0
+//!   mdns:
+//!     active: true
+//!     query_interval: 30
+//!     ttl: 300
+//!     ipv6: false
+//!   interfaces:
+//!     allow: []
+//!     deny:
+//!       - docker0
+//!   peers:
+//!     - /ip4/192.168.1.10/tcp/9229
 //! ```
+//!
+//! When an interface selection is configured, the unspecified
+//! listen addresses are replaced by the addresses of the
+//! selected interfaces. The interfaces are checked regularly
+//! and the listeners follow their address changes.
+//!
+//! Static peers are dialed directly and redialed when they are
+//! not connected. This allows to connect in networks blocking
+//! multicast, where mDNS can't discover any nodes.
 
 use libp2p::{
-    core::upgrade,
+    core::{either::EitherTransport, multiaddr::Protocol, transport::ListenerId, upgrade},
     floodsub::{Floodsub, FloodsubEvent},
     mdns,
     mdns::{async_io::Behaviour as Mdns, Config},
     mplex,
     noise::{AuthenticKeypair, NoiseConfig, X25519Spec},
     ping,
-    swarm::{keep_alive, NetworkBehaviour, Swarm},
+    pnet::{PnetConfig, PreSharedKey},
+    swarm::{behaviour::toggle::Toggle, keep_alive, NetworkBehaviour, Swarm},
     tcp::{async_io::Transport as TcpTransport, Config as GenTcpConfig},
-    yamux,
+    yamux, Multiaddr,
 };
 use prost::Message;
-use std::time::Duration;
+use state::Storage;
+use std::{collections::BTreeMap, net::IpAddr, sync::RwLock, time::Duration};
 
 // DNS is excluded on mobile, as it is not working there
 #[cfg(not(any(target_os = "android", target_os = "ios")))]
 use libp2p::dns::DnsConfig;
 use libp2p::Transport;
 
-use crate::connections::{events, ConnectionModule};
+use crate::connections::{bandwidth::Bandwidth, events, ConnectionModule};
 use crate::node::Node;
+use crate::router::flooder::Flooder;
 use crate::services::feed::proto_net;
-use crate::services::feed::Feed;
-use crate::storage::configuration::Configuration;
+use crate::storage::configuration::{self, Configuration};
+use crate::utilities::timestamp::Timestamp;
 use qaul_info::{QaulInfo, QaulInfoEvent};
 use qaul_messaging::{QaulMessaging, QaulMessagingEvent};
 
@@ -48,7 +73,7 @@ use qaul_messaging::{QaulMessaging, QaulMessagingEvent};
 #[behaviour(out_event = "QaulLanEvent")]
 pub struct QaulLanBehaviour {
     pub floodsub: Floodsub,
-    pub mdns: Mdns,
+    pub mdns: Toggle<Mdns>,
     pub keep_alive: keep_alive::Behaviour,
     pub ping: ping::Behaviour,
     pub qaul_info: QaulInfo,
@@ -100,7 +125,11 @@ impl QaulLanBehaviour {
             }
             mdns::Event::Expired(expired_list) => {
                 for (peer, _addr) in expired_list {
-                    if !self.mdns.has_node(&peer) {
+                    let known = match self.mdns.as_ref() {
+                        Some(mdns) => mdns.has_node(&peer),
+                        None => false,
+                    };
+                    if !known {
                         log::trace!("MdnsEvent::Expired, peer {:?} from floodsub removed", peer);
                         self.floodsub.remove_node_from_partial_view(&peer);
                     }
@@ -112,9 +141,11 @@ impl QaulLanBehaviour {
     fn floodsub_event(&mut self, event: FloodsubEvent) {
         match event {
             FloodsubEvent::Message(msg) => {
+                Bandwidth::received_floodsub(ConnectionModule::Lan, msg.data.len());
+
                 // feed Message
                 if let Ok(resp) = proto_net::FeedContainer::decode(&msg.data[..]) {
-                    Feed::received(ConnectionModule::Lan, msg.source, resp);
+                    Flooder::received_feed(msg.source, resp);
                 }
             }
             _ => (),
@@ -168,6 +199,35 @@ impl From<QaulMessagingEvent> for QaulLanEvent {
     }
 }
 
+/// redial interval of static peers in milli seconds
+const STATIC_PEER_REDIAL: u64 = 10_000;
+
+/// Static LAN peer
+struct LanStaticPeer {
+    /// a connection to the peer is established
+    connected: bool,
+    /// timestamp of the last dial
+    last_try: u64,
+}
+
+/// static LAN peers by address
+static LANSTATICPEERS: Storage<RwLock<BTreeMap<Multiaddr, LanStaticPeer>>> = Storage::new();
+
+/// interval in which the listen addresses are checked
+/// for interface changes, in milli seconds
+const LISTEN_CHECK_INTERVAL: u64 = 10_000;
+
+/// Listeners of the LAN module
+struct LanListeners {
+    /// listener ids by listen address
+    listeners: BTreeMap<Multiaddr, ListenerId>,
+    /// timestamp of the last check
+    last_check: u64,
+}
+
+/// listeners of the LAN module
+static LANLISTENERS: Storage<RwLock<LanListeners>> = Storage::new();
+
 pub struct Lan {
     pub swarm: Swarm<QaulLanBehaviour>,
 }
@@ -195,6 +255,15 @@ impl Lan {
 
         log::trace!("Lan::init() transport created");
 
+        // protect the transport with the pre-shared key,
+        // when this node is part of a private network
+        let transport = match Node::get_network_key() {
+            Some(key) => EitherTransport::Left(transport.and_then(move |socket, _| {
+                PnetConfig::new(PreSharedKey::new(key)).handshake(socket)
+            })),
+            None => EitherTransport::Right(transport),
+        };
+
         let transport_upgraded = transport
             .upgrade(upgrade::Version::V1)
             .authenticate(NoiseConfig::xx(auth_keys).into_authenticated())
@@ -223,16 +292,32 @@ impl Lan {
             log::trace!("Lan::init() swarm creation started");
 
             // create MDNS behaviour
-            // TODO create MdnsConfig {ttl: Duration::from_secs(300), query_interval: Duration::from_secs(30) }
-            let mdns = Mdns::new(Config::default()).unwrap();
+            let mdns = match config.lan.mdns.active {
+                true => {
+                    let mdns_config = Config {
+                        ttl: Duration::from_secs(config.lan.mdns.ttl),
+                        query_interval: Duration::from_secs(config.lan.mdns.query_interval),
+                        enable_ipv6: config.lan.mdns.ipv6,
+                    };
+                    match Mdns::new(mdns_config) {
+                        Ok(mdns) => Some(mdns),
+                        Err(e) => {
+                            log::error!("mdns can't be started: {}", e);
+                            None
+                        }
+                    }
+                }
+                false => {
+                    log::info!("mdns discovery disabled");
+                    None
+                }
+            };
 
             log::trace!("Lan::init() swarm mdns module created");
 
-            // TODO: set shorter re-advertisement time
-            //       see here: libp2p-mdns/src/behaviour.rs
             let mut behaviour = QaulLanBehaviour {
                 floodsub: Floodsub::new(Node::get_id()),
-                mdns,
+                mdns: Toggle::from(mdns),
                 keep_alive: libp2p::swarm::keep_alive::Behaviour::default(),
                 ping: ping::Behaviour::new(ping_config),
                 qaul_info: QaulInfo::new(Node::get_id()),
@@ -250,19 +335,198 @@ impl Lan {
 
         log::trace!("Lan::init() swarm created");
 
-        // connect swarm to the listening interface in
-        // the configuration config.lan.listen
+        // connect swarm to the listening interfaces in
+        // the configuration config.lan
         let config = Configuration::get();
-        Swarm::listen_on(
-            &mut swarm,
-            config.lan.listen.parse().expect("can get a local socket"),
-        )
-        .expect("swarm can be started");
+        LANLISTENERS.set(RwLock::new(LanListeners {
+            listeners: BTreeMap::new(),
+            last_check: 0,
+        }));
+        Self::check_listen_addresses(&mut swarm);
 
         log::trace!("Lan::init() swarm connected");
 
+        // connect to the static peers
+        let mut peers = BTreeMap::new();
+        for address in &config.lan.peers {
+            match address.parse::<Multiaddr>() {
+                Ok(multiaddr) => {
+                    peers.insert(
+                        multiaddr,
+                        LanStaticPeer {
+                            connected: false,
+                            last_try: 0,
+                        },
+                    );
+                }
+                Err(e) => log::error!("lan peer address {} parse error: {}", address, e),
+            }
+        }
+        LANSTATICPEERS.set(RwLock::new(peers));
+        Self::check_static_peers(&mut swarm);
+
         let lan = Lan { swarm };
 
         lan
     }
+
+    /// create the addresses to listen on
+    ///
+    /// When an interface selection is configured, unspecified
+    /// addresses are replaced by the addresses of the selected interfaces.
+    fn listen_addresses(config: &configuration::Lan) -> Vec<Multiaddr> {
+        let mut addresses = Vec::new();
+        let selection = !config.interfaces.allow.is_empty() || !config.interfaces.deny.is_empty();
+        let interface_ips = match selection {
+            true => Self::selected_interface_ips(&config.interfaces),
+            false => Vec::new(),
+        };
+
+        for address in std::iter::once(&config.listen).chain(config.listen_extra.iter()) {
+            let multiaddr: Multiaddr = match address.parse() {
+                Ok(multiaddr) => multiaddr,
+                Err(e) => {
+                    log::error!("lan listen address {} parse error: {}", address, e);
+                    continue;
+                }
+            };
+
+            if !selection {
+                addresses.push(multiaddr);
+                continue;
+            }
+
+            let mut iter = multiaddr.iter();
+            let ipv6 = match iter.next() {
+                Some(Protocol::Ip4(ip)) if ip.is_unspecified() => false,
+                Some(Protocol::Ip6(ip)) if ip.is_unspecified() => true,
+                _ => {
+                    addresses.push(multiaddr.clone());
+                    continue;
+                }
+            };
+            let rest: Vec<Protocol> = iter.collect();
+
+            for ip in &interface_ips {
+                if ip.is_ipv6() != ipv6 {
+                    continue;
+                }
+                let mut interface_address = Multiaddr::from(*ip);
+                for protocol in &rest {
+                    interface_address.push(protocol.clone());
+                }
+                addresses.push(interface_address);
+            }
+        }
+
+        addresses
+    }
+
+    /// listen on the current listen addresses
+    ///
+    /// The listen addresses are re-evaluated in a regular interval.
+    /// Listeners of addresses that disappeared are removed,
+    /// new addresses of the selected interfaces are listened on.
+    pub fn check_listen_addresses(swarm: &mut Swarm<QaulLanBehaviour>) {
+        let mut lan_listeners = LANLISTENERS.get().write().unwrap();
+        let now = Timestamp::get_timestamp();
+        if now.saturating_sub(lan_listeners.last_check) < LISTEN_CHECK_INTERVAL {
+            return;
+        }
+        lan_listeners.last_check = now;
+
+        let config = Configuration::get();
+        let addresses = Self::listen_addresses(&config.lan);
+
+        // remove listeners of addresses that disappeared
+        lan_listeners.listeners.retain(|address, listener| {
+            if addresses.contains(address) {
+                return true;
+            }
+            log::info!("lan stops listening on {}", address);
+            swarm.remove_listener(*listener);
+            false
+        });
+
+        // listen on new addresses
+        for address in addresses {
+            if lan_listeners.listeners.contains_key(&address) {
+                continue;
+            }
+            match Swarm::listen_on(swarm, address.clone()) {
+                Ok(listener) => {
+                    lan_listeners.listeners.insert(address, listener);
+                }
+                Err(e) => log::error!("lan can't listen on {}: {}", address, e),
+            }
+        }
+    }
+
+    /// a listener of the LAN swarm was closed
+    ///
+    /// It is listened on again with the next check,
+    /// if the address still exists.
+    pub fn listener_closed(listener: ListenerId) {
+        let mut lan_listeners = LANLISTENERS.get().write().unwrap();
+        lan_listeners.listeners.retain(|_, id| *id != listener);
+    }
+
+    /// get the IP addresses of the selected interfaces
+    fn selected_interface_ips(selection: &configuration::LanInterfaces) -> Vec<IpAddr> {
+        let interfaces = match if_addrs::get_if_addrs() {
+            Ok(interfaces) => interfaces,
+            Err(e) => {
+                log::error!("network interfaces can't be read: {}", e);
+                return Vec::new();
+            }
+        };
+
+        let mut ips = Vec::new();
+        for interface in interfaces {
+            if !selection.allow.is_empty() && !selection.allow.contains(&interface.name) {
+                continue;
+            }
+            if selection.deny.contains(&interface.name) {
+                continue;
+            }
+
+            // IPv6 link local addresses can't be listened on without scope
+            let ip = interface.ip();
+            if let IpAddr::V6(ipv6) = ip {
+                if ipv6.segments()[0] & 0xffc0 == 0xfe80 {
+                    continue;
+                }
+            }
+
+            log::trace!("lan interface {} selected: {}", interface.name, ip);
+            ips.push(ip);
+        }
+
+        ips
+    }
+
+    /// dial the static peers that are not connected
+    pub fn check_static_peers(swarm: &mut Swarm<QaulLanBehaviour>) {
+        let mut peers = LANSTATICPEERS.get().write().unwrap();
+        let now = Timestamp::get_timestamp();
+
+        for (address, peer) in peers.iter_mut() {
+            if peer.connected || now - peer.last_try < STATIC_PEER_REDIAL {
+                continue;
+            }
+            peer.last_try = now;
+            match swarm.dial(address.clone()) {
+                Ok(_) => log::trace!("lan peer {} dialed", address),
+                Err(e) => log::trace!("lan peer {} dial error: {:?}", address, e),
+            }
+        }
+    }
+
+    /// set the connection state of a static peer
+    pub fn set_static_peer_connected(address: &Multiaddr, connected: bool) {
+        let mut peers = LANSTATICPEERS.get().write().unwrap();
+        if let Some(peer) = peers.get_mut(address) {
+            peer.connected = connected;
+        }
+    }
 }
@@ -5,25 +5,33 @@
 //!
 //! The modules define how and where to connect to network interfaces.
 
+pub mod bandwidth;
 pub mod ble;
 pub mod events;
 pub mod internet;
 pub mod lan;
+pub mod proxy;
+pub mod stream;
+pub mod transport;
 
 use libp2p::{
     noise::{Keypair, X25519Spec},
-    Multiaddr,
+    Multiaddr, PeerId,
 };
 use prost::Message;
 use serde::{Deserialize, Serialize};
 
 use crate::node::Node;
 use crate::rpc::Rpc;
+use crate::services::messaging::Messaging;
 use crate::storage::configuration::Configuration;
 use crate::storage::configuration::InternetPeer;
-use ble::Ble;
+use bandwidth::Bandwidth;
+use ble::{Ble, BleTransport};
 use internet::Internet;
 use lan::Lan;
+use stream::Stream;
+use transport::Transport;
 
 /// Import protobuf message definition generated by
 /// the rust module prost-build.
@@ -45,11 +53,25 @@ pub enum ConnectionModule {
     Internet,
     /// BLE module
     Ble,
+    /// Stream module, for serial lines, radio modems
+    /// and point-to-point stream links.
+    Stream,
     /// no connection module known for this
     None,
 }
 
 impl ConnectionModule {
+    /// ids of all network transports
+    ///
+    /// The neighbour and connection tables are created
+    /// for each of these modules, in this order of preference.
+    pub const TRANSPORTS: [ConnectionModule; 4] = [
+        ConnectionModule::Lan,
+        ConnectionModule::Internet,
+        ConnectionModule::Ble,
+        ConnectionModule::Stream,
+    ];
+
     /// String value of the enum field names used in the ProtoBuf definition.
     ///
     /// The values are not transformed in any way and thus are considered stable
@@ -62,6 +84,7 @@ impl ConnectionModule {
             ConnectionModule::Internet => "INTERNET",
             ConnectionModule::Ble => "BLE",
             ConnectionModule::Local => "LOCAL",
+            ConnectionModule::Stream => "STREAM",
         }
     }
 
@@ -72,15 +95,20 @@ impl ConnectionModule {
             ConnectionModule::Internet => 2,
             ConnectionModule::Ble => 3,
             ConnectionModule::Local => 4,
+            ConnectionModule::Stream => 5,
         }
     }
 }
 
 /// Collection of all connections of libqaul
-/// each collection is a libp2p swarm
+///
+/// Each connection module is a transport,
+/// the libp2p modules contain their swarm.
 pub struct Connections {
-    pub lan: Option<Lan>,
-    pub internet: Option<Internet>,
+    pub lan: Lan,
+    pub internet: Internet,
+    pub ble: BleTransport,
+    pub stream: Stream,
 }
 
 impl Connections {
@@ -91,6 +119,9 @@ impl Connections {
             .into_authentic(Node::get_keys())
             .expect("can create auth keys");
 
+        // initialize bandwidth accounting
+        Bandwidth::init();
+
         // initialize Lan module
         let lan = Lan::init(auth_keys.clone()).await;
 
@@ -100,14 +131,104 @@ impl Connections {
         // initialize BLE  module
         Ble::init();
 
+        // initialize stream module
+        let stream = Stream::init();
+
         let conn = Connections {
-            lan: Some(lan),
-            internet: Some(internet),
+            lan,
+            internet,
+            ble: BleTransport,
+            stream,
         };
 
         conn
     }
 
+    /// get all transports
+    pub fn transports(&mut self) -> Vec<&mut dyn Transport> {
+        vec![
+            &mut self.lan,
+            &mut self.internet,
+            &mut self.ble,
+            &mut self.stream,
+        ]
+    }
+
+    /// get the transport of a connection module
+    ///
+    /// Returns `None` for modules without a transport,
+    /// like `Local` and `None`.
+    pub fn transport(&mut self, module: ConnectionModule) -> Option<&mut dyn Transport> {
+        self.transports()
+            .into_iter()
+            .find(|transport| transport.module() == module)
+    }
+
+    /// send a router info message to a neighbour
+    ///
+    /// The message is counted and dropped if it exceeds
+    /// the bandwidth limit of the connection module.
+    pub fn send_info(&mut self, module: ConnectionModule, neighbour_id: PeerId, data: Vec<u8>) {
+        if let Some(transport) = self.transport(module) {
+            let service = Bandwidth::info_service(&data);
+            if Bandwidth::sending(module, &neighbour_id, service, data.len()) {
+                transport.send_info(neighbour_id, data);
+            }
+        }
+    }
+
+    /// send a messaging message to a neighbour
+    ///
+    /// The message is counted and dropped if it exceeds
+    /// the bandwidth limit of the connection module.
+    /// A dropped message is un-recorded in the messaging
+    /// service, to be retransmitted.
+    pub fn send_messaging(
+        &mut self,
+        module: ConnectionModule,
+        neighbour_id: PeerId,
+        data: Vec<u8>,
+    ) {
+        if let Some(transport) = self.transport(module) {
+            let service = Bandwidth::messaging_service(&data);
+            if Bandwidth::sending(module, &neighbour_id, service, data.len()) {
+                transport.send_messaging(neighbour_id, data);
+            } else {
+                Messaging::on_dropped(&neighbour_id, module, &data);
+            }
+        }
+    }
+
+    /// publish a feed message to the neighbours of a module
+    /// not supporting broadcast messages
+    ///
+    /// The message is counted per neighbour and not sent to
+    /// the neighbours exceeding the bandwidth limit.
+    pub fn publish_feed(
+        &mut self,
+        module: ConnectionModule,
+        neighbours: Vec<PeerId>,
+        data: Vec<u8>,
+    ) {
+        if let Some(transport) = self.transport(module) {
+            let neighbours: Vec<PeerId> = neighbours
+                .into_iter()
+                .filter(|neighbour_id| {
+                    Bandwidth::sending(
+                        module,
+                        neighbour_id,
+                        proto::BandwidthService::Feed,
+                        data.len(),
+                    )
+                })
+                .collect();
+
+            if neighbours.len() > 0 {
+                transport.publish_feed(neighbours, data);
+            }
+        }
+    }
+
     /// Process incoming RPC request messages
     pub fn rpc(data: Vec<u8>, internet_opt: Option<&mut Internet>) {
         match proto::Connections::decode(&data[..]) {
@@ -320,6 +441,16 @@ impl Connections {
                         // send response
                         Self::rpc_send_node_list(info);
                     }
+                    Some(proto::connections::Message::BandwidthRequest(request)) => {
+                        let statistics = Bandwidth::rpc_statistics(request.reset);
+
+                        // send response
+                        Self::rpc_send_message(proto::Connections {
+                            message: Some(proto::connections::Message::BandwidthStatistics(
+                                statistics,
+                            )),
+                        });
+                    }
                     _ => {}
                 }
             }
@@ -342,6 +473,7 @@ impl Connections {
                 address: peer.address.clone(),
                 name: peer.name.clone(),
                 enabled: peer.enabled,
+                status: Some(Internet::rpc_peer_status(&peer.address, peer.enabled)),
             });
         }
 
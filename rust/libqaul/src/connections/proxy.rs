@@ -0,0 +1,585 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Outbound Proxy Transport
+//!
+//! libp2p transport dialing TCP connections via a SOCKS5
+//! or an HTTP CONNECT proxy.
+//!
+//! The host names of DNS addresses are handed to the proxy,
+//! they are therefore resolved by the proxy and not locally.
+//!
+//! ```yaml
+//! internet:
+//!   proxy:
+//!     active: true
+//!     # socks5 or http
+//!     kind: socks5
+//!     address: 127.0.0.1:9050
+//!     username: ""
+//!     password: ""
+//! ```
+//!
+//! The proxy transport can't listen for incoming connections.
+//! The direct transport is wrapped in `ListenOnly`, to listen
+//! for incoming connections without ever dialing directly.
+
+use async_std::net::TcpStream;
+use futures::{future::BoxFuture, prelude::*};
+use libp2p::core::{
+    multiaddr::Protocol,
+    transport::{ListenerId, TransportError, TransportEvent},
+    Multiaddr, Transport,
+};
+use std::{
+    io,
+    net::IpAddr,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::storage::configuration::InternetProxy;
+
+/// maximal size of an HTTP proxy response header
+const HTTP_HEADER_MAX: usize = 8192;
+
+/// Proxy Protocol
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProxyKind {
+    /// SOCKS5 proxy, with optional username & password authentication
+    Socks5,
+    /// HTTP proxy supporting the CONNECT method
+    Http,
+}
+
+impl ProxyKind {
+    /// get proxy kind from it's configuration name
+    pub fn from_name(name: &str) -> Option<ProxyKind> {
+        match name.to_lowercase().as_str() {
+            "socks5" => Some(ProxyKind::Socks5),
+            "http" => Some(ProxyKind::Http),
+            _ => None,
+        }
+    }
+}
+
+/// Transport dialing via an outbound proxy
+#[derive(Clone, Debug)]
+pub struct ProxyTransport {
+    kind: ProxyKind,
+    address: String,
+    username: String,
+    password: String,
+}
+
+impl ProxyTransport {
+    /// create the proxy transport from the configuration
+    ///
+    /// Returns None if no proxy is configured.
+    /// An invalid proxy configuration panics, as connecting
+    /// directly would bypass the proxy.
+    pub fn from_config(config: &InternetProxy) -> Option<Self> {
+        if !config.active {
+            return None;
+        }
+
+        let kind = ProxyKind::from_name(&config.kind).expect("unknown internet proxy kind");
+
+        Some(ProxyTransport {
+            kind,
+            address: config.address.clone(),
+            username: config.username.clone(),
+            password: config.password.clone(),
+        })
+    }
+
+    /// get host name & port of a TCP multi address
+    fn target(addr: &Multiaddr) -> Option<(String, u16)> {
+        let mut iter = addr.iter();
+
+        let host = match iter.next()? {
+            Protocol::Ip4(ip) => ip.to_string(),
+            Protocol::Ip6(ip) => ip.to_string(),
+            Protocol::Dns(name) | Protocol::Dns4(name) | Protocol::Dns6(name) => name.to_string(),
+            _ => return None,
+        };
+        let port = match iter.next()? {
+            Protocol::Tcp(port) => port,
+            _ => return None,
+        };
+        match iter.next() {
+            None | Some(Protocol::P2p(_)) => Some((host, port)),
+            _ => None,
+        }
+    }
+
+    /// connect to the target via the proxy
+    async fn connect(self, host: String, port: u16) -> io::Result<TcpStream> {
+        let stream = TcpStream::connect(self.address.as_str()).await?;
+        stream.set_nodelay(true)?;
+
+        match self.kind {
+            ProxyKind::Socks5 => self.socks5_connect(stream, &host, port).await,
+            ProxyKind::Http => self.http_connect(stream, &host, port).await,
+        }
+    }
+
+    /// SOCKS5 handshake, RFC 1928 & RFC 1929
+    async fn socks5_connect(
+        &self,
+        mut stream: TcpStream,
+        host: &str,
+        port: u16,
+    ) -> io::Result<TcpStream> {
+        // greeting with the authentication method
+        let auth = !self.username.is_empty();
+        let method = if auth { 0x02 } else { 0x00 };
+        stream.write_all(&[0x05, 0x01, method]).await?;
+
+        let mut reply = [0u8; 2];
+        stream.read_exact(&mut reply).await?;
+        if reply[0] != 0x05 || reply[1] != method {
+            return Err(proxy_error("SOCKS5 authentication method rejected"));
+        }
+
+        // username & password authentication
+        if auth {
+            if self.username.len() > 255 || self.password.len() > 255 {
+                return Err(proxy_error("SOCKS5 username or password too long"));
+            }
+            let mut request = vec![0x01, self.username.len() as u8];
+            request.extend_from_slice(self.username.as_bytes());
+            request.push(self.password.len() as u8);
+            request.extend_from_slice(self.password.as_bytes());
+            stream.write_all(&request).await?;
+
+            stream.read_exact(&mut reply).await?;
+            if reply[1] != 0x00 {
+                return Err(proxy_error("SOCKS5 authentication failed"));
+            }
+        }
+
+        // connect request
+        let mut request = vec![0x05, 0x01, 0x00];
+        match host.parse::<IpAddr>() {
+            Ok(IpAddr::V4(ip)) => {
+                request.push(0x01);
+                request.extend_from_slice(&ip.octets());
+            }
+            Ok(IpAddr::V6(ip)) => {
+                request.push(0x04);
+                request.extend_from_slice(&ip.octets());
+            }
+            Err(_) => {
+                if host.len() > 255 {
+                    return Err(proxy_error("SOCKS5 host name too long"));
+                }
+                request.push(0x03);
+                request.push(host.len() as u8);
+                request.extend_from_slice(host.as_bytes());
+            }
+        }
+        request.extend_from_slice(&port.to_be_bytes());
+        stream.write_all(&request).await?;
+
+        let mut reply = [0u8; 4];
+        stream.read_exact(&mut reply).await?;
+        if reply[1] != 0x00 {
+            return Err(proxy_error(&format!(
+                "SOCKS5 connect failed with code {}",
+                reply[1]
+            )));
+        }
+
+        // skip the bound address & port
+        let len = match reply[3] {
+            0x01 => 4,
+            0x04 => 16,
+            0x03 => {
+                let mut len = [0u8; 1];
+                stream.read_exact(&mut len).await?;
+                len[0] as usize
+            }
+            _ => return Err(proxy_error("SOCKS5 invalid address type")),
+        };
+        let mut bound = vec![0u8; len + 2];
+        stream.read_exact(&mut bound).await?;
+
+        Ok(stream)
+    }
+
+    /// HTTP CONNECT handshake
+    async fn http_connect(
+        &self,
+        mut stream: TcpStream,
+        host: &str,
+        port: u16,
+    ) -> io::Result<TcpStream> {
+        let authority = match host.parse::<IpAddr>() {
+            Ok(IpAddr::V6(_)) => format!("[{}]:{}", host, port),
+            _ => format!("{}:{}", host, port),
+        };
+
+        let mut request = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", authority);
+        if !self.username.is_empty() {
+            let credentials = base64::encode(format!("{}:{}", self.username, self.password));
+            request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", credentials));
+        }
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes()).await?;
+
+        // read the response header byte by byte,
+        // in order not to consume any data of the tunnel
+        let mut header = Vec::new();
+        let mut byte = [0u8; 1];
+        while !header.ends_with(b"\r\n\r\n") {
+            if header.len() > HTTP_HEADER_MAX {
+                return Err(proxy_error("HTTP proxy response header too long"));
+            }
+            stream.read_exact(&mut byte).await?;
+            header.push(byte[0]);
+        }
+
+        let header = String::from_utf8_lossy(&header);
+        let status_line = header.lines().next().unwrap_or_default();
+        match status_line.split_whitespace().nth(1) {
+            Some("200") => Ok(stream),
+            _ => Err(proxy_error(&format!(
+                "HTTP proxy CONNECT failed: {}",
+                status_line
+            ))),
+        }
+    }
+}
+
+impl Transport for ProxyTransport {
+    type Output = TcpStream;
+    type Error = io::Error;
+    type ListenerUpgrade = future::Pending<Result<Self::Output, Self::Error>>;
+    type Dial = BoxFuture<'static, Result<Self::Output, Self::Error>>;
+
+    fn listen_on(&mut self, addr: Multiaddr) -> Result<ListenerId, TransportError<Self::Error>> {
+        // incoming connections can't be received via the proxy
+        Err(TransportError::MultiaddrNotSupported(addr))
+    }
+
+    fn remove_listener(&mut self, _id: ListenerId) -> bool {
+        false
+    }
+
+    fn dial(&mut self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
+        let (host, port) = match Self::target(&addr) {
+            Some(target) => target,
+            None => return Err(TransportError::MultiaddrNotSupported(addr)),
+        };
+
+        log::trace!("dial {}:{} via proxy {}", host, port, self.address);
+        let proxy = self.clone();
+        Ok(proxy.connect(host, port).boxed())
+    }
+
+    fn dial_as_listener(
+        &mut self,
+        addr: Multiaddr,
+    ) -> Result<Self::Dial, TransportError<Self::Error>> {
+        self.dial(addr)
+    }
+
+    fn poll(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<TransportEvent<Self::ListenerUpgrade, Self::Error>> {
+        Poll::Pending
+    }
+
+    fn address_translation(&self, _listen: &Multiaddr, _observed: &Multiaddr) -> Option<Multiaddr> {
+        None
+    }
+}
+
+/// Transport that only listens for incoming connections
+///
+/// When a proxy is configured, the direct transport is only
+/// used to listen. Addresses the proxy can't dial are refused,
+/// instead of being dialed directly.
+pub struct ListenOnly<T> {
+    inner: T,
+}
+
+impl<T> ListenOnly<T> {
+    /// wrap a transport to only listen with it
+    pub fn new(inner: T) -> Self {
+        ListenOnly { inner }
+    }
+}
+
+impl<T> Transport for ListenOnly<T>
+where
+    T: Transport + Unpin,
+{
+    type Output = T::Output;
+    type Error = T::Error;
+    type ListenerUpgrade = T::ListenerUpgrade;
+    type Dial = T::Dial;
+
+    fn listen_on(&mut self, addr: Multiaddr) -> Result<ListenerId, TransportError<Self::Error>> {
+        self.inner.listen_on(addr)
+    }
+
+    fn remove_listener(&mut self, id: ListenerId) -> bool {
+        self.inner.remove_listener(id)
+    }
+
+    fn dial(&mut self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
+        log::debug!("{} can't be dialed via the proxy", addr);
+        Err(TransportError::MultiaddrNotSupported(addr))
+    }
+
+    fn dial_as_listener(
+        &mut self,
+        addr: Multiaddr,
+    ) -> Result<Self::Dial, TransportError<Self::Error>> {
+        self.dial(addr)
+    }
+
+    fn poll(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<TransportEvent<Self::ListenerUpgrade, Self::Error>> {
+        Pin::new(&mut self.inner).poll(cx)
+    }
+
+    fn address_translation(&self, listen: &Multiaddr, observed: &Multiaddr) -> Option<Multiaddr> {
+        self.inner.address_translation(listen, observed)
+    }
+}
+
+/// create a proxy error
+fn proxy_error(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_std::{net::TcpListener, task};
+    use libp2p::core::transport::MemoryTransport;
+
+    fn proxy(kind: ProxyKind, address: String, username: &str, password: &str) -> ProxyTransport {
+        ProxyTransport {
+            kind,
+            address,
+            username: username.to_string(),
+            password: password.to_string(),
+        }
+    }
+
+    /// SOCKS5 connect request to example.org:4001
+    fn socks5_domain_request() -> Vec<u8> {
+        let mut request = vec![0x05, 0x01, 0x00, 0x03, 11];
+        request.extend_from_slice(b"example.org");
+        request.extend_from_slice(&4001u16.to_be_bytes());
+        request
+    }
+
+    /// send a ping through the tunnel and wait for the pong
+    async fn tunnel_ping(mut stream: TcpStream) {
+        stream.write_all(b"ping").await.unwrap();
+        let mut pong = [0u8; 4];
+        stream.read_exact(&mut pong).await.unwrap();
+        assert_eq!(&pong, b"pong");
+    }
+
+    /// mock SOCKS5 proxy
+    ///
+    /// Expects the connect request to the target and
+    /// answers a ping through the tunnel.
+    async fn mock_socks5(
+        listener: TcpListener,
+        credentials: Option<(&'static str, &'static str)>,
+        target: Vec<u8>,
+    ) {
+        let (mut stream, _) = listener.accept().await.unwrap();
+
+        let method = if credentials.is_some() { 0x02 } else { 0x00 };
+        let mut greeting = [0u8; 3];
+        stream.read_exact(&mut greeting).await.unwrap();
+        assert_eq!(greeting, [0x05, 0x01, method]);
+        stream.write_all(&[0x05, method]).await.unwrap();
+
+        if let Some((username, password)) = credentials {
+            let mut expected = vec![0x01, username.len() as u8];
+            expected.extend_from_slice(username.as_bytes());
+            expected.push(password.len() as u8);
+            expected.extend_from_slice(password.as_bytes());
+
+            let mut auth = vec![0u8; expected.len()];
+            stream.read_exact(&mut auth).await.unwrap();
+            if auth != expected {
+                stream.write_all(&[0x01, 0x01]).await.unwrap();
+                return;
+            }
+            stream.write_all(&[0x01, 0x00]).await.unwrap();
+        }
+
+        let mut request = vec![0u8; target.len()];
+        stream.read_exact(&mut request).await.unwrap();
+        assert_eq!(request, target);
+        stream
+            .write_all(&[0x05, 0x00, 0x00, 0x01, 127, 0, 0, 1, 0x1f, 0x90])
+            .await
+            .unwrap();
+
+        let mut ping = [0u8; 4];
+        stream.read_exact(&mut ping).await.unwrap();
+        assert_eq!(&ping, b"ping");
+        stream.write_all(b"pong").await.unwrap();
+    }
+
+    /// mock HTTP proxy
+    ///
+    /// Answers with the status and the first tunnel data
+    /// in the same packet, returns the request header.
+    async fn mock_http(listener: TcpListener, status: &'static str) -> String {
+        let (mut stream, _) = listener.accept().await.unwrap();
+
+        let mut header = Vec::new();
+        let mut byte = [0u8; 1];
+        while !header.ends_with(b"\r\n\r\n") {
+            stream.read_exact(&mut byte).await.unwrap();
+            header.push(byte[0]);
+        }
+        stream
+            .write_all(format!("HTTP/1.1 {}\r\n\r\npong", status).as_bytes())
+            .await
+            .unwrap();
+
+        String::from_utf8(header).unwrap()
+    }
+
+    #[async_std::test]
+    async fn socks5_dial_domain() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = task::spawn(mock_socks5(listener, None, socks5_domain_request()));
+
+        let mut transport = proxy(ProxyKind::Socks5, address, "", "");
+        let stream = transport
+            .dial("/dns4/example.org/tcp/4001".parse().unwrap())
+            .unwrap()
+            .await
+            .unwrap();
+        tunnel_ping(stream).await;
+        server.await;
+    }
+
+    #[async_std::test]
+    async fn socks5_dial_ip_with_authentication() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let target = vec![0x05, 0x01, 0x00, 0x01, 10, 0, 0, 1, 0x0f, 0xa1];
+        let server = task::spawn(mock_socks5(listener, Some(("user", "pass")), target));
+
+        let mut transport = proxy(ProxyKind::Socks5, address, "user", "pass");
+        let stream = transport
+            .dial("/ip4/10.0.0.1/tcp/4001".parse().unwrap())
+            .unwrap()
+            .await
+            .unwrap();
+        tunnel_ping(stream).await;
+        server.await;
+    }
+
+    #[async_std::test]
+    async fn socks5_authentication_failure() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = task::spawn(mock_socks5(
+            listener,
+            Some(("user", "pass")),
+            socks5_domain_request(),
+        ));
+
+        let transport = proxy(ProxyKind::Socks5, address, "user", "fail");
+        let result = transport.connect("example.org".to_string(), 4001).await;
+        assert!(result.is_err());
+        server.await;
+    }
+
+    #[async_std::test]
+    async fn http_connect() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = task::spawn(mock_http(listener, "200 Connection established"));
+
+        let transport = proxy(ProxyKind::Http, address, "user", "pass");
+        let mut stream = transport
+            .connect("example.org".to_string(), 4001)
+            .await
+            .unwrap();
+
+        // the tunnel data is not consumed by the handshake
+        let mut pong = [0u8; 4];
+        stream.read_exact(&mut pong).await.unwrap();
+        assert_eq!(&pong, b"pong");
+
+        let request = server.await;
+        assert!(request.starts_with("CONNECT example.org:4001 HTTP/1.1\r\n"));
+        assert!(request.contains("Proxy-Authorization: Basic dXNlcjpwYXNz\r\n"));
+    }
+
+    #[async_std::test]
+    async fn http_connect_ipv6() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = task::spawn(mock_http(listener, "200 OK"));
+
+        let transport = proxy(ProxyKind::Http, address, "", "");
+        assert!(transport.connect("::1".to_string(), 4001).await.is_ok());
+
+        let request = server.await;
+        assert!(request.starts_with("CONNECT [::1]:4001 HTTP/1.1\r\n"));
+        assert!(!request.contains("Proxy-Authorization"));
+    }
+
+    #[async_std::test]
+    async fn http_connect_refused() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = task::spawn(mock_http(listener, "407 Proxy Authentication Required"));
+
+        let transport = proxy(ProxyKind::Http, address, "", "");
+        assert!(transport
+            .connect("example.org".to_string(), 4001)
+            .await
+            .is_err());
+        server.await;
+    }
+
+    #[test]
+    fn target_addresses() {
+        let target = |addr: &str| ProxyTransport::target(&addr.parse().unwrap());
+
+        assert_eq!(
+            target("/ip4/10.0.0.1/tcp/4001"),
+            Some(("10.0.0.1".to_string(), 4001))
+        );
+        assert_eq!(
+            target("/dns4/example.org/tcp/443/p2p/12D3KooWSRJX1aWUUJo82DaizXCivDN3mGQ69QR1yQqFNRU8UaEw"),
+            Some(("example.org".to_string(), 443))
+        );
+        assert_eq!(target("/ip4/10.0.0.1/udp/4001/quic"), None);
+        assert_eq!(target("/ip4/10.0.0.1/tcp/4001/ws"), None);
+    }
+
+    #[test]
+    fn listen_only_refuses_dial() {
+        let mut transport = ListenOnly::new(MemoryTransport::default());
+
+        assert!(transport.listen_on("/memory/4001".parse().unwrap()).is_ok());
+        assert!(matches!(
+            transport.dial("/memory/4001".parse().unwrap()),
+            Err(TransportError::MultiaddrNotSupported(_))
+        ));
+    }
+}
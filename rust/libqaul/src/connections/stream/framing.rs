@@ -0,0 +1,219 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # KISS & SLIP Framing
+//!
+//! Frames are delimited by a frame end byte, frame end and
+//! escape bytes within the frame are escaped.
+//!
+//! KISS frames carry an additional command byte at the
+//! beginning of the frame. We only send and accept data
+//! frames on port 0, all other commands are ignored.
+
+/// frame end
+const FEND: u8 = 0xC0;
+/// frame escape
+const FESC: u8 = 0xDB;
+/// transposed frame end
+const TFEND: u8 = 0xDC;
+/// transposed frame escape
+const TFESC: u8 = 0xDD;
+
+/// KISS data frame command on port 0
+const KISS_DATA: u8 = 0x00;
+
+/// maximal size of a frame
+///
+/// Longer frames are dropped.
+const MAX_FRAME_SIZE: usize = 256 * 1024;
+
+/// Framing of a byte stream
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Framing {
+    /// KISS TNC framing
+    Kiss,
+    /// Serial Line Internet Protocol framing
+    Slip,
+}
+
+impl Framing {
+    /// get framing from it's configuration name
+    pub fn from_name(name: &str) -> Option<Framing> {
+        match name.to_lowercase().as_str() {
+            "kiss" => Some(Framing::Kiss),
+            "slip" => Some(Framing::Slip),
+            _ => None,
+        }
+    }
+
+    /// encode a frame
+    pub fn encode(&self, data: &[u8]) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(data.len() + data.len() / 16 + 3);
+
+        frame.push(FEND);
+        if *self == Framing::Kiss {
+            frame.push(KISS_DATA);
+        }
+        for byte in data {
+            match *byte {
+                FEND => frame.extend_from_slice(&[FESC, TFEND]),
+                FESC => frame.extend_from_slice(&[FESC, TFESC]),
+                _ => frame.push(*byte),
+            }
+        }
+        frame.push(FEND);
+
+        frame
+    }
+}
+
+/// Decoder collecting the frames of a byte stream
+pub struct Deframer {
+    framing: Framing,
+    frame: Vec<u8>,
+    escaped: bool,
+    overflow: bool,
+}
+
+impl Deframer {
+    /// create a new decoder
+    pub fn new(framing: Framing) -> Self {
+        Deframer {
+            framing,
+            frame: Vec::new(),
+            escaped: false,
+            overflow: false,
+        }
+    }
+
+    /// decode the received bytes
+    ///
+    /// returns all frames completed by these bytes
+    pub fn decode(&mut self, bytes: &[u8]) -> Vec<Vec<u8>> {
+        let mut frames = Vec::new();
+
+        for byte in bytes {
+            match *byte {
+                FEND => {
+                    if let Some(frame) = self.finish() {
+                        frames.push(frame);
+                    }
+                }
+                FESC => self.escaped = true,
+                _ => {
+                    let byte = match (self.escaped, *byte) {
+                        (true, TFEND) => FEND,
+                        (true, TFESC) => FESC,
+                        (_, byte) => byte,
+                    };
+                    self.escaped = false;
+
+                    if self.frame.len() < MAX_FRAME_SIZE {
+                        self.frame.push(byte);
+                    } else {
+                        self.overflow = true;
+                    }
+                }
+            }
+        }
+
+        frames
+    }
+
+    /// finish the current frame
+    fn finish(&mut self) -> Option<Vec<u8>> {
+        let mut frame = std::mem::take(&mut self.frame);
+        let overflow = self.overflow;
+        self.escaped = false;
+        self.overflow = false;
+
+        if overflow {
+            log::warn!("stream frame too long, dropped");
+            return None;
+        }
+        if frame.len() == 0 {
+            return None;
+        }
+
+        if self.framing == Framing::Kiss {
+            // only accept data frames on port 0
+            if frame[0] != KISS_DATA {
+                return None;
+            }
+            frame.remove(0);
+            if frame.len() == 0 {
+                return None;
+            }
+        }
+
+        Some(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for framing in [Framing::Kiss, Framing::Slip] {
+            let data = b"qaul stream frame".to_vec();
+            let mut deframer = Deframer::new(framing);
+
+            assert_eq!(deframer.decode(&framing.encode(&data)), vec![data]);
+        }
+    }
+
+    #[test]
+    fn round_trip_split_stream() {
+        let framing = Framing::Kiss;
+        let mut stream = framing.encode(b"first");
+        stream.extend(framing.encode(b"second"));
+        let mut deframer = Deframer::new(framing);
+
+        let (head, tail) = stream.split_at(4);
+        assert!(deframer.decode(head).is_empty());
+        assert_eq!(
+            deframer.decode(tail),
+            vec![b"first".to_vec(), b"second".to_vec()]
+        );
+    }
+
+    #[test]
+    fn escape() {
+        let framing = Framing::Slip;
+        let data = vec![1, FEND, 2, FESC, 3, TFEND, TFESC];
+        let frame = framing.encode(&data);
+
+        // the frame end byte only delimits the frame
+        assert_eq!(frame.iter().filter(|byte| **byte == FEND).count(), 2);
+        assert_eq!(
+            frame,
+            vec![FEND, 1, FESC, TFEND, 2, FESC, TFESC, 3, TFEND, TFESC, FEND]
+        );
+        assert_eq!(Deframer::new(framing).decode(&frame), vec![data]);
+    }
+
+    #[test]
+    fn overflow() {
+        let framing = Framing::Slip;
+        let mut deframer = Deframer::new(framing);
+        let mut stream = framing.encode(&vec![1; MAX_FRAME_SIZE + 1]);
+        stream.extend(framing.encode(b"next"));
+
+        // the oversized frame is dropped, the next frame is received
+        assert_eq!(deframer.decode(&stream), vec![b"next".to_vec()]);
+    }
+
+    #[test]
+    fn kiss_non_data_command() {
+        let mut deframer = Deframer::new(Framing::Kiss);
+
+        // set TX delay command
+        assert!(deframer.decode(&[FEND, 0x01, 50, FEND]).is_empty());
+        // data frame on port 1
+        assert!(deframer.decode(&[FEND, 0x10, 1, 2, FEND]).is_empty());
+        // empty data frame
+        assert!(deframer.decode(&[FEND, KISS_DATA, FEND]).is_empty());
+    }
+}
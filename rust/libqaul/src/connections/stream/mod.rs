@@ -0,0 +1,827 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Stream Connection Module
+//!
+//! **Connect to neighbour nodes via byte streams**
+//!
+//! Each configured stream interface is a point-to-point link
+//! to a single neighbour node. The following streams are supported:
+//!
+//! * `serial`: serial devices, e.g. radio modems and serial lines
+//! * `tcp`: outgoing TCP connection
+//! * `tcp_listen`: incoming TCP connection
+//! * `pipe`: named pipes and character devices
+//!
+//! The links carry the BLE network messages (`BleMessage`),
+//! framed with KISS or SLIP.
+//! The neighbours identify themselves with an identification
+//! message when the link is opened, and in a regular interval
+//! afterwards to keep the link alive.
+//! In a private network, the neighbours prove the possession
+//! of the network key, by answering each other's random
+//! challenge with an HMAC.
+//!
+//! Every link runs in it's own threads and is reopened
+//! when it fails. The received frames are processed
+//! in the event loop.
+
+pub mod framing;
+
+use crossbeam_channel::{bounded, unbounded, Receiver, RecvTimeoutError, Sender, TrySendError};
+use libp2p::PeerId;
+use prost::Message;
+use rand::Rng;
+use std::{
+    fs::OpenOptions,
+    io::{ErrorKind, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use super::bandwidth::Bandwidth;
+use super::ble::proto_net;
+use super::proto::BandwidthService;
+use super::transport::Transport;
+use super::ConnectionModule;
+use crate::node::Node;
+use crate::router::{flooder::Flooder, info::RouterInfo, neighbours::Neighbours};
+use crate::services::{feed, messaging};
+use crate::storage::configuration::{Configuration, StreamInterface};
+use crate::utilities::timestamp::Timestamp;
+use framing::{Deframer, Framing};
+
+/// interval in which we identify ourselves to the neighbour, in seconds
+const KEEPALIVE_INTERVAL: u64 = 10;
+/// the neighbour is lost, when nothing was received
+/// within this time, in seconds
+const KEEPALIVE_TIMEOUT: u64 = 35;
+/// time to wait until a failed link is reopened, in seconds
+const REOPEN_DELAY: u64 = 5;
+/// timeout of a blocking read, in milliseconds
+///
+/// The reader thread checks in this interval,
+/// whether the link was closed.
+const READ_TIMEOUT: u64 = 500;
+/// round trip time in micro seconds, until it is measured
+const DEFAULT_RTT: u32 = 100_000;
+/// maximal number of frames queued for sending on a link
+///
+/// When the queue is full, the oldest frame is dropped.
+const SEND_QUEUE_SIZE: usize = 64;
+
+type StreamReader = Box<dyn Read + Send>;
+type StreamWriter = Box<dyn Write + Send>;
+
+/// events from the link threads
+///
+/// The generation counts the openings of a link.
+/// Frames of a reader thread of a former opening are ignored.
+enum LinkEvent {
+    /// the link with this index was opened in this generation
+    Opened(usize, u64),
+    /// the link with this index was closed
+    Closed(usize),
+    /// a frame was received on the link with this index and generation
+    Frame(usize, u64, Vec<u8>),
+}
+
+/// Link to a neighbour node
+struct StreamLink {
+    /// name of the interface
+    name: String,
+    /// framing of the stream
+    framing: Framing,
+    /// sending queue of the link thread
+    sender: Sender<Vec<u8>>,
+    /// receiving end of the sending queue,
+    /// to drop the oldest frames when the queue is full
+    outgoing: Receiver<Vec<u8>>,
+    /// is the stream open
+    open: bool,
+    /// generation of the current opening of the stream
+    generation: u64,
+    /// the identified neighbour node
+    neighbour: Option<PeerId>,
+    /// round trip time in micro seconds
+    rtt: u32,
+    /// when was the last frame received
+    last_received: u64,
+    /// when was the last identification request sent
+    identification_sent_at: u64,
+    /// our random challenge for the neighbour on this link
+    challenge: Vec<u8>,
+    /// the last challenge received from the neighbour
+    peer_challenge: Vec<u8>,
+}
+
+/// Stream Connection Module
+pub struct Stream {
+    /// all links of the configured interfaces
+    links: Vec<StreamLink>,
+    /// events of the link threads
+    events: Receiver<LinkEvent>,
+}
+
+impl Stream {
+    /// Initialize the stream module
+    ///
+    /// A link thread is started for each enabled interface.
+    pub fn init() -> Stream {
+        let (events_sender, events) = unbounded();
+        let mut links = Vec::new();
+
+        let config = Configuration::get();
+        if config.stream.active {
+            for interface in &config.stream.interfaces {
+                if !interface.enabled {
+                    continue;
+                }
+                let framing;
+                match Framing::from_name(&interface.framing) {
+                    Some(f) => framing = f,
+                    None => {
+                        log::error!(
+                            "stream interface {}: unknown framing {}",
+                            interface.name,
+                            interface.framing
+                        );
+                        continue;
+                    }
+                }
+
+                let index = links.len();
+                let (sender, outgoing) = bounded(SEND_QUEUE_SIZE);
+                let worker_interface = interface.clone();
+                let worker_outgoing = outgoing.clone();
+                let worker_events = events_sender.clone();
+                let spawned = thread::Builder::new()
+                    .name(format!("stream-{}", interface.name))
+                    .spawn(move || {
+                        Self::link_worker(
+                            index,
+                            worker_interface,
+                            framing,
+                            worker_outgoing,
+                            worker_events,
+                        )
+                    });
+                if let Err(e) = spawned {
+                    log::error!("stream interface {}: {}", interface.name, e);
+                    continue;
+                }
+
+                links.push(StreamLink {
+                    name: interface.name.clone(),
+                    framing,
+                    sender,
+                    outgoing,
+                    open: false,
+                    generation: 0,
+                    neighbour: None,
+                    rtt: DEFAULT_RTT,
+                    last_received: 0,
+                    identification_sent_at: 0,
+                    challenge: Self::create_challenge(),
+                    peer_challenge: Vec::new(),
+                });
+            }
+        }
+
+        Stream { links, events }
+    }
+
+    /// open the stream of an interface
+    fn open(interface: &StreamInterface) -> Result<(StreamReader, StreamWriter), String> {
+        match interface.kind.as_str() {
+            #[cfg(not(any(target_os = "android", target_os = "ios")))]
+            "serial" => {
+                let port = serialport::new(&interface.address, interface.baud_rate)
+                    .timeout(Duration::from_millis(READ_TIMEOUT))
+                    .open()
+                    .map_err(|e| e.to_string())?;
+                let reader = port.try_clone().map_err(|e| e.to_string())?;
+                Ok((Box::new(reader), Box::new(port)))
+            }
+            #[cfg(any(target_os = "android", target_os = "ios"))]
+            "serial" => Err("serial devices are not supported on this platform".to_string()),
+            "tcp" => {
+                let stream = TcpStream::connect(&interface.address).map_err(|e| e.to_string())?;
+                let _ = stream.set_nodelay(true);
+                stream
+                    .set_read_timeout(Some(Duration::from_millis(READ_TIMEOUT)))
+                    .map_err(|e| e.to_string())?;
+                let reader = stream.try_clone().map_err(|e| e.to_string())?;
+                Ok((Box::new(reader), Box::new(stream)))
+            }
+            "tcp_listen" => {
+                let listener = TcpListener::bind(&interface.address).map_err(|e| e.to_string())?;
+                let (stream, address) = listener.accept().map_err(|e| e.to_string())?;
+                log::debug!(
+                    "stream interface {}: connection from {}",
+                    interface.name,
+                    address
+                );
+                let _ = stream.set_nodelay(true);
+                stream
+                    .set_read_timeout(Some(Duration::from_millis(READ_TIMEOUT)))
+                    .map_err(|e| e.to_string())?;
+                let reader = stream.try_clone().map_err(|e| e.to_string())?;
+                Ok((Box::new(reader), Box::new(stream)))
+            }
+            "pipe" => {
+                let file = OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .open(&interface.address)
+                    .map_err(|e| e.to_string())?;
+                let reader = file.try_clone().map_err(|e| e.to_string())?;
+                Ok((Box::new(reader), Box::new(file)))
+            }
+            kind => Err(format!("unknown stream kind {}", kind)),
+        }
+    }
+
+    /// link thread
+    ///
+    /// Opens the stream, writes the outgoing frames to it
+    /// and reopens it when it failed.
+    ///
+    /// Pipes can't time out their reads, the reader thread
+    /// of a closed pipe may therefore still be blocked, when the
+    /// pipe is reopened. Each opening gets a new generation,
+    /// to ignore the frames of such a stale reader.
+    fn link_worker(
+        index: usize,
+        interface: StreamInterface,
+        framing: Framing,
+        outgoing: Receiver<Vec<u8>>,
+        events: Sender<LinkEvent>,
+    ) {
+        let mut generation: u64 = 0;
+        loop {
+            match Self::open(&interface) {
+                Ok((reader, mut writer)) => {
+                    log::info!("stream interface {} opened", interface.name);
+                    generation += 1;
+
+                    // drop everything queued while the link was closed
+                    while let Ok(_) = outgoing.try_recv() {}
+
+                    let closed = Arc::new(AtomicBool::new(false));
+                    {
+                        let closed = closed.clone();
+                        let events = events.clone();
+                        thread::spawn(move || {
+                            Self::link_reader(index, generation, reader, framing, events, closed)
+                        });
+                    }
+                    if events.send(LinkEvent::Opened(index, generation)).is_err() {
+                        return;
+                    }
+
+                    while !closed.load(Ordering::Relaxed) {
+                        match outgoing.recv_timeout(Duration::from_secs(1)) {
+                            Ok(frame) => {
+                                if let Err(e) =
+                                    writer.write_all(&frame).and_then(|_| writer.flush())
+                                {
+                                    log::error!("stream interface {}: {}", interface.name, e);
+                                    closed.store(true, Ordering::Relaxed);
+                                }
+                            }
+                            Err(RecvTimeoutError::Timeout) => {}
+                            Err(RecvTimeoutError::Disconnected) => return,
+                        }
+                    }
+
+                    log::info!("stream interface {} closed", interface.name);
+                    if events.send(LinkEvent::Closed(index)).is_err() {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    log::debug!("stream interface {}: {}", interface.name, e);
+                }
+            }
+
+            thread::sleep(Duration::from_secs(REOPEN_DELAY));
+        }
+    }
+
+    /// reader thread of an open stream
+    ///
+    /// Decodes the frames of the stream until it fails
+    /// or the link is closed.
+    fn link_reader(
+        index: usize,
+        generation: u64,
+        mut reader: StreamReader,
+        framing: Framing,
+        events: Sender<LinkEvent>,
+        closed: Arc<AtomicBool>,
+    ) {
+        let mut deframer = Deframer::new(framing);
+        let mut buf = [0u8; 4096];
+
+        while !closed.load(Ordering::Relaxed) {
+            match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(len) => {
+                    for frame in deframer.decode(&buf[..len]) {
+                        if events
+                            .send(LinkEvent::Frame(index, generation, frame))
+                            .is_err()
+                        {
+                            break;
+                        }
+                    }
+                }
+                Err(e)
+                    if e.kind() == ErrorKind::TimedOut
+                        || e.kind() == ErrorKind::WouldBlock
+                        || e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => {
+                    log::debug!("stream read error: {}", e);
+                    break;
+                }
+            }
+        }
+
+        closed.store(true, Ordering::Relaxed);
+    }
+
+    /// process the events of the link threads
+    /// and keep the links alive
+    fn process_events(&mut self) {
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                LinkEvent::Opened(index, generation) => {
+                    if let Some(link) = self.links.get_mut(index) {
+                        link.open = true;
+                        link.generation = generation;
+                        link.last_received = Timestamp::get_timestamp();
+                        link.challenge = Self::create_challenge();
+                        link.peer_challenge = Vec::new();
+                    }
+                    self.identification_send(index, true);
+                }
+                LinkEvent::Closed(index) => {
+                    if let Some(link) = self.links.get_mut(index) {
+                        link.open = false;
+                    }
+                    self.link_lost(index);
+                }
+                LinkEvent::Frame(index, generation, data) => match self.links.get(index) {
+                    Some(link) if link.open && link.generation == generation => {
+                        self.frame_received(index, data)
+                    }
+                    _ => log::debug!("stream frame of a closed link ignored"),
+                },
+            }
+        }
+
+        // keep links alive
+        let now = Timestamp::get_timestamp();
+        for index in 0..self.links.len() {
+            let link = &self.links[index];
+            if !link.open {
+                continue;
+            }
+            if link.neighbour.is_some()
+                && now.saturating_sub(link.last_received) > KEEPALIVE_TIMEOUT * 1000
+            {
+                log::info!("stream interface {}: neighbour timed out", link.name);
+                self.link_lost(index);
+            }
+            if now.saturating_sub(self.links[index].identification_sent_at)
+                >= KEEPALIVE_INTERVAL * 1000
+            {
+                self.identification_send(index, true);
+            }
+        }
+    }
+
+    /// remove the neighbour of a link
+    fn link_lost(&mut self, index: usize) {
+        if let Some(link) = self.links.get_mut(index) {
+            if let Some(neighbour) = link.neighbour.take() {
+                self.neighbour_lost(neighbour);
+            }
+        }
+    }
+
+    /// process a frame received on a link
+    fn frame_received(&mut self, index: usize, data: Vec<u8>) {
+        let neighbour;
+        match self.links.get_mut(index) {
+            Some(link) => {
+                link.last_received = Timestamp::get_timestamp();
+                neighbour = link.neighbour;
+            }
+            None => return,
+        }
+
+        let message;
+        match proto_net::BleMessage::decode(&data[..]) {
+            Ok(ble_message) => message = ble_message.message,
+            Err(e) => {
+                log::error!("Protobuf stream message decoding error: {}", e);
+                return;
+            }
+        }
+
+        // all messages but the identification need an identified neighbour
+        let node_id;
+        match (&message, neighbour) {
+            (Some(proto_net::ble_message::Message::Identification(identification)), _) => {
+                self.identification_received(index, identification.to_owned());
+                return;
+            }
+            (_, Some(id)) => node_id = id,
+            (_, None) => {
+                self.identification_send(index, true);
+                return;
+            }
+        }
+
+        match message {
+            Some(proto_net::ble_message::Message::Info(data)) => {
+                Bandwidth::received_info(ConnectionModule::Stream, &node_id, &data);
+
+                let received = qaul_info::QaulInfoReceived {
+                    received_from: node_id,
+                    data,
+                };
+                RouterInfo::received(received);
+            }
+            Some(proto_net::ble_message::Message::Feed(data)) => {
+                Bandwidth::received(
+                    ConnectionModule::Stream,
+                    &node_id,
+                    BandwidthService::Feed,
+                    data.len(),
+                );
+
+                match feed::proto_net::FeedContainer::decode(&data[..]) {
+                    Ok(feed_container) => {
+                        Flooder::received_feed(node_id, feed_container);
+                    }
+                    Err(e) => {
+                        log::error!("stream feed message decoding error: {}", e);
+                    }
+                }
+            }
+            Some(proto_net::ble_message::Message::Messaging(data)) => {
+                Bandwidth::received_messaging(ConnectionModule::Stream, &node_id, &data);
+
+                let received = qaul_messaging::QaulMessagingReceived {
+                    received_from: node_id,
+                    data,
+                };
+                messaging::Messaging::received(received);
+            }
+            _ => {
+                log::error!("unprocessable stream message");
+            }
+        }
+    }
+
+    /// identification of the neighbour received
+    fn identification_received(&mut self, index: usize, identification: proto_net::Identification) {
+        let node_id;
+        match identification.node {
+            Some(node) if node.network_id != Node::get_network_id() => {
+                log::warn!(
+                    "stream interface {}: node of another network ignored",
+                    self.links[index].name
+                );
+                return;
+            }
+            Some(node) => match PeerId::from_bytes(&node.id) {
+                Ok(id) => node_id = id,
+                Err(e) => {
+                    log::error!("stream identification: {}", e);
+                    return;
+                }
+            },
+            None => return,
+        }
+
+        // check the neighbour's proof of the network key
+        let verified;
+        {
+            let link = &mut self.links[index];
+            link.peer_challenge = identification.challenge.clone();
+            verified =
+                Node::verify_identification_proof(&link.challenge, &node_id, &identification.proof);
+        }
+        if !verified {
+            if identification.proof.is_empty() && !identification.challenge.is_empty() {
+                // the neighbour didn't know our challenge yet,
+                // answer it's challenge and request the proof
+                self.identification_send(index, true);
+            } else {
+                log::warn!(
+                    "stream interface {}: identification of {} failed",
+                    self.links[index].name,
+                    node_id.to_base58()
+                );
+            }
+            return;
+        }
+
+        let now = Timestamp::get_timestamp();
+        let replaced;
+        let rtt;
+        {
+            let link = &mut self.links[index];
+
+            // measure the round trip time on the answer to our request
+            if !identification.request && link.identification_sent_at > 0 {
+                let micros = now.saturating_sub(link.identification_sent_at) * 1000;
+                link.rtt = u32::try_from(micros).unwrap_or(u32::MAX).max(1);
+            }
+            rtt = link.rtt;
+
+            replaced = match link.neighbour.replace(node_id) {
+                Some(old) if old != node_id => Some(old),
+                _ => None,
+            };
+        }
+
+        if let Some(old) = replaced {
+            self.neighbour_lost(old);
+        }
+        Neighbours::update_node(ConnectionModule::Stream, node_id, rtt);
+
+        if identification.request {
+            self.identification_send(index, false);
+        }
+    }
+
+    /// send our identification to the neighbour
+    ///
+    /// In a private network, the identification contains our
+    /// challenge and the answer to the neighbour's challenge.
+    fn identification_send(&mut self, index: usize, request: bool) {
+        let (challenge, proof) = match (Node::get_network_key(), self.links.get(index)) {
+            (Some(_), Some(link)) => {
+                let mut proof = Vec::new();
+                if !link.peer_challenge.is_empty() {
+                    proof =
+                        Node::create_identification_proof(&link.peer_challenge, &Node::get_id());
+                }
+                (link.challenge.clone(), proof)
+            }
+            _ => (Vec::new(), Vec::new()),
+        };
+
+        let message = proto_net::ble_message::Message::Identification(proto_net::Identification {
+            request,
+            node: Some(proto_net::NodeIdentification {
+                id: Node::get_id().to_bytes(),
+                network_id: Node::get_network_id(),
+            }),
+            challenge,
+            proof,
+        });
+
+        if request {
+            if let Some(link) = self.links.get_mut(index) {
+                link.identification_sent_at = Timestamp::get_timestamp();
+            }
+        }
+        self.link_send(index, message);
+    }
+
+    /// send a message to a neighbour
+    fn send_to_neighbour(
+        &mut self,
+        neighbour_id: PeerId,
+        message: proto_net::ble_message::Message,
+    ) {
+        let index = self
+            .links
+            .iter()
+            .position(|link| link.open && link.neighbour == Some(neighbour_id));
+
+        match index {
+            Some(index) => self.link_send(index, message),
+            None => log::warn!("stream neighbour {} not found", neighbour_id.to_base58()),
+        }
+    }
+
+    /// create a random identification challenge
+    fn create_challenge() -> Vec<u8> {
+        rand::thread_rng().gen::<[u8; 16]>().to_vec()
+    }
+
+    /// frame a message and queue it for sending on a link
+    fn link_send(&mut self, index: usize, message: proto_net::ble_message::Message) {
+        let link;
+        match self.links.get(index) {
+            Some(l) if l.open => link = l,
+            _ => return,
+        }
+
+        let proto_message = proto_net::BleMessage {
+            message: Some(message),
+        };
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // drop the oldest frames, when the link is slower than our sending
+        let mut frame = link.framing.encode(&buf);
+        loop {
+            match link.sender.try_send(frame) {
+                Ok(()) => break,
+                Err(TrySendError::Full(rejected)) => {
+                    log::debug!("stream interface {}: queue full, frame dropped", link.name);
+                    let _ = link.outgoing.try_recv();
+                    frame = rejected;
+                }
+                Err(TrySendError::Disconnected(_)) => {
+                    log::error!("stream interface {}: link thread stopped", link.name);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl Transport for Stream {
+    fn module(&self) -> ConnectionModule {
+        ConnectionModule::Stream
+    }
+
+    fn send_info(&mut self, neighbour_id: PeerId, data: Vec<u8>) {
+        self.send_to_neighbour(neighbour_id, proto_net::ble_message::Message::Info(data));
+    }
+
+    fn send_messaging(&mut self, neighbour_id: PeerId, data: Vec<u8>) {
+        self.send_to_neighbour(
+            neighbour_id,
+            proto_net::ble_message::Message::Messaging(data),
+        );
+    }
+
+    fn publish_feed(&mut self, neighbours: Vec<PeerId>, data: Vec<u8>) {
+        for neighbour_id in neighbours {
+            self.send_to_neighbour(
+                neighbour_id,
+                proto_net::ble_message::Message::Feed(data.clone()),
+            );
+        }
+    }
+
+    fn poll(&mut self) {
+        self.process_events();
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    /// create a stream interface of the given kind
+    fn interface(kind: &str, address: &str) -> StreamInterface {
+        StreamInterface {
+            name: kind.to_string(),
+            kind: kind.to_string(),
+            address: address.to_string(),
+            baud_rate: 9600,
+            framing: "kiss".to_string(),
+            enabled: true,
+        }
+    }
+
+    /// frame a message for sending
+    fn frame(framing: Framing, message: proto_net::ble_message::Message) -> Vec<u8> {
+        let proto_message = proto_net::BleMessage {
+            message: Some(message),
+        };
+        framing.encode(&proto_message.encode_to_vec())
+    }
+
+    /// receive the next frame of a reader thread and decode it
+    fn receive(
+        events: &Receiver<LinkEvent>,
+        generation: u64,
+    ) -> Option<proto_net::ble_message::Message> {
+        match events.recv_timeout(Duration::from_secs(5)) {
+            Ok(LinkEvent::Frame(0, g, data)) if g == generation => {
+                proto_net::BleMessage::decode(&data[..]).unwrap().message
+            }
+            _ => panic!("no frame received"),
+        }
+    }
+
+    #[test]
+    fn tcp_identification_and_info() {
+        let framing = Framing::Kiss;
+        let node_a = PeerId::random();
+        let node_b = PeerId::random();
+
+        // find a free port
+        let address = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .to_string();
+
+        let listen = interface("tcp_listen", &address);
+        let listening = thread::spawn(move || Stream::open(&listen));
+        let connect = interface("tcp", &address);
+        let mut connected = Stream::open(&connect);
+        for _ in 0..50 {
+            if connected.is_ok() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(100));
+            connected = Stream::open(&connect);
+        }
+        let (reader_a, mut writer_a) = connected.unwrap();
+        let (reader_b, mut writer_b) = listening.join().unwrap().unwrap();
+
+        let (events_a_sender, events_a) = unbounded();
+        let closed_a = Arc::new(AtomicBool::new(false));
+        let reader_closed = closed_a.clone();
+        let reader_a = thread::spawn(move || {
+            Stream::link_reader(0, 1, reader_a, framing, events_a_sender, reader_closed)
+        });
+        let (events_b_sender, events_b) = unbounded();
+        let closed_b = Arc::new(AtomicBool::new(false));
+        let reader_closed = closed_b.clone();
+        thread::spawn(move || {
+            Stream::link_reader(0, 1, reader_b, framing, events_b_sender, reader_closed)
+        });
+
+        let identification = |node: PeerId, request: bool| {
+            proto_net::ble_message::Message::Identification(proto_net::Identification {
+                request,
+                node: Some(proto_net::NodeIdentification {
+                    id: node.to_bytes(),
+                    network_id: Vec::new(),
+                }),
+                challenge: Stream::create_challenge(),
+                proof: Vec::new(),
+            })
+        };
+
+        // identification request of a, answered by b
+        writer_a
+            .write_all(&frame(framing, identification(node_a, true)))
+            .unwrap();
+        match receive(&events_b, 1) {
+            Some(proto_net::ble_message::Message::Identification(received)) => {
+                assert!(received.request);
+                assert_eq!(received.node.unwrap().id, node_a.to_bytes());
+                assert_eq!(received.challenge.len(), 16);
+            }
+            _ => panic!("identification request expected"),
+        }
+        writer_b
+            .write_all(&frame(framing, identification(node_b, false)))
+            .unwrap();
+        match receive(&events_a, 1) {
+            Some(proto_net::ble_message::Message::Identification(received)) => {
+                assert!(!received.request);
+                assert_eq!(received.node.unwrap().id, node_b.to_bytes());
+            }
+            _ => panic!("identification answer expected"),
+        }
+
+        // framed info message, containing the frame delimiters
+        let info: Vec<u8> = (0..=255).collect();
+        writer_a
+            .write_all(&frame(
+                framing,
+                proto_net::ble_message::Message::Info(info.clone()),
+            ))
+            .unwrap();
+        match receive(&events_b, 1) {
+            Some(proto_net::ble_message::Message::Info(data)) => assert_eq!(data, info),
+            _ => panic!("info message expected"),
+        }
+
+        // the reader thread ends on a silent link, once it is closed
+        closed_a.store(true, Ordering::Relaxed);
+        let (done_sender, done) = unbounded();
+        thread::spawn(move || {
+            let _ = reader_a.join();
+            let _ = done_sender.send(());
+        });
+        assert!(done
+            .recv_timeout(Duration::from_millis(READ_TIMEOUT * 4))
+            .is_ok());
+        closed_b.store(true, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,129 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Connection Module Transport Interface
+//!
+//! Each connection module implements the `Transport` trait.
+//! The event loop, the router and the services only talk to the
+//! connection modules via this interface and address them
+//! by their `ConnectionModule` id.
+//!
+//! To add a new transport, implement this trait for it,
+//! add it to `Connections` and list it's id in
+//! `ConnectionModule::TRANSPORTS`.
+
+use libp2p::PeerId;
+
+use super::{
+    ble::{Ble, BleTransport},
+    internet::Internet,
+    lan::Lan,
+    ConnectionModule,
+};
+use crate::node::Node;
+use crate::router::neighbours::Neighbours;
+
+/// Interface of a connection module
+pub trait Transport {
+    /// connection module id of this transport
+    fn module(&self) -> ConnectionModule;
+
+    /// send a qaul info message to a neighbour
+    ///
+    /// qaul info messages are the routing information,
+    /// and the feed & user requests and responses.
+    fn send_info(&mut self, neighbour_id: PeerId, data: Vec<u8>);
+
+    /// send a messaging message to a neighbour
+    fn send_messaging(&mut self, neighbour_id: PeerId, data: Vec<u8>);
+
+    /// publish a feed message to neighbours not supporting broadcast messages
+    fn publish_feed(&mut self, neighbours: Vec<PeerId>, data: Vec<u8>);
+
+    /// the connection to a neighbour was lost
+    fn neighbour_lost(&self, neighbour_id: PeerId) {
+        Neighbours::delete(self.module(), neighbour_id);
+    }
+
+    /// process the pending events of the transport
+    ///
+    /// This is called regularly from the event loop for
+    /// transports that are not driven by a libp2p swarm.
+    fn poll(&mut self) {}
+}
+
+impl Transport for Lan {
+    fn module(&self) -> ConnectionModule {
+        ConnectionModule::Lan
+    }
+
+    fn send_info(&mut self, neighbour_id: PeerId, data: Vec<u8>) {
+        self.swarm
+            .behaviour_mut()
+            .qaul_info
+            .send_qaul_info_message(neighbour_id, data);
+    }
+
+    fn send_messaging(&mut self, neighbour_id: PeerId, data: Vec<u8>) {
+        self.swarm
+            .behaviour_mut()
+            .qaul_messaging
+            .send_qaul_messaging_message(neighbour_id, data);
+    }
+
+    fn publish_feed(&mut self, _neighbours: Vec<PeerId>, data: Vec<u8>) {
+        self.swarm
+            .behaviour_mut()
+            .floodsub
+            .publish(Node::get_topic(), data);
+    }
+}
+
+impl Transport for Internet {
+    fn module(&self) -> ConnectionModule {
+        ConnectionModule::Internet
+    }
+
+    fn send_info(&mut self, neighbour_id: PeerId, data: Vec<u8>) {
+        Internet::count_sent(&neighbour_id, data.len());
+        self.swarm
+            .behaviour_mut()
+            .qaul_info
+            .send_qaul_info_message(neighbour_id, data);
+    }
+
+    fn send_messaging(&mut self, neighbour_id: PeerId, data: Vec<u8>) {
+        Internet::count_sent(&neighbour_id, data.len());
+        self.swarm
+            .behaviour_mut()
+            .qaul_messaging
+            .send_qaul_messaging_message(neighbour_id, data);
+    }
+
+    fn publish_feed(&mut self, _neighbours: Vec<PeerId>, data: Vec<u8>) {
+        self.swarm
+            .behaviour_mut()
+            .floodsub
+            .publish(Node::get_topic(), data);
+    }
+}
+
+impl Transport for BleTransport {
+    fn module(&self) -> ConnectionModule {
+        ConnectionModule::Ble
+    }
+
+    fn send_info(&mut self, neighbour_id: PeerId, data: Vec<u8>) {
+        Ble::send_routing_info(neighbour_id, data);
+    }
+
+    fn send_messaging(&mut self, neighbour_id: PeerId, data: Vec<u8>) {
+        Ble::send_messaging_message(neighbour_id, data);
+    }
+
+    fn publish_feed(&mut self, neighbours: Vec<PeerId>, data: Vec<u8>) {
+        for neighbour_id in neighbours {
+            Ble::send_feed_message(neighbour_id, data.clone());
+        }
+    }
+}
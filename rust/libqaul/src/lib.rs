@@ -25,7 +25,9 @@ mod services;
 pub mod storage;
 pub mod utilities;
 
-use connections::{ble::Ble, internet::Internet, ConnectionModule, Connections};
+use connections::{
+    internet::Internet, lan::Lan, transport::Transport, ConnectionModule, Connections,
+};
 use node::Node;
 use router::{
     feed_requester, flooder, info::RouterInfo, neighbours::Neighbours, user_requester, Router,
@@ -78,6 +80,9 @@ enum EventType {
     RoutingTable(bool),
     Messaging(bool),
     Retransmit(bool),
+    Retention(bool),
+    RoutingPersist(bool),
+    Transports(bool),
 }
 
 /// initialize and start libqaul with a optional custom configuration options
@@ -230,9 +235,7 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
     Router::init();
 
     // initialize Connection Modules
-    let conn = Connections::init().await;
-    let mut internet = conn.internet.unwrap();
-    let mut lan = conn.lan.unwrap();
+    let mut conn = Connections::init().await;
 
     // initialize services
     Services::init();
@@ -279,6 +282,15 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
     // manage the message retransmit
     let mut retransmit_ticker = Ticker::new(Duration::from_millis(1000));
 
+    // delete expired chat messages
+    let mut retention_ticker = Ticker::new(Duration::from_secs(60));
+
+    // persist the routing state
+    let mut routing_persist_ticker = Ticker::new(Duration::from_secs(30));
+
+    // process the events of the transports without swarm
+    let mut transports_ticker = Ticker::new(Duration::from_millis(10));
+
     // set initialized flag
     INITIALIZED.set(true);
 
@@ -286,8 +298,8 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
 
     loop {
         let evt = {
-            let lan_fut = lan.swarm.next().fuse();
-            let internet_fut = internet.swarm.next().fuse();
+            let lan_fut = conn.lan.swarm.next().fuse();
+            let internet_fut = conn.internet.swarm.next().fuse();
             let rpc_fut = rpc_ticker.next().fuse();
             let sys_fut = sys_ticker.next().fuse();
             let flooding_fut = flooding_ticker.next().fuse();
@@ -300,6 +312,9 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
             let routing_table_fut = routing_table_ticker.next().fuse();
             let messaging_fut = messaging_ticker.next().fuse();
             let retransmit_fut = retransmit_ticker.next().fuse();
+            let retention_fut = retention_ticker.next().fuse();
+            let routing_persist_fut = routing_persist_ticker.next().fuse();
+            let transports_fut = transports_ticker.next().fuse();
 
             // This Macro is shown wrong by Rust-Language-Server > 0.2.400
             // You need to downgrade to version 0.2.400 if this happens to you
@@ -318,24 +333,39 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                 routing_table_fut,
                 messaging_fut,
                 retransmit_fut,
+                retention_fut,
+                routing_persist_fut,
+                transports_fut,
             );
 
             select! {
                 lan_event = lan_fut => {
                     //log::trace!("Unhandled lan connection module event: {:?}", lan_event);
                     match lan_event.unwrap() {
-                        libp2p::swarm::SwarmEvent::ConnectionClosed{peer_id, ..} => {
+                        libp2p::swarm::SwarmEvent::ConnectionEstablished{endpoint, ..} => {
+                            if let libp2p::core::ConnectedPoint::Dialer{address, ..} = endpoint {
+                                Lan::set_static_peer_connected(&address, true);
+                            }
+                        },
+                        libp2p::swarm::SwarmEvent::ConnectionClosed{peer_id, endpoint, ..} => {
                             //remove from neighbour table, after then scheduler will auto remove this neighbour
                             log::trace!("lan connection closed: {:?}", peer_id);
-                            Neighbours::delete(ConnectionModule::Lan, peer_id);
+                            conn.lan.neighbour_lost(peer_id);
+
+                            if let libp2p::core::ConnectedPoint::Dialer{address, ..} = endpoint {
+                                Lan::set_static_peer_connected(&address, false);
+                            }
                         },
                         libp2p::swarm::SwarmEvent::BannedPeer {peer_id, ..} => {
                             //remove from neighbour table, after then scheduler will auto remove this neighbour
                             log::trace!("lan connection banned: {:?}", peer_id);
-                            Neighbours::delete(ConnectionModule::Lan, peer_id);
+                            conn.lan.neighbour_lost(peer_id);
+                        },
+                        libp2p::swarm::SwarmEvent::ListenerClosed {listener_id, ..} => {
+                            Lan::listener_closed(listener_id);
                         },
                         libp2p::swarm::SwarmEvent::Behaviour(behaviour) => {
-                            lan.swarm.behaviour_mut().process_events(behaviour);
+                            conn.lan.swarm.behaviour_mut().process_events(behaviour);
                         }
                         _ => {}
                     }
@@ -350,7 +380,8 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                             // list of outgoing connection errors to get list of addresses
                             match error {
                                 libp2p::swarm::DialError::Transport(unreachable_addrs) => {
-                                    for (addr, _) in unreachable_addrs {
+                                    for (addr, error) in unreachable_addrs {
+                                        Internet::set_failed(&addr, error.to_string());
 
                                         //check if address is active
                                         if Internet::is_active_connection(&addr){
@@ -359,7 +390,16 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
 
                                     }
                                 },
-                                _ => {}
+                                libp2p::swarm::DialError::WrongPeerId{endpoint: libp2p::core::ConnectedPoint::Dialer{address, ..}, ..} => {
+                                    Internet::set_failed(&address, "wrong peer id".to_string());
+                                    if Internet::is_active_connection(&address){
+                                        Internet::add_reconnection(address);
+                                    }
+                                },
+                                error => {
+                                    // the dialed peer is failed by the dial timeout
+                                    log::debug!("internet dial error: {}", error);
+                                }
                             }
                         }
                         libp2p::swarm::SwarmEvent::ConnectionEstablished{peer_id, endpoint, ..} =>{
@@ -367,20 +407,22 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                             match endpoint{
                                 libp2p::core::ConnectedPoint::Dialer{address, ..} =>{
                                     log::info!("connection established! peer={}, endpoint={}", peer_id.to_base58(), address.to_string());
-                                    Internet::remove_reconnection(address.clone());
+                                    Internet::set_reconnected(&address);
                                     Internet::add_connection(address.to_string(), &peer_id);
                                 }
                                 _ => {}
                             }
                         }
-                        libp2p::swarm::SwarmEvent::ConnectionClosed{peer_id, endpoint, ..} => {
+                        libp2p::swarm::SwarmEvent::ConnectionClosed{peer_id, endpoint, cause, ..} => {
                             // remove from neighbour table, after then scheduler will auto remove this neighbour
                             log::trace!("internet connection closed: {:?}", peer_id);
-                            Neighbours::delete(ConnectionModule::Internet, peer_id);
+                            conn.internet.neighbour_lost(peer_id);
 
                             // add new reconnection
                             match endpoint {
                                 libp2p::core::ConnectedPoint::Dialer{address, ..} =>{
+                                    Internet::set_disconnected(&address, cause.map(|error| format!("{:?}", error)));
+
                                     //check if address is active
                                     if Internet::is_active_connection(&address){
                                         Internet::add_reconnection(address);
@@ -392,10 +434,13 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                         libp2p::swarm::SwarmEvent::BannedPeer {peer_id, ..} => {
                             //remove from neighbour table, after then scheduler will auto remove this neighbour
                             log::trace!("internet connection banned: {:?}", peer_id);
-                            Neighbours::delete(ConnectionModule::Internet, peer_id);
+                            conn.internet.neighbour_lost(peer_id);
+                        },
+                        libp2p::swarm::SwarmEvent::ListenerClosed {listener_id, ..} => {
+                            Internet::listener_closed(listener_id);
                         },
                         libp2p::swarm::SwarmEvent::Behaviour(behaviour) => {
-                            internet.swarm.behaviour_mut().process_events(behaviour);
+                            conn.internet.swarm.behaviour_mut().process_events(behaviour);
                         }
                         _ => {}
                     }
@@ -413,6 +458,9 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                 _routing_table_event = routing_table_fut => Some(EventType::RoutingTable(true)),
                 _messaging_event = messaging_fut => Some(EventType::Messaging(true)),
                 _retransmit_event = retransmit_fut => Some(EventType::Retransmit(true)),
+                _retention_event = retention_fut => Some(EventType::Retention(true)),
+                _routing_persist_event = routing_persist_fut => Some(EventType::RoutingPersist(true)),
+                _transports_event = transports_fut => Some(EventType::Transports(true)),
             }
         };
 
@@ -423,8 +471,8 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                         // we received a message, send it to RPC crate
                         Rpc::process_received_message(
                             rpc_message,
-                            Some(&mut lan),
-                            Some(&mut internet),
+                            Some(&mut conn.lan),
+                            Some(&mut conn.internet),
                         )
                         .await;
                     }
@@ -434,8 +482,8 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                         // we received a message, send it to RPC crate
                         Sys::process_received_message(
                             sys_message,
-                            Some(&mut lan),
-                            Some(&mut internet),
+                            Some(&mut conn.lan),
+                            Some(&mut conn.internet),
                         );
                     }
                 }
@@ -446,23 +494,14 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
 
                     // loop over messages to send & flood them
                     while let Some(msg) = flooder.to_send.pop_front() {
-                        // check which swarm to send to
-                        if !matches!(msg.incoming_via, ConnectionModule::Lan) {
-                            lan.swarm
-                                .behaviour_mut()
-                                .floodsub
-                                .publish(msg.topic.clone(), msg.message.clone());
-                        }
-                        if !matches!(msg.incoming_via, ConnectionModule::Internet) {
-                            internet
-                                .swarm
-                                .behaviour_mut()
-                                .floodsub
-                                .publish(msg.topic.clone(), msg.message.clone());
-                        }
-                        if !matches!(msg.incoming_via, ConnectionModule::Ble) {
-                            Ble::send_feed_message(msg.topic, msg.message);
-                        }
+                        // send it via the module of the neighbour
+                        let connection_module = Neighbours::is_neighbour(&msg.neighbour_id);
+                        conn.send_info(connection_module, msg.neighbour_id, msg.message);
+                    }
+
+                    // publish feed messages to neighbours not supporting broadcasts
+                    while let Some(msg) = flooder.to_publish.pop_front() {
+                        conn.publish_feed(msg.module, msg.neighbours, msg.data);
                     }
                 }
                 EventType::FeedRequest(_) => {
@@ -482,23 +521,7 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                         }
                         //make dataMessaging
                         let data = RouterInfo::create_feed_request(&request.feed_ids);
-                        match connection_module {
-                            ConnectionModule::Lan => lan
-                                .swarm
-                                .behaviour_mut()
-                                .qaul_info
-                                .send_qaul_info_message(request.neighbour_id, data),
-                            ConnectionModule::Internet => internet
-                                .swarm
-                                .behaviour_mut()
-                                .qaul_info
-                                .send_qaul_info_message(request.neighbour_id, data),
-                            ConnectionModule::Ble => {
-                                Ble::send_routing_info(request.neighbour_id, data);
-                            }
-                            ConnectionModule::Local => {}
-                            ConnectionModule::None => {}
-                        }
+                        conn.send_info(connection_module, request.neighbour_id, data);
                     }
                 }
                 EventType::FeedResponse(_) => {
@@ -519,23 +542,7 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
 
                         //make data
                         let data = RouterInfo::create_feed_response(&request.feeds);
-                        match connection_module {
-                            ConnectionModule::Lan => lan
-                                .swarm
-                                .behaviour_mut()
-                                .qaul_info
-                                .send_qaul_info_message(request.neighbour_id, data),
-                            ConnectionModule::Internet => internet
-                                .swarm
-                                .behaviour_mut()
-                                .qaul_info
-                                .send_qaul_info_message(request.neighbour_id, data),
-                            ConnectionModule::Ble => {
-                                Ble::send_routing_info(request.neighbour_id, data);
-                            }
-                            ConnectionModule::Local => {}
-                            ConnectionModule::None => {}
-                        }
+                        conn.send_info(connection_module, request.neighbour_id, data);
                     }
                 }
                 EventType::UserRequest(_) => {
@@ -555,23 +562,7 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                         }
                         //make dataMessaging
                         let data = RouterInfo::create_user_request(&request.user_ids);
-                        match connection_module {
-                            ConnectionModule::Lan => lan
-                                .swarm
-                                .behaviour_mut()
-                                .qaul_info
-                                .send_qaul_info_message(request.neighbour_id, data),
-                            ConnectionModule::Internet => internet
-                                .swarm
-                                .behaviour_mut()
-                                .qaul_info
-                                .send_qaul_info_message(request.neighbour_id, data),
-                            ConnectionModule::Ble => {
-                                Ble::send_routing_info(request.neighbour_id, data);
-                            }
-                            ConnectionModule::Local => {}
-                            ConnectionModule::None => {}
-                        }
+                        conn.send_info(connection_module, request.neighbour_id, data);
                     }
                 }
                 EventType::UserResponse(_) => {
@@ -592,30 +583,14 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
 
                         //make data
                         let data = RouterInfo::create_user_response(&request.users);
-                        match connection_module {
-                            ConnectionModule::Lan => lan
-                                .swarm
-                                .behaviour_mut()
-                                .qaul_info
-                                .send_qaul_info_message(request.neighbour_id, data),
-                            ConnectionModule::Internet => internet
-                                .swarm
-                                .behaviour_mut()
-                                .qaul_info
-                                .send_qaul_info_message(request.neighbour_id, data),
-                            ConnectionModule::Ble => {
-                                Ble::send_routing_info(request.neighbour_id, data);
-                            }
-                            ConnectionModule::Local => {}
-                            ConnectionModule::None => {}
-                        }
+                        conn.send_info(connection_module, request.neighbour_id, data);
                     }
                 }
 
                 EventType::RoutingInfo(_) => {
                     // send routing info to neighbours
                     // check scheduler
-                    if let Some((neighbour_id, connection_module, data)) =
+                    if let Some((neighbour_id, connection_module, frames)) =
                         RouterInfo::check_scheduler()
                     {
                         log::trace!(
@@ -625,29 +600,20 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                             Timestamp::get_timestamp()
                         );
                         // send routing information
-                        match connection_module {
-                            ConnectionModule::Lan => lan
-                                .swarm
-                                .behaviour_mut()
-                                .qaul_info
-                                .send_qaul_info_message(neighbour_id, data),
-                            ConnectionModule::Internet => internet
-                                .swarm
-                                .behaviour_mut()
-                                .qaul_info
-                                .send_qaul_info_message(neighbour_id, data),
-                            ConnectionModule::Ble => {
-                                Ble::send_routing_info(neighbour_id, data);
-                            }
-                            ConnectionModule::Local => {}
-                            ConnectionModule::None => {}
+                        for data in frames {
+                            conn.send_info(connection_module, neighbour_id, data);
                         }
                     }
                 }
                 EventType::ReConnecting(_) => {
+                    Lan::check_static_peers(&mut conn.lan.swarm);
+                    Lan::check_listen_addresses(&mut conn.lan.swarm);
+
+                    Internet::check_dial_timeouts();
+                    Internet::check_relay_listeners(&mut conn.internet.swarm);
                     if let Some(addr) = Internet::check_reconnection() {
                         log::trace!("redial....: {:?}", addr);
-                        Internet::peer_redial(&addr, &mut internet.swarm).await;
+                        Internet::peer_redial(&addr, &mut conn.internet.swarm).await;
                         Internet::set_redialed(&addr);
                     }
                 }
@@ -657,50 +623,41 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                 }
                 EventType::Messaging(_) => {
                     // send scheduled messages
-                    if let Some((neighbour_id, connection_module, data)) =
-                        Messaging::check_scheduler()
-                    {
+                    for (neighbour_id, connection_module, data) in Messaging::check_scheduler() {
                         log::trace!(
                             "sending messaging message via {:?} to {}",
                             connection_module,
                             neighbour_id.to_base58()
                         );
-                        // send messaging message via the best module
-                        match connection_module {
-                            ConnectionModule::Lan => {
-                                lan.swarm
-                                    .behaviour_mut()
-                                    .qaul_messaging
-                                    .send_qaul_messaging_message(neighbour_id, data);
-                            }
-                            ConnectionModule::Internet => {
-                                internet
-                                    .swarm
-                                    .behaviour_mut()
-                                    .qaul_messaging
-                                    .send_qaul_messaging_message(neighbour_id, data);
-                            }
-                            ConnectionModule::Ble => {
-                                Ble::send_messaging_message(neighbour_id, data);
-                            }
-                            ConnectionModule::Local => {
-                                let message = qaul_messaging::types::QaulMessagingReceived {
-                                    received_from: neighbour_id,
-                                    data,
-                                };
-                                // forward to messaging module
-                                Messaging::received(message);
-                            }
-                            ConnectionModule::None => {
-                                // TODO: DTN behaviour
-                                // reschedule it for the moment
-                            }
+                        // messages to local users don't leave this node
+                        if connection_module == ConnectionModule::Local {
+                            let message = qaul_messaging::types::QaulMessagingReceived {
+                                received_from: neighbour_id,
+                                data,
+                            };
+                            // forward to messaging module
+                            Messaging::received(message);
+                            continue;
                         }
+
+                        // send messaging message via the best module
+                        conn.send_messaging(connection_module, neighbour_id, data);
                     }
                 }
                 EventType::Retransmit(_) => {
                     services::messaging::retransmit::MessagingRetransmit::process();
                 }
+                EventType::Retention(_) => {
+                    services::chat::ChatRetention::process();
+                }
+                EventType::RoutingPersist(_) => {
+                    Router::save_state();
+                }
+                EventType::Transports(_) => {
+                    for transport in conn.transports() {
+                        transport.poll();
+                    }
+                }
             }
         }
     }
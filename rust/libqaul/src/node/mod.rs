@@ -11,12 +11,14 @@
 pub mod user_accounts;
 
 use base64;
+use hmac::{Hmac, Mac};
 use libp2p::{
     floodsub::Topic,
     identity::{ed25519, Keypair},
     PeerId,
 };
 use prost::Message;
+use sha2::{Digest, Sha256};
 use state;
 
 use crate::connections::{internet::Internet, lan::Lan};
@@ -39,6 +41,8 @@ pub struct Node {
     id: PeerId,
     keys: Keypair,
     topic: Topic,
+    /// pre-shared key of the private mesh network
+    network_key: Option<[u8; 32]>,
 }
 
 impl Node {
@@ -67,8 +71,14 @@ impl Node {
         let keys_ed25519 = ed25519::Keypair::generate();
         let keys = Keypair::Ed25519(keys_ed25519.clone());
         let id = PeerId::from(keys.public());
-        let topic = Topic::new("pages");
-        let node = Node { id, keys, topic };
+        let network_key = Self::network_key_from_config(&Configuration::get());
+        let topic = Self::create_topic(&network_key);
+        let node = Node {
+            id,
+            keys,
+            topic,
+            network_key,
+        };
 
         // save node to configuration file
         {
@@ -92,7 +102,8 @@ impl Node {
         let mut basedecode = base64::decode(&config.node.keys).unwrap();
         let keys = Keypair::Ed25519(ed25519::Keypair::decode(&mut basedecode).unwrap());
         let id = PeerId::from(keys.public());
-        let topic = Topic::new("pages");
+        let network_key = Self::network_key_from_config(&config);
+        let topic = Self::create_topic(&network_key);
 
         // check if saved ID and the id from the keypair are equal
         if id.to_string() == config.node.id {
@@ -104,10 +115,93 @@ impl Node {
             log::error!("------------------------------------");
         }
 
-        let node = Node { id, keys, topic };
+        let node = Node {
+            id,
+            keys,
+            topic,
+            network_key,
+        };
         NODE.set(node);
     }
 
+    /// read the pre-shared network key from the configuration
+    ///
+    /// The key is 32 bytes, base64 encoded.
+    /// An empty key joins the public qaul network.
+    fn network_key_from_config(config: &Configuration) -> Option<[u8; 32]> {
+        if config.node.network_key.is_empty() {
+            return None;
+        }
+
+        // an invalid key must never fall back to the public network
+        let decoded =
+            base64::decode(&config.node.network_key).expect("network_key is not valid base64");
+        let key: [u8; 32] = decoded
+            .try_into()
+            .expect("network_key needs to be 32 bytes long");
+        Some(key)
+    }
+
+    /// create the flooding topic
+    ///
+    /// Private networks have their own topic,
+    /// derived from the network id.
+    fn create_topic(network_key: &Option<[u8; 32]>) -> Topic {
+        match network_key {
+            Some(key) => Topic::new(format!(
+                "pages/{}",
+                bs58::encode(Self::create_network_id(key)).into_string()
+            )),
+            None => Topic::new("pages"),
+        }
+    }
+
+    /// create the public network id of a private network
+    ///
+    /// The id is derived from the network key and can be
+    /// advertised without revealing the key.
+    fn create_network_id(key: &[u8; 32]) -> Vec<u8> {
+        let mut hasher = Sha256::new();
+        hasher.update(b"qaul network id");
+        hasher.update(key);
+        hasher.finalize()[..16].to_vec()
+    }
+
+    /// create the proof of the network key for an identification
+    ///
+    /// The proof is the HMAC of the neighbour's challenge and
+    /// our node id. It is empty for the public network.
+    pub fn create_identification_proof(challenge: &[u8], node_id: &PeerId) -> Vec<u8> {
+        match Self::get_network_key() {
+            Some(key) => Self::identification_mac(&key, challenge, node_id)
+                .finalize()
+                .into_bytes()
+                .to_vec(),
+            None => Vec::new(),
+        }
+    }
+
+    /// verify the proof of the network key of a neighbour
+    ///
+    /// Every neighbour is accepted in the public network.
+    pub fn verify_identification_proof(challenge: &[u8], node_id: &PeerId, proof: &[u8]) -> bool {
+        match Self::get_network_key() {
+            Some(key) => Self::identification_mac(&key, challenge, node_id)
+                .verify_slice(proof)
+                .is_ok(),
+            None => true,
+        }
+    }
+
+    /// create the HMAC of an identification challenge
+    fn identification_mac(key: &[u8; 32], challenge: &[u8], node_id: &PeerId) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+        mac.update(b"qaul identification");
+        mac.update(challenge);
+        mac.update(&node_id.to_bytes());
+        mac
+    }
+
     /// get a cloned PeerId
     pub fn get_id() -> PeerId {
         let node = NODE.get();
@@ -138,6 +232,25 @@ impl Node {
         node.topic.clone()
     }
 
+    /// get the pre-shared key of the private network
+    ///
+    /// returns None when this node is part of the public network
+    pub fn get_network_key() -> Option<[u8; 32]> {
+        let node = NODE.get();
+        node.network_key
+    }
+
+    /// get the network id
+    ///
+    /// returns an empty vector for the public network
+    pub fn get_network_id() -> Vec<u8> {
+        let node = NODE.get();
+        match &node.network_key {
+            Some(key) => Self::create_network_id(key),
+            None => Vec::new(),
+        }
+    }
+
     /// Process incoming RPC request messages for node module
     pub fn rpc(data: Vec<u8>, lan: Option<&mut Lan>, internet: Option<&mut Internet>) {
         match proto::Node::decode(&data[..]) {
@@ -205,3 +318,47 @@ impl Node {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identification_proof_verifies() {
+        let key = [7; 32];
+        let node_id = PeerId::random();
+        let proof = Node::identification_mac(&key, b"challenge", &node_id)
+            .finalize()
+            .into_bytes()
+            .to_vec();
+
+        assert!(Node::identification_mac(&key, b"challenge", &node_id)
+            .verify_slice(&proof)
+            .is_ok());
+    }
+
+    #[test]
+    fn identification_proof_is_bound_to_key_challenge_and_node() {
+        let key = [7; 32];
+        let node_id = PeerId::random();
+        let proof = Node::identification_mac(&key, b"challenge", &node_id)
+            .finalize()
+            .into_bytes()
+            .to_vec();
+
+        assert!(Node::identification_mac(&[8; 32], b"challenge", &node_id)
+            .verify_slice(&proof)
+            .is_err());
+        assert!(Node::identification_mac(&key, b"other challenge", &node_id)
+            .verify_slice(&proof)
+            .is_err());
+        assert!(
+            Node::identification_mac(&key, b"challenge", &PeerId::random())
+                .verify_slice(&proof)
+                .is_err()
+        );
+        assert!(Node::identification_mac(&key, b"challenge", &node_id)
+            .verify_slice(&[])
+            .is_err());
+    }
+}
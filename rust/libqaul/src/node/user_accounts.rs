@@ -103,6 +103,7 @@ impl UserAccounts {
                 id: id.to_string(),
                 keys: keys_config,
                 storage: configuration::StorageOptions::default(),
+                chat: configuration::ChatOptions::default(),
             });
         }
         Configuration::save();
@@ -136,6 +137,12 @@ impl UserAccounts {
         account_result
     }
 
+    /// get the IDs of all user accounts of this node
+    pub fn get_account_ids() -> Vec<PeerId> {
+        let accounts = USERACCOUNTS.get().read().unwrap();
+        accounts.users.iter().map(|user| user.id).collect()
+    }
+
     /// Return the number of registered user accounts on this node.
     #[allow(dead_code)]
     pub fn len() -> usize {
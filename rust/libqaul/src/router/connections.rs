@@ -10,10 +10,15 @@
 //!   all currently reachable users via this module.
 //! * Each user has an entry for each node over which it can be reached.
 //! * Out of this information the global table is constructed,
-//!   containing only the best entry.
+//!   containing the best entries per module, ranked by link quality.
+//! * The tables are persisted regularly and reloaded on startup,
+//!   so that a restarted node can route to recently seen users
+//!   as soon as it's neighbours reconnect.
 
 use libp2p::PeerId;
 use prost::Message;
+use serde::{Deserialize, Serialize};
+use sled_extensions::{bincode::Tree, DbExt};
 use state::Storage;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
@@ -28,14 +33,61 @@ use crate::router::{
     table::{RoutingConnectionEntry, RoutingTable, RoutingUserEntry},
 };
 use crate::rpc::Rpc;
+use crate::storage::database::DataBase;
 use crate::utilities::qaul_id::QaulId;
 use crate::utilities::timestamp::Timestamp;
 
 /// Mutable module state
 /// Tables with all stats for each connection module
 static LOCAL: Storage<RwLock<RoutingTable>> = Storage::new();
-static INTERNET: Storage<RwLock<ConnectionTable>> = Storage::new();
-static LAN: Storage<RwLock<ConnectionTable>> = Storage::new();
+/// connection module => connection table of the module
+static TABLES: Storage<BTreeMap<ConnectionModule, RwLock<ConnectionTable>>> = Storage::new();
+
+/// Data base table of the persisted connection tables
+///
+/// connection module name => PersistedConnectionTable
+static PERSISTED: Storage<Tree<PersistedConnectionTable>> = Storage::new();
+
+/// Persisted connection table of a connection module
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PersistedConnectionTable {
+    /// time when the table was saved
+    pub saved_at: u64,
+    /// all users of the table
+    pub users: Vec<PersistedUserEntry>,
+}
+
+/// Persisted user entry of a connection table
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PersistedUserEntry {
+    /// user q8id
+    pub id: Vec<u8>,
+    /// propagation id
+    pub pgid: u32,
+    /// when was the propagation id last updated
+    pub pgid_update: u64,
+    /// hop count of the last propagation id update
+    pub pgid_update_hc: u8,
+    /// online time
+    pub online_time: u64,
+    /// connection entries
+    pub connections: Vec<PersistedNeighbourEntry>,
+}
+
+/// Persisted connection entry of a user
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PersistedNeighbourEntry {
+    /// node id of the neighbour
+    pub id: Vec<u8>,
+    /// round trip time
+    pub rtt: u32,
+    /// hop count
+    pub hc: u8,
+    /// expected transmission count
+    pub etx: u32,
+    /// link quality
+    pub lq: u32,
+}
 
 /// Connection entry for UserEntry
 struct NeighbourEntry {
@@ -45,6 +97,8 @@ struct NeighbourEntry {
     rtt: u32,
     /// hop count
     hc: u8,
+    /// expected transmission count of the route
+    etx: u32,
     /// link quality
     lq: u32,
     /// time when the node was last updated
@@ -82,15 +136,16 @@ impl ConnectionTable {
     /// and saves it to state.
     pub fn init() {
         {
-            let internet = ConnectionTable {
-                table: HashMap::new(),
-            };
-            INTERNET.set(RwLock::new(internet));
-
-            let lan = ConnectionTable {
-                table: HashMap::new(),
-            };
-            LAN.set(RwLock::new(lan));
+            let mut tables = BTreeMap::new();
+            for module in ConnectionModule::TRANSPORTS {
+                tables.insert(
+                    module,
+                    RwLock::new(ConnectionTable {
+                        table: HashMap::new(),
+                    }),
+                );
+            }
+            TABLES.set(tables);
 
             let local = RoutingTable {
                 table: HashMap::new(),
@@ -104,6 +159,140 @@ impl ConnectionTable {
                 Self::add_local_user(user.id);
             }
         }
+
+        // open persisted tables
+        let db = DataBase::get_node_db();
+        let tree: Tree<PersistedConnectionTable> =
+            db.open_bincode_tree("routing_connections").unwrap();
+        PERSISTED.set(tree);
+
+        // reload the last known connection tables
+        for module in ConnectionModule::TRANSPORTS {
+            Self::load(module);
+        }
+        Self::create_routing_table();
+    }
+
+    /// get the connection table of a connection module
+    ///
+    /// Returns `None` for modules without a connection table.
+    fn table(module: &ConnectionModule) -> Option<&'static RwLock<ConnectionTable>> {
+        TABLES.get().get(module)
+    }
+
+    /// persist the connection tables to the data base
+    pub fn save() {
+        let tree = PERSISTED.get();
+
+        for (module, table) in TABLES.get() {
+            let connection_table = table.read().unwrap();
+
+            let mut persisted = PersistedConnectionTable {
+                saved_at: Timestamp::get_timestamp(),
+                users: Vec::new(),
+            };
+            for (user_id, user) in connection_table.table.iter() {
+                persisted.users.push(PersistedUserEntry {
+                    id: user_id.to_owned(),
+                    pgid: user.pgid,
+                    pgid_update: user.pgid_update,
+                    pgid_update_hc: user.pgid_update_hc,
+                    online_time: user.online_time,
+                    connections: user
+                        .connections
+                        .values()
+                        .map(|connection| PersistedNeighbourEntry {
+                            id: connection.id.to_bytes(),
+                            rtt: connection.rtt,
+                            hc: connection.hc,
+                            etx: connection.etx,
+                            lq: connection.lq,
+                        })
+                        .collect(),
+                });
+            }
+
+            if let Err(e) = tree.insert(module.as_str_name().as_bytes(), persisted) {
+                log::error!("Error saving connection table: {}", e);
+            }
+        }
+
+        if let Err(e) = tree.flush() {
+            log::error!("Error when flushing data base to disk: {}", e);
+        }
+    }
+
+    /// reload the persisted connection table of a module
+    ///
+    /// Users whose propagation id has not been updated within
+    /// `maintain_period_limit` are not reloaded.
+    /// The propagation timestamps keep their age, the
+    /// connections are valid until the next expiry period,
+    /// unless they are refreshed by the neighbours.
+    fn load(module: ConnectionModule) {
+        let persisted;
+        match PERSISTED.get().get(module.as_str_name().as_bytes()) {
+            Ok(Some(table)) => persisted = table,
+            Ok(None) => return,
+            Err(e) => {
+                log::error!("Error loading connection table: {}", e);
+                return;
+            }
+        }
+
+        let config = super::Router::get_configuration();
+        let now = Timestamp::get_timestamp();
+
+        let mut connection_table;
+        match Self::table(&module) {
+            Some(table) => connection_table = table.write().unwrap(),
+            None => return,
+        }
+
+        for user in persisted.users {
+            // check if user is outdated
+            if now.saturating_sub(user.pgid_update) >= config.maintain_period_limit * 1000 {
+                continue;
+            }
+
+            let mut connections = BTreeMap::new();
+            for connection in user.connections {
+                if let Ok(id) = PeerId::from_bytes(&connection.id) {
+                    connections.insert(
+                        id,
+                        NeighbourEntry {
+                            id,
+                            rtt: connection.rtt,
+                            hc: connection.hc,
+                            etx: connection.etx,
+                            lq: connection.lq,
+                            last_update: now,
+                        },
+                    );
+                }
+            }
+            if connections.len() == 0 {
+                continue;
+            }
+
+            connection_table.table.insert(
+                user.id.clone(),
+                UserEntry {
+                    id: user.id,
+                    pgid: user.pgid,
+                    pgid_update: user.pgid_update,
+                    pgid_update_hc: user.pgid_update_hc,
+                    online_time: user.online_time,
+                    connections,
+                },
+            );
+        }
+
+        log::debug!(
+            "reloaded {} users of {} connection table",
+            connection_table.table.len(),
+            module.as_str_name()
+        );
     }
 
     /// add a new local user to state
@@ -122,6 +311,7 @@ impl ConnectionTable {
             node: node_id,
             rtt: 0,
             hc: 0,
+            etx: 0,
             lq: 0,
             last_update: now_ts,
         });
@@ -152,14 +342,12 @@ impl ConnectionTable {
         //     log::trace!("receive_routing_info user={}, hc={}, propg_id={}", userid, inf.hc[0], inf.pgid);
         // }
 
-        // try Lan module
-        if let Some(rtt) = Neighbours::get_rtt(&neighbour_id, &ConnectionModule::Lan) {
-            Self::fill_received_routing_info(ConnectionModule::Lan, neighbour_id, rtt, info);
-        }
-
-        // try Internet module
-        if let Some(rtt) = Neighbours::get_rtt(&neighbour_id, &ConnectionModule::Internet) {
-            Self::fill_received_routing_info(ConnectionModule::Internet, neighbour_id, rtt, info);
+        // enter it for all modules we are connected via
+        for module in ConnectionModule::TRANSPORTS {
+            if let Some(rtt) = Neighbours::get_rtt(&neighbour_id, &module) {
+                let etx = Neighbours::get_etx(&neighbour_id, &module).unwrap_or(100);
+                Self::fill_received_routing_info(module, neighbour_id, rtt, etx, info);
+            }
         }
     }
 
@@ -168,6 +356,7 @@ impl ConnectionTable {
         conn: ConnectionModule,
         neighbour_id: PeerId,
         rtt: u32,
+        etx: u32,
         info: &Vec<router_net_proto::RoutingInfoEntry>,
     ) {
         log::trace!("fill_received_routing_info {}", info.len());
@@ -182,12 +371,22 @@ impl ConnectionTable {
                 return;
             }
 
+            // add the ETX of the link to the ETX of the route,
+            // routes from nodes that don't send the ETX count as perfect
+            let route_etx = entry.etx.max(entry.hc[0] as u32 * 100).saturating_add(etx);
+
             // fill structure
             let neighbour = NeighbourEntry {
                 id: neighbour_id,
-                rtt: entry.rtt + rtt,
+                rtt: entry.rtt.saturating_add(rtt),
                 hc,
-                lq: Self::calculate_linkquality(entry.rtt + rtt, hc),
+                etx: route_etx,
+                lq: Self::calculate_linkquality(
+                    entry.rtt.saturating_add(rtt),
+                    hc,
+                    route_etx,
+                    &conn,
+                ),
                 last_update: Timestamp::get_timestamp(),
             };
 
@@ -200,21 +399,40 @@ impl ConnectionTable {
     ///
     /// returns the calculated link quality for a connection.
     ///
-    /// The link quality is currently calculated using the
-    /// round trip time (rtt) and adding a penalty for each hop
-    /// according hop count (hc).
+    /// The link quality is calculated using the
+    /// round trip time (rtt), adding a penalty for each hop
+    /// according hop count (hc) and a penalty for each expected
+    /// retransmission on the route.
+    /// The ETX of the route is the addition of the ETX of all hops
+    /// in hundredths, a route of perfect links has an ETX of
+    /// 100 per hop.
+    /// The result is weighted according to the connection module.
     ///
     /// The smaller the value is better is the link quality.
-    pub fn calculate_linkquality(rtt: u32, hc: u8) -> u32 {
+    pub fn calculate_linkquality(rtt: u32, hc: u8, etx: u32, module: &ConnectionModule) -> u32 {
         // get the router configuration
         let config = super::Router::get_configuration();
 
         // calculate link quality
-        // `hop_count_penalty` is seconds unit, thus it must be converted micro seconds
-        let lq = rtt + (hc as u32 * (config.hop_count_penalty as u32) * 1000_000);
+        // `hop_count_penalty` and `etx_penalty` are seconds unit,
+        // thus they must be converted micro seconds
+        let hop_penalty = hc as u64 * config.hop_count_penalty * 1000_000;
+        let etx_penalty =
+            etx.saturating_sub(hc as u32 * 100) as u64 * config.etx_penalty * 1000_000 / 100;
+        let lq = rtt as u64 + hop_penalty + etx_penalty;
+
+        // weight link quality per connection module
+        let weight = match module {
+            ConnectionModule::Lan => config.lq_weight_lan,
+            ConnectionModule::Internet => config.lq_weight_internet,
+            ConnectionModule::Ble => config.lq_weight_ble,
+            ConnectionModule::Stream => config.lq_weight_stream,
+            _ => 100,
+        };
+        let lq = lq * weight as u64 / 100;
 
         // return link quality
-        lq
+        u32::try_from(lq).unwrap_or(u32::MAX)
     }
 
     /// add connection to local state
@@ -226,12 +444,9 @@ impl ConnectionTable {
     ) {
         // get access to the connection table
         let mut connection_table;
-        match module {
-            ConnectionModule::Internet => connection_table = INTERNET.get().write().unwrap(),
-            ConnectionModule::Lan => connection_table = LAN.get().write().unwrap(),
-            ConnectionModule::Ble => return,
-            ConnectionModule::Local => return,
-            ConnectionModule::None => return,
+        match Self::table(&module) {
+            Some(table) => connection_table = table.write().unwrap(),
+            None => return,
         }
 
         let now_ts = Timestamp::get_timestamp();
@@ -316,11 +531,10 @@ impl ConnectionTable {
             table = Self::local_routes_to_intermediary_table(table);
         }
 
-        // calculate from lan module
-        table = Self::calculate_intermediary_table(table, ConnectionModule::Lan);
-
-        // calculate from internet module
-        table = Self::calculate_intermediary_table(table, ConnectionModule::Internet);
+        // calculate from all connection modules
+        for module in ConnectionModule::TRANSPORTS {
+            table = Self::calculate_intermediary_table(table, module);
+        }
 
         // set table as new active routing table
         RoutingTable::set(table);
@@ -349,37 +563,38 @@ impl ConnectionTable {
 
         // get connections table
         let mut connection_table;
-        match conn.clone() {
-            ConnectionModule::Internet => connection_table = INTERNET.get().write().unwrap(),
-            ConnectionModule::Lan => connection_table = LAN.get().write().unwrap(),
-            ConnectionModule::Ble => return table,
-            ConnectionModule::Local => return table,
-            ConnectionModule::None => return table,
+        match Self::table(&conn) {
+            Some(connections) => connection_table = connections.write().unwrap(),
+            None => return table,
         }
 
         // iterate over connection table
         for (user_id, user) in connection_table.table.iter_mut() {
-            let (b_expired_pgid, connection_entry) = Self::find_best_connection(user);
+            let (b_expired_pgid, connection_entries) = Self::find_best_connections(user);
             if b_expired_pgid == false {
-                if let Some(connection) = connection_entry {
-                    // fill entry into routing table
-                    let routing_connection_entry = RoutingConnectionEntry {
-                        module: conn.clone(),
-                        node: connection.id,
-                        rtt: connection.rtt,
-                        hc: connection.hc,
-                        lq: connection.lq,
-                        last_update: connection.last_update,
-                    };
+                if connection_entries.len() > 0 {
+                    // fill entries into routing table
+                    let routing_connection_entries: Vec<RoutingConnectionEntry> =
+                        connection_entries
+                            .iter()
+                            .map(|connection| RoutingConnectionEntry {
+                                module: conn.clone(),
+                                node: connection.id,
+                                rtt: connection.rtt,
+                                hc: connection.hc,
+                                etx: connection.etx,
+                                lq: connection.lq,
+                                last_update: connection.last_update,
+                            })
+                            .collect();
 
                     // check if user entry already exists hashmap
                     if let Some(routing_user_entry) = table.table.get_mut(&user.id) {
                         routing_user_entry
                             .connections
-                            .push(routing_connection_entry);
+                            .extend(routing_connection_entries);
                     } else {
-                        let mut connections = Vec::new();
-                        connections.push(routing_connection_entry);
+                        let connections = routing_connection_entries;
 
                         let routing_user_entry = RoutingUserEntry {
                             id: user_id.to_owned(),
@@ -417,25 +632,24 @@ impl ConnectionTable {
         table
     }
 
-    /// find best entry
+    /// find the best entries, ranked by link quality,
     /// and remove all old entries
-    fn find_best_connection(user: &mut UserEntry) -> (bool, Option<NeighbourEntry>) {
+    ///
+    /// At most `max_routes_per_module` entries are returned.
+    fn find_best_connections(user: &mut UserEntry) -> (bool, Vec<NeighbourEntry>) {
         // initialize helper variables
         let mut expired_connections: Vec<PeerId> = Vec::new();
-        let mut return_entry = None;
-        let mut lq = u32::MAX;
+        let mut return_entries: Vec<NeighbourEntry> = Vec::new();
 
         //remove user after 5min from last pgid updated
         //config.maintain_period_limit is seconds unit, need to convert into mili seconds
         let config = super::Router::get_configuration();
         if Timestamp::get_timestamp() - user.pgid_update >= (config.maintain_period_limit * 1000) {
-            return (true, None);
+            return (true, Vec::new());
         }
 
         // create return value
         {
-            let mut entry_found = None;
-
             // loop through all connections
             for (key, value) in &user.connections {
                 let mut expired = true;
@@ -450,10 +664,14 @@ impl ConnectionTable {
                 {
                     expired = false;
 
-                    if value.lq < lq {
-                        lq = value.lq;
-                        entry_found = Some(value);
-                    }
+                    return_entries.push(NeighbourEntry {
+                        id: value.id.clone(),
+                        rtt: value.rtt,
+                        hc: value.hc,
+                        etx: value.etx,
+                        lq: value.lq,
+                        last_update: value.last_update.clone(),
+                    });
                 }
 
                 // put connection for removal if expired
@@ -467,15 +685,9 @@ impl ConnectionTable {
                 }
             }
 
-            if let Some(entry) = entry_found {
-                return_entry = Some(NeighbourEntry {
-                    id: entry.id.clone(),
-                    rtt: entry.rtt,
-                    hc: entry.hc,
-                    lq: entry.lq,
-                    last_update: entry.last_update.clone(),
-                })
-            }
+            // rank entries by link quality
+            return_entries.sort_by_key(|entry| entry.lq);
+            return_entries.truncate(config.max_routes_per_module.max(1) as usize);
         }
 
         // remove expired connections
@@ -483,7 +695,7 @@ impl ConnectionTable {
             user.connections.remove(&node_id);
         }
 
-        (false, return_entry)
+        (false, return_entries)
     }
 
     /// send protobuf RPC connections list
@@ -494,6 +706,7 @@ impl ConnectionTable {
             internet: Self::rpc_create_connection_module_list(ConnectionModule::Internet),
             ble: Self::rpc_create_connection_module_list(ConnectionModule::Ble),
             local: Self::rpc_create_connection_module_list(ConnectionModule::Local),
+            stream: Self::rpc_create_connection_module_list(ConnectionModule::Stream),
         };
 
         // create rpc connections list protobuf message
@@ -525,12 +738,9 @@ impl ConnectionTable {
 
         // request connection table from state
         let connection_table;
-        match conn {
-            ConnectionModule::Lan => connection_table = LAN.get().read().unwrap(),
-            ConnectionModule::Internet => connection_table = INTERNET.get().read().unwrap(),
-            ConnectionModule::Ble => return connections_list,
-            ConnectionModule::Local => return connections_list,
-            ConnectionModule::None => return connections_list,
+        match Self::table(&conn) {
+            Some(table) => connection_table = table.read().unwrap(),
+            None => return connections_list,
         }
 
         // loop through all table entries per user
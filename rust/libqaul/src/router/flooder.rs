@@ -1,51 +1,455 @@
 // Copyright (c) 2021 Open Community Project Association https://ocpa.ch
 // This software is published under the AGPLv3 license.
 
-//! The flooder floods messages via floodsub/Gossipsub to the network.
-//! It contains a ring buffer of messages to process.
-//! 
+//! # Broadcast Flooder
+//!
+//! The flooder broadcasts messages to the entire network.
 //! It sends the messages of the feed service.
-//! 
-//! Most messages are repostings from incoming floods on some interface,
-//! that need to be flooded via the other interfaces.
+//!
+//! The flooder is shared by all connection modules:
+//! A broadcast message is sent to each neighbour individually,
+//! via the router info channel of the connection module the
+//! neighbour is connected with. A neighbour connected via several
+//! modules receives the message only once.
+//!
+//! * Every message is only forwarded once, the ids of the
+//!   seen messages are kept in a cache.
+//! * Every message carries a hop limit (ttl), which is
+//!   decreased on each hop.
+//! * Messages are only marked as seen, after the service
+//!   validated them.
+//! * The number of messages forwarded per neighbour is
+//!   rate limited.
+//! * Messages are never sent back to the neighbour they
+//!   were received from.
+//!
+//! When lazy forwarding is configured, the flooder only
+//! announces the ids of new messages to it's neighbours.
+//! The neighbours request the messages they haven't seen yet.
+//!
+//! Feed messages received via floodsub or BLE from nodes
+//! not supporting the broadcast messages are processed
+//! by the flooder as well.
+//! The neighbours announce their protocol version in the
+//! router info messages. Until a neighbour announced a version
+//! supporting the broadcast messages, feed messages are sent to
+//! it via floodsub or as BLE feed messages.
 
-use libp2p::floodsub::Topic;
+use libp2p::PeerId;
+use prost::Message;
 use state::Storage;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::sync::RwLock;
-use std::collections::VecDeque;
+
+use super::{
+    info::{RouterInfo, BROADCAST_VERSION},
+    neighbours::Neighbours,
+    router_net_proto, Router,
+};
 use crate::connections::ConnectionModule;
+use crate::node::Node;
+use crate::services::feed::{proto_net as feed_net, Feed};
+use crate::utilities::timestamp::Timestamp;
 
-// mutable state of feed messages
+/// mutable state of the flooder
 pub static FLOODER: Storage<RwLock<Flooder>> = Storage::new();
 
+/// number of message ids kept in the seen cache
+const SEEN_CACHE_SIZE: usize = 10_000;
+/// number of messages kept for lazy forwarding
+const LAZY_STORE_SIZE: usize = 500;
+/// rate limiting window in milli seconds
+const RATE_LIMIT_WINDOW: u64 = 60_000;
 
+/// Message to send to a neighbour
 pub struct FloodMessageContainer {
+    /// neighbour to send the message to
+    pub neighbour_id: PeerId,
+    /// encoded router info container
     pub message: Vec<u8>,
-    pub topic: Topic,
-    pub incoming_via: ConnectionModule,
+}
+
+/// Feed message to publish to the neighbours not supporting broadcast messages
+pub struct FloodFeedContainer {
+    /// connection module to publish the message with
+    pub module: ConnectionModule,
+    /// neighbours of the module not supporting broadcast messages
+    pub neighbours: Vec<PeerId>,
+    /// encoded feed container
+    pub data: Vec<u8>,
+}
+
+/// Forwarded messages of a neighbour within the rate limiting window
+struct NeighbourRate {
+    window_start: u64,
+    count: u32,
 }
 
 pub struct Flooder {
+    /// messages to send to the neighbours
     pub to_send: VecDeque<FloodMessageContainer>,
+    /// feed messages to publish to neighbours not supporting broadcast messages
+    pub to_publish: VecDeque<FloodFeedContainer>,
+    /// ids of the seen messages
+    seen: HashMap<Vec<u8>, u64>,
+    /// order of the seen message ids, for eviction
+    seen_order: VecDeque<Vec<u8>>,
+    /// messages announced for lazy forwarding
+    lazy_store: HashMap<Vec<u8>, router_net_proto::BroadcastMessage>,
+    /// order of the stored messages, for eviction
+    lazy_order: VecDeque<Vec<u8>>,
+    /// forwarding rate per neighbour
+    rates: HashMap<PeerId, NeighbourRate>,
 }
 
 impl Flooder {
     /// Initialize the flooder and create the ring buffer.
     pub fn init() {
-        let flooder = Flooder { to_send: VecDeque::new() };
-        FLOODER.set(RwLock::new(flooder));
+        FLOODER.set(RwLock::new(Self::new()));
+    }
+
+    /// create an empty flooder state
+    fn new() -> Flooder {
+        Flooder {
+            to_send: VecDeque::new(),
+            to_publish: VecDeque::new(),
+            seen: HashMap::new(),
+            seen_order: VecDeque::new(),
+            lazy_store: HashMap::new(),
+            lazy_order: VecDeque::new(),
+            rates: HashMap::new(),
+        }
+    }
+
+    /// broadcast a new message of this node
+    pub fn broadcast(service: router_net_proto::BroadcastService, id: Vec<u8>, data: Vec<u8>) {
+        let message = router_net_proto::BroadcastMessage {
+            id,
+            origin: Node::get_id().to_bytes(),
+            ttl: Router::get_configuration().broadcast_ttl,
+            service: service as i32,
+            data,
+        };
+
+        let mut flooder = FLOODER.get().write().unwrap();
+        flooder.mark_seen(&message.id);
+        flooder.forward(message, None);
+    }
+
+    /// process a broadcast message received from a neighbour
+    pub fn received(neighbour_id: PeerId, message: router_net_proto::BroadcastMessage) {
+        {
+            let flooder = FLOODER.get().read().unwrap();
+            if flooder.seen.contains_key(&message.id) {
+                log::trace!("broadcast message {:?} already seen", message.id);
+                return;
+            }
+        }
+
+        // deliver message to the service
+        let new_message = match router_net_proto::BroadcastService::from_i32(message.service) {
+            Some(router_net_proto::BroadcastService::Feed) => {
+                match feed_net::FeedContainer::decode(&message.data[..]) {
+                    Ok(container) => Feed::received(neighbour_id, container),
+                    Err(e) => {
+                        log::error!("broadcast feed container decoding error: {}", e);
+                        false
+                    }
+                }
+            }
+            None => {
+                log::warn!("unknown broadcast service {}", message.service);
+                false
+            }
+        };
+
+        // only validated messages are marked as seen
+        if !new_message {
+            return;
+        }
+        let mut flooder = FLOODER.get().write().unwrap();
+        flooder.mark_seen(&message.id);
+
+        // forward message
+        if message.ttl > 1 {
+            let limit = Router::get_configuration().broadcast_rate_limit;
+            if flooder.rate_limited(&neighbour_id, limit, Timestamp::get_timestamp()) {
+                log::debug!(
+                    "broadcasts of neighbour {} rate limited, message not forwarded",
+                    neighbour_id.to_base58()
+                );
+                return;
+            }
+
+            let forward = router_net_proto::BroadcastMessage {
+                ttl: message.ttl - 1,
+                ..message
+            };
+            flooder.forward(forward, Some(neighbour_id));
+        }
     }
 
-    /// Add a message to the ring buffer for sending.
-    pub fn add(message: Vec<u8>, topic: Topic, incoming_via: ConnectionModule) {
-        let msg = FloodMessageContainer {
-            message,
-            topic,
-            incoming_via,
+    /// process a feed message received via floodsub or BLE
+    ///
+    /// These messages are sent by nodes that don't support
+    /// the broadcast messages yet.
+    pub fn received_feed(neighbour_id: PeerId, container: feed_net::FeedContainer) {
+        let mut data = Vec::with_capacity(container.encoded_len());
+        container
+            .encode(&mut data)
+            .expect("Vec<u8> provides capacity as needed");
+
+        let message = router_net_proto::BroadcastMessage {
+            id: container.signature,
+            origin: neighbour_id.to_bytes(),
+            ttl: Router::get_configuration().broadcast_ttl,
+            service: router_net_proto::BroadcastService::Feed as i32,
+            data,
         };
 
-        // add it to sending queue
+        Self::received(neighbour_id, message);
+    }
+
+    /// process the message ids announced by a neighbour
+    ///
+    /// request all messages that haven't been seen yet
+    pub fn received_have(neighbour_id: PeerId, ids: Vec<Vec<u8>>) {
+        let mut flooder = FLOODER.get().write().unwrap();
+
+        let wanted = flooder.unseen(ids);
+
+        if wanted.len() > 0 {
+            let message =
+                Self::encode_ids(router_net_proto::RouterInfoModule::BroadcastWant, wanted);
+            flooder.to_send.push_back(FloodMessageContainer {
+                neighbour_id,
+                message,
+            });
+        }
+    }
+
+    /// send the messages requested by a neighbour
+    pub fn received_want(neighbour_id: PeerId, ids: Vec<Vec<u8>>) {
         let mut flooder = FLOODER.get().write().unwrap();
-        flooder.to_send.push_back(msg);    
+
+        for id in ids {
+            let broadcast = match flooder.lazy_store.get(&id) {
+                Some(broadcast) => broadcast.clone(),
+                None => continue,
+            };
+
+            let mut content = Vec::with_capacity(broadcast.encoded_len());
+            broadcast
+                .encode(&mut content)
+                .expect("Vec<u8> provides capacity as needed");
+            let message = RouterInfo::create_broadcast(
+                router_net_proto::RouterInfoModule::Broadcast,
+                content,
+            );
+
+            flooder.to_send.push_back(FloodMessageContainer {
+                neighbour_id,
+                message,
+            });
+        }
+    }
+
+    /// filter out the ids of all seen messages
+    fn unseen(&self, ids: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+        ids.into_iter()
+            .filter(|id| !self.seen.contains_key(id))
+            .collect()
+    }
+
+    /// add a message id to the seen cache
+    fn mark_seen(&mut self, id: &Vec<u8>) {
+        if self
+            .seen
+            .insert(id.clone(), Timestamp::get_timestamp())
+            .is_none()
+        {
+            self.seen_order.push_back(id.clone());
+        }
+
+        while self.seen_order.len() > SEEN_CACHE_SIZE {
+            if let Some(old_id) = self.seen_order.pop_front() {
+                self.seen.remove(&old_id);
+            }
+        }
+    }
+
+    /// check & count the forwarding rate of a neighbour
+    ///
+    /// The rate is counted per neighbour the messages are received from,
+    /// as the origin of a message is not authenticated.
+    ///
+    /// returns true if the neighbour exceeded it's rate
+    fn rate_limited(&mut self, neighbour_id: &PeerId, limit: u32, now: u64) -> bool {
+        let rate = self.rates.entry(*neighbour_id).or_insert(NeighbourRate {
+            window_start: now,
+            count: 0,
+        });
+        if now - rate.window_start > RATE_LIMIT_WINDOW {
+            rate.window_start = now;
+            rate.count = 0;
+        }
+        if rate.count >= limit {
+            return true;
+        }
+        rate.count += 1;
+
+        // remove neighbours with expired windows
+        if self.rates.len() > SEEN_CACHE_SIZE {
+            self.rates
+                .retain(|_, rate| now - rate.window_start <= RATE_LIMIT_WINDOW);
+        }
+
+        false
+    }
+
+    /// forward a message to all neighbours
+    ///
+    /// The message is not sent to the neighbour it was received from.
+    /// Neighbours not supporting broadcast messages receive
+    /// feed messages via their connection module.
+    fn forward(&mut self, broadcast: router_net_proto::BroadcastMessage, from: Option<PeerId>) {
+        let mut neighbours: Vec<PeerId> = Vec::new();
+        let mut legacy: BTreeMap<ConnectionModule, Vec<PeerId>> = BTreeMap::new();
+        for neighbour_id in Neighbours::get_all() {
+            if Some(neighbour_id) == from {
+                continue;
+            }
+            if Neighbours::get_version(&neighbour_id) >= BROADCAST_VERSION {
+                neighbours.push(neighbour_id);
+            } else {
+                legacy
+                    .entry(Neighbours::is_neighbour(&neighbour_id))
+                    .or_insert(Vec::new())
+                    .push(neighbour_id);
+            }
+        }
+
+        // publish feed messages to the neighbours not supporting broadcast messages
+        if broadcast.service == router_net_proto::BroadcastService::Feed as i32 {
+            for (module, legacy_neighbours) in legacy {
+                self.to_publish.push_back(FloodFeedContainer {
+                    module,
+                    neighbours: legacy_neighbours,
+                    data: broadcast.data.clone(),
+                });
+            }
+        }
+
+        if neighbours.len() == 0 {
+            return;
+        }
+
+        let message = match Router::get_configuration().broadcast_lazy {
+            true => {
+                // store the message and only announce it's id
+                let id = broadcast.id.clone();
+                self.lazy_store.insert(id.clone(), broadcast);
+                self.lazy_order.push_back(id.clone());
+                while self.lazy_order.len() > LAZY_STORE_SIZE {
+                    if let Some(old_id) = self.lazy_order.pop_front() {
+                        self.lazy_store.remove(&old_id);
+                    }
+                }
+
+                Self::encode_ids(router_net_proto::RouterInfoModule::BroadcastHave, vec![id])
+            }
+            false => {
+                let mut content = Vec::with_capacity(broadcast.encoded_len());
+                broadcast
+                    .encode(&mut content)
+                    .expect("Vec<u8> provides capacity as needed");
+
+                RouterInfo::create_broadcast(router_net_proto::RouterInfoModule::Broadcast, content)
+            }
+        };
+
+        for neighbour_id in neighbours {
+            self.to_send.push_back(FloodMessageContainer {
+                neighbour_id,
+                message: message.clone(),
+            });
+        }
+    }
+
+    /// encode a broadcast ids message
+    fn encode_ids(module: router_net_proto::RouterInfoModule, ids: Vec<Vec<u8>>) -> Vec<u8> {
+        let table = router_net_proto::BroadcastIds { ids };
+
+        let mut content = Vec::with_capacity(table.encoded_len());
+        table
+            .encode(&mut content)
+            .expect("Vec<u8> provides capacity as needed");
+
+        RouterInfo::create_broadcast(module, content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(index: usize) -> Vec<u8> {
+        index.to_be_bytes().to_vec()
+    }
+
+    #[test]
+    fn seen_message_is_filtered() {
+        let mut flooder = Flooder::new();
+        flooder.mark_seen(&id(1));
+
+        assert_eq!(flooder.unseen(vec![id(1), id(2)]), vec![id(2)]);
+    }
+
+    #[test]
+    fn marking_twice_keeps_a_single_entry() {
+        let mut flooder = Flooder::new();
+        flooder.mark_seen(&id(0));
+        flooder.mark_seen(&id(0));
+        assert_eq!(flooder.seen_order.len(), 1);
+
+        // the id is only evicted after the cache is full
+        for index in 1..SEEN_CACHE_SIZE {
+            flooder.mark_seen(&id(index));
+        }
+        assert!(flooder.seen.contains_key(&id(0)));
+    }
+
+    #[test]
+    fn oldest_seen_id_is_evicted() {
+        let mut flooder = Flooder::new();
+        for index in 0..SEEN_CACHE_SIZE + 1 {
+            flooder.mark_seen(&id(index));
+        }
+
+        assert_eq!(flooder.seen.len(), SEEN_CACHE_SIZE);
+        assert!(!flooder.seen.contains_key(&id(0)));
+        assert!(flooder.seen.contains_key(&id(SEEN_CACHE_SIZE)));
+    }
+
+    #[test]
+    fn rate_is_limited_per_neighbour() {
+        let mut flooder = Flooder::new();
+        let neighbour = PeerId::random();
+        let other = PeerId::random();
+
+        assert!(!flooder.rate_limited(&neighbour, 2, 1000));
+        assert!(!flooder.rate_limited(&neighbour, 2, 1000));
+        assert!(flooder.rate_limited(&neighbour, 2, 1000));
+        assert!(!flooder.rate_limited(&other, 2, 1000));
+    }
+
+    #[test]
+    fn rate_limit_resets_after_window() {
+        let mut flooder = Flooder::new();
+        let neighbour = PeerId::random();
+
+        assert!(!flooder.rate_limited(&neighbour, 1, 1000));
+        assert!(flooder.rate_limited(&neighbour, 1, 1000 + RATE_LIMIT_WINDOW));
+        assert!(!flooder.rate_limited(&neighbour, 1, 1001 + RATE_LIMIT_WINDOW));
     }
 }
@@ -15,11 +15,25 @@
 //! of them to make sure, each node is only sent the routing
 //! table over one of the interfaces.
 //! The timer needs to be polled manually.
+//!
+//! ## Delta Updates
+//!
+//! Each neighbour is sent a full snapshot of the routing
+//! information every `full_table_period`. In between, only the
+//! routes that changed since the last message are sent,
+//! as well as the users that are not reachable anymore.
+//! Each message carries a sequence number per neighbour.
+//! The receiving node keeps the routing information of each
+//! neighbour and asks for a full snapshot, when it missed a message.
+//!
+//! The routing information is compressed and split into several
+//! frames, when it doesn't fit into a single qaul_info message.
 
-use crate::utilities::qaul_id::QaulId;
+use crate::utilities::{compression::Compression, qaul_id::QaulId};
 use libp2p::PeerId;
 use prost::Message;
 use qaul_info::QaulInfoReceived;
+use sled_extensions::{bincode::Tree, DbExt};
 use state::Storage;
 use std::{
     collections::HashMap,
@@ -31,14 +45,17 @@ use crate::{
     connections::ConnectionModule,
     node::Node,
     router::{
-        connections::ConnectionTable, neighbours::Neighbours, router_net_proto,
+        connections::ConnectionTable, neighbours::Neighbours, proto, router_net_proto,
         table::RoutingTable, users::Users,
     },
+    rpc::Rpc,
+    storage::database::DataBase,
     utilities::timestamp::Timestamp,
 };
 
 use crate::feed_requester::FeedRequester;
 use crate::feed_requester::FeedResponser;
+use crate::router::flooder::Flooder;
 use crate::services::feed::Feed;
 
 use crate::router::user_requester::UserRequester;
@@ -47,6 +64,52 @@ use crate::router::user_requester::UserResponser;
 /// mutable state of Neighbours table per ConnectionModule
 static SCHEDULER: Storage<RwLock<Scheduler>> = Storage::new();
 
+/// routing information received from each neighbour
+static RECEIVED: Storage<RwLock<HashMap<PeerId, ReceivedRoutingInfo>>> = Storage::new();
+
+/// Data base table of the persisted propagation ID
+static PERSISTED: Storage<Tree<u32>> = Storage::new();
+
+/// routing information overhead per neighbour
+static OVERHEAD: Storage<RwLock<HashMap<PeerId, RoutingOverhead>>> = Storage::new();
+
+/// maximal size of the routing entries in a single frame
+///
+/// The frames are sent via qaul_info, which accepts
+/// messages of up to 65536 bytes.
+const MAX_FRAME_CONTENT: usize = 32 * 1024;
+
+/// rtt change in percent, from which on a route is
+/// sent in a delta update
+const RTT_CHANGE_PERCENT: u32 = 10;
+
+/// propagation cycles after which an unchanged route
+/// is sent in a delta update, to refresh it's propagation id
+const PGID_REFRESH_CYCLES: u32 = 3;
+
+/// propagation ids reserved ahead when persisting the propagation id
+///
+/// The propagation id advances every 10 seconds, but is only
+/// persisted every 30 seconds. After a restart the node continues
+/// with the reserved id, which is never below an id already sent.
+const PGID_RESERVE: u32 = 10;
+
+/// protocol version of this node
+///
+/// The version is sent to the neighbours in each router info message.
+/// Nodes not sending a version have version 0.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// first protocol version understanding the broadcast messages
+pub const BROADCAST_VERSION: u32 = 1;
+
+/// first protocol version understanding compressed and
+/// framed delta updates of the routing information
+///
+/// Neighbours of older versions receive the full routing
+/// information in a single, uncompressed message.
+pub const DELTA_VERSION: u32 = 1;
+
 /// global scheduler state
 #[derive(Clone, Debug)]
 pub struct Scheduler {
@@ -74,11 +137,62 @@ pub struct Scheduler {
 
 /// An entry for the scheduler neighbour list
 /// that contains the time stamp
-#[derive(Clone, Debug, Copy)]
+#[derive(Clone, Debug)]
 struct SchedulerEntry {
     /// time of the last send
     timestamp: SystemTime,
     is_first: bool,
+    /// sequence number of the last routing information sent
+    sequence: u32,
+    /// send a full snapshot with the next message
+    send_full: bool,
+    /// time of the last full snapshot in milli seconds
+    last_full: u64,
+    /// routes advertised to this neighbour
+    ///
+    /// user q8id => routing info entry
+    advertised: HashMap<Vec<u8>, router_net_proto::RoutingInfoEntry>,
+}
+
+/// Routing information received from a neighbour
+#[derive(Clone, Debug, Default)]
+struct ReceivedRoutingInfo {
+    /// was routing information received from this neighbour
+    initialized: bool,
+    /// sequence number of the last message
+    sequence: u32,
+    /// frame index of the last message
+    frame: u32,
+    /// we missed a message and need a full snapshot
+    request_full: bool,
+    /// routes of this neighbour
+    ///
+    /// user q8id => routing info entry
+    entries: HashMap<Vec<u8>, router_net_proto::RoutingInfoEntry>,
+}
+
+/// Routing information overhead of a neighbour
+#[derive(Clone, Debug)]
+struct RoutingOverhead {
+    bytes_sent: u64,
+    bytes_received: u64,
+    frames_sent: u64,
+    frames_received: u64,
+    full_sent: u64,
+    since: u64,
+}
+
+impl RoutingOverhead {
+    fn new() -> Self {
+        RoutingOverhead {
+            bytes_sent: 0,
+            bytes_received: 0,
+            frames_sent: 0,
+            frames_received: 0,
+            full_sent: 0,
+            since: Timestamp::get_timestamp(),
+        }
+    }
 }
 
 /// RouterInfo Module
@@ -91,24 +205,39 @@ impl RouterInfo {
     /// routing information shall be sent
     /// to neighbours.
     pub fn init(interval_seconds: u64) {
+        // reload the last propagation ID
+        let db = DataBase::get_node_db();
+        let tree: Tree<u32> = db.open_bincode_tree("router_info").unwrap();
+        let mut propagation_id = 0;
+        let mut propagation_timestamp = Timestamp::get_timestamp();
+        if let Ok(Some(persisted_id)) = tree.get("propagation_id") {
+            // continue with the reserved propagation ID and update it
+            // immediately, that our neighbours accept our routes without delay
+            propagation_id = persisted_id;
+            propagation_timestamp = 0;
+        }
+        PERSISTED.set(tree);
+
         // neighbours list for routing info scheduler
         let scheduler = Scheduler {
             neighbours: HashMap::new(),
             interval: Duration::from_secs(interval_seconds),
-            propagation_id: 0,
-            propagation_timestamp: Timestamp::get_timestamp(),
+            propagation_id,
+            propagation_timestamp,
         };
         SCHEDULER.set(RwLock::new(scheduler));
+
+        RECEIVED.set(RwLock::new(HashMap::new()));
+        OVERHEAD.set(RwLock::new(HashMap::new()));
     }
 
     /// This loops over all neighbours
     /// and checks if there is any timeout.
     /// If it finds a timeout it returns the node id
-    /// to send a routing information to.
-    pub fn check_scheduler() -> Option<(PeerId, ConnectionModule, Vec<u8>)> {
+    /// to send a routing information to, together with
+    /// the frames of the routing information.
+    pub fn check_scheduler() -> Option<(PeerId, ConnectionModule, Vec<Vec<u8>>)> {
         let mut found_neighbour: Option<PeerId> = None;
-        let mut neighbour_last_sent: u64 = 0;
-        let mut neighbour_is_first: bool = false;
         let mut propagation_id: u32;
         let mut propagation_timestamp: u64;
 
@@ -120,8 +249,6 @@ impl RouterInfo {
             for (id, ctx) in scheduler.neighbours.iter() {
                 if ctx.timestamp + scheduler.interval < SystemTime::now() {
                     found_neighbour = Some(id.clone());
-                    neighbour_last_sent = Timestamp::get_timestamp_by(&ctx.timestamp);
-                    neighbour_is_first = ctx.is_first;
                     break;
                 }
             }
@@ -152,31 +279,178 @@ impl RouterInfo {
             // we can approach it.
             let module = Neighbours::is_neighbour(&node_id);
 
-            // get SCHEDULER for writing
-            let mut scheduler = SCHEDULER.get().write().unwrap();
-
             if module == ConnectionModule::None {
                 log::debug!("node is not a neighbour anymore: {:?}", node_id);
                 // delete this entry
+                let mut scheduler = SCHEDULER.get().write().unwrap();
                 scheduler.neighbours.remove(&node_id);
-            } else {
-                // update timer
-                if let Some(entry) = scheduler.neighbours.get_mut(&node_id) {
-                    entry.timestamp = SystemTime::now();
-                    entry.is_first = false;
+
+                // delete the received routing information
+                let mut received = RECEIVED.get().write().unwrap();
+                received.remove(&node_id);
+
+                return None;
+            }
+
+            // create current routing information
+            let current = RoutingTable::create_routing_info(node_id);
+
+            // check if we need to request a full snapshot
+            let request_full;
+            {
+                let mut received = RECEIVED.get().write().unwrap();
+                match received.get_mut(&node_id) {
+                    Some(info) => {
+                        request_full = info.request_full;
+                        info.request_full = false;
+                    }
+                    None => request_full = false,
                 }
+            }
+
+            let config = super::Router::get_configuration();
+            let now = Timestamp::get_timestamp();
+            let version = Neighbours::get_version(&node_id);
+            let routes;
+            let full;
+            let is_first;
+            let sequence;
+            {
+                // get SCHEDULER for writing
+                let mut scheduler = SCHEDULER.get().write().unwrap();
+                let entry;
+                match scheduler.neighbours.get_mut(&node_id) {
+                    Some(my_entry) => entry = my_entry,
+                    None => return None,
+                }
+
+                // decide whether to send a full snapshot or a delta
+                full = Self::is_full(entry, version, now, config.full_table_period);
 
-                // create routing information
-                let data = Self::create(node_id.clone(), neighbour_last_sent, neighbour_is_first);
+                if full {
+                    routes = current;
+                } else {
+                    routes = Self::create_delta(&entry.advertised, &current);
+                }
+
+                // update scheduler entry
+                is_first = entry.is_first;
+                entry.timestamp = SystemTime::now();
+                entry.is_first = false;
+                entry.send_full = false;
+                entry.sequence = entry.sequence.wrapping_add(1);
+                if full {
+                    entry.last_full = now;
+                    entry.advertised.clear();
+                }
+                for removed in &routes.removed {
+                    entry.advertised.remove(removed);
+                }
+                for route in &routes.entry {
+                    entry.advertised.insert(route.user.clone(), route.clone());
+                }
+                sequence = entry.sequence;
+            }
 
-                // create result
-                return Some((node_id, module, data));
+            // create routing information
+            let frames = Self::create(routes, sequence, full, request_full, is_first, version);
+
+            // count overhead
+            {
+                let mut overhead = OVERHEAD.get().write().unwrap();
+                let neighbour_overhead =
+                    overhead.entry(node_id).or_insert_with(RoutingOverhead::new);
+                for frame in &frames {
+                    neighbour_overhead.bytes_sent += frame.len() as u64;
+                    neighbour_overhead.frames_sent += 1;
+                }
+                if full {
+                    neighbour_overhead.full_sent += 1;
+                }
             }
+
+            // create result
+            return Some((node_id, module, frames));
         }
 
         None
     }
 
+    /// decide whether to send a full snapshot to a neighbour
+    ///
+    /// Neighbours not understanding delta updates always
+    /// receive full snapshots.
+    fn is_full(entry: &SchedulerEntry, version: u32, now: u64, full_table_period: u64) -> bool {
+        version < DELTA_VERSION
+            || entry.is_first
+            || entry.send_full
+            || now >= entry.last_full + full_table_period * 1000
+    }
+
+    /// create the delta between the routes advertised to
+    /// a neighbour and the current routes
+    ///
+    /// A route is sent, when it is new, when it's hop count changed,
+    /// when it's rtt or ETX changed significantly, or when it's
+    /// propagation id needs to be refreshed.
+    fn create_delta(
+        advertised: &HashMap<Vec<u8>, router_net_proto::RoutingInfoEntry>,
+        current: &router_net_proto::RoutingInfoTable,
+    ) -> router_net_proto::RoutingInfoTable {
+        let mut delta = router_net_proto::RoutingInfoTable {
+            entry: Vec::new(),
+            removed: Vec::new(),
+        };
+
+        let mut current_users: HashMap<&Vec<u8>, ()> = HashMap::new();
+        for route in &current.entry {
+            current_users.insert(&route.user, ());
+
+            match advertised.get(&route.user) {
+                Some(previous) => {
+                    let rtt_change = (route.rtt as i64 - previous.rtt as i64).unsigned_abs();
+                    let etx_change = (route.etx as i64 - previous.etx as i64).unsigned_abs();
+                    if route.hc != previous.hc
+                        || rtt_change * 100 > previous.rtt as u64 * RTT_CHANGE_PERCENT as u64
+                        || etx_change * 100 > previous.etx as u64 * RTT_CHANGE_PERCENT as u64
+                        || route.pgid >= previous.pgid.wrapping_add(PGID_REFRESH_CYCLES)
+                        || route.pgid < previous.pgid
+                    {
+                        delta.entry.push(route.clone());
+                    }
+                }
+                None => delta.entry.push(route.clone()),
+            }
+        }
+
+        // users not reachable anymore
+        for user in advertised.keys() {
+            if !current_users.contains_key(user) {
+                delta.removed.push(user.clone());
+            }
+        }
+
+        delta
+    }
+
+    /// persist the propagation ID to the data base
+    ///
+    /// The propagation IDs until the next save are reserved ahead.
+    pub fn save() {
+        let propagation_id;
+        {
+            let scheduler = SCHEDULER.get().read().unwrap();
+            propagation_id = scheduler.propagation_id.saturating_add(PGID_RESERVE);
+        }
+
+        let tree = PERSISTED.get();
+        if let Err(e) = tree.insert("propagation_id", propagation_id) {
+            log::error!("Error saving propagation id: {}", e);
+        } else if let Err(e) = tree.flush() {
+            log::error!("Error when flushing data base to disk: {}", e);
+        }
+    }
+
     /// add new neighbour entry
     pub fn add_neighbour(node_id: PeerId) {
         let exists;
@@ -196,18 +470,69 @@ impl RouterInfo {
                 SchedulerEntry {
                     timestamp: SystemTime::now() - interval,
                     is_first: true,
+                    sequence: 0,
+                    send_full: false,
+                    last_full: 0,
+                    advertised: HashMap::new(),
                 },
             );
         }
     }
 
     /// Create routing information for a neighbour node,
-    /// encode the information and return the byte code.
-    pub fn create(neighbour: PeerId, last_sent: u64, is_first: bool) -> Vec<u8> {
+    /// encode the information and return the frames to send.
+    ///
+    /// `version` is the protocol version of the neighbour.
+    pub fn create(
+        routes: router_net_proto::RoutingInfoTable,
+        sequence: u32,
+        full: bool,
+        request_full: bool,
+        is_first: bool,
+        version: u32,
+    ) -> Vec<Vec<u8>> {
         let node_id = Node::get_id();
 
-        // create routing table
-        let routes = RoutingTable::create_routing_info(neighbour, last_sent);
+        // older neighbours expect the table in a single message
+        let max_frame_content = match version < DELTA_VERSION {
+            true => usize::MAX,
+            false => MAX_FRAME_CONTENT,
+        };
+
+        // split routing table into frames
+        let mut tables: Vec<router_net_proto::RoutingInfoTable> = Vec::new();
+        let mut table = router_net_proto::RoutingInfoTable {
+            entry: Vec::new(),
+            removed: Vec::new(),
+        };
+        let mut size = 0;
+        for route in routes.entry {
+            let route_size = route.encoded_len() + 3;
+            if size + route_size > max_frame_content && size > 0 {
+                tables.push(table);
+                table = router_net_proto::RoutingInfoTable {
+                    entry: Vec::new(),
+                    removed: Vec::new(),
+                };
+                size = 0;
+            }
+            size += route_size;
+            table.entry.push(route);
+        }
+        for user in routes.removed {
+            let user_size = user.len() + 3;
+            if size + user_size > max_frame_content && size > 0 {
+                tables.push(table);
+                table = router_net_proto::RoutingInfoTable {
+                    entry: Vec::new(),
+                    removed: Vec::new(),
+                };
+                size = 0;
+            }
+            size += user_size;
+            table.removed.push(user);
+        }
+        tables.push(table);
 
         // create latest Feed ids table
         let mut feeds = router_net_proto::FeedIdsTable { ids: Vec::new() };
@@ -219,53 +544,97 @@ impl RouterInfo {
             }
         }
 
-        // create router info protobuf message
-        let timestamp = Timestamp::get_timestamp();
-        let router_info = router_net_proto::RouterInfoMessage {
-            node: node_id.clone().to_bytes(),
-            routes: Some(routes),
-            //users: Some(users),
-            feeds: Some(feeds),
-            timestamp,
-        };
+        // create a message for each frame
+        let frame_count = tables.len() as u32;
+        let mut frames: Vec<Vec<u8>> = Vec::new();
+        for (frame, routes) in tables.into_iter().enumerate() {
+            // create router info protobuf message
+            let timestamp = Timestamp::get_timestamp();
+            let router_info = router_net_proto::RouterInfoMessage {
+                node: node_id.clone().to_bytes(),
+                routes: Some(routes),
+                //users: Some(users),
+                feeds: if frame == 0 {
+                    Some(feeds.clone())
+                } else {
+                    None
+                },
+                timestamp,
+                sequence,
+                full,
+                frame: frame as u32,
+                frames: frame_count,
+                request_full,
+                version: PROTOCOL_VERSION,
+            };
 
-        // encode router info message
-        let mut buf = Vec::with_capacity(router_info.encoded_len());
-        router_info
-            .encode(&mut buf)
-            .expect("Vec<u8> provides capacity as needed");
+            // encode & compress router info message
+            let (buf, compression) = Self::encode_router_info(&router_info, version);
 
-        // create router info content message
-        let router_info_proto = router_net_proto::RouterInfoContent {
-            id: node_id.to_bytes(),
-            router_info_module: router_net_proto::RouterInfoModule::RouterInfo as i32,
-            content: buf,
-            time: timestamp,
-        };
+            // create router info content message
+            let router_info_proto = router_net_proto::RouterInfoContent {
+                id: node_id.to_bytes(),
+                router_info_module: router_net_proto::RouterInfoModule::RouterInfo as i32,
+                content: buf,
+                time: timestamp,
+                compression: compression as i32,
+            };
 
-        // encode content message
-        let mut buf = Vec::with_capacity(router_info_proto.encoded_len());
-        router_info_proto
-            .encode(&mut buf)
-            .expect("Vec<u8> provides capacity as needed");
+            // encode content message
+            let mut buf = Vec::with_capacity(router_info_proto.encoded_len());
+            router_info_proto
+                .encode(&mut buf)
+                .expect("Vec<u8> provides capacity as needed");
 
-        // sign data
-        let keys = Node::get_keys();
-        let signature = keys.sign(&buf).unwrap();
+            // sign data
+            let keys = Node::get_keys();
+            let signature = keys.sign(&buf).unwrap();
 
-        // create signed container
-        let router_info_container = router_net_proto::RouterInfoContainer {
-            signature,
-            message: buf,
-        };
+            // create signed container
+            let router_info_container = router_net_proto::RouterInfoContainer {
+                signature,
+                message: buf,
+            };
 
-        // encode container message
-        let mut buf = Vec::with_capacity(router_info_container.encoded_len());
-        router_info_container
+            // encode container message
+            let mut buf = Vec::with_capacity(router_info_container.encoded_len());
+            router_info_container
+                .encode(&mut buf)
+                .expect("Vec<u8> provides capacity as needed");
+
+            frames.push(buf);
+        }
+
+        frames
+    }
+
+    /// encode a router info message for a neighbour
+    ///
+    /// The message is compressed, if the neighbour understands
+    /// compressed messages and the compressed message is smaller.
+    fn encode_router_info(
+        router_info: &router_net_proto::RouterInfoMessage,
+        version: u32,
+    ) -> (Vec<u8>, router_net_proto::Compression) {
+        let mut buf = Vec::with_capacity(router_info.encoded_len());
+        router_info
             .encode(&mut buf)
             .expect("Vec<u8> provides capacity as needed");
 
-        buf
+        if version < DELTA_VERSION {
+            return (buf, router_net_proto::Compression::None);
+        }
+
+        match Compression::deflate(&buf) {
+            Ok(compressed) => {
+                if compressed.len() < buf.len() {
+                    return (compressed, router_net_proto::Compression::Deflate);
+                }
+            }
+            Err(e) => log::error!("{}", e),
+        }
+
+        (buf, router_net_proto::Compression::None)
     }
 
     /// creating feed request message
@@ -288,6 +657,7 @@ impl RouterInfo {
             router_info_module: router_net_proto::RouterInfoModule::FeedRequest as i32,
             content: buf,
             time: timestamp,
+            compression: router_net_proto::Compression::None as i32,
         };
 
         // encode message
@@ -344,6 +714,7 @@ impl RouterInfo {
             router_info_module: router_net_proto::RouterInfoModule::FeedResponse as i32,
             content: buf,
             time: timestamp,
+            compression: router_net_proto::Compression::None as i32,
         };
 
         // encode message
@@ -389,6 +760,7 @@ impl RouterInfo {
             router_info_module: router_net_proto::RouterInfoModule::UserRequest as i32,
             content: buf,
             time: timestamp,
+            compression: router_net_proto::Compression::None as i32,
         };
 
         // encode message
@@ -431,6 +803,48 @@ impl RouterInfo {
             router_info_module: router_net_proto::RouterInfoModule::UserResponse as i32,
             content: buf,
             time: timestamp,
+            compression: router_net_proto::Compression::None as i32,
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(router_info_proto.encoded_len());
+        router_info_proto
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // sign data
+        let keys = Node::get_keys();
+        let signature = keys.sign(&buf).unwrap();
+
+        // create signed container
+        let router_info_container = router_net_proto::RouterInfoContainer {
+            signature,
+            message: buf,
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(router_info_container.encoded_len());
+        router_info_container
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        buf
+    }
+
+    /// create a broadcast router info message
+    ///
+    /// used by the flooder for the broadcast messages
+    /// and the broadcast id tables
+    pub fn create_broadcast(
+        router_info_module: router_net_proto::RouterInfoModule,
+        content: Vec<u8>,
+    ) -> Vec<u8> {
+        let router_info_proto = router_net_proto::RouterInfoContent {
+            id: Node::get_id().to_bytes(),
+            router_info_module: router_info_module as i32,
+            content,
+            time: Timestamp::get_timestamp(),
+            compression: router_net_proto::Compression::None as i32,
         };
 
         // encode message
@@ -473,7 +887,18 @@ impl RouterInfo {
                     router_net_proto::RouterInfoContent::decode(&container.message[..]);
 
                 match message_result {
-                    Ok(content) => {
+                    Ok(mut content) => {
+                        // decompress content
+                        if content.compression == router_net_proto::Compression::Deflate as i32 {
+                            match Compression::inflate(&content.content) {
+                                Ok(inflated) => content.content = inflated,
+                                Err(e) => {
+                                    log::error!("RouterInfoContent {}", e);
+                                    return;
+                                }
+                            }
+                        }
+
                         match router_net_proto::RouterInfoModule::from_i32(
                             content.router_info_module,
                         ) {
@@ -482,11 +907,30 @@ impl RouterInfo {
                                     &content.content[..],
                                 );
                                 if let Ok(message) = message_info {
+                                    // count overhead
+                                    Self::count_received(&received);
+
+                                    // remember the protocol version of the neighbour
+                                    Neighbours::set_version(
+                                        &received.received_from,
+                                        message.version,
+                                    );
+
+                                    // the neighbour asks for a full snapshot
+                                    if message.request_full {
+                                        let mut scheduler = SCHEDULER.get().write().unwrap();
+                                        if let Some(entry) =
+                                            scheduler.neighbours.get_mut(&received.received_from)
+                                        {
+                                            entry.send_full = true;
+                                        }
+                                    }
+
                                     // collect users and routes
                                     let messages = message;
                                     //let users = messages.users;
-                                    let routes = messages.routes;
-                                    let feeds = messages.feeds;
+                                    let routes = messages.routes.clone();
+                                    let feeds = messages.feeds.clone();
 
                                     // match users {
                                     //     Some(router_net_proto::UserInfoTable { info }) => {
@@ -496,7 +940,10 @@ impl RouterInfo {
                                     // }
 
                                     match routes {
-                                        Some(router_net_proto::RoutingInfoTable { entry }) => {
+                                        Some(router_net_proto::RoutingInfoTable {
+                                            entry,
+                                            removed,
+                                        }) => {
                                             //check missed user ids
                                             let mut user_ids: Vec<Vec<u8>> = vec![];
                                             for e in &entry {
@@ -510,10 +957,18 @@ impl RouterInfo {
                                                 );
                                             }
 
+                                            // merge delta into the neighbour's routes
+                                            let all_entries = Self::merge_received_routing_info(
+                                                &received.received_from,
+                                                &messages,
+                                                entry,
+                                                removed,
+                                            );
+
                                             //process routing table
                                             ConnectionTable::process_received_routing_info(
                                                 received.received_from,
-                                                &entry,
+                                                &all_entries,
                                             );
                                         }
                                         _ => {}
@@ -595,6 +1050,28 @@ impl RouterInfo {
                                     Users::add_user_info_table(&message.info);
                                 }
                             }
+                            Some(router_net_proto::RouterInfoModule::Broadcast) => {
+                                let message_info = router_net_proto::BroadcastMessage::decode(
+                                    &content.content[..],
+                                );
+                                if let Ok(message) = message_info {
+                                    Flooder::received(received.received_from, message);
+                                }
+                            }
+                            Some(router_net_proto::RouterInfoModule::BroadcastHave) => {
+                                let message_info =
+                                    router_net_proto::BroadcastIds::decode(&content.content[..]);
+                                if let Ok(message) = message_info {
+                                    Flooder::received_have(received.received_from, message.ids);
+                                }
+                            }
+                            Some(router_net_proto::RouterInfoModule::BroadcastWant) => {
+                                let message_info =
+                                    router_net_proto::BroadcastIds::decode(&content.content[..]);
+                                if let Ok(message) = message_info {
+                                    Flooder::received_want(received.received_from, message.ids);
+                                }
+                            }
                             _ => {}
                         }
                     }
@@ -608,4 +1085,302 @@ impl RouterInfo {
             }
         }
     }
+
+    /// merge received routing information into the routes
+    /// of the neighbour and return all routes of the neighbour
+    ///
+    /// When a message is missing, a full snapshot is requested.
+    fn merge_received_routing_info(
+        neighbour_id: &PeerId,
+        message: &router_net_proto::RouterInfoMessage,
+        entries: Vec<router_net_proto::RoutingInfoEntry>,
+        removed: Vec<Vec<u8>>,
+    ) -> Vec<router_net_proto::RoutingInfoEntry> {
+        // nodes without delta support send their routes
+        // without frame information
+        if message.frames == 0 {
+            return entries;
+        }
+
+        let mut received = RECEIVED.get().write().unwrap();
+        let info = received.entry(neighbour_id.to_owned()).or_default();
+
+        // check sequence
+        let mut in_sequence;
+        if message.frame == 0 {
+            in_sequence = info.initialized && message.sequence == info.sequence.wrapping_add(1);
+        } else {
+            in_sequence = info.initialized
+                && message.sequence == info.sequence
+                && message.frame == info.frame + 1;
+        }
+        if message.full && message.frame == 0 {
+            info.entries.clear();
+            in_sequence = true;
+        }
+        if !in_sequence {
+            log::debug!(
+                "routing information from {} out of sequence",
+                neighbour_id.to_base58()
+            );
+            info.request_full = true;
+        }
+        info.initialized = true;
+        info.sequence = message.sequence;
+        info.frame = message.frame;
+
+        // merge routes
+        for user in removed {
+            info.entries.remove(&user);
+        }
+        for entry in entries {
+            info.entries.insert(entry.user.clone(), entry);
+        }
+
+        info.entries.values().cloned().collect()
+    }
+
+    /// count received routing information overhead
+    fn count_received(received: &QaulInfoReceived) {
+        let mut overhead = OVERHEAD.get().write().unwrap();
+        let neighbour_overhead = overhead
+            .entry(received.received_from)
+            .or_insert_with(RoutingOverhead::new);
+        neighbour_overhead.bytes_received += received.data.len() as u64;
+        neighbour_overhead.frames_received += 1;
+    }
+
+    /// send protobuf RPC routing overhead list
+    pub fn rpc_send_overhead_list() {
+        let mut neighbours: Vec<proto::RoutingOverheadEntry> = Vec::new();
+
+        {
+            let overhead = OVERHEAD.get().read().unwrap();
+            for (id, value) in overhead.iter() {
+                neighbours.push(proto::RoutingOverheadEntry {
+                    node_id: id.to_bytes(),
+                    bytes_sent: value.bytes_sent,
+                    bytes_received: value.bytes_received,
+                    frames_sent: value.frames_sent,
+                    frames_received: value.frames_received,
+                    full_sent: value.full_sent,
+                    since: value.since,
+                });
+            }
+        }
+
+        // create overhead list message
+        let proto_message = proto::Router {
+            message: Some(proto::router::Message::RoutingOverheadList(
+                proto::RoutingOverheadList { neighbours },
+            )),
+        };
+
+        // send message
+        Rpc::send_message(
+            proto_message.encode_to_vec(),
+            crate::rpc::proto::Modules::Router.into(),
+            "".to_string(),
+            Vec::new(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn init() {
+        // the storage can only be set once per test binary
+        RECEIVED.set(RwLock::new(HashMap::new()));
+    }
+
+    fn message(
+        sequence: u32,
+        full: bool,
+        frame: u32,
+        frames: u32,
+    ) -> router_net_proto::RouterInfoMessage {
+        router_net_proto::RouterInfoMessage {
+            node: Vec::new(),
+            routes: None,
+            feeds: None,
+            timestamp: 0,
+            sequence,
+            full,
+            frame,
+            frames,
+            request_full: false,
+            version: PROTOCOL_VERSION,
+        }
+    }
+
+    fn entry(user: u8) -> router_net_proto::RoutingInfoEntry {
+        router_net_proto::RoutingInfoEntry {
+            user: vec![user; 8],
+            rtt: 100,
+            hc: vec![1],
+            pgid: 1,
+            etx: 100,
+        }
+    }
+
+    fn request_full(neighbour_id: &PeerId) -> bool {
+        let received = RECEIVED.get().read().unwrap();
+        received.get(neighbour_id).unwrap().request_full
+    }
+
+    #[test]
+    fn merge_delta_in_sequence() {
+        init();
+        let neighbour_id = PeerId::random();
+
+        RouterInfo::merge_received_routing_info(
+            &neighbour_id,
+            &message(1, true, 0, 1),
+            vec![entry(1), entry(2)],
+            Vec::new(),
+        );
+        let routes = RouterInfo::merge_received_routing_info(
+            &neighbour_id,
+            &message(2, false, 0, 1),
+            vec![entry(3)],
+            vec![vec![1; 8]],
+        );
+
+        assert_eq!(routes.len(), 2);
+        assert!(!routes.iter().any(|route| route.user == vec![1; 8]));
+        assert!(!request_full(&neighbour_id));
+    }
+
+    #[test]
+    fn merge_missed_delta_requests_full() {
+        init();
+        let neighbour_id = PeerId::random();
+
+        RouterInfo::merge_received_routing_info(
+            &neighbour_id,
+            &message(1, true, 0, 1),
+            vec![entry(1)],
+            Vec::new(),
+        );
+        RouterInfo::merge_received_routing_info(
+            &neighbour_id,
+            &message(3, false, 0, 1),
+            vec![entry(2)],
+            Vec::new(),
+        );
+
+        assert!(request_full(&neighbour_id));
+    }
+
+    #[test]
+    fn merge_lost_snapshot_frame_requests_full() {
+        init();
+        let neighbour_id = PeerId::random();
+
+        RouterInfo::merge_received_routing_info(
+            &neighbour_id,
+            &message(5, true, 0, 3),
+            vec![entry(1)],
+            Vec::new(),
+        );
+        // frame 1 was lost
+        RouterInfo::merge_received_routing_info(
+            &neighbour_id,
+            &message(5, true, 2, 3),
+            vec![entry(3)],
+            Vec::new(),
+        );
+
+        assert!(request_full(&neighbour_id));
+    }
+
+    #[test]
+    fn merge_full_snapshot_replaces_routes() {
+        init();
+        let neighbour_id = PeerId::random();
+
+        RouterInfo::merge_received_routing_info(
+            &neighbour_id,
+            &message(1, true, 0, 1),
+            vec![entry(1)],
+            Vec::new(),
+        );
+        // a snapshot after missed messages resynchronizes
+        let routes = RouterInfo::merge_received_routing_info(
+            &neighbour_id,
+            &message(9, true, 0, 2),
+            vec![entry(2)],
+            Vec::new(),
+        );
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].user, vec![2; 8]);
+
+        let routes = RouterInfo::merge_received_routing_info(
+            &neighbour_id,
+            &message(9, true, 1, 2),
+            vec![entry(3)],
+            Vec::new(),
+        );
+        assert_eq!(routes.len(), 2);
+        assert!(!request_full(&neighbour_id));
+    }
+
+    #[test]
+    fn merge_sequence_wraparound() {
+        init();
+        let neighbour_id = PeerId::random();
+
+        RouterInfo::merge_received_routing_info(
+            &neighbour_id,
+            &message(u32::MAX, true, 0, 1),
+            vec![entry(1)],
+            Vec::new(),
+        );
+        let routes = RouterInfo::merge_received_routing_info(
+            &neighbour_id,
+            &message(0, false, 0, 1),
+            vec![entry(2)],
+            Vec::new(),
+        );
+
+        assert_eq!(routes.len(), 2);
+        assert!(!request_full(&neighbour_id));
+    }
+
+    #[test]
+    fn legacy_neighbour_gets_full_uncompressed_message() {
+        let now = 1_000_000;
+        let scheduler_entry = SchedulerEntry {
+            timestamp: SystemTime::now(),
+            is_first: false,
+            sequence: 1,
+            send_full: false,
+            last_full: now,
+            advertised: HashMap::new(),
+        };
+        assert!(RouterInfo::is_full(&scheduler_entry, 0, now, 60));
+        assert!(!RouterInfo::is_full(
+            &scheduler_entry,
+            PROTOCOL_VERSION,
+            now,
+            60
+        ));
+
+        let mut router_info = message(2, true, 0, 1);
+        router_info.routes = Some(router_net_proto::RoutingInfoTable {
+            entry: (0..100).map(entry).collect(),
+            removed: Vec::new(),
+        });
+
+        let (buf, compression) = RouterInfo::encode_router_info(&router_info, 0);
+        assert_eq!(compression, router_net_proto::Compression::None);
+        let decoded = router_net_proto::RouterInfoMessage::decode(&buf[..]).unwrap();
+        assert!(decoded.full);
+        assert_eq!(decoded.routes.unwrap().entry.len(), 100);
+
+        let (_, compression) = RouterInfo::encode_router_info(&router_info, PROTOCOL_VERSION);
+        assert_eq!(compression, router_net_proto::Compression::Deflate);
+    }
 }
@@ -92,6 +92,15 @@ impl Router {
         RouterInfo::init(config.routing.sending_table_period);
     }
 
+    /// Persist the routing state
+    ///
+    /// The connection tables and the propagation ID are saved
+    /// to the data base, to be reloaded when the node restarts.
+    pub fn save_state() {
+        ConnectionTable::save();
+        RouterInfo::save();
+    }
+
     /// Get router configuration from state
     pub fn get_configuration() -> RoutingOptions {
         let router = ROUTER.get().read().unwrap();
@@ -116,6 +125,10 @@ impl Router {
                         // send neighbours list
                         Neighbours::rpc_send_neighbours_list();
                     }
+                    Some(proto::router::Message::RoutingOverheadRequest(_request)) => {
+                        // send routing overhead list
+                        RouterInfo::rpc_send_overhead_list();
+                    }
                     _ => {}
                 }
             }
@@ -4,13 +4,26 @@
 //! Table of all direct neighbour nodes
 //!
 //! There is a table per connection module.
+//!
+//! Besides the round trip time, the delivery ratio of each
+//! neighbour is tracked over the last deliveries.
+//! Deliveries are measured from the pings, which probe
+//! the link to the neighbour, and from the confirmations of
+//! the messages we last sent via a single neighbour.
+//! A missing confirmation is not counted as failed delivery,
+//! as the message may have been lost further along the route.
+//! The delivery ratio is used to calculate the expected
+//! transmission count (ETX) of the link.
 
 use libp2p::PeerId;
 use prost::Message;
 use serde::{Deserialize, Serialize};
 use sled_extensions::{bincode::Tree, DbExt};
 use state::Storage;
-use std::{collections::HashMap, sync::RwLock};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    sync::RwLock,
+};
 
 use super::info::RouterInfo;
 use super::proto;
@@ -19,12 +32,10 @@ use crate::rpc::Rpc;
 use crate::storage::database::DataBase;
 use crate::utilities::{qaul_id::QaulId, timestamp::Timestamp};
 
-/// mutable state of Internet neighbour node table
-static INTERNET: Storage<RwLock<Neighbours>> = Storage::new();
-/// mutable state of LAN neighbour node table
-static LAN: Storage<RwLock<Neighbours>> = Storage::new();
-/// mutable state of BLE neighbour node table
-static BLE: Storage<RwLock<Neighbours>> = Storage::new();
+/// mutable state of the neighbour node tables
+///
+/// connection module => neighbour node table of the module
+static NEIGHBOURS: Storage<BTreeMap<ConnectionModule, RwLock<Neighbours>>> = Storage::new();
 
 /// Data base table of all ever discovered neighbour nodes
 ///
@@ -55,28 +66,26 @@ pub struct Neighbour {
     rtt: u32,
     /// when was this node last seen
     updated_at: u64,
+    /// results of the last deliveries, true if successful
+    deliveries: VecDeque<bool>,
+    /// protocol version announced by the node
+    version: u32,
 }
 
 impl Neighbours {
     /// Initialize neighbours module
     pub fn init() {
-        // neighbours table for internet connection module
-        let internet = Neighbours {
-            nodes: HashMap::new(),
-        };
-        INTERNET.set(RwLock::new(internet));
-
-        // neighbours table for lan connection module
-        let lan = Neighbours {
-            nodes: HashMap::new(),
-        };
-        LAN.set(RwLock::new(lan));
-
-        // neighbours table for ble connection module
-        let ble = Neighbours {
-            nodes: HashMap::new(),
-        };
-        BLE.set(RwLock::new(ble));
+        // create a neighbours table for each connection module
+        let mut neighbours = BTreeMap::new();
+        for module in ConnectionModule::TRANSPORTS {
+            neighbours.insert(
+                module,
+                RwLock::new(Neighbours {
+                    nodes: HashMap::new(),
+                }),
+            );
+        }
+        NEIGHBOURS.set(neighbours);
 
         // get nodes tree from data base and set it to state
         let db = DataBase::get_node_db();
@@ -84,6 +93,13 @@ impl Neighbours {
         NODES.set(tree);
     }
 
+    /// get the neighbours table of a connection module
+    ///
+    /// Returns `None` for modules without a neighbours table.
+    fn table(module: &ConnectionModule) -> Option<&'static RwLock<Neighbours>> {
+        NEIGHBOURS.get().get(module)
+    }
+
     /// update table with a new value
     ///
     /// If the node already exists, it updates it's rtt value.
@@ -92,12 +108,9 @@ impl Neighbours {
         log::trace!("update_node node {:?}", node_id);
         // get table
         let mut neighbours;
-        match module {
-            ConnectionModule::Lan => neighbours = LAN.get().write().unwrap(),
-            ConnectionModule::Internet => neighbours = INTERNET.get().write().unwrap(),
-            ConnectionModule::Ble => neighbours = BLE.get().write().unwrap(),
-            ConnectionModule::Local => return,
-            ConnectionModule::None => return,
+        match Self::table(&module) {
+            Some(table) => neighbours = table.write().unwrap(),
+            None => return,
         }
 
         // get node from table
@@ -112,6 +125,8 @@ impl Neighbours {
                 Neighbour {
                     rtt,
                     updated_at: Timestamp::get_timestamp(),
+                    deliveries: VecDeque::new(),
+                    version: 0,
                 },
             );
 
@@ -145,12 +160,9 @@ impl Neighbours {
     pub fn delete(module: ConnectionModule, node_id: PeerId) {
         // get table
         let mut neighbours;
-        match module {
-            ConnectionModule::Lan => neighbours = LAN.get().write().unwrap(),
-            ConnectionModule::Internet => neighbours = INTERNET.get().write().unwrap(),
-            ConnectionModule::Ble => neighbours = BLE.get().write().unwrap(),
-            ConnectionModule::Local => return,
-            ConnectionModule::None => return,
+        match Self::table(&module) {
+            Some(table) => neighbours = table.write().unwrap(),
+            None => return,
         }
 
         // delete entry
@@ -166,19 +178,81 @@ impl Neighbours {
         new_rtt
     }
 
+    /// record the result of a delivery to a neighbour
+    ///
+    /// Only the last `delivery_window` results are kept.
+    pub fn record_delivery(module: ConnectionModule, node_id: PeerId, success: bool) {
+        let window = super::Router::get_configuration().delivery_window.max(1) as usize;
+
+        // get table
+        let mut neighbours;
+        match Self::table(&module) {
+            Some(table) => neighbours = table.write().unwrap(),
+            None => return,
+        }
+
+        if let Some(node) = neighbours.nodes.get_mut(&node_id) {
+            node.deliveries.push_back(success);
+            while node.deliveries.len() > window {
+                node.deliveries.pop_front();
+            }
+        }
+    }
+
+    /// Calculate the expected transmission count (ETX)
+    ///
+    /// The ETX is returned in hundredths, 100 is a perfect link.
+    /// A link without any recorded deliveries counts as perfect.
+    fn calculate_etx(deliveries: &VecDeque<bool>) -> u32 {
+        let total = deliveries.len() as u32;
+        let successes = deliveries.iter().filter(|success| **success).count() as u32;
+
+        if total == 0 {
+            return 100;
+        }
+        if successes == 0 {
+            // nothing arrived, count it as the worst link of the window
+            return total * 100;
+        }
+
+        total * 100 / successes
+    }
+
+    /// get the ETX of a neighbour
+    ///
+    /// returns the expected transmission count in hundredths
+    /// for the neighbour in the connection module.
+    /// If the neighbour does not exist, it returns None.
+    pub fn get_etx(neighbour_id: &PeerId, module: &ConnectionModule) -> Option<u32> {
+        // get table
+        if *module == ConnectionModule::Local {
+            return Some(100);
+        }
+        let neighbours;
+        match Self::table(module) {
+            Some(table) => neighbours = table.read().unwrap(),
+            None => return None,
+        }
+
+        neighbours
+            .nodes
+            .get(neighbour_id)
+            .map(|neighbour| Self::calculate_etx(&neighbour.deliveries))
+    }
+
     /// get rtt for a neighbour
     /// returns the round trip time for the neighbour in the
     /// connection module.
     /// If the neighbour does not exist, it returns None.
     pub fn get_rtt(neighbour_id: &PeerId, module: &ConnectionModule) -> Option<u32> {
         // get table
+        if *module == ConnectionModule::Local {
+            return Some(0);
+        }
         let neighbours;
-        match module {
-            ConnectionModule::Lan => neighbours = LAN.get().read().unwrap(),
-            ConnectionModule::Internet => neighbours = INTERNET.get().read().unwrap(),
-            ConnectionModule::Ble => neighbours = BLE.get().read().unwrap(),
-            ConnectionModule::Local => return Some(0),
-            ConnectionModule::None => return None,
+        match Self::table(module) {
+            Some(table) => neighbours = table.read().unwrap(),
+            None => return None,
         }
 
         // search for neighbour
@@ -189,34 +263,62 @@ impl Neighbours {
         }
     }
 
-    /// Is this node ID a neighbour in any module?
-    /// returns the first found module or `None`
-    pub fn is_neighbour(node_id: &PeerId) -> ConnectionModule {
-        // check if neighbour is in Lan table
-        {
-            let lan = LAN.get().read().unwrap();
-            if lan.nodes.contains_key(node_id) {
-                return ConnectionModule::Lan;
+    /// set the protocol version announced by a neighbour
+    ///
+    /// The version is set in the entries of all modules
+    /// the neighbour is connected with.
+    pub fn set_version(node_id: &PeerId, version: u32) {
+        for table in NEIGHBOURS.get().values() {
+            let mut neighbours = table.write().unwrap();
+            if let Some(node) = neighbours.nodes.get_mut(node_id) {
+                node.version = version;
             }
         }
-        // check if neighbour exists in Internet table
-        {
-            let internet = INTERNET.get().read().unwrap();
-            if internet.nodes.contains_key(node_id) {
-                return ConnectionModule::Internet;
+    }
+
+    /// get the protocol version of a neighbour
+    ///
+    /// Returns 0 if the neighbour has not announced a version yet.
+    pub fn get_version(node_id: &PeerId) -> u32 {
+        let mut version = 0;
+
+        for table in NEIGHBOURS.get().values() {
+            let neighbours = table.read().unwrap();
+            if let Some(node) = neighbours.nodes.get(node_id) {
+                version = version.max(node.version);
             }
         }
-        // check if neighbour exists in BLE table
-        {
-            let ble = BLE.get().read().unwrap();
-            if ble.nodes.contains_key(node_id) {
-                return ConnectionModule::Ble;
+
+        version
+    }
+
+    /// Is this node ID a neighbour in any module?
+    /// returns the first found module or `None`
+    pub fn is_neighbour(node_id: &PeerId) -> ConnectionModule {
+        for (module, table) in NEIGHBOURS.get() {
+            let neighbours = table.read().unwrap();
+            if neighbours.nodes.contains_key(node_id) {
+                return *module;
             }
         }
 
         ConnectionModule::None
     }
 
+    /// Get the node ids of all neighbours of all modules
+    ///
+    /// Each neighbour is only listed once.
+    pub fn get_all() -> Vec<PeerId> {
+        let mut nodes: BTreeSet<PeerId> = BTreeSet::new();
+
+        for table in NEIGHBOURS.get().values() {
+            let neighbours = table.read().unwrap();
+            nodes.extend(neighbours.nodes.keys().cloned());
+        }
+
+        nodes.into_iter().collect()
+    }
+
     /// Search for a neighbour by it's small qaul ID
     ///
     /// Returns node if it exists in the data base,
@@ -259,84 +361,14 @@ impl Neighbours {
         None
     }
 
-    /// Get a list of all neighbours that are only connected via BLE module
-    ///
-    /// This function is used to decide to which nodes we need to send the
-    /// flooding information.
-    pub fn get_ble_only_nodes() -> Vec<PeerId> {
-        let mut nodes: Vec<PeerId> = Vec::new();
-
-        // get state
-        let ble = BLE.get().read().unwrap();
-
-        // check if we have nodes listed
-        if ble.nodes.len() > 0 {
-            let lan = LAN.get().read().unwrap();
-            let internet = INTERNET.get().read().unwrap();
-
-            // search for all nodes that are only reachable via BLE
-            for (id, _val) in ble.nodes.iter() {
-                // check if it exists in LAN
-                if lan.nodes.contains_key(id) {
-                    continue;
-                }
-
-                // check if it exists in Internet
-                if internet.nodes.contains_key(id) {
-                    continue;
-                }
-
-                // if not found, add it to the nodes list
-                nodes.push(id.to_owned());
-            }
-        }
-
-        // return list of neighbour nodes
-        nodes
-    }
-
     /// send protobuf RPC neighbours list
     pub fn rpc_send_neighbours_list() {
         // create lists per module
-        let mut lan_neighbours: Vec<proto::NeighboursEntry> = Vec::new();
-        let mut internet_neighbours: Vec<proto::NeighboursEntry> = Vec::new();
-        let mut ble_neighbours: Vec<proto::NeighboursEntry> = Vec::new();
-
-        // fill lan connection module neighbours
-        {
-            let lan = LAN.get().read().unwrap();
-
-            for (id, value) in &lan.nodes {
-                lan_neighbours.push(proto::NeighboursEntry {
-                    node_id: id.to_bytes(),
-                    rtt: value.rtt,
-                });
-            }
-        }
-
-        // fill internet connection module neighbours
-        {
-            let internet = INTERNET.get().write().unwrap();
-
-            for (id, value) in &internet.nodes {
-                internet_neighbours.push(proto::NeighboursEntry {
-                    node_id: id.to_bytes(),
-                    rtt: value.rtt,
-                });
-            }
-        }
-
-        // fill ble connection module neighbours
-        {
-            let ble = BLE.get().write().unwrap();
-
-            for (id, value) in &ble.nodes {
-                ble_neighbours.push(proto::NeighboursEntry {
-                    node_id: id.to_bytes(),
-                    rtt: value.rtt,
-                });
-            }
-        }
+        let lan_neighbours = Self::rpc_create_neighbours_module_list(ConnectionModule::Lan);
+        let internet_neighbours =
+            Self::rpc_create_neighbours_module_list(ConnectionModule::Internet);
+        let ble_neighbours = Self::rpc_create_neighbours_module_list(ConnectionModule::Ble);
+        let stream_neighbours = Self::rpc_create_neighbours_module_list(ConnectionModule::Stream);
 
         // create neighbours list message
         let proto_message = proto::Router {
@@ -345,6 +377,7 @@ impl Neighbours {
                     lan: lan_neighbours,
                     internet: internet_neighbours,
                     ble: ble_neighbours,
+                    stream: stream_neighbours,
                 },
             )),
         };
@@ -363,4 +396,23 @@ impl Neighbours {
             Vec::new(),
         );
     }
+
+    /// create rpc neighbours list of a connection module
+    fn rpc_create_neighbours_module_list(module: ConnectionModule) -> Vec<proto::NeighboursEntry> {
+        let mut neighbours_list: Vec<proto::NeighboursEntry> = Vec::new();
+
+        if let Some(table) = Self::table(&module) {
+            let neighbours = table.read().unwrap();
+
+            for (id, value) in &neighbours.nodes {
+                neighbours_list.push(proto::NeighboursEntry {
+                    node_id: id.to_bytes(),
+                    rtt: value.rtt,
+                    etx: Self::calculate_etx(&value.deliveries),
+                });
+            }
+        }
+
+        neighbours_list
+    }
 }
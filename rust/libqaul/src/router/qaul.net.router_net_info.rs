@@ -23,8 +23,16 @@ pub struct RouterInfoContent {
     /// timestamp in milli seconds
     #[prost(uint64, tag="4")]
     pub time: u64,
+    /// compression of the message content
+    #[prost(enumeration="Compression", tag="5")]
+    pub compression: i32,
 }
 /// Router information message
+///
+/// The routing information is sent as a full snapshot
+/// or as a delta to the previous message.
+/// A message can be split into several frames, which
+/// share the same sequence number.
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct RouterInfoMessage {
     /// node id
@@ -39,12 +47,38 @@ pub struct RouterInfoMessage {
     /// timestamp
     #[prost(uint64, tag="5")]
     pub timestamp: u64,
+    /// sequence number of the routing information
+    /// sent to this neighbour
+    #[prost(uint32, tag="6")]
+    pub sequence: u32,
+    /// is this a full snapshot of the routing information
+    #[prost(bool, tag="7")]
+    pub full: bool,
+    /// frame index of this message
+    #[prost(uint32, tag="8")]
+    pub frame: u32,
+    /// total number of frames of this message
+    #[prost(uint32, tag="9")]
+    pub frames: u32,
+    /// ask the neighbour to send a full snapshot
+    #[prost(bool, tag="10")]
+    pub request_full: bool,
+    /// protocol version of the sending node
+    ///
+    /// nodes from version 1 on understand the
+    /// broadcast messages.
+    #[prost(uint32, tag="11")]
+    pub version: u32,
 }
 /// Routing information to send to neighbours
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct RoutingInfoTable {
     #[prost(message, repeated, tag="1")]
     pub entry: ::prost::alloc::vec::Vec<RoutingInfoEntry>,
+    /// users that are not reachable anymore
+    /// via the sending node
+    #[prost(bytes="vec", repeated, tag="2")]
+    pub removed: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
 }
 /// Routing structures to send over the network
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -61,6 +95,11 @@ pub struct RoutingInfoEntry {
     /// propagation id
     #[prost(uint32, tag="5")]
     pub pgid: u32,
+    /// expected transmission count (ETX) of the route
+    ///
+    /// addition of the ETX of all hops, in hundredths
+    #[prost(uint32, tag="6")]
+    pub etx: u32,
 }
 /// User information table
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -134,6 +173,56 @@ pub struct FeedMessage {
     #[prost(uint64, tag="4")]
     pub time: u64,
 }
+/// Broadcast Message
+///
+/// Broadcast messages are flooded to the entire
+/// network via all connection modules.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BroadcastMessage {
+    /// unique message id
+    #[prost(bytes="vec", tag="1")]
+    pub id: ::prost::alloc::vec::Vec<u8>,
+    /// node id of the originating node
+    #[prost(bytes="vec", tag="2")]
+    pub origin: ::prost::alloc::vec::Vec<u8>,
+    /// number of hops the message may still travel
+    #[prost(uint32, tag="3")]
+    pub ttl: u32,
+    /// service of the message
+    #[prost(enumeration="BroadcastService", tag="4")]
+    pub service: i32,
+    /// message data
+    #[prost(bytes="vec", tag="5")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+}
+/// Broadcast message ids
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BroadcastIds {
+    /// message ids
+    #[prost(bytes="vec", repeated, tag="1")]
+    pub ids: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+}
+/// Compression of the message content
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum Compression {
+    /// content is not compressed
+    None = 0,
+    /// content is compressed with deflate
+    Deflate = 1,
+}
+impl Compression {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Compression::None => "NONE",
+            Compression::Deflate => "DEFLATE",
+        }
+    }
+}
 /// RouterInfoModule
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -148,6 +237,14 @@ pub enum RouterInfoModule {
     UserRequest = 3,
     /// Message is a UserResponseMessage
     UserResponse = 4,
+    /// Message is a BroadcastMessage
+    Broadcast = 5,
+    /// Message is a BroadcastIds table, announcing
+    /// broadcast messages for lazy forwarding
+    BroadcastHave = 6,
+    /// Message is a BroadcastIds table, requesting
+    /// announced broadcast messages
+    BroadcastWant = 7,
 }
 impl RouterInfoModule {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -161,6 +258,27 @@ impl RouterInfoModule {
             RouterInfoModule::FeedResponse => "FEED_RESPONSE",
             RouterInfoModule::UserRequest => "USER_REQUEST",
             RouterInfoModule::UserResponse => "USER_RESPONSE",
+            RouterInfoModule::Broadcast => "BROADCAST",
+            RouterInfoModule::BroadcastHave => "BROADCAST_HAVE",
+            RouterInfoModule::BroadcastWant => "BROADCAST_WANT",
+        }
+    }
+}
+/// Service of a broadcast message
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum BroadcastService {
+    /// data is a feed container
+    Feed = 0,
+}
+impl BroadcastService {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            BroadcastService::Feed => "FEED",
         }
     }
 }
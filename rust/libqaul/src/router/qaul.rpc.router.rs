@@ -1,7 +1,7 @@
 /// router rpc message container
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Router {
-    #[prost(oneof="router::Message", tags="1, 2, 3, 4, 5, 6")]
+    #[prost(oneof="router::Message", tags="1, 2, 3, 4, 5, 6, 7, 8")]
     pub message: ::core::option::Option<router::Message>,
 }
 /// Nested message and enum types in `Router`.
@@ -20,6 +20,10 @@ pub mod router {
         NeighboursRequest(super::NeighboursRequest),
         #[prost(message, tag="6")]
         NeighboursList(super::NeighboursList),
+        #[prost(message, tag="7")]
+        RoutingOverheadRequest(super::RoutingOverheadRequest),
+        #[prost(message, tag="8")]
+        RoutingOverheadList(super::RoutingOverheadList),
     }
 }
 /// UI request for routing table list
@@ -82,6 +86,9 @@ pub struct ConnectionsList {
     /// users connected locally (on the same node)
     #[prost(message, repeated, tag="4")]
     pub local: ::prost::alloc::vec::Vec<ConnectionsUserEntry>,
+    /// users connected via the stream module
+    #[prost(message, repeated, tag="5")]
+    pub stream: ::prost::alloc::vec::Vec<ConnectionsUserEntry>,
 }
 /// connections entry for a user
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -120,6 +127,8 @@ pub struct NeighboursList {
     pub internet: ::prost::alloc::vec::Vec<NeighboursEntry>,
     #[prost(message, repeated, tag="3")]
     pub ble: ::prost::alloc::vec::Vec<NeighboursEntry>,
+    #[prost(message, repeated, tag="4")]
+    pub stream: ::prost::alloc::vec::Vec<NeighboursEntry>,
 }
 /// neighbours entry
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -130,6 +139,45 @@ pub struct NeighboursEntry {
     /// rtt to this neighbour
     #[prost(uint32, tag="2")]
     pub rtt: u32,
+    /// expected transmission count to this neighbour
+    /// in hundredths, 100 is a loss free link
+    #[prost(uint32, tag="3")]
+    pub etx: u32,
+}
+/// UI request for the routing overhead per neighbour
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RoutingOverheadRequest {
+}
+/// routing overhead list per neighbour
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RoutingOverheadList {
+    #[prost(message, repeated, tag="1")]
+    pub neighbours: ::prost::alloc::vec::Vec<RoutingOverheadEntry>,
+}
+/// routing information overhead of a neighbour
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RoutingOverheadEntry {
+    /// the ID of the neighbour node
+    #[prost(bytes="vec", tag="1")]
+    pub node_id: ::prost::alloc::vec::Vec<u8>,
+    /// bytes of routing information sent to this neighbour
+    #[prost(uint64, tag="2")]
+    pub bytes_sent: u64,
+    /// bytes of routing information received from this neighbour
+    #[prost(uint64, tag="3")]
+    pub bytes_received: u64,
+    /// number of routing information frames sent
+    #[prost(uint64, tag="4")]
+    pub frames_sent: u64,
+    /// number of routing information frames received
+    #[prost(uint64, tag="5")]
+    pub frames_received: u64,
+    /// number of full snapshots sent
+    #[prost(uint64, tag="6")]
+    pub full_sent: u64,
+    /// time since when the overhead is measured, in milli seconds
+    #[prost(uint64, tag="7")]
+    pub since: u64,
 }
 /// Connection modules
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
@@ -140,6 +188,7 @@ pub enum ConnectionModule {
     Internet = 2,
     Ble = 3,
     Local = 4,
+    Stream = 5,
 }
 impl ConnectionModule {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -153,6 +202,7 @@ impl ConnectionModule {
             ConnectionModule::Internet => "INTERNET",
             ConnectionModule::Ble => "BLE",
             ConnectionModule::Local => "LOCAL",
+            ConnectionModule::Stream => "STREAM",
         }
     }
 }
@@ -7,8 +7,8 @@
 //!
 //! * contains all currently reachable users.
 //! * There is an entry for each user over which connection modules
-//!   it can be reached. Each connection module only contains
-//!   information of the best node.
+//!   it can be reached. Each connection module contains
+//!   the best nodes, ranked by link quality.
 
 use libp2p::PeerId;
 use prost::Message;
@@ -16,6 +16,7 @@ use state::Storage;
 use std::collections::{BTreeMap, HashMap};
 use std::sync::RwLock;
 
+use super::neighbours::Neighbours;
 use super::proto;
 use crate::connections::ConnectionModule;
 use crate::router::router_net_proto;
@@ -38,7 +39,7 @@ pub struct RoutingUserEntry {
     pub pgid_update_hc: u8,
     //online time
     pub online_time: u64,
-    /// best routing entry per connection module
+    /// best routing entries per connection module
     pub connections: Vec<RoutingConnectionEntry>,
 }
 
@@ -56,6 +57,9 @@ pub struct RoutingConnectionEntry {
     /// hop count
     /// how many hops has the connection
     pub hc: u8,
+    /// expected transmission count
+    /// addition of the ETX of all hops, in hundredths
+    pub etx: u32,
     /// link quality
     pub lq: u32,
     /// last_update
@@ -90,11 +94,14 @@ impl RoutingTable {
 
     /// Create routing information for a specific neighbour node,
     /// to be sent to this neighbour node.
-    pub fn create_routing_info(
-        neighbour: PeerId,
-        last_sent: u64,
-    ) -> router_net_proto::RoutingInfoTable {
-        let mut table = router_net_proto::RoutingInfoTable { entry: Vec::new() };
+    ///
+    /// It contains the best route of all users, except the
+    /// ones we reach via this neighbour.
+    pub fn create_routing_info(neighbour: PeerId) -> router_net_proto::RoutingInfoTable {
+        let mut table = router_net_proto::RoutingInfoTable {
+            entry: Vec::new(),
+            removed: Vec::new(),
+        };
 
         // get access to routing table
         let routing_table = ROUTINGTABLE.get().read().unwrap();
@@ -113,8 +120,7 @@ impl RoutingTable {
                 }
             }
 
-            if neighbour != min_conn.node && (min_conn.last_update >= last_sent || min_conn.hc == 0)
-            {
+            if neighbour != min_conn.node {
                 let mut hc = Vec::new();
                 hc.push(min_conn.hc);
 
@@ -123,6 +129,7 @@ impl RoutingTable {
                     rtt: min_conn.rtt,
                     hc,
                     pgid: user.pgid,
+                    etx: min_conn.etx,
                 };
                 table.entry.push(table_entry);
             }
@@ -204,6 +211,7 @@ impl RoutingTable {
                     ConnectionModule::Internet => module = proto::ConnectionModule::Internet as i32,
                     ConnectionModule::Ble => module = proto::ConnectionModule::Ble as i32,
                     ConnectionModule::Local => module = proto::ConnectionModule::Local as i32,
+                    ConnectionModule::Stream => module = proto::ConnectionModule::Stream as i32,
                     _ => module = proto::ConnectionModule::None as i32,
                 }
 
@@ -250,59 +258,60 @@ impl RoutingTable {
     /// the neighbour id as well as the connection module via
     /// which to send the packages.
     ///
-    /// It selects the best route according to the rank_routing_connection function.
+    /// It selects the best route of `get_routes_to_user`.
     ///
     pub fn get_route_to_user(user_id: PeerId) -> Option<RoutingConnectionEntry> {
-        // get routing table state
+        Self::get_routes_to_user(user_id).into_iter().next()
+    }
+
+    /// Check if there are routes to a user in the routing table
+    ///
+    /// This includes routes via neighbours, that are not
+    /// connected at the moment.
+    pub fn has_routes_to_user(user_id: PeerId) -> bool {
         let routing_table = ROUTINGTABLE.get().read().unwrap();
+        match routing_table.table.get(&QaulId::to_q8id(user_id)) {
+            Some(user_entry) => user_entry.connections.len() > 0,
+            None => false,
+        }
+    }
+
+    /// Get all routing connection entries for a specific user
+    ///
+    /// The routes are ranked, the best route comes first.
+    /// They are ordered according to the rank_routing_connection
+    /// function and the link quality within a connection module.
+    ///
+    /// Routes via neighbours that disappeared since the
+    /// routing table was created are skipped.
+    ///
+    pub fn get_routes_to_user(user_id: PeerId) -> Vec<RoutingConnectionEntry> {
+        let mut routes: Vec<RoutingConnectionEntry> = Vec::new();
 
         // get q8id for qaul user
         let user_q8id = QaulId::to_q8id(user_id);
 
         // find user
-        if let Some(user_entry) = routing_table.table.get(&user_q8id) {
-            let mut compare: Option<&RoutingConnectionEntry> = None;
-
-            // find best route
-            for connection in &user_entry.connections {
-                match compare {
-                    Some(current) => {
-                        if Self::compare_connections(current, connection) {
-                            compare = Some(connection);
-                        }
-                    }
-                    None => compare = Some(connection),
-                }
-            }
-
-            // return route
-            match compare {
-                None => return None,
-                Some(connection) => return Some(connection.to_owned()),
+        {
+            let routing_table = ROUTINGTABLE.get().read().unwrap();
+            if let Some(user_entry) = routing_table.table.get(&user_q8id) {
+                routes = user_entry.connections.clone();
             }
         }
-        None
-    }
 
-    /// Compare two routing connections and decides which one is better
-    ///
-    /// This function decides which connection to favour based on the
-    /// rank_routing_connection function
-    ///
-    /// Return values:
-    ///
-    /// * returns true, when the new connection is better
-    /// * returns false, when the current connection is better
-    ///
-    fn compare_connections(current: &RoutingConnectionEntry, new: &RoutingConnectionEntry) -> bool {
-        let current_value = Self::rank_routing_connection(current);
-        let new_value = Self::rank_routing_connection(new);
+        // check if the neighbours are still connected
+        routes.retain(|connection| {
+            Neighbours::get_rtt(&connection.node, &connection.module).is_some()
+        });
 
-        if current_value < new_value {
-            return true;
-        }
+        // rank routes
+        routes.sort_by(|a, b| {
+            Self::rank_routing_connection(b)
+                .cmp(&Self::rank_routing_connection(a))
+                .then(a.lq.cmp(&b.lq))
+        });
 
-        false
+        routes
     }
 
     /// give a ranking to the routing connection
@@ -310,15 +319,16 @@ impl RoutingTable {
     /// This function decides which connection to favour based on the following qualities:
     ///
     /// * Hierarchy of connection modules in the following order:
-    ///   Local, LAN, Internet, BLE, None
+    ///   Local, LAN, Internet, BLE, Stream, None
     ///
     fn rank_routing_connection(connection: &RoutingConnectionEntry) -> u8 {
         match connection.module {
             ConnectionModule::None => return 0,
-            ConnectionModule::Ble => return 1,
-            ConnectionModule::Internet => return 2,
-            ConnectionModule::Lan => return 3,
-            ConnectionModule::Local => return 4,
+            ConnectionModule::Stream => return 1,
+            ConnectionModule::Ble => return 2,
+            ConnectionModule::Internet => return 3,
+            ConnectionModule::Lan => return 4,
+            ConnectionModule::Local => return 5,
         }
     }
 }
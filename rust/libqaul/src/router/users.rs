@@ -153,6 +153,18 @@ impl Users {
         Self::get_pub_key_by_q8id(&q8id)
     }
 
+    /// get the name of a known user
+    pub fn get_name(user_id: &PeerId) -> Option<String> {
+        // get q8id
+        let q8id = QaulId::to_q8id(user_id.to_owned());
+
+        let store = USERS.get().read().unwrap();
+        match store.users.get(&q8id) {
+            Some(user) => Some(user.name.clone()),
+            None => None,
+        }
+    }
+
     /// get the public key of a known user by it's q8id
     pub fn get_pub_key_by_q8id(q8id: &Vec<u8>) -> Option<PublicKey> {
         let store = USERS.get().read().unwrap();
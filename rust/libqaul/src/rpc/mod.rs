@@ -28,6 +28,7 @@ use crate::services::dtn::Dtn;
 use crate::services::feed::Feed;
 use crate::services::group::Group;
 use crate::services::rtc::Rtc;
+use crate::services::search::Search;
 use debug::Debug;
 
 /// Import protobuf message definition generated by
@@ -185,6 +186,10 @@ impl Rpc {
                         log::trace!("Message Modules::Group received");
                         Dtn::rpc(message.data, message.user_id);
                     }
+                    Some(Modules::Search) => {
+                        log::trace!("Message Modules::Search received");
+                        Search::rpc(message.data, message.user_id);
+                    }
                     Some(Modules::None) => {
                         log::error!("Message Modules::None received");
                     }
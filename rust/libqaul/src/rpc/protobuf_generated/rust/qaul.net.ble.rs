@@ -31,6 +31,14 @@ pub struct Identification {
     pub request: bool,
     #[prost(message, optional, tag="2")]
     pub node: ::core::option::Option<NodeIdentification>,
+    /// random challenge of a private network,
+    /// to be answered by the neighbour
+    #[prost(bytes="vec", tag="3")]
+    pub challenge: ::prost::alloc::vec::Vec<u8>,
+    /// proof of the network key:
+    /// HMAC-SHA256 of the neighbour's challenge and the node id
+    #[prost(bytes="vec", tag="4")]
+    pub proof: ::prost::alloc::vec::Vec<u8>,
 }
 /// Identity Information
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -38,4 +46,8 @@ pub struct NodeIdentification {
     /// Node ID
     #[prost(bytes="vec", tag="1")]
     pub id: ::prost::alloc::vec::Vec<u8>,
+    /// network id of a private network,
+    /// empty for the public network
+    #[prost(bytes="vec", tag="2")]
+    pub network_id: ::prost::alloc::vec::Vec<u8>,
 }
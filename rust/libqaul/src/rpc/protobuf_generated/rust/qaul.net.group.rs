@@ -1,7 +1,7 @@
 /// Group network message container
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct GroupContainer {
-    #[prost(oneof="group_container::Message", tags="1, 2, 3, 4")]
+    #[prost(oneof="group_container::Message", tags="1, 2, 3, 4, 5, 6, 7, 8, 9, 10")]
     pub message: ::core::option::Option<group_container::Message>,
 }
 /// Nested message and enum types in `GroupContainer`.
@@ -20,6 +20,24 @@ pub mod group_container {
         /// member removed
         #[prost(message, tag="4")]
         Removed(super::RemovedMember),
+        /// member left the group
+        #[prost(message, tag="5")]
+        Left(super::LeftGroup),
+        /// group disbanded by an administrator
+        #[prost(message, tag="6")]
+        Closed(super::GroupClosed),
+        /// request the latest group info
+        #[prost(message, tag="7")]
+        InfoRequest(super::GroupInfoRequest),
+        /// signed group info
+        #[prost(message, tag="8")]
+        SignedInfo(super::SignedGroupInfo),
+        /// sender key of a group member
+        #[prost(message, tag="9")]
+        SenderKey(super::SenderKeyDistribution),
+        /// redeem an invite code
+        #[prost(message, tag="10")]
+        RedeemCode(super::RedeemInviteCode),
     }
 }
 /// Invite member
@@ -68,6 +86,43 @@ pub struct GroupInfo {
     /// updated members
     #[prost(message, repeated, tag="5")]
     pub members: ::prost::alloc::vec::Vec<GroupMember>,
+    /// message retention time in seconds
+    ///
+    /// Messages older than this time are deleted
+    /// by all group members.
+    /// 0 = messages are kept forever
+    #[prost(uint64, tag="6")]
+    pub retention: u64,
+    /// number of uses of the group's invite codes
+    #[prost(message, repeated, tag="7")]
+    pub invite_code_uses: ::prost::alloc::vec::Vec<InviteCodeUse>,
+    /// group description
+    #[prost(string, tag="8")]
+    pub description: ::prost::alloc::string::String,
+    /// group avatar image
+    #[prost(bytes="vec", tag="9")]
+    pub avatar: ::prost::alloc::vec::Vec<u8>,
+    /// group permission settings
+    #[prost(message, optional, tag="10")]
+    pub settings: ::core::option::Option<GroupSettings>,
+}
+/// Group permission settings
+///
+/// The default settings allow only administrators
+/// to invite and rename, and all members to post.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GroupSettings {
+    /// members are allowed to invite new members
+    #[prost(bool, tag="1")]
+    pub members_can_invite: bool,
+    /// only administrators are allowed to post messages
+    ///
+    /// This turns the group into an announcement group.
+    #[prost(bool, tag="2")]
+    pub admins_only_post: bool,
+    /// members are allowed to rename the group
+    #[prost(bool, tag="3")]
+    pub members_can_rename: bool,
 }
 /// Reply to Invite
 ///
@@ -88,6 +143,150 @@ pub struct RemovedMember {
     #[prost(bytes="vec", tag="1")]
     pub group_id: ::prost::alloc::vec::Vec<u8>,
 }
+/// Member left the group
+///
+/// This message is sent by the leaving member
+/// to all other members of the group.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LeftGroup {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+}
+/// Group closed
+///
+/// The group was disbanded by an administrator.
+/// This message is sent to all members of the group.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GroupClosed {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+}
+/// Request the latest group info
+///
+/// This message is sent to a member that has a newer
+/// group revision than ours.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GroupInfoRequest {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// our current group revision
+    #[prost(uint32, tag="2")]
+    pub revision: u32,
+    /// hash of our signed group info
+    #[prost(bytes="vec", tag="3")]
+    pub info_hash: ::prost::alloc::vec::Vec<u8>,
+}
+/// Signed group info
+///
+/// The group info is signed by the member who changed it,
+/// usually an administrator.
+/// The signed group info can be forwarded by every member.
+/// The receiver validates the signature and the
+/// permissions of the signing member.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SignedGroupInfo {
+    /// protobuf encoded GroupInfo message
+    #[prost(bytes="vec", tag="1")]
+    pub group_info: ::prost::alloc::vec::Vec<u8>,
+    /// user id of the signing member
+    #[prost(bytes="vec", tag="2")]
+    pub admin_id: ::prost::alloc::vec::Vec<u8>,
+    /// signature of the group_info field
+    #[prost(bytes="vec", tag="3")]
+    pub signature: ::prost::alloc::vec::Vec<u8>,
+}
+/// Sender key distribution
+///
+/// Each group member encrypts it's group messages with
+/// it's own sender key. The sender key is distributed to
+/// all group members via the end to end encrypted direct
+/// messaging channel.
+/// A new sender key is created whenever the group members change.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SenderKeyDistribution {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// id of the sender key
+    #[prost(uint32, tag="2")]
+    pub key_id: u32,
+    /// symmetric ChaCha20-Poly1305 key
+    #[prost(bytes="vec", tag="3")]
+    pub key: ::prost::alloc::vec::Vec<u8>,
+}
+/// Group invite code
+///
+/// An invite code is created and signed by a group administrator.
+/// It can be shared out-of-band, e.g. as text or QR code.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct InviteCode {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// group name
+    #[prost(string, tag="2")]
+    pub group_name: ::prost::alloc::string::String,
+    /// user id of the administrator who created the code
+    #[prost(bytes="vec", tag="3")]
+    pub admin_id: ::prost::alloc::vec::Vec<u8>,
+    /// random id of the invite code
+    #[prost(uint32, tag="4")]
+    pub code_id: u32,
+    /// creation time
+    #[prost(uint64, tag="5")]
+    pub created_at: u64,
+    /// expiry time
+    ///
+    /// 0 = the code doesn't expire
+    #[prost(uint64, tag="6")]
+    pub expires_at: u64,
+    /// maximal number of uses
+    ///
+    /// 0 = unlimited
+    #[prost(uint32, tag="7")]
+    pub max_uses: u32,
+    /// protobuf encoded public key of the administrator
+    ///
+    /// The code can be validated without knowing
+    /// the administrator.
+    #[prost(bytes="vec", tag="8")]
+    pub admin_key: ::prost::alloc::vec::Vec<u8>,
+}
+/// Signed group invite code
+///
+/// This message is bs58 encoded and shared as invite code string.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SignedInviteCode {
+    /// protobuf encoded InviteCode message
+    #[prost(bytes="vec", tag="1")]
+    pub code: ::prost::alloc::vec::Vec<u8>,
+    /// signature of the administrator
+    #[prost(bytes="vec", tag="2")]
+    pub signature: ::prost::alloc::vec::Vec<u8>,
+}
+/// Redeem an invite code
+///
+/// This message is sent by the holder of an invite
+/// code to an administrator of the group.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RedeemInviteCode {
+    /// protobuf encoded SignedInviteCode message
+    #[prost(bytes="vec", tag="1")]
+    pub invite_code: ::prost::alloc::vec::Vec<u8>,
+}
+/// Number of uses of an invite code
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct InviteCodeUse {
+    /// id of the invite code
+    #[prost(uint32, tag="1")]
+    pub code_id: u32,
+    /// number of uses
+    #[prost(uint32, tag="2")]
+    pub uses: u32,
+}
 /// Group member state
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -11,6 +11,12 @@ pub struct Container {
     /// Message envelope
     #[prost(message, optional, tag="2")]
     pub envelope: ::core::option::Option<Envelope>,
+    /// group messages: qaul IDs of the receivers
+    /// this container is forwarded to on this route
+    ///
+    /// This field is not signed and only used for routing.
+    #[prost(bytes="vec", repeated, tag="3")]
+    pub forward_to: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
 }
 /// message envelop with sender and receiver
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -20,16 +26,21 @@ pub struct Envelope {
     #[prost(bytes="vec", tag="1")]
     pub sender_id: ::prost::alloc::vec::Vec<u8>,
     /// the qaul ID of the receiver
+    ///
+    /// for group messages this is the group id
     #[prost(bytes="vec", tag="2")]
     pub receiver_id: ::prost::alloc::vec::Vec<u8>,
     /// payload
     #[prost(bytes="vec", tag="3")]
     pub payload: ::prost::alloc::vec::Vec<u8>,
+    /// group messages: qaul IDs of all receivers
+    #[prost(bytes="vec", repeated, tag="4")]
+    pub receiver_ids: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
 }
 /// envelop payload
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct EnvelopPayload {
-    #[prost(oneof="envelop_payload::Payload", tags="1, 2")]
+    #[prost(oneof="envelop_payload::Payload", tags="1, 2, 3")]
     pub payload: ::core::option::Option<envelop_payload::Payload>,
 }
 /// Nested message and enum types in `EnvelopPayload`.
@@ -42,6 +53,9 @@ pub mod envelop_payload {
         /// DTN message
         #[prost(bytes, tag="2")]
         Dtn(::prost::alloc::vec::Vec<u8>),
+        /// group message encrypted with the sender key
+        #[prost(message, tag="3")]
+        GroupEncrypted(super::GroupEncrypted),
     }
 }
 /// encrypted message data
@@ -59,6 +73,25 @@ pub struct Encrypted {
     #[prost(message, repeated, tag="3")]
     pub data: ::prost::alloc::vec::Vec<Data>,
 }
+/// group message data encrypted with the sender key
+///
+/// The message is encrypted once by the sender and the
+/// same encrypted data is delivered to all group members.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GroupEncrypted {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// id of the sender key
+    #[prost(uint32, tag="2")]
+    pub key_id: u32,
+    /// message nonce for encryption
+    #[prost(uint64, tag="3")]
+    pub nonce: u64,
+    /// the encrypted message data
+    #[prost(bytes="vec", tag="4")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+}
 /// encrypted message data
 #[derive(serde::Serialize, serde::Deserialize)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -78,7 +111,7 @@ pub struct Data {
 /// messaging unified message
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Messaging {
-    #[prost(oneof="messaging::Message", tags="1, 2, 3, 4, 5, 6")]
+    #[prost(oneof="messaging::Message", tags="1, 2, 3, 4, 5, 6, 7")]
     pub message: ::core::option::Option<messaging::Message>,
 }
 /// Nested message and enum types in `Messaging`.
@@ -103,6 +136,9 @@ pub mod messaging {
         /// common message
         #[prost(message, tag="6")]
         CommonMessage(super::CommonMessage),
+        /// chat read receipt
+        #[prost(message, tag="7")]
+        ReadReceipt(super::ReadReceipt),
     }
 }
 /// message received confirmation
@@ -120,6 +156,24 @@ pub struct Confirmation {
     #[prost(uint64, tag="2")]
     pub received_at: u64,
 }
+/// chat read receipt
+///
+/// An optional message a user sends to the group members
+/// when it has read the messages of a group.
+/// It is independent of the reception confirmation.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReadReceipt {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// index of the last message of the receiving
+    /// user, which has been read.
+    #[prost(uint32, tag="2")]
+    pub last_read_index: u32,
+    /// read at timestamp
+    #[prost(uint64, tag="3")]
+    pub read_at: u64,
+}
 /// Crypto Service Message
 ///
 /// This message is for crypto specific tasks,
@@ -151,8 +205,21 @@ pub struct CommonMessage {
     /// sent at timestamp
     #[prost(uint64, tag="3")]
     pub sent_at: u64,
+    /// group revision
+    ///
+    /// The revision of the group known to the sender.
+    /// It is used to detect outdated group states between members.
+    #[prost(uint32, tag="8")]
+    pub group_revision: u32,
+    /// group info hash
+    ///
+    /// Hash of the signed group info of the revision known
+    /// to the sender. It is used to detect members with
+    /// diverging group states of the same revision.
+    #[prost(bytes="vec", tag="11")]
+    pub group_info_hash: ::prost::alloc::vec::Vec<u8>,
     /// payload
-    #[prost(oneof="common_message::Payload", tags="4, 5, 6, 7")]
+    #[prost(oneof="common_message::Payload", tags="4, 5, 6, 7, 9, 10")]
     pub payload: ::core::option::Option<common_message::Payload>,
 }
 /// Nested message and enum types in `CommonMessage`.
@@ -172,6 +239,12 @@ pub mod common_message {
         /// rtc message
         #[prost(message, tag="7")]
         RtcMessage(super::RtcMessage),
+        /// poll message
+        #[prost(message, tag="9")]
+        PollMessage(super::PollMessage),
+        /// vote on a poll
+        #[prost(message, tag="10")]
+        VoteMessage(super::VoteMessage),
     }
 }
 /// chat content
@@ -181,6 +254,22 @@ pub struct ChatMessage {
     /// content
     #[prost(string, tag="1")]
     pub content: ::prost::alloc::string::String,
+    /// the message this message replies to
+    #[prost(message, optional, tag="2")]
+    pub reply: ::core::option::Option<ChatReply>,
+}
+/// reply reference of a chat message
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatReply {
+    /// message id of the referenced message
+    #[prost(bytes="vec", tag="1")]
+    pub message_id: ::prost::alloc::vec::Vec<u8>,
+    /// sender of the referenced message
+    #[prost(bytes="vec", tag="2")]
+    pub sender_id: ::prost::alloc::vec::Vec<u8>,
+    /// quoted preview of the referenced message
+    #[prost(string, tag="3")]
+    pub preview: ::prost::alloc::string::String,
 }
 /// file message
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -188,6 +277,61 @@ pub struct FileMessage {
     #[prost(bytes="vec", tag="1")]
     pub content: ::prost::alloc::vec::Vec<u8>,
 }
+/// poll message
+///
+/// The message id of the poll message is the id of the poll.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PollMessage {
+    /// question
+    #[prost(string, tag="1")]
+    pub question: ::prost::alloc::string::String,
+    /// answer options
+    #[prost(string, repeated, tag="2")]
+    pub options: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// more than one option can be chosen
+    #[prost(bool, tag="3")]
+    pub multiple_choice: bool,
+    /// time after which no more votes are accepted
+    ///
+    /// 0 = the poll has no deadline
+    #[prost(uint64, tag="4")]
+    pub deadline: u64,
+}
+/// signed vote on a poll
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct VoteMessage {
+    /// chosen option indexes
+    #[prost(uint32, repeated, tag="1")]
+    pub options: ::prost::alloc::vec::Vec<u32>,
+    /// message id of the poll
+    #[prost(bytes="vec", tag="2")]
+    pub poll_id: ::prost::alloc::vec::Vec<u8>,
+    /// time of the vote
+    #[prost(uint64, tag="3")]
+    pub voted_at: u64,
+    /// signature of the voter
+    ///
+    /// The signature is created over the protobuf
+    /// encoded `PollVote` message.
+    #[prost(bytes="vec", tag="4")]
+    pub signature: ::prost::alloc::vec::Vec<u8>,
+}
+/// the signed data of a vote
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PollVote {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// message id of the poll
+    #[prost(bytes="vec", tag="2")]
+    pub poll_id: ::prost::alloc::vec::Vec<u8>,
+    /// chosen option indexes
+    #[prost(uint32, repeated, tag="3")]
+    pub options: ::prost::alloc::vec::Vec<u32>,
+    /// time of the vote
+    #[prost(uint64, tag="4")]
+    pub voted_at: u64,
+}
 /// group message
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct GroupMessage {
@@ -2,7 +2,7 @@
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Chat {
     /// message type
-    #[prost(oneof="chat::Message", tags="3, 4, 5")]
+    #[prost(oneof="chat::Message", tags="3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18")]
     pub message: ::core::option::Option<chat::Message>,
 }
 /// Nested message and enum types in `Chat`.
@@ -19,6 +19,48 @@ pub mod chat {
         /// send a new chat message
         #[prost(message, tag="5")]
         Send(super::ChatMessageSend),
+        /// request the chat configuration
+        #[prost(message, tag="6")]
+        ConfigRequest(super::ChatConfigRequest),
+        /// chat configuration of the user account
+        #[prost(message, tag="7")]
+        Config(super::ChatConfig),
+        /// set the read receipts configuration
+        ///
+        /// the default retention time is set
+        /// via `retention_set`
+        #[prost(message, tag="8")]
+        ConfigSet(super::ChatConfig),
+        /// export a chat conversation
+        #[prost(message, tag="9")]
+        ExportRequest(super::ChatExportRequest),
+        /// result of a chat conversation export
+        #[prost(message, tag="10")]
+        ExportResponse(super::ChatExportResponse),
+        /// set the default message retention time
+        #[prost(message, tag="11")]
+        RetentionSet(super::ChatRetentionSet),
+        /// request all replies to a message
+        #[prost(message, tag="12")]
+        ThreadRequest(super::ChatThreadRequest),
+        /// list of a message thread
+        #[prost(message, tag="13")]
+        ThreadList(super::ChatThreadList),
+        /// create a new poll
+        #[prost(message, tag="14")]
+        PollCreate(super::ChatPollCreate),
+        /// vote on a poll
+        #[prost(message, tag="15")]
+        PollVote(super::ChatPollVote),
+        /// request the results of a poll
+        #[prost(message, tag="16")]
+        PollResultRequest(super::ChatPollResultRequest),
+        /// results of a poll
+        #[prost(message, tag="17")]
+        PollResult(super::ChatPollResult),
+        /// result of a poll creation or vote
+        #[prost(message, tag="18")]
+        PollResponse(super::ChatPollResponse),
     }
 }
 /// request messages of a specific chat conversation
@@ -92,7 +134,7 @@ pub struct MessageReceptionConfirmed {
 /// chat content message
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ChatContentMessage {
-    #[prost(oneof="chat_content_message::Message", tags="1, 2, 3")]
+    #[prost(oneof="chat_content_message::Message", tags="1, 2, 3, 4, 5")]
     pub message: ::core::option::Option<chat_content_message::Message>,
 }
 /// Nested message and enum types in `ChatContentMessage`.
@@ -108,6 +150,12 @@ pub mod chat_content_message {
         /// a group event information
         #[prost(message, tag="3")]
         GroupEvent(super::GroupEvent),
+        /// a poll
+        #[prost(message, tag="4")]
+        PollContent(super::PollContent),
+        /// a vote on a poll
+        #[prost(message, tag="5")]
+        PollVoteContent(super::PollVoteContent),
     }
 }
 /// chat content
@@ -116,6 +164,28 @@ pub struct ChatContent {
     /// message text
     #[prost(string, tag="1")]
     pub text: ::prost::alloc::string::String,
+    /// the message this message replies to
+    ///
+    /// only set for replies
+    #[prost(message, optional, tag="2")]
+    pub reply: ::core::option::Option<ChatReply>,
+}
+/// reply reference of a chat message
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatReply {
+    /// message id of the referenced message
+    #[prost(bytes="vec", tag="1")]
+    pub message_id: ::prost::alloc::vec::Vec<u8>,
+    /// sender of the referenced message
+    #[prost(bytes="vec", tag="2")]
+    pub sender_id: ::prost::alloc::vec::Vec<u8>,
+    /// quoted preview of the referenced message
+    ///
+    /// The preview is sent with the reply, so that
+    /// it can be displayed even if the referenced
+    /// message is not available.
+    #[prost(string, tag="3")]
+    pub preview: ::prost::alloc::string::String,
 }
 /// file content
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -136,6 +206,48 @@ pub struct FileContent {
     #[prost(string, tag="5")]
     pub file_description: ::prost::alloc::string::String,
 }
+/// poll content
+///
+/// The message id of the poll message is the id of the poll.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PollContent {
+    /// question
+    #[prost(string, tag="1")]
+    pub question: ::prost::alloc::string::String,
+    /// answer options
+    #[prost(string, repeated, tag="2")]
+    pub options: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// more than one option can be chosen
+    #[prost(bool, tag="3")]
+    pub multiple_choice: bool,
+    /// time after which no more votes are accepted
+    ///
+    /// 0 = the poll has no deadline
+    #[prost(uint64, tag="4")]
+    pub deadline: u64,
+}
+/// vote content
+///
+/// The vote is signed by the voter, the signature
+/// is validated when the votes are counted.
+/// A later vote of the same voter replaces the earlier one.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PollVoteContent {
+    /// message id of the poll
+    #[prost(bytes="vec", tag="1")]
+    pub poll_id: ::prost::alloc::vec::Vec<u8>,
+    /// chosen option indexes
+    ///
+    /// an empty list withdraws the vote
+    #[prost(uint32, repeated, tag="2")]
+    pub options: ::prost::alloc::vec::Vec<u32>,
+    /// time of the vote
+    #[prost(uint64, tag="3")]
+    pub voted_at: u64,
+    /// signature of the voter
+    #[prost(bytes="vec", tag="4")]
+    pub signature: ::prost::alloc::vec::Vec<u8>,
+}
 /// Group event information
 /// this message is purely informational
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -156,6 +268,221 @@ pub struct ChatMessageSend {
     /// content of the message
     #[prost(string, tag="2")]
     pub content: ::prost::alloc::string::String,
+    /// message id of the message this message replies to
+    ///
+    /// leave empty if the message is not a reply
+    #[prost(bytes="vec", tag="3")]
+    pub reply_to: ::prost::alloc::vec::Vec<u8>,
+}
+/// request the chat configuration
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatConfigRequest {
+}
+/// chat configuration of the user account
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatConfig {
+    /// send read receipts
+    ///
+    /// if enabled, the group members are informed
+    /// when we have read their messages.
+    #[prost(bool, tag="1")]
+    pub read_receipts: bool,
+    /// default message retention time in seconds
+    ///
+    /// This time is used for all groups which
+    /// don't have their own retention time.
+    /// 0 = messages are kept forever
+    #[prost(uint64, tag="2")]
+    pub retention: u64,
+}
+/// set the default message retention time
+/// of the user account
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatRetentionSet {
+    /// retention time in seconds
+    ///
+    /// 0 = messages are kept forever
+    #[prost(uint64, tag="1")]
+    pub retention: u64,
+}
+/// request the export of a chat conversation
+///
+/// The conversation is written to the `exports` folder
+/// of the user account.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatExportRequest {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// export file format
+    #[prost(enumeration="ChatExportFormat", tag="2")]
+    pub format: i32,
+    /// copy the files of the conversation
+    /// into the export folder
+    #[prost(bool, tag="3")]
+    pub include_files: bool,
+}
+/// result of a chat conversation export
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatExportResponse {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// was the export successful
+    #[prost(bool, tag="2")]
+    pub success: bool,
+    /// error message
+    #[prost(string, tag="3")]
+    pub error_message: ::prost::alloc::string::String,
+    /// path of the export folder
+    #[prost(string, tag="4")]
+    pub path: ::prost::alloc::string::String,
+    /// number of exported messages
+    #[prost(uint32, tag="5")]
+    pub message_count: u32,
+    /// number of bundled files
+    #[prost(uint32, tag="6")]
+    pub file_count: u32,
+}
+/// request a message thread
+///
+/// Returns the message and all replies to it,
+/// including the replies to the replies.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatThreadRequest {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// message id of the first message of the thread
+    #[prost(bytes="vec", tag="2")]
+    pub message_id: ::prost::alloc::vec::Vec<u8>,
+}
+/// list of the messages of a thread
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatThreadList {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// message id of the first message of the thread
+    #[prost(bytes="vec", tag="2")]
+    pub message_id: ::prost::alloc::vec::Vec<u8>,
+    /// the messages of the thread
+    ///
+    /// The first message of the thread is the first entry
+    /// in the list, if it is still available.
+    #[prost(message, repeated, tag="3")]
+    pub message_list: ::prost::alloc::vec::Vec<ChatMessage>,
+}
+/// create a new poll in a group
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatPollCreate {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// question
+    #[prost(string, tag="2")]
+    pub question: ::prost::alloc::string::String,
+    /// answer options
+    #[prost(string, repeated, tag="3")]
+    pub options: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// more than one option can be chosen
+    #[prost(bool, tag="4")]
+    pub multiple_choice: bool,
+    /// time after which no more votes are accepted
+    ///
+    /// 0 = the poll has no deadline
+    #[prost(uint64, tag="5")]
+    pub deadline: u64,
+}
+/// vote on a poll
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatPollVote {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// message id of the poll
+    #[prost(bytes="vec", tag="2")]
+    pub poll_id: ::prost::alloc::vec::Vec<u8>,
+    /// chosen option indexes
+    ///
+    /// an empty list withdraws the vote
+    #[prost(uint32, repeated, tag="3")]
+    pub options: ::prost::alloc::vec::Vec<u32>,
+}
+/// result of a poll creation or vote
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatPollResponse {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// message id of the poll
+    ///
+    /// the id of the new poll, if a poll was created
+    #[prost(bytes="vec", tag="2")]
+    pub poll_id: ::prost::alloc::vec::Vec<u8>,
+    /// was the request successful
+    #[prost(bool, tag="3")]
+    pub success: bool,
+    /// error message
+    #[prost(string, tag="4")]
+    pub error_message: ::prost::alloc::string::String,
+}
+/// request the results of a poll
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatPollResultRequest {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// message id of the poll
+    #[prost(bytes="vec", tag="2")]
+    pub poll_id: ::prost::alloc::vec::Vec<u8>,
+}
+/// results of a poll
+///
+/// The results are counted from the votes in the
+/// chat conversation. Only the last valid vote of
+/// each voter is counted.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatPollResult {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// message id of the poll
+    #[prost(bytes="vec", tag="2")]
+    pub poll_id: ::prost::alloc::vec::Vec<u8>,
+    /// the poll
+    #[prost(message, optional, tag="3")]
+    pub poll: ::core::option::Option<PollContent>,
+    /// results per option
+    #[prost(message, repeated, tag="4")]
+    pub options: ::prost::alloc::vec::Vec<ChatPollOptionResult>,
+    /// number of voters
+    #[prost(uint32, tag="5")]
+    pub voters: u32,
+    /// the deadline of the poll has passed
+    #[prost(bool, tag="6")]
+    pub closed: bool,
+    /// error message
+    ///
+    /// the message is empty if the results could be counted
+    #[prost(string, tag="7")]
+    pub error_message: ::prost::alloc::string::String,
+}
+/// results of a poll option
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChatPollOptionResult {
+    /// option index
+    #[prost(uint32, tag="1")]
+    pub index: u32,
+    /// option text
+    #[prost(string, tag="2")]
+    pub option: ::prost::alloc::string::String,
+    /// number of votes
+    #[prost(uint32, tag="3")]
+    pub votes: u32,
+    /// user ids of the voters
+    #[prost(bytes="vec", repeated, tag="4")]
+    pub voter_ids: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
 }
 /// Sending status of sent messages
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
@@ -238,3 +565,24 @@ impl GroupEventType {
         }
     }
 }
+/// export file format
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ChatExportFormat {
+    /// JSON file
+    Json = 0,
+    /// Markdown file
+    Markdown = 1,
+}
+impl ChatExportFormat {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            ChatExportFormat::Json => "JSON",
+            ChatExportFormat::Markdown => "MARKDOWN",
+        }
+    }
+}
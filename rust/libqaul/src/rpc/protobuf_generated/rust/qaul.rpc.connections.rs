@@ -1,7 +1,7 @@
 /// Connections rpc message container
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Connections {
-    #[prost(oneof="connections::Message", tags="1, 2, 3, 4, 5, 6")]
+    #[prost(oneof="connections::Message", tags="1, 2, 3, 4, 5, 6, 7, 8")]
     pub message: ::core::option::Option<connections::Message>,
 }
 /// Nested message and enum types in `Connections`.
@@ -32,6 +32,13 @@ pub mod connections {
         /// libqaul returns an internet_nodes_list message.
         #[prost(message, tag="6")]
         InternetNodesRename(super::InternetNodesEntry),
+        /// Request the bandwidth statistics.
+        /// libqaul returns a bandwidth_statistics message.
+        #[prost(message, tag="7")]
+        BandwidthRequest(super::BandwidthRequest),
+        /// Bandwidth statistics
+        #[prost(message, tag="8")]
+        BandwidthStatistics(super::BandwidthStatistics),
     }
 }
 /// UI request for Internet nodes list
@@ -72,6 +79,126 @@ pub struct InternetNodesEntry {
     /// name
     #[prost(string, tag="3")]
     pub name: ::prost::alloc::string::String,
+    /// live connection status of the node
+    ///
+    /// This field is only filled in by libqaul
+    /// in the internet nodes list.
+    #[prost(message, optional, tag="4")]
+    pub status: ::core::option::Option<InternetNodeStatus>,
+}
+/// Live connection status of an internet node
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct InternetNodeStatus {
+    /// connection state
+    #[prost(enumeration="InternetNodeState", tag="1")]
+    pub state: i32,
+    /// error message of the last failed
+    /// connection attempt
+    #[prost(string, tag="2")]
+    pub last_error: ::prost::alloc::string::String,
+    /// round trip time in micro seconds
+    #[prost(uint32, tag="3")]
+    pub rtt: u32,
+    /// connected since, timestamp in milli seconds
+    #[prost(uint64, tag="4")]
+    pub connected_since: u64,
+    /// bytes of qaul messages sent to the node
+    #[prost(uint64, tag="5")]
+    pub bytes_sent: u64,
+    /// bytes of qaul messages received from the node
+    #[prost(uint64, tag="6")]
+    pub bytes_received: u64,
+    /// node id of the connected node
+    #[prost(bytes="vec", tag="7")]
+    pub node_id: ::prost::alloc::vec::Vec<u8>,
+    /// number of failed connection attempts
+    #[prost(uint32, tag="8")]
+    pub attempts: u32,
+    /// next connection attempt, timestamp in milli seconds
+    #[prost(uint64, tag="9")]
+    pub next_attempt: u64,
+}
+/// UI request for the bandwidth statistics
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BandwidthRequest {
+    /// reset all counters after creating the statistics
+    #[prost(bool, tag="1")]
+    pub reset: bool,
+}
+/// Bandwidth Statistics
+///
+/// Bytes and messages sent & received since the
+/// start of libqaul or since the last reset.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BandwidthStatistics {
+    /// counting since, timestamp in milli seconds
+    #[prost(uint64, tag="1")]
+    pub since: u64,
+    /// statistics per connection module
+    #[prost(message, repeated, tag="2")]
+    pub modules: ::prost::alloc::vec::Vec<BandwidthModuleEntry>,
+    /// statistics per neighbour
+    #[prost(message, repeated, tag="3")]
+    pub neighbours: ::prost::alloc::vec::Vec<BandwidthNeighbourEntry>,
+    /// statistics per service
+    #[prost(message, repeated, tag="4")]
+    pub services: ::prost::alloc::vec::Vec<BandwidthServiceEntry>,
+}
+/// Bandwidth counters
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BandwidthCounter {
+    /// bytes sent
+    #[prost(uint64, tag="1")]
+    pub bytes_sent: u64,
+    /// bytes received
+    #[prost(uint64, tag="2")]
+    pub bytes_received: u64,
+    /// messages sent
+    #[prost(uint64, tag="3")]
+    pub messages_sent: u64,
+    /// messages received
+    #[prost(uint64, tag="4")]
+    pub messages_received: u64,
+    /// messages not sent due to the bandwidth limit
+    #[prost(uint64, tag="5")]
+    pub messages_dropped: u64,
+}
+/// Bandwidth of a connection module
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BandwidthModuleEntry {
+    /// connection module
+    #[prost(enumeration="BandwidthModule", tag="1")]
+    pub module: i32,
+    /// counters
+    #[prost(message, optional, tag="2")]
+    pub counter: ::core::option::Option<BandwidthCounter>,
+    /// configured limit in bytes per second,
+    /// 0 means unlimited
+    #[prost(uint64, tag="3")]
+    pub limit: u64,
+}
+/// Bandwidth of a neighbour node
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BandwidthNeighbourEntry {
+    /// node id of the neighbour
+    #[prost(bytes="vec", tag="1")]
+    pub node_id: ::prost::alloc::vec::Vec<u8>,
+    /// connection module
+    #[prost(enumeration="BandwidthModule", tag="2")]
+    pub module: i32,
+    /// counters
+    #[prost(message, optional, tag="3")]
+    pub counter: ::core::option::Option<BandwidthCounter>,
+}
+/// Bandwidth of a service
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BandwidthServiceEntry {
+    /// service
+    #[prost(enumeration="BandwidthService", tag="1")]
+    pub service: i32,
+    /// counters
+    #[prost(message, optional, tag="2")]
+    pub counter: ::core::option::Option<BandwidthCounter>,
 }
 /// Information about the system actions that led to
 /// the creation of this message.
@@ -111,3 +238,100 @@ impl Info {
         }
     }
 }
+/// Connection state of an internet node
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum InternetNodeState {
+    /// not connected
+    Disconnected = 0,
+    /// the node is being dialed
+    Connecting = 1,
+    /// the node is connected
+    Connected = 2,
+    /// the last connection attempt failed
+    Failed = 3,
+    /// the node is disabled
+    Disabled = 4,
+}
+impl InternetNodeState {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            InternetNodeState::Disconnected => "DISCONNECTED",
+            InternetNodeState::Connecting => "CONNECTING",
+            InternetNodeState::Connected => "CONNECTED",
+            InternetNodeState::Failed => "FAILED",
+            InternetNodeState::Disabled => "DISABLED",
+        }
+    }
+}
+/// Connection module the bandwidth is counted for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum BandwidthModule {
+    Lan = 0,
+    Internet = 1,
+    Ble = 2,
+    Stream = 3,
+}
+impl BandwidthModule {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            BandwidthModule::Lan => "LAN",
+            BandwidthModule::Internet => "INTERNET",
+            BandwidthModule::Ble => "BLE",
+            BandwidthModule::Stream => "STREAM",
+        }
+    }
+}
+/// Service the bandwidth is counted for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum BandwidthService {
+    /// routing information and user tables
+    Routing = 0,
+    /// public feed messages
+    Feed = 1,
+    /// chat messages
+    Chat = 2,
+    /// file messages
+    Files = 3,
+    /// DTN messages
+    Dtn = 4,
+    /// RTC messages
+    Rtc = 5,
+    /// group management messages
+    Group = 6,
+    /// crypto handshake messages
+    Crypto = 7,
+    /// all other messages, e.g. forwarded and
+    /// received end-to-end encrypted messages
+    /// and confirmations
+    Messaging = 8,
+}
+impl BandwidthService {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            BandwidthService::Routing => "ROUTING",
+            BandwidthService::Feed => "FEED",
+            BandwidthService::Chat => "CHAT",
+            BandwidthService::Files => "FILES",
+            BandwidthService::Dtn => "DTN",
+            BandwidthService::Rtc => "RTC",
+            BandwidthService::Group => "GROUP",
+            BandwidthService::Crypto => "CRYPTO",
+            BandwidthService::Messaging => "MESSAGING",
+        }
+    }
+}
@@ -63,6 +63,8 @@ pub enum Modules {
     Rtc = 13,
     /// Delay Tolerant Networking
     Dtn = 14,
+    /// Search chat messages & feed messages
+    Search = 15,
 }
 impl Modules {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -86,6 +88,7 @@ impl Modules {
             Modules::Ble => "BLE",
             Modules::Rtc => "RTC",
             Modules::Dtn => "DTN",
+            Modules::Search => "SEARCH",
         }
     }
 }
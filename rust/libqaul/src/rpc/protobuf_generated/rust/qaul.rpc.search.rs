@@ -0,0 +1,167 @@
+/// Search service RPC message container
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Search {
+    /// message type
+    #[prost(oneof="search::Message", tags="1, 2")]
+    pub message: ::core::option::Option<search::Message>,
+}
+/// Nested message and enum types in `Search`.
+pub mod search {
+    /// message type
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Message {
+        /// search request
+        #[prost(message, tag="1")]
+        SearchRequest(super::SearchRequest),
+        /// search response
+        #[prost(message, tag="2")]
+        SearchResponse(super::SearchResponse),
+    }
+}
+/// Search Request
+///
+/// All search terms of the query need to be found
+/// in a message, for the message to be a result.
+/// A search term matches all words starting with it.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SearchRequest {
+    /// search query
+    #[prost(string, tag="1")]
+    pub query: ::prost::alloc::string::String,
+    /// sources to search in
+    #[prost(enumeration="SearchSource", tag="2")]
+    pub source: i32,
+    /// only search in this group
+    ///
+    /// if empty, all groups are searched.
+    /// if set, no feed messages are searched.
+    #[prost(bytes="vec", tag="3")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// only search messages sent by this user
+    ///
+    /// if empty, messages of all users are searched.
+    #[prost(bytes="vec", tag="4")]
+    pub sender_id: ::prost::alloc::vec::Vec<u8>,
+    /// only search messages sent at or after this time
+    ///
+    /// time stamp in milli seconds,
+    /// 0 = no lower limit
+    #[prost(uint64, tag="5")]
+    pub sent_after: u64,
+    /// only search messages sent at or before this time
+    ///
+    /// time stamp in milli seconds,
+    /// 0 = no upper limit
+    #[prost(uint64, tag="6")]
+    pub sent_before: u64,
+    /// offset of the results
+    #[prost(uint32, tag="7")]
+    pub offset: u32,
+    /// maximal number of results
+    ///
+    /// 0 = default limit of 20 results
+    #[prost(uint32, tag="8")]
+    pub limit: u32,
+}
+/// Search Response
+///
+/// The results are sorted by the time they were sent,
+/// the newest result first.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SearchResponse {
+    /// search query
+    #[prost(string, tag="1")]
+    pub query: ::prost::alloc::string::String,
+    /// total number of results found
+    #[prost(uint32, tag="2")]
+    pub total: u32,
+    /// offset of the results
+    #[prost(uint32, tag="3")]
+    pub offset: u32,
+    /// the results
+    #[prost(message, repeated, tag="4")]
+    pub results: ::prost::alloc::vec::Vec<SearchResult>,
+}
+/// A single search result
+///
+/// It references the message in the chat conversation
+/// or the feed.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SearchResult {
+    /// type of the result
+    #[prost(enumeration="SearchResultType", tag="1")]
+    pub result_type: i32,
+    /// group id of the chat conversation
+    ///
+    /// empty for feed messages
+    #[prost(bytes="vec", tag="2")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// message id
+    ///
+    /// chat message id or feed message id
+    #[prost(bytes="vec", tag="3")]
+    pub message_id: ::prost::alloc::vec::Vec<u8>,
+    /// index of the message
+    ///
+    /// this is the index of the message within the
+    /// chat conversation or the feed index.
+    #[prost(uint64, tag="4")]
+    pub index: u64,
+    /// id of the sending user
+    #[prost(bytes="vec", tag="5")]
+    pub sender_id: ::prost::alloc::vec::Vec<u8>,
+    /// time when the message was sent
+    #[prost(uint64, tag="6")]
+    pub sent_at: u64,
+    /// text snippet around the first search term found
+    #[prost(string, tag="7")]
+    pub snippet: ::prost::alloc::string::String,
+}
+/// Sources to search in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum SearchSource {
+    /// search chat messages and feed messages
+    All = 0,
+    /// search chat messages and chat files only
+    Chat = 1,
+    /// search public feed messages only
+    Feed = 2,
+}
+impl SearchSource {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            SearchSource::All => "ALL",
+            SearchSource::Chat => "CHAT",
+            SearchSource::Feed => "FEED",
+        }
+    }
+}
+/// Type of a search result
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum SearchResultType {
+    /// chat text message
+    ChatMessage = 0,
+    /// chat file message
+    ChatFile = 1,
+    /// public feed message
+    FeedMessage = 2,
+}
+impl SearchResultType {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            SearchResultType::ChatMessage => "CHAT_MESSAGE",
+            SearchResultType::ChatFile => "CHAT_FILE",
+            SearchResultType::FeedMessage => "FEED_MESSAGE",
+        }
+    }
+}
@@ -147,6 +147,7 @@ pub enum ConnectionModule {
     Internet = 2,
     Ble = 3,
     Local = 4,
+    Stream = 5,
 }
 impl ConnectionModule {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -160,6 +161,7 @@ impl ConnectionModule {
             ConnectionModule::Internet => "INTERNET",
             ConnectionModule::Ble => "BLE",
             ConnectionModule::Local => "LOCAL",
+            ConnectionModule::Stream => "STREAM",
         }
     }
 }
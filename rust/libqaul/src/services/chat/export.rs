@@ -0,0 +1,417 @@
+// Copyright (c) 2023 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Chat Conversation Export
+//!
+//! Exports the decoded history of a chat conversation
+//! into a JSON or a Markdown file.
+//!
+//! The export is written into the `exports` folder of
+//! the user account:
+//!
+//! `{account folder}/exports/{group id}_{timestamp}/conversation.{json|md}`
+//!
+//! If requested, the files of the conversation are copied into
+//! the `files` sub folder of the export folder.
+
+use libp2p::PeerId;
+use prost::Message;
+use serde::Serialize;
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use super::{rpc_proto, ChatFile, ChatStorage};
+use crate::router::users::Users;
+use crate::services::group::{group_id::GroupId, GroupStorage};
+use crate::utilities::timestamp::Timestamp;
+
+/// Exported chat conversation
+#[derive(Serialize)]
+pub struct ExportConversation {
+    /// group id
+    pub group_id: String,
+    /// group name
+    pub group_name: String,
+    /// time of the export
+    pub exported_at: String,
+    /// messages of the conversation
+    pub messages: Vec<ExportMessage>,
+}
+
+/// Exported chat message
+#[derive(Serialize)]
+pub struct ExportMessage {
+    /// index of the message in the conversation
+    pub index: u64,
+    /// message id
+    pub message_id: String,
+    /// user id of the sender
+    pub sender_id: String,
+    /// name of the sender
+    pub sender_name: String,
+    /// time when the message was sent
+    pub sent_at: String,
+    /// time when the message was received
+    pub received_at: String,
+    /// delivery status of the message
+    pub status: String,
+    /// text of a chat message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// the message this chat message replies to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply: Option<ExportReply>,
+    /// file reference of a file message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<ExportFile>,
+    /// group event
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event: Option<ExportEvent>,
+}
+
+/// Exported reply reference
+#[derive(Serialize)]
+pub struct ExportReply {
+    /// message id of the referenced message
+    pub message_id: String,
+    /// user id of the sender of the referenced message
+    pub sender_id: String,
+    /// name of the sender of the referenced message
+    pub sender_name: String,
+    /// quoted preview of the referenced message
+    pub preview: String,
+}
+
+/// Exported file reference
+#[derive(Serialize)]
+pub struct ExportFile {
+    /// file id
+    pub file_id: u64,
+    /// file name
+    pub file_name: String,
+    /// file extension
+    pub file_extension: String,
+    /// file size in bytes
+    pub file_size: u32,
+    /// file description
+    pub file_description: String,
+    /// relative path of the file in the export folder
+    ///
+    /// only set if the file was bundled with the export
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// Exported group event
+#[derive(Serialize)]
+pub struct ExportEvent {
+    /// group event type
+    pub event_type: String,
+    /// user id of the concerned user
+    pub user_id: String,
+    /// name of the concerned user
+    pub user_name: String,
+}
+
+/// Chat Conversation Export
+pub struct ChatExport {}
+
+impl ChatExport {
+    /// Export a chat conversation
+    ///
+    /// Returns the response message, which is sent to the RPC client.
+    pub fn export(
+        account_id: &PeerId,
+        request: &rpc_proto::ChatExportRequest,
+    ) -> rpc_proto::ChatExportResponse {
+        let mut response = rpc_proto::ChatExportResponse {
+            group_id: request.group_id.clone(),
+            success: false,
+            error_message: "".to_string(),
+            path: "".to_string(),
+            message_count: 0,
+            file_count: 0,
+        };
+
+        match Self::write_export(account_id, request) {
+            Ok((path, message_count, file_count)) => {
+                response.success = true;
+                response.path = path.to_string_lossy().to_string();
+                response.message_count = message_count;
+                response.file_count = file_count;
+            }
+            Err(e) => {
+                log::error!("chat export error: {}", e);
+                response.error_message = e;
+            }
+        }
+
+        response
+    }
+
+    /// Create the export folder and write the export into it
+    ///
+    /// Returns the path of the export folder, the number of
+    /// exported messages and the number of bundled files.
+    fn write_export(
+        account_id: &PeerId,
+        request: &rpc_proto::ChatExportRequest,
+    ) -> Result<(PathBuf, u32, u32), String> {
+        // get group
+        let group;
+        match GroupStorage::get_group(account_id.to_owned(), request.group_id.clone()) {
+            Some(my_group) => group = my_group,
+            None => return Err("group not found".to_string()),
+        }
+        let group_id = GroupId::slice_to_string(&group.id);
+
+        // create export folder
+        let exported_at = Timestamp::get_timestamp();
+        let export_path = crate::storage::Storage::get_account_path(account_id.to_owned())
+            .join("exports")
+            .join(format!("{}_{}", group_id, exported_at));
+        if let Err(e) = fs::create_dir_all(&export_path) {
+            return Err(format!("creating export folder error {}", e));
+        }
+
+        // create conversation
+        let mut conversation = ExportConversation {
+            group_id,
+            group_name: group.name,
+            exported_at: Self::format_time(exported_at),
+            messages: Vec::new(),
+        };
+
+        let mut file_count = 0;
+        for message in ChatStorage::get_conversation(account_id.to_owned(), &group.id) {
+            let mut export_message = Self::create_message(&message);
+
+            // bundle file
+            if request.include_files {
+                if let Some(file) = export_message.file.as_mut() {
+                    if let Some(path) = Self::bundle_file(
+                        account_id,
+                        &export_path,
+                        file.file_id,
+                        &file.file_extension,
+                    ) {
+                        file.path = Some(path);
+                        file_count = file_count + 1;
+                    }
+                }
+            }
+
+            conversation.messages.push(export_message);
+        }
+        let message_count = conversation.messages.len() as u32;
+
+        // write export file
+        let (file_name, content) = match rpc_proto::ChatExportFormat::from_i32(request.format) {
+            Some(rpc_proto::ChatExportFormat::Markdown) => {
+                ("conversation.md", Self::to_markdown(&conversation))
+            }
+            _ => match serde_json::to_string_pretty(&conversation) {
+                Ok(json) => ("conversation.json", json),
+                Err(e) => return Err(format!("json encoding error {}", e)),
+            },
+        };
+        if let Err(e) = fs::write(export_path.join(file_name), content) {
+            return Err(format!("writing export file error {}", e));
+        }
+
+        Ok((export_path, message_count, file_count))
+    }
+
+    /// Decode a chat message into it's export structure
+    fn create_message(message: &rpc_proto::ChatMessage) -> ExportMessage {
+        let status = match rpc_proto::MessageStatus::from_i32(message.status) {
+            Some(status) => status.as_str_name().to_lowercase(),
+            None => "".to_string(),
+        };
+
+        let mut export_message = ExportMessage {
+            index: message.index,
+            message_id: bs58::encode(&message.message_id).into_string(),
+            sender_id: bs58::encode(&message.sender_id).into_string(),
+            sender_name: Self::user_name(&message.sender_id),
+            sent_at: Self::format_time(message.sent_at),
+            received_at: Self::format_time(message.received_at),
+            status,
+            text: None,
+            reply: None,
+            file: None,
+            event: None,
+        };
+
+        match rpc_proto::ChatContentMessage::decode(&message.content[..]) {
+            Ok(content) => match content.message {
+                Some(rpc_proto::chat_content_message::Message::ChatContent(chat_content)) => {
+                    export_message.text = Some(chat_content.text);
+                    export_message.reply = chat_content.reply.map(|reply| ExportReply {
+                        message_id: bs58::encode(&reply.message_id).into_string(),
+                        sender_id: bs58::encode(&reply.sender_id).into_string(),
+                        sender_name: Self::user_name(&reply.sender_id),
+                        preview: reply.preview,
+                    });
+                }
+                Some(rpc_proto::chat_content_message::Message::FileContent(file_content)) => {
+                    export_message.file = Some(ExportFile {
+                        file_id: file_content.file_id,
+                        file_name: file_content.file_name,
+                        file_extension: file_content.file_extension,
+                        file_size: file_content.file_size,
+                        file_description: file_content.file_description,
+                        path: None,
+                    });
+                }
+                Some(rpc_proto::chat_content_message::Message::PollContent(poll_content)) => {
+                    let mut text = format!("Poll: {}", poll_content.question);
+                    for (index, option) in poll_content.options.iter().enumerate() {
+                        text = text + &format!("\n{}. {}", index + 1, option);
+                    }
+                    export_message.text = Some(text);
+                }
+                Some(rpc_proto::chat_content_message::Message::PollVoteContent(vote_content)) => {
+                    let options: Vec<String> = vote_content
+                        .options
+                        .iter()
+                        .map(|index| (index + 1).to_string())
+                        .collect();
+                    export_message.text = Some(format!(
+                        "Vote on poll {}: {}",
+                        bs58::encode(&vote_content.poll_id).into_string(),
+                        options.join(", ")
+                    ));
+                }
+                Some(rpc_proto::chat_content_message::Message::GroupEvent(group_event)) => {
+                    let event_type =
+                        match rpc_proto::GroupEventType::from_i32(group_event.event_type) {
+                            Some(event_type) => event_type.as_str_name().to_lowercase(),
+                            None => "".to_string(),
+                        };
+                    export_message.event = Some(ExportEvent {
+                        event_type,
+                        user_id: bs58::encode(&group_event.user_id).into_string(),
+                        user_name: Self::user_name(&group_event.user_id),
+                    });
+                }
+                None => {}
+            },
+            Err(e) => {
+                log::error!("chat export: content decoding error {}", e);
+            }
+        }
+
+        export_message
+    }
+
+    /// Copy a file of the conversation into the export folder
+    ///
+    /// Returns the relative path of the copied file,
+    /// if the file exists in the files folder of the user account.
+    fn bundle_file(
+        account_id: &PeerId,
+        export_path: &PathBuf,
+        file_id: u64,
+        file_extension: &str,
+    ) -> Option<String> {
+        let source = ChatFile::create_file_path(account_id.to_owned(), file_id, file_extension);
+        if !source.exists() {
+            return None;
+        }
+
+        let files_path = export_path.join("files");
+        if let Err(e) = fs::create_dir_all(&files_path) {
+            log::error!("creating export files folder error {}", e);
+            return None;
+        }
+
+        let file_name = source.file_name()?.to_string_lossy().to_string();
+        match fs::copy(&source, files_path.join(&file_name)) {
+            Ok(_) => Some(format!("files/{}", file_name)),
+            Err(e) => {
+                log::error!("copying file {} error {}", file_name, e);
+                None
+            }
+        }
+    }
+
+    /// Create the Markdown representation of a conversation
+    fn to_markdown(conversation: &ExportConversation) -> String {
+        let mut md = String::new();
+
+        md.push_str(&format!("# {}\n\n", conversation.group_name));
+        md.push_str(&format!("* Group ID: `{}`\n", conversation.group_id));
+        md.push_str(&format!("* Exported at: {}\n\n", conversation.exported_at));
+
+        for message in &conversation.messages {
+            if let Some(event) = &message.event {
+                md.push_str(&format!(
+                    "*{}: {} {}*\n\n",
+                    message.sent_at, event.user_name, event.event_type
+                ));
+                continue;
+            }
+
+            md.push_str(&format!(
+                "**{}** ({}, {})\n\n",
+                message.sender_name, message.sent_at, message.status
+            ));
+
+            if let Some(reply) = &message.reply {
+                md.push_str(&format!(
+                    "> Reply to {}: *{}*\n>\n",
+                    reply.sender_name, reply.preview
+                ));
+            }
+
+            if let Some(text) = &message.text {
+                for line in text.lines() {
+                    md.push_str(&format!("> {}\n", line));
+                }
+                md.push_str("\n");
+            }
+
+            if let Some(file) = &message.file {
+                match &file.path {
+                    Some(path) => md.push_str(&format!(
+                        "> File: [{}]({}) ({} bytes)\n",
+                        file.file_name, path, file.file_size
+                    )),
+                    None => md.push_str(&format!(
+                        "> File: {} ({} bytes)\n",
+                        file.file_name, file.file_size
+                    )),
+                }
+                if file.file_description.len() > 0 {
+                    md.push_str(&format!(">\n> {}\n", file.file_description));
+                }
+                md.push_str("\n");
+            }
+        }
+
+        md
+    }
+
+    /// get the name of a user
+    ///
+    /// If the user is unknown, the bs58 encoded user id is returned.
+    fn user_name(user_id: &Vec<u8>) -> String {
+        if let Ok(id) = PeerId::from_bytes(user_id) {
+            if let Some(name) = Users::get_name(&id) {
+                return name;
+            }
+        }
+
+        bs58::encode(user_id).into_string()
+    }
+
+    /// format a timestamp in milliseconds as RFC3339 time string
+    fn format_time(timestamp: u64) -> String {
+        let time = UNIX_EPOCH + Duration::from_millis(timestamp);
+        humantime::format_rfc3339_millis(time).to_string()
+    }
+}
@@ -116,6 +116,32 @@ impl UserFiles {
         }
     }
 
+    /// remove file history and all file chunks of a file
+    pub fn remove_file(&self, file_id: u64) {
+        // remove file history
+        if let Err(e) = self.histories.remove(file_id.to_be_bytes().to_vec()) {
+            log::error!("Error removing file history from data base: {}", e);
+        }
+
+        // remove file chunks
+        let (first_key, last_key) = Self::get_chunk_key_range(&file_id.to_be_bytes().to_vec());
+        for res in self.file_chunks.range(first_key..last_key) {
+            if let Ok((key, _chunk)) = res {
+                if let Err(e) = self.file_chunks.remove(key) {
+                    log::error!("Error removing file chunk from data base: {}", e);
+                }
+            }
+        }
+
+        // flush trees to disk
+        if let Err(e) = self.histories.flush() {
+            log::error!("Error file history flush: {}", e);
+        }
+        if let Err(e) = self.file_chunks.flush() {
+            log::error!("Error file chunks flush: {}", e);
+        }
+    }
+
     /// create a db chunk key
     fn get_chunk_key(file_id: &Vec<u8>, index: u32) -> Vec<u8> {
         let mut index_bytes = index.to_be_bytes().to_vec();
@@ -372,13 +398,31 @@ impl ChatFile {
         }
     }
 
+    /// Delete a file
+    ///
+    /// Removes the file from the files folder of the user account
+    /// and deletes it's file history and file chunks from the data base.
+    pub fn delete_file(account_id: &PeerId, file_id: u64, file_extension: &str) {
+        // remove file from data base
+        let user_files = ChatFile::get_db_ref(account_id);
+        user_files.remove_file(file_id);
+
+        // remove file from disk
+        let file_path = Self::create_file_path(account_id.to_owned(), file_id, file_extension);
+        if file_path.exists() {
+            if let Err(e) = fs::remove_file(&file_path) {
+                log::error!("removing file {} error {}", file_path.display(), e);
+            }
+        }
+    }
+
     /// getting file extension from given filename
     fn get_extension_from_filename(filename: &str) -> Option<&str> {
         Path::new(filename).extension().and_then(OsStr::to_str)
     }
 
     /// Create and return the file path for a file
-    fn create_file_path(account_id: PeerId, file_id: u64, file_extension: &str) -> PathBuf {
+    pub fn create_file_path(account_id: PeerId, file_id: u64, file_extension: &str) -> PathBuf {
         // create path to file storage directory
         let account_storage_path = crate::storage::Storage::get_account_path(account_id);
         let files_storage_path = account_storage_path.join("files");
@@ -479,6 +523,11 @@ impl ChatFile {
             }
         }
 
+        // check post permission
+        if !group.can_post(&user_account.id.to_bytes()) {
+            return Err("only administrators can post into this group".to_string());
+        }
+
         let timestamp = Timestamp::get_timestamp();
 
         let mut file: File;
@@ -710,6 +759,8 @@ impl ChatFile {
             payload: Some(messaging::proto::common_message::Payload::FileMessage(
                 messaging::proto::FileMessage { content: data },
             )),
+            group_revision: group.revision,
+            group_info_hash: group.info_hash(),
         };
 
         let message = messaging::proto::Messaging {
@@ -718,6 +769,20 @@ impl ChatFile {
             )),
         };
 
+        // encrypt once with our sender key and send to all members
+        if !group.is_direct_chat {
+            if let Err(error) = Messaging::pack_and_send_group_message(
+                user_account,
+                group,
+                message.encode_to_vec(),
+                MessagingServiceType::ChatFile,
+                message_id,
+            ) {
+                log::error!("sending file message error {}", error);
+            }
+            return;
+        }
+
         // send to all members
         for user_id in group.members.keys() {
             let receiver = PeerId::from_bytes(&user_id.clone()).unwrap();
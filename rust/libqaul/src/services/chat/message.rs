@@ -16,8 +16,12 @@ use super::{Chat, ChatStorage};
 use crate::node::user_accounts::{UserAccount, UserAccounts};
 use crate::services::group::{Group, GroupId, GroupManage, GroupStorage};
 use crate::services::messaging::{proto, Messaging, MessagingServiceType};
+use crate::storage::configuration::Configuration;
 use crate::utilities::timestamp::Timestamp;
 
+/// maximal number of characters of a quoted reply preview
+const REPLY_PREVIEW_LENGTH: usize = 100;
+
 /// Incoming and outgoing chat message handling
 pub struct ChatMessage {}
 
@@ -44,12 +48,67 @@ impl ChatMessage {
     }
 
     /// send message
+    ///
+    /// If `reply_to` contains a message id, the message is sent
+    /// as a reply to this message.
     pub fn send_chat_message(
         account_id: &PeerId,
         group_id: &Vec<u8>,
         message: String,
+        reply_to: &Vec<u8>,
     ) -> Result<bool, String> {
-        let groupid = GroupId::from_bytes(&group_id).unwrap();
+        let group = Self::get_group(account_id, group_id)?;
+
+        // check post permission
+        if !group.can_post(&account_id.to_bytes()) {
+            return Err("only administrators can post into this group".to_string());
+        }
+
+        // create reply reference
+        let mut reply: Option<rpc_proto::ChatReply> = None;
+        if reply_to.len() > 0 {
+            reply = Some(Self::create_reply(account_id, &group.id, reply_to)?);
+        }
+
+        // pack message
+        let payload = proto::common_message::Payload::ChatMessage(proto::ChatMessage {
+            content: message.clone(),
+            reply: reply.clone().map(|r| proto::ChatReply {
+                message_id: r.message_id,
+                sender_id: r.sender_id,
+                preview: r.preview,
+            }),
+        });
+
+        let message_content = super::rpc_proto::ChatContentMessage {
+            message: Some(
+                super::rpc_proto::chat_content_message::Message::ChatContent(
+                    super::rpc_proto::ChatContent {
+                        text: message,
+                        reply,
+                    },
+                ),
+            ),
+        };
+
+        Self::send_content(account_id, &group, payload, message_content)?;
+
+        Ok(true)
+    }
+
+    /// get the group to send a message to
+    ///
+    /// If the group is a direct chat group that does not exist yet,
+    /// it is created.
+    /// Returns an error if we are not a member of the group
+    /// or if the group is deactivated.
+    pub fn get_group(account_id: &PeerId, group_id: &Vec<u8>) -> Result<Group, String> {
+        let groupid;
+        match GroupId::from_bytes(group_id) {
+            Ok(id) => groupid = id,
+            Err(_) => return Err("invalid group id".to_string()),
+        }
+
         let group;
         match GroupStorage::get_group(account_id.to_owned(), group_id.to_owned()) {
             Some(v) => group = v,
@@ -73,6 +132,30 @@ impl ChatMessage {
             }
         }
 
+        if !group.is_member(&account_id.to_bytes()) {
+            return Err("you are not member in this group".to_string());
+        }
+
+        // a deactivated group doesn't accept new messages
+        if group.status == crate::services::group::proto_rpc::GroupStatus::Deactivated as i32 {
+            return Err("group is deactivated".to_string());
+        }
+
+        Ok(group)
+    }
+
+    /// send a chat content to all group members
+    ///
+    /// The content is saved to the conversation and the payload
+    /// is sent to all members of the group.
+    ///
+    /// Returns the message id of the sent message.
+    pub fn send_content(
+        account_id: &PeerId,
+        group: &Group,
+        payload: proto::common_message::Payload,
+        content: rpc_proto::ChatContentMessage,
+    ) -> Result<Vec<u8>, String> {
         let mut my_member;
         match group.get_member(&account_id.to_bytes()) {
             Some(v) => {
@@ -83,6 +166,12 @@ impl ChatMessage {
             }
         }
 
+        let groupid;
+        match GroupId::from_bytes(&group.id) {
+            Ok(id) => groupid = id,
+            Err(_) => return Err("invalid group id".to_string()),
+        }
+
         let last_index = my_member.last_message_index + 1;
         let timestamp = Timestamp::get_timestamp();
         let message_id = Chat::generate_message_id(&group.id, account_id, last_index);
@@ -92,19 +181,9 @@ impl ChatMessage {
             message_id: message_id.clone(),
             group_id: groupid.to_bytes(),
             sent_at: timestamp,
-            payload: Some(proto::common_message::Payload::ChatMessage(
-                proto::ChatMessage {
-                    content: message.clone(),
-                },
-            )),
-        };
-
-        let message_content = super::rpc_proto::ChatContentMessage {
-            message: Some(
-                super::rpc_proto::chat_content_message::Message::ChatContent(
-                    super::rpc_proto::ChatContent { text: message },
-                ),
-            ),
+            payload: Some(payload),
+            group_revision: group.revision,
+            group_info_hash: group.info_hash(),
         };
 
         // save outgoing message
@@ -114,18 +193,36 @@ impl ChatMessage {
             account_id,
             &message_id,
             timestamp,
-            message_content.clone(),
+            content,
             rpc_proto::MessageStatus::Sending,
         );
 
         // send to all group members
         if let Some(user_account) = UserAccounts::get_by_id(account_id.clone()) {
-            for user_id in group.members.keys() {
-                let receiver = PeerId::from_bytes(&user_id.clone()).unwrap();
-                if receiver != *account_id {
-                    log::trace!("send message to {}", receiver.to_base58());
-                    if let Err(error) = Self::send(&user_account, &receiver, &common_message) {
-                        log::error!("chat message send error {}", error);
+            if !group.is_direct_chat {
+                // encrypt once with our sender key
+                let send_message = proto::Messaging {
+                    message: Some(proto::messaging::Message::CommonMessage(
+                        common_message.clone(),
+                    )),
+                };
+                if let Err(error) = Messaging::pack_and_send_group_message(
+                    &user_account,
+                    group,
+                    send_message.encode_to_vec(),
+                    MessagingServiceType::Chat,
+                    &message_id,
+                ) {
+                    log::error!("chat message send error {}", error);
+                }
+            } else {
+                for user_id in group.members.keys() {
+                    let receiver = PeerId::from_bytes(&user_id.clone()).unwrap();
+                    if receiver != *account_id {
+                        log::trace!("send message to {}", receiver.to_base58());
+                        if let Err(error) = Self::send(&user_account, &receiver, &common_message) {
+                            log::error!("chat message send error {}", error);
+                        }
                     }
                 }
             }
@@ -133,7 +230,155 @@ impl ChatMessage {
 
         // update member state
         my_member.last_message_index = last_index;
-        Group::update_group_member(account_id, group_id, &my_member);
+        Group::update_group_member(account_id, &group.id, &my_member);
+
+        Ok(message_id)
+    }
+
+    /// create the reply reference to a message of the group
+    ///
+    /// The reference contains a quoted preview of the
+    /// referenced message.
+    fn create_reply(
+        account_id: &PeerId,
+        group_id: &Vec<u8>,
+        reply_to: &Vec<u8>,
+    ) -> Result<rpc_proto::ChatReply, String> {
+        let message;
+        match ChatStorage::get_message_by_id(account_id.to_owned(), reply_to) {
+            Some(msg) => message = msg,
+            None => return Err("reply message not found".to_string()),
+        }
+
+        if message.group_id != *group_id {
+            return Err("reply message is not in this group".to_string());
+        }
+
+        // create preview
+        let mut preview = String::new();
+        if let Ok(content) = rpc_proto::ChatContentMessage::decode(&message.content[..]) {
+            match content.message {
+                Some(rpc_proto::chat_content_message::Message::ChatContent(chat_content)) => {
+                    preview = chat_content.text;
+                }
+                Some(rpc_proto::chat_content_message::Message::FileContent(file_content)) => {
+                    preview = file_content.file_name;
+                }
+                Some(rpc_proto::chat_content_message::Message::PollContent(poll_content)) => {
+                    preview = poll_content.question;
+                }
+                _ => {}
+            }
+        }
+        if preview.chars().count() > REPLY_PREVIEW_LENGTH {
+            preview = preview
+                .chars()
+                .take(REPLY_PREVIEW_LENGTH)
+                .collect::<String>()
+                + "...";
+        }
+
+        Ok(rpc_proto::ChatReply {
+            message_id: message.message_id,
+            sender_id: message.sender_id,
+            preview,
+        })
+    }
+
+    /// send read receipts to the group members
+    ///
+    /// Every member, from whom we have received messages,
+    /// gets informed up to which of it's message indexes
+    /// we have read the messages.
+    ///
+    /// No read receipts are sent if the user account
+    /// disabled them in the configuration.
+    pub fn send_read_receipts(account_id: &PeerId, group_id: &Vec<u8>) {
+        // check if read receipts are enabled
+        match Configuration::get_user(account_id.to_string()) {
+            Some(user_profile) => {
+                if !user_profile.chat.read_receipts {
+                    return;
+                }
+            }
+            None => return,
+        }
+
+        let user_account;
+        match UserAccounts::get_by_id(account_id.to_owned()) {
+            Some(account) => user_account = account,
+            None => return,
+        }
+
+        let group;
+        match GroupStorage::get_group(account_id.to_owned(), group_id.to_owned()) {
+            Some(v) => group = v,
+            None => return,
+        }
+
+        let read_at = Timestamp::get_timestamp();
+
+        for member in group.members.values() {
+            // only inform members we have received messages from
+            if member.last_message_index == 0 {
+                continue;
+            }
+
+            let receiver;
+            match PeerId::from_bytes(&member.user_id) {
+                Ok(id) => receiver = id,
+                Err(_) => continue,
+            }
+            if receiver == *account_id {
+                continue;
+            }
+
+            let send_message = proto::Messaging {
+                message: Some(proto::messaging::Message::ReadReceipt(proto::ReadReceipt {
+                    group_id: group_id.clone(),
+                    last_read_index: member.last_message_index,
+                    read_at,
+                })),
+            };
+
+            if let Err(error) = Messaging::pack_and_send_message(
+                &user_account,
+                &receiver,
+                send_message.encode_to_vec(),
+                MessagingServiceType::Chat,
+                &Vec::new(),
+                false,
+            ) {
+                log::error!("read receipt send error {}", error);
+            }
+        }
+    }
+
+    /// process a read receipt from the network
+    ///
+    /// Updates the read index of the sending member.
+    pub fn on_read_receipt(
+        sender_id: &PeerId,
+        account_id: &PeerId,
+        receipt: &proto::ReadReceipt,
+    ) -> Result<bool, String> {
+        let group;
+        match GroupStorage::get_group(account_id.to_owned(), receipt.group_id.clone()) {
+            Some(v) => group = v,
+            None => return Err("group not found".to_string()),
+        }
+
+        let mut member;
+        match group.get_member(&sender_id.to_bytes()) {
+            Some(v) => member = v.clone(),
+            None => return Err("the sender is not member in this group".to_string()),
+        }
+
+        // only move the read index forward
+        if receipt.last_read_index > member.last_read_index {
+            member.last_read_index = receipt.last_read_index;
+            Group::update_group_member(account_id, &receipt.group_id, &member);
+        }
 
         Ok(true)
     }
@@ -8,15 +8,22 @@
 use libp2p::PeerId;
 use prost::Message;
 
+pub mod export;
 pub mod file;
 pub mod message;
+pub mod poll;
+pub mod retention;
 pub mod storage;
 
 use crate::connections::{internet::Internet, lan::Lan};
 use crate::node::user_accounts::UserAccounts;
 use crate::rpc::Rpc;
+use crate::storage::configuration::{ChatOptions, Configuration};
+pub use export::ChatExport;
 pub use file::ChatFile;
 pub use message::ChatMessage;
+pub use poll::ChatPoll;
+pub use retention::ChatRetention;
 pub use storage::ChatStorage;
 
 /// Import protobuf message definition generated by
@@ -126,10 +133,129 @@ impl Chat {
                             &user_account.id,
                             &message.group_id,
                             message.content,
+                            &message.reply_to,
                         ) {
                             log::error!("Outgoing chat message error: {}", error)
                         }
                     }
+                    Some(rpc_proto::chat::Message::ConfigRequest(_config_request)) => {
+                        Self::send_config(&account_id);
+                    }
+                    Some(rpc_proto::chat::Message::ConfigSet(config)) => {
+                        Self::update_options(&account_id, |options| {
+                            options.read_receipts = config.read_receipts;
+                        });
+                    }
+                    Some(rpc_proto::chat::Message::ThreadRequest(thread_request)) => {
+                        let thread_list = rpc_proto::ChatThreadList {
+                            message_list: ChatStorage::get_thread(
+                                account_id,
+                                &thread_request.group_id,
+                                &thread_request.message_id,
+                            ),
+                            group_id: thread_request.group_id,
+                            message_id: thread_request.message_id,
+                        };
+
+                        let proto_message = rpc_proto::Chat {
+                            message: Some(rpc_proto::chat::Message::ThreadList(thread_list)),
+                        };
+
+                        Rpc::send_message(
+                            proto_message.encode_to_vec(),
+                            crate::rpc::proto::Modules::Chat.into(),
+                            "".to_string(),
+                            Vec::new(),
+                        );
+                    }
+                    Some(rpc_proto::chat::Message::PollCreate(poll_create)) => {
+                        let mut response = rpc_proto::ChatPollResponse {
+                            group_id: poll_create.group_id.clone(),
+                            poll_id: Vec::new(),
+                            success: true,
+                            error_message: "".to_string(),
+                        };
+                        match ChatPoll::create(&account_id, &poll_create) {
+                            Ok(poll_id) => response.poll_id = poll_id,
+                            Err(error) => {
+                                log::error!("Outgoing poll error: {}", error);
+                                response.success = false;
+                                response.error_message = error;
+                            }
+                        }
+
+                        let proto_message = rpc_proto::Chat {
+                            message: Some(rpc_proto::chat::Message::PollResponse(response)),
+                        };
+
+                        Rpc::send_message(
+                            proto_message.encode_to_vec(),
+                            crate::rpc::proto::Modules::Chat.into(),
+                            "".to_string(),
+                            Vec::new(),
+                        );
+                    }
+                    Some(rpc_proto::chat::Message::PollVote(poll_vote)) => {
+                        let mut response = rpc_proto::ChatPollResponse {
+                            group_id: poll_vote.group_id.clone(),
+                            poll_id: poll_vote.poll_id.clone(),
+                            success: true,
+                            error_message: "".to_string(),
+                        };
+                        if let Err(error) = ChatPoll::vote(&account_id, &poll_vote) {
+                            log::error!("Outgoing vote error: {}", error);
+                            response.success = false;
+                            response.error_message = error;
+                        }
+
+                        let proto_message = rpc_proto::Chat {
+                            message: Some(rpc_proto::chat::Message::PollResponse(response)),
+                        };
+
+                        Rpc::send_message(
+                            proto_message.encode_to_vec(),
+                            crate::rpc::proto::Modules::Chat.into(),
+                            "".to_string(),
+                            Vec::new(),
+                        );
+                    }
+                    Some(rpc_proto::chat::Message::PollResultRequest(result_request)) => {
+                        let result = ChatPoll::results(
+                            &account_id,
+                            &result_request.group_id,
+                            &result_request.poll_id,
+                        );
+
+                        let proto_message = rpc_proto::Chat {
+                            message: Some(rpc_proto::chat::Message::PollResult(result)),
+                        };
+
+                        Rpc::send_message(
+                            proto_message.encode_to_vec(),
+                            crate::rpc::proto::Modules::Chat.into(),
+                            "".to_string(),
+                            Vec::new(),
+                        );
+                    }
+                    Some(rpc_proto::chat::Message::RetentionSet(retention_set)) => {
+                        Self::update_options(&account_id, |options| {
+                            options.retention = retention_set.retention;
+                        });
+                    }
+                    Some(rpc_proto::chat::Message::ExportRequest(export_request)) => {
+                        let response = ChatExport::export(&account_id, &export_request);
+
+                        let proto_message = rpc_proto::Chat {
+                            message: Some(rpc_proto::chat::Message::ExportResponse(response)),
+                        };
+
+                        Rpc::send_message(
+                            proto_message.encode_to_vec(),
+                            crate::rpc::proto::Modules::Chat.into(),
+                            "".to_string(),
+                            Vec::new(),
+                        );
+                    }
                     _ => {
                         log::error!("Unhandled Protobuf Chat Message");
                     }
@@ -140,4 +266,54 @@ impl Chat {
             }
         }
     }
+
+    /// update the chat options of a user account
+    ///
+    /// The configuration is saved and the updated
+    /// options are sent to the UI.
+    fn update_options<F: FnOnce(&mut ChatOptions)>(account_id: &PeerId, update: F) {
+        {
+            let mut config = Configuration::get_mut();
+            let user_id = account_id.to_string();
+            match config
+                .user_accounts
+                .iter_mut()
+                .find(|user| user.id == user_id)
+            {
+                Some(user) => update(&mut user.chat),
+                None => {
+                    log::error!("user profile does not exists");
+                    return;
+                }
+            }
+        }
+        Configuration::save();
+
+        // send updated configuration
+        Self::send_config(account_id);
+    }
+
+    /// send the chat configuration of a user account via RPC
+    fn send_config(account_id: &PeerId) {
+        match Configuration::get_user(account_id.to_string()) {
+            Some(user_profile) => {
+                let proto_message = rpc_proto::Chat {
+                    message: Some(rpc_proto::chat::Message::Config(rpc_proto::ChatConfig {
+                        read_receipts: user_profile.chat.read_receipts,
+                        retention: user_profile.chat.retention,
+                    })),
+                };
+
+                Rpc::send_message(
+                    proto_message.encode_to_vec(),
+                    crate::rpc::proto::Modules::Chat.into(),
+                    "".to_string(),
+                    Vec::new(),
+                );
+            }
+            None => {
+                log::error!("user profile does not exists");
+            }
+        }
+    }
 }
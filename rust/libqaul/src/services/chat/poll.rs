@@ -0,0 +1,421 @@
+// Copyright (c) 2023 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Chat Polls
+//!
+//! Polls are sent as chat messages into a group.
+//! The message id of the poll message is the id of the poll.
+//!
+//! Votes are sent as chat messages referencing the poll.
+//! Every vote is signed by the voter, so that the votes
+//! can be validated whenever the results are counted.
+//! A later vote of the same voter replaces the earlier one.
+//! The deadline of a poll is checked against the time a vote
+//! was received, as the voting time is chosen by the voter.
+//!
+//! The results are counted from the votes saved in the
+//! chat conversation.
+
+use libp2p::PeerId;
+use prost::Message;
+use std::collections::BTreeMap;
+
+use super::rpc_proto;
+use super::{ChatMessage, ChatStorage};
+use crate::node::user_accounts::UserAccounts;
+use crate::router::users::Users;
+use crate::services::group::GroupId;
+use crate::services::messaging::proto;
+use crate::utilities::timestamp::Timestamp;
+
+/// maximal number of options of a poll
+const MAX_POLL_OPTIONS: usize = 32;
+
+/// Chat Polls
+pub struct ChatPoll {}
+
+impl ChatPoll {
+    /// create a new poll from RPC command
+    ///
+    /// Returns the id of the poll.
+    pub fn create(
+        account_id: &PeerId,
+        request: &rpc_proto::ChatPollCreate,
+    ) -> Result<Vec<u8>, String> {
+        if request.question.trim().len() == 0 {
+            return Err("poll question is missing".to_string());
+        }
+        if request.options.len() < 2 {
+            return Err("a poll needs at least two options".to_string());
+        }
+        if request.options.len() > MAX_POLL_OPTIONS {
+            return Err(format!(
+                "a poll can have at most {} options",
+                MAX_POLL_OPTIONS
+            ));
+        }
+        if request.deadline > 0 && request.deadline < Timestamp::get_timestamp() {
+            return Err("poll deadline is in the past".to_string());
+        }
+
+        let group = ChatMessage::get_group(account_id, &request.group_id)?;
+
+        // check post permission
+        if !group.can_post(&account_id.to_bytes()) {
+            return Err("only administrators can post into this group".to_string());
+        }
+
+        let payload = proto::common_message::Payload::PollMessage(proto::PollMessage {
+            question: request.question.clone(),
+            options: request.options.clone(),
+            multiple_choice: request.multiple_choice,
+            deadline: request.deadline,
+        });
+
+        let content = rpc_proto::ChatContentMessage {
+            message: Some(rpc_proto::chat_content_message::Message::PollContent(
+                rpc_proto::PollContent {
+                    question: request.question.clone(),
+                    options: request.options.clone(),
+                    multiple_choice: request.multiple_choice,
+                    deadline: request.deadline,
+                },
+            )),
+        };
+
+        ChatMessage::send_content(account_id, &group, payload, content)
+    }
+
+    /// vote on a poll from RPC command
+    ///
+    /// An empty option list withdraws our vote.
+    pub fn vote(account_id: &PeerId, request: &rpc_proto::ChatPollVote) -> Result<(), String> {
+        let user_account;
+        match UserAccounts::get_by_id(*account_id) {
+            Some(my_account) => user_account = my_account,
+            None => return Err("user account has problem".to_string()),
+        }
+
+        let group = ChatMessage::get_group(account_id, &request.group_id)?;
+
+        // check vote
+        let poll = Self::get_poll(account_id, &request.group_id, &request.poll_id)?;
+        let voted_at = Timestamp::get_timestamp();
+        Self::check_vote(&poll, &request.options, voted_at)?;
+
+        // sign vote
+        let vote = proto::PollVote {
+            group_id: request.group_id.clone(),
+            poll_id: request.poll_id.clone(),
+            options: request.options.clone(),
+            voted_at,
+        };
+        let signature;
+        match user_account.keys.sign(&vote.encode_to_vec()) {
+            Ok(sig) => signature = sig,
+            Err(e) => return Err(format!("vote signing error {}", e)),
+        }
+
+        let payload = proto::common_message::Payload::VoteMessage(proto::VoteMessage {
+            options: request.options.clone(),
+            poll_id: request.poll_id.clone(),
+            voted_at,
+            signature: signature.clone(),
+        });
+
+        let content = rpc_proto::ChatContentMessage {
+            message: Some(rpc_proto::chat_content_message::Message::PollVoteContent(
+                rpc_proto::PollVoteContent {
+                    poll_id: request.poll_id.clone(),
+                    options: request.options.clone(),
+                    voted_at,
+                    signature,
+                },
+            )),
+        };
+
+        ChatMessage::send_content(account_id, &group, payload, content)?;
+
+        Ok(())
+    }
+
+    /// count the results of a poll from RPC command
+    pub fn results(
+        account_id: &PeerId,
+        group_id: &Vec<u8>,
+        poll_id: &Vec<u8>,
+    ) -> rpc_proto::ChatPollResult {
+        let mut result = rpc_proto::ChatPollResult {
+            group_id: group_id.clone(),
+            poll_id: poll_id.clone(),
+            poll: None,
+            options: Vec::new(),
+            voters: 0,
+            closed: false,
+            error_message: "".to_string(),
+        };
+
+        let poll;
+        match Self::get_poll(account_id, group_id, poll_id) {
+            Ok(my_poll) => poll = my_poll,
+            Err(e) => {
+                result.error_message = e;
+                return result;
+            }
+        }
+
+        // get the last valid vote of each voter
+        let mut votes: BTreeMap<Vec<u8>, rpc_proto::PollVoteContent> = BTreeMap::new();
+        for message in ChatStorage::get_conversation(account_id.to_owned(), group_id) {
+            let vote;
+            match rpc_proto::ChatContentMessage::decode(&message.content[..]) {
+                Ok(rpc_proto::ChatContentMessage {
+                    message:
+                        Some(rpc_proto::chat_content_message::Message::PollVoteContent(content)),
+                }) => vote = content,
+                _ => continue,
+            }
+            if vote.poll_id != *poll_id {
+                continue;
+            }
+
+            if let Some(last_vote) = votes.get(&message.sender_id) {
+                if last_vote.voted_at > vote.voted_at {
+                    continue;
+                }
+            }
+
+            if let Err(e) = Self::check_vote(&poll, &vote.options, message.received_at) {
+                log::warn!("invalid vote: {}", e);
+                continue;
+            }
+            if !Self::verify_vote(group_id, &message.sender_id, &vote) {
+                log::warn!(
+                    "invalid vote signature from {}",
+                    bs58::encode(&message.sender_id).into_string()
+                );
+                continue;
+            }
+
+            votes.insert(message.sender_id.clone(), vote);
+        }
+
+        // count votes
+        let mut options: Vec<rpc_proto::ChatPollOptionResult> = poll
+            .options
+            .iter()
+            .enumerate()
+            .map(|(index, option)| rpc_proto::ChatPollOptionResult {
+                index: index as u32,
+                option: option.clone(),
+                votes: 0,
+                voter_ids: Vec::new(),
+            })
+            .collect();
+        for (voter_id, vote) in &votes {
+            if vote.options.len() == 0 {
+                continue;
+            }
+            for index in &vote.options {
+                let option = &mut options[*index as usize];
+                option.votes = option.votes + 1;
+                option.voter_ids.push(voter_id.clone());
+            }
+            result.voters = result.voters + 1;
+        }
+
+        result.closed = poll.deadline > 0 && poll.deadline < Timestamp::get_timestamp();
+        result.options = options;
+        result.poll = Some(poll);
+
+        result
+    }
+
+    /// process a received poll message
+    pub fn on_poll(
+        sender_id: &PeerId,
+        account_id: &PeerId,
+        group_id: &GroupId,
+        common: &proto::CommonMessage,
+        poll: &proto::PollMessage,
+    ) {
+        if poll.options.len() < 2 || poll.options.len() > MAX_POLL_OPTIONS {
+            log::warn!("received invalid poll from {}", sender_id.to_base58());
+            return;
+        }
+
+        let content = rpc_proto::ChatContentMessage {
+            message: Some(rpc_proto::chat_content_message::Message::PollContent(
+                rpc_proto::PollContent {
+                    question: poll.question.clone(),
+                    options: poll.options.clone(),
+                    multiple_choice: poll.multiple_choice,
+                    deadline: poll.deadline,
+                },
+            )),
+        };
+
+        ChatStorage::save_message(
+            account_id,
+            group_id,
+            sender_id,
+            &common.message_id,
+            common.sent_at,
+            content,
+            rpc_proto::MessageStatus::Received,
+        );
+    }
+
+    /// process a received vote
+    ///
+    /// The signature of the vote is validated before it is saved.
+    pub fn on_vote(
+        sender_id: &PeerId,
+        account_id: &PeerId,
+        group_id: &GroupId,
+        common: &proto::CommonMessage,
+        vote: &proto::VoteMessage,
+    ) {
+        let content = rpc_proto::PollVoteContent {
+            poll_id: vote.poll_id.clone(),
+            options: vote.options.clone(),
+            voted_at: vote.voted_at,
+            signature: vote.signature.clone(),
+        };
+
+        if !Self::verify_vote(&group_id.to_bytes(), &sender_id.to_bytes(), &content) {
+            log::warn!("invalid vote signature from {}", sender_id.to_base58());
+            return;
+        }
+
+        ChatStorage::save_message(
+            account_id,
+            group_id,
+            sender_id,
+            &common.message_id,
+            common.sent_at,
+            rpc_proto::ChatContentMessage {
+                message: Some(rpc_proto::chat_content_message::Message::PollVoteContent(
+                    content,
+                )),
+            },
+            rpc_proto::MessageStatus::Received,
+        );
+    }
+
+    /// get a poll from the conversation of a group
+    fn get_poll(
+        account_id: &PeerId,
+        group_id: &Vec<u8>,
+        poll_id: &Vec<u8>,
+    ) -> Result<rpc_proto::PollContent, String> {
+        let message;
+        match ChatStorage::get_message_by_id(account_id.to_owned(), poll_id) {
+            Some(msg) => message = msg,
+            None => return Err("poll not found".to_string()),
+        }
+
+        if message.group_id != *group_id {
+            return Err("poll is not in this group".to_string());
+        }
+
+        match rpc_proto::ChatContentMessage::decode(&message.content[..]) {
+            Ok(rpc_proto::ChatContentMessage {
+                message: Some(rpc_proto::chat_content_message::Message::PollContent(poll)),
+            }) => Ok(poll),
+            _ => Err("message is not a poll".to_string()),
+        }
+    }
+
+    /// check if the chosen options are valid for this poll
+    ///
+    /// `received_at` is the local time the vote was received.
+    fn check_vote(
+        poll: &rpc_proto::PollContent,
+        options: &Vec<u32>,
+        received_at: u64,
+    ) -> Result<(), String> {
+        if poll.deadline > 0 && received_at > poll.deadline {
+            return Err("the poll is closed".to_string());
+        }
+        if !poll.multiple_choice && options.len() > 1 {
+            return Err("only one option can be chosen".to_string());
+        }
+        for (position, index) in options.iter().enumerate() {
+            if *index as usize >= poll.options.len() {
+                return Err("invalid poll option".to_string());
+            }
+            if options[..position].contains(index) {
+                return Err("option chosen twice".to_string());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// verify the signature of a vote
+    fn verify_vote(
+        group_id: &Vec<u8>,
+        voter_id: &Vec<u8>,
+        vote: &rpc_proto::PollVoteContent,
+    ) -> bool {
+        let voter;
+        match PeerId::from_bytes(voter_id) {
+            Ok(id) => voter = id,
+            Err(_) => return false,
+        }
+
+        // get public key of the voter
+        let key;
+        match UserAccounts::get_by_id(voter) {
+            Some(voter_account) => key = voter_account.keys.public(),
+            None => match Users::get_pub_key(&voter) {
+                Some(pub_key) => key = pub_key,
+                None => return false,
+            },
+        }
+
+        let signed_vote = proto::PollVote {
+            group_id: group_id.clone(),
+            poll_id: vote.poll_id.clone(),
+            options: vote.options.clone(),
+            voted_at: vote.voted_at,
+        };
+
+        key.verify(&signed_vote.encode_to_vec(), &vote.signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn poll(multiple_choice: bool, deadline: u64) -> rpc_proto::PollContent {
+        rpc_proto::PollContent {
+            question: "question".to_string(),
+            options: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            multiple_choice,
+            deadline,
+        }
+    }
+
+    #[test]
+    fn check_vote_accepts_valid_votes() {
+        assert!(ChatPoll::check_vote(&poll(false, 0), &vec![1], 100).is_ok());
+        assert!(ChatPoll::check_vote(&poll(true, 0), &vec![0, 2], 100).is_ok());
+        assert!(ChatPoll::check_vote(&poll(false, 0), &vec![], 100).is_ok());
+    }
+
+    #[test]
+    fn check_vote_refuses_votes_received_after_deadline() {
+        assert!(ChatPoll::check_vote(&poll(false, 100), &vec![0], 100).is_ok());
+        assert!(ChatPoll::check_vote(&poll(false, 100), &vec![0], 101).is_err());
+    }
+
+    #[test]
+    fn check_vote_refuses_invalid_options() {
+        assert!(ChatPoll::check_vote(&poll(false, 0), &vec![0, 1], 100).is_err());
+        assert!(ChatPoll::check_vote(&poll(true, 0), &vec![3], 100).is_err());
+        assert!(ChatPoll::check_vote(&poll(true, 0), &vec![1, 1], 100).is_err());
+    }
+}
@@ -0,0 +1,103 @@
+// Copyright (c) 2023 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Chat Message Retention
+//!
+//! Deletes the chat messages and their files after the
+//! retention time of a group has expired.
+//!
+//! The retention time of a group is set by the group administrators
+//! and synchronized to all members via the group info message.
+//! Groups without a retention time use the default retention time
+//! of the user account.
+//! A retention time of 0 keeps the messages forever.
+
+use libp2p::PeerId;
+use prost::Message;
+
+use super::{rpc_proto, ChatFile, ChatStorage};
+use crate::node::user_accounts::UserAccounts;
+use crate::services::group::GroupStorage;
+use crate::services::search::Search;
+use crate::storage::configuration::Configuration;
+use crate::utilities::timestamp::Timestamp;
+
+/// Chat Message Retention
+pub struct ChatRetention {}
+
+impl ChatRetention {
+    /// Delete the expired messages of all user accounts
+    ///
+    /// This function is called periodically from the libqaul event loop.
+    pub fn process() {
+        for account_id in UserAccounts::get_account_ids() {
+            Self::process_account(&account_id);
+        }
+    }
+
+    /// Delete the expired messages of all groups of a user account
+    fn process_account(account_id: &PeerId) {
+        // get default retention of the user account
+        let mut default_retention = 0;
+        if let Some(user_profile) = Configuration::get_user(account_id.to_string()) {
+            default_retention = user_profile.chat.retention;
+        }
+
+        let now = Timestamp::get_timestamp();
+        let db_ref = GroupStorage::get_db_ref(account_id.to_owned());
+
+        for entry in db_ref.groups.iter() {
+            let mut group;
+            match entry {
+                Ok((_, my_group)) => group = my_group,
+                Err(e) => {
+                    log::error!("retention group error: {}", e);
+                    continue;
+                }
+            }
+
+            let mut retention = group.retention;
+            if retention == 0 {
+                retention = default_retention;
+            }
+            if retention == 0 {
+                continue;
+            }
+
+            // messages received before this time are deleted
+            let expired_before = now.saturating_sub(retention.saturating_mul(1000));
+
+            let removed = ChatStorage::remove_messages_before(
+                account_id.to_owned(),
+                &group.id,
+                expired_before,
+            );
+            if removed.len() == 0 {
+                continue;
+            }
+            log::trace!("retention: {} expired messages deleted", removed.len());
+
+            for message in &removed {
+                // remove message from search index
+                Search::remove_chat_message(account_id, message);
+
+                // delete file of file messages
+                if let Ok(content) = rpc_proto::ChatContentMessage::decode(&message.content[..]) {
+                    if let Some(rpc_proto::chat_content_message::Message::FileContent(file)) =
+                        content.message
+                    {
+                        ChatFile::delete_file(account_id, file.file_id, &file.file_extension);
+                    }
+                }
+            }
+
+            // remove the preview of an expired last message
+            if group.last_message_at < expired_before && group.last_message_data.len() > 0 {
+                group.last_message_data = Vec::new();
+                group.last_message_sender_id = Vec::new();
+                group.unread_messages = 0;
+                GroupStorage::save_group(account_id.to_owned(), group);
+            }
+        }
+    }
+}
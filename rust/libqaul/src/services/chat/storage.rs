@@ -9,7 +9,7 @@
 use libp2p::PeerId;
 use sled_extensions::{bincode::Tree, DbExt};
 use state::Storage;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::sync::RwLock;
 
 use super::rpc_proto;
@@ -86,6 +86,29 @@ impl ChatStorage {
         res
     }
 
+    /// get a chat message by it's message id
+    pub fn get_message_by_id(
+        account_id: PeerId,
+        message_id: &Vec<u8>,
+    ) -> Option<rpc_proto::ChatMessage> {
+        let db_ref = Self::get_db_ref(account_id);
+
+        match db_ref.message_ids.get(message_id) {
+            Ok(Some(db_key)) => match db_ref.messages.get(&db_key) {
+                Ok(message) => message,
+                Err(e) => {
+                    log::error!("get_message_by_id error: {}", e);
+                    None
+                }
+            },
+            Ok(None) => None,
+            Err(e) => {
+                log::error!("get_message_by_id error: {}", e);
+                None
+            }
+        }
+    }
+
     /// remove messages by ids
     #[allow(dead_code)]
     pub fn remove_messages(user_id: &PeerId, message_ids: &Vec<Vec<u8>>) {
@@ -176,6 +199,9 @@ impl ChatStorage {
             content: content.encode_to_vec(),
         };
 
+        // add message to search index
+        crate::services::search::Search::index_chat_message(account_id, &chat_message);
+
         // save message in data base
         if let Err(e) = db_ref.messages.insert(db_key.clone(), chat_message) {
             log::error!("Error saving chat message to data base: {}", e);
@@ -264,6 +290,28 @@ impl ChatStorage {
 
     /// Get chat messages of a specific conversation from data base
     pub fn get_messages(account_id: PeerId, group_id: Vec<u8>) -> rpc_proto::ChatConversationList {
+        // get all messages of the conversation
+        let message_list = Self::get_conversation(account_id, &group_id);
+
+        // clear unread messages from group
+        // and inform the members that we have read their messages
+        if group_id.len() == 16 {
+            if GroupStorage::group_clear_unread(account_id, group_id.clone()) {
+                super::ChatMessage::send_read_receipts(&account_id, &group_id);
+            }
+        }
+
+        rpc_proto::ChatConversationList {
+            group_id,
+            message_list,
+        }
+    }
+
+    /// Get all chat messages of a conversation
+    ///
+    /// In contrast to `get_messages`, the messages
+    /// are not marked as read.
+    pub fn get_conversation(account_id: PeerId, group_id: &Vec<u8>) -> Vec<rpc_proto::ChatMessage> {
         // create empty messages list
         let mut message_list: Vec<rpc_proto::ChatMessage> = Vec::new();
 
@@ -272,7 +320,7 @@ impl ChatStorage {
             let db_ref = Self::get_db_ref(account_id);
 
             // create message keys
-            let (first_key, last_key) = Self::get_db_key_range(&group_id.clone());
+            let (first_key, last_key) = Self::get_db_key_range(group_id);
 
             // iterate over all values in chat_messages db
             for res in db_ref
@@ -288,15 +336,134 @@ impl ChatStorage {
                     }
                 }
             }
+        }
+
+        message_list
+    }
+
+    /// Get a message thread
+    ///
+    /// Returns the message with the given message id and all
+    /// replies to it, including the replies to the replies,
+    /// in the order of the conversation.
+    pub fn get_thread(
+        account_id: PeerId,
+        group_id: &Vec<u8>,
+        message_id: &Vec<u8>,
+    ) -> Vec<rpc_proto::ChatMessage> {
+        // get all messages with their reply references
+        let messages: Vec<(rpc_proto::ChatMessage, Option<Vec<u8>>)> =
+            Self::get_conversation(account_id, group_id)
+                .into_iter()
+                .map(|message| {
+                    let reply_to = Self::get_reply_to(&message);
+                    (message, reply_to)
+                })
+                .collect();
+
+        Self::thread_closure(message_id, messages)
+    }
 
-            // clear unread messages from group
-            GroupStorage::group_clear_unread(account_id, group_id.clone());
+    /// get the message id a chat message replies to
+    fn get_reply_to(message: &rpc_proto::ChatMessage) -> Option<Vec<u8>> {
+        match rpc_proto::ChatContentMessage::decode(&message.content[..]) {
+            Ok(rpc_proto::ChatContentMessage {
+                message: Some(rpc_proto::chat_content_message::Message::ChatContent(chat_content)),
+            }) => chat_content.reply.map(|reply| reply.message_id),
+            _ => None,
         }
+    }
 
-        rpc_proto::ChatConversationList {
-            group_id,
-            message_list,
+    /// collect the messages of a thread
+    ///
+    /// Messages can be delivered out of order, e.g. via DTN,
+    /// a reply can therefore be stored before the message it
+    /// refers to. The thread is extended until no further
+    /// replies are found.
+    fn thread_closure(
+        message_id: &Vec<u8>,
+        messages: Vec<(rpc_proto::ChatMessage, Option<Vec<u8>>)>,
+    ) -> Vec<rpc_proto::ChatMessage> {
+        // message ids of the thread
+        let mut thread_ids: BTreeSet<Vec<u8>> = BTreeSet::new();
+        thread_ids.insert(message_id.clone());
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (message, reply_to) in &messages {
+                if let Some(reply_to) = reply_to {
+                    if thread_ids.contains(reply_to) && !thread_ids.contains(&message.message_id) {
+                        thread_ids.insert(message.message_id.clone());
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        messages
+            .into_iter()
+            .map(|(message, _)| message)
+            .filter(|message| thread_ids.contains(&message.message_id))
+            .collect()
+    }
+
+    /// Remove all messages of a conversation received before a certain time
+    ///
+    /// Returns the removed messages.
+    pub fn remove_messages_before(
+        account_id: PeerId,
+        group_id: &Vec<u8>,
+        timestamp: u64,
+    ) -> Vec<rpc_proto::ChatMessage> {
+        let mut removed: Vec<rpc_proto::ChatMessage> = Vec::new();
+
+        // get database references for this user account
+        let db_ref = Self::get_db_ref(account_id);
+
+        // create message keys
+        let (first_key, last_key) = Self::get_db_key_range(group_id);
+
+        // iterate over all messages of the conversation
+        for res in db_ref
+            .messages
+            .range(first_key.as_slice()..last_key.as_slice())
+        {
+            match res {
+                Ok((key, message)) => {
+                    if message.received_at >= timestamp {
+                        continue;
+                    }
+
+                    if let Err(e) = db_ref.messages.remove(key) {
+                        log::error!("Error removing chat message: {}", e);
+                        continue;
+                    }
+                    if message.message_id.len() > 0 {
+                        if let Err(e) = db_ref.message_ids.remove(message.message_id.clone()) {
+                            log::error!("Error removing chat message id: {}", e);
+                        }
+                    }
+
+                    removed.push(message);
+                }
+                Err(e) => {
+                    log::error!("remove_messages_before error: {}", e);
+                }
+            }
         }
+
+        // flush trees to disk
+        if removed.len() > 0 {
+            if let Err(e) = db_ref.messages.flush() {
+                log::error!("Error chat messages flush: {}", e);
+            }
+            if let Err(e) = db_ref.message_ids.flush() {
+                log::error!("Error chat message_ids flush: {}", e);
+            }
+        }
+
+        removed
     }
 
     /// get DB key range for a group id
@@ -332,7 +499,7 @@ impl ChatStorage {
     }
 
     /// get user account data base tree references
-    fn get_db_ref(account_id: PeerId) -> ChatAccountDb {
+    pub fn get_db_ref(account_id: PeerId) -> ChatAccountDb {
         // check if user account data exists
         {
             // get chat state
@@ -381,3 +548,60 @@ impl ChatStorage {
         chat_user
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: u8, reply_to: Option<u8>) -> (rpc_proto::ChatMessage, Option<Vec<u8>>) {
+        let message = rpc_proto::ChatMessage {
+            index: id as u64,
+            message_id: vec![id],
+            ..Default::default()
+        };
+        (message, reply_to.map(|parent| vec![parent]))
+    }
+
+    fn ids(messages: Vec<rpc_proto::ChatMessage>) -> Vec<Vec<u8>> {
+        messages
+            .into_iter()
+            .map(|message| message.message_id)
+            .collect()
+    }
+
+    #[test]
+    fn thread_contains_replies_to_replies() {
+        let messages = vec![
+            message(1, None),
+            message(2, Some(1)),
+            message(3, None),
+            message(4, Some(2)),
+        ];
+        let thread = ChatStorage::thread_closure(&vec![1], messages);
+        assert_eq!(ids(thread), vec![vec![1], vec![2], vec![4]]);
+    }
+
+    #[test]
+    fn thread_contains_replies_stored_before_their_parent() {
+        // reply to a reply, delivered before the reply it refers to
+        let messages = vec![
+            message(1, None),
+            message(4, Some(3)),
+            message(5, Some(4)),
+            message(3, Some(2)),
+            message(2, Some(1)),
+        ];
+        let thread = ChatStorage::thread_closure(&vec![1], messages);
+        assert_eq!(
+            ids(thread),
+            vec![vec![1], vec![4], vec![5], vec![3], vec![2]]
+        );
+    }
+
+    #[test]
+    fn thread_of_a_reply_does_not_contain_the_parent() {
+        let messages = vec![message(1, None), message(2, Some(1)), message(3, Some(2))];
+        let thread = ChatStorage::thread_closure(&vec![2], messages);
+        assert_eq!(ids(thread), vec![vec![2], vec![3]]);
+    }
+}
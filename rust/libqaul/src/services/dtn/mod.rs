@@ -190,6 +190,7 @@ impl Dtn {
             sender_id: user_account.id.to_bytes(),
             receiver_id: receiver_id.to_bytes(),
             payload: payload.encode_to_vec(),
+            receiver_ids: Vec::new(),
         };
 
         if let Ok(signature) = user_account.keys.sign(&envelop.encode_to_vec()) {
@@ -226,6 +227,7 @@ impl Dtn {
             let container = super::messaging::proto::Container {
                 signature: signature.clone(),
                 envelope: Some(envelop),
+                forward_to: Vec::new(),
             };
             super::messaging::Messaging::save_unconfirmed_message(
                 MessagingServiceType::DtnStored,
@@ -287,8 +289,6 @@ impl Dtn {
         if let Some(user_account) = UserAccounts::get_by_id(*user_id) {
             match proto::Container::decode(&dtn_payload[..]) {
                 Ok(container) => {
-                    let envelope = container.envelope.as_ref().unwrap();
-
                     let mut res: (i32, i32) = (
                         super::messaging::proto::dtn_response::ResponseType::Accepted
                             .try_into()
@@ -298,9 +298,11 @@ impl Dtn {
                             .unwrap(),
                     );
 
-                    //if container.envelope.receiver_id
-                    if let Ok(receiver_id) = PeerId::from_bytes(&envelope.receiver_id) {
-                        if receiver_id == *user_id {
+                    // group containers are stored for the receiver
+                    // they are forwarded to
+                    let receivers = super::messaging::Messaging::get_receivers(&container);
+                    if let Some(receiver_id) = receivers.first().cloned() {
+                        if receivers.contains(user_id) {
                             // by process geneal message, the confirm message is transfered to the original sender.
                             super::messaging::process::MessagingProcess::process_received_message(
                                 user_account.clone(),
@@ -6,6 +6,8 @@
 //! The feed service sends and receives feed messages into the network.
 //! Feed messages are not encrypted and for everybody to read.
 //! They should reach everyone in the network.
+//!
+//! Feed messages are broadcast via the router's flooder.
 
 //use bs58::decode;
 use libp2p::{
@@ -19,14 +21,11 @@ use state::Storage;
 use std::collections::BTreeMap;
 use std::{convert::TryInto, sync::RwLock};
 
-use crate::node::{
-    user_accounts::{UserAccount, UserAccounts},
-    Node,
-};
+use crate::node::user_accounts::{UserAccount, UserAccounts};
 
-use crate::connections::{internet::Internet, lan::Lan, ConnectionModule};
+use crate::connections::{internet::Internet, lan::Lan};
 use crate::router;
-use crate::router::flooder::Flooder;
+use crate::router::{flooder::Flooder, router_net_proto};
 use crate::rpc::Rpc;
 use crate::storage::database::DataBase;
 use crate::utilities::timestamp;
@@ -148,13 +147,8 @@ impl Feed {
         FEED.set(RwLock::new(feed));
     }
 
-    /// Send message via all swarms
-    pub fn send(
-        user_account: &UserAccount,
-        content: String,
-        lan: Option<&mut Lan>,
-        internet: Option<&mut Internet>,
-    ) {
+    /// Send message via the broadcast flooder
+    pub fn send(user_account: &UserAccount, content: String) {
         // create timestamp
         let timestamp = timestamp::Timestamp::get_timestamp();
 
@@ -188,30 +182,19 @@ impl Feed {
         // save message in feed store
         Self::save_message(container.signature.clone(), msg);
 
-        // flood via floodsub
-        if lan.is_some() {
-            lan.unwrap()
-                .swarm
-                .behaviour_mut()
-                .floodsub
-                .publish(Node::get_topic(), buf.clone());
-        }
-        if internet.is_some() {
-            internet
-                .unwrap()
-                .swarm
-                .behaviour_mut()
-                .floodsub
-                .publish(Node::get_topic(), buf);
-        }
+        // broadcast it to the network
+        Flooder::broadcast(
+            router_net_proto::BroadcastService::Feed,
+            container.signature,
+            buf,
+        );
     }
 
     /// Process a received message
-    pub fn received(
-        via_conn: ConnectionModule,
-        _via_node: PeerId,
-        feed_container: proto_net::FeedContainer,
-    ) {
+    ///
+    /// Returns true if the message is valid and new,
+    /// and needs to be forwarded.
+    pub fn received(_via_node: PeerId, feed_container: proto_net::FeedContainer) -> bool {
         match proto_net::FeedMessageContent::decode(&feed_container.message[..]) {
             Ok(feed_content) => {
                 let message = feed_content.clone();
@@ -232,7 +215,7 @@ impl Feed {
                             let (key_type, key_base58) =
                                 crate::router::users::Users::get_protobuf_public_key(key);
                             log::error!("  sender key [{}]: {}", key_type, key_base58);
-                            return;
+                            return false;
                         }
 
                         // check if message exists is in feed store
@@ -260,14 +243,7 @@ impl Feed {
                             );
                             log::trace!(" Message Content {}", message.content);
 
-                            // encode container
-                            let mut buf = Vec::with_capacity(feed_container.encoded_len());
-                            feed_container
-                                .encode(&mut buf)
-                                .expect("Vec<u8> provides capacity as needed");
-
-                            // forward message
-                            Flooder::add(buf, Node::get_topic(), via_conn);
+                            return true;
                         } else {
                             log::trace!(
                                 "message key {:?} already in store",
@@ -276,7 +252,7 @@ impl Feed {
                         }
                     } else {
                         log::error!("Sender of feed message not known: {}", user_id_decoded);
-                        return;
+                        return false;
                     }
                 }
             }
@@ -284,6 +260,8 @@ impl Feed {
                 log::error!("{:?}", error);
             }
         }
+
+        false
     }
 
     //Save message by sync
@@ -323,6 +301,9 @@ impl Feed {
             content: content.clone(),
         };
 
+        // add message to search index
+        crate::services::search::Search::index_feed_message(&message_data);
+
         // save to data base
         if let Err(e) = feed.tree.insert(&last_message.to_be_bytes(), message_data) {
             log::error!("Error saving feed message to data base: {}", e);
@@ -370,6 +351,9 @@ impl Feed {
             content: message.content.clone(),
         };
 
+        // add message to search index
+        crate::services::search::Search::index_feed_message(&message_data);
+
         // save to data base
         if let Err(e) = feed.tree.insert(&last_message.to_be_bytes(), message_data) {
             log::error!("Error saving feed message to data base: {}", e);
@@ -452,6 +436,24 @@ impl Feed {
 
     /// Get messages from data base
     ///
+    /// Get all feed messages from the data base
+    ///
+    /// This function is used to create the search index.
+    pub fn get_message_data() -> Vec<FeedMessageData> {
+        let feed = FEED.get().read().unwrap();
+
+        feed.tree
+            .iter()
+            .filter_map(|res| match res {
+                Ok((_key, message)) => Some(message),
+                Err(e) => {
+                    log::error!("Error retrieving feed message from data base: {}", e);
+                    None
+                }
+            })
+            .collect()
+    }
+
     /// This function get messages from data base
     /// that are newer then the last message.
     fn get_messages(last_message: u64) -> proto::FeedMessageList {
@@ -531,8 +533,8 @@ impl Feed {
     pub fn rpc(
         data: Vec<u8>,
         user_id: Vec<u8>,
-        lan: Option<&mut Lan>,
-        internet: Option<&mut Internet>,
+        _lan: Option<&mut Lan>,
+        _internet: Option<&mut Internet>,
     ) {
         match proto::Feed::decode(&data[..]) {
             Ok(feed) => {
@@ -572,7 +574,7 @@ impl Feed {
                                     Some(account) => {
                                         user_account = account;
                                         // send the message
-                                        Self::send(&user_account, send_feed.content, lan, internet);
+                                        Self::send(&user_account, send_feed.content);
                                     }
                                     None => {
                                         log::error!(
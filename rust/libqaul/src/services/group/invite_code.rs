@@ -0,0 +1,341 @@
+// Copyright (c) 2023 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Group Invite Codes
+//!
+//! Group administrators can create signed invite codes, which
+//! can be shared out-of-band, e.g. as text or as QR code.
+//! An invite code can have an expiry time and a maximal
+//! number of uses.
+//!
+//! The public key of the creating administrator is embedded
+//! in the code, so that it can be validated by anybody.
+//!
+//! The holder of an invite code sends it to an administrator
+//! of the group, who verifies it and sends a normal group
+//! invitation to the holder.
+//!
+//! The number of uses of each code is synchronized between
+//! the administrators via the group info.
+
+use libp2p::{identity::PublicKey, PeerId};
+use prost::Message;
+use rand::{thread_rng, Rng};
+
+use super::{proto_net, Group, GroupStorage, Member};
+use crate::node::user_accounts::UserAccounts;
+use crate::utilities::timestamp::Timestamp;
+
+/// Group Invite Codes
+pub struct InviteCode {}
+
+impl InviteCode {
+    /// create a new invite code from RPC command
+    ///
+    /// `expires_in` validity of the code in seconds, 0 = no expiry
+    /// `max_uses` maximal number of uses, 0 = unlimited
+    ///
+    /// Returns the invite code string and the expiry time.
+    pub fn create(
+        account_id: &PeerId,
+        group_id: &Vec<u8>,
+        expires_in: u64,
+        max_uses: u32,
+    ) -> Result<(String, u64), String> {
+        let user_account;
+        match UserAccounts::get_by_id(*account_id) {
+            Some(my_account) => user_account = my_account,
+            None => return Err("user account has problem".to_string()),
+        }
+
+        let group;
+        match GroupStorage::get_group(account_id.to_owned(), group_id.to_owned()) {
+            Some(my_group) => group = my_group,
+            None => return Err("group not found".to_string()),
+        }
+
+        if group.is_direct_chat {
+            return Err("direct chat room does not allow this action".to_string());
+        }
+        if group.status == super::proto_rpc::GroupStatus::Deactivated as i32 {
+            return Err("group is deactivated".to_string());
+        }
+        if !group.is_administrator(&account_id.to_bytes()) {
+            return Err("you don't have the permissions to create invite codes".to_string());
+        }
+
+        // create code
+        let created_at = Timestamp::get_timestamp();
+        let mut expires_at = 0;
+        if expires_in > 0 {
+            expires_at = created_at + expires_in.saturating_mul(1000);
+        }
+
+        let code = proto_net::InviteCode {
+            group_id: group.id.clone(),
+            group_name: group.name.clone(),
+            admin_id: account_id.to_bytes(),
+            code_id: thread_rng().gen(),
+            created_at,
+            expires_at,
+            max_uses,
+            admin_key: user_account.keys.public().to_protobuf_encoding(),
+        }
+        .encode_to_vec();
+
+        // sign code
+        let signature;
+        match user_account.keys.sign(&code) {
+            Ok(sig) => signature = sig,
+            Err(e) => return Err(format!("invite code signing error {}", e)),
+        }
+
+        let signed_code = proto_net::SignedInviteCode { code, signature }.encode_to_vec();
+
+        Ok((bs58::encode(signed_code).into_string(), expires_at))
+    }
+
+    /// redeem an invite code from RPC command
+    ///
+    /// The code is sent to the administrator `admin_id`.
+    /// If `admin_id` is empty, the code is sent to the
+    /// administrator who created it.
+    ///
+    /// Returns the decoded invite code.
+    pub fn redeem(
+        account_id: &PeerId,
+        code_string: &str,
+        admin_id: &Vec<u8>,
+    ) -> Result<proto_net::InviteCode, String> {
+        let user_account;
+        match UserAccounts::get_by_id(*account_id) {
+            Some(my_account) => user_account = my_account,
+            None => return Err("user account has problem".to_string()),
+        }
+
+        let (signed_code, code) = Self::decode(code_string.trim())?;
+
+        // check if we are already a member
+        if let Some(group) = GroupStorage::get_group(account_id.to_owned(), code.group_id.clone()) {
+            if group.is_member(&account_id.to_bytes())
+                && group.status != super::proto_rpc::GroupStatus::Deactivated as i32
+            {
+                return Err("you are already member of this group".to_string());
+            }
+        }
+
+        // check expiry
+        if code.expires_at > 0 && code.expires_at < Timestamp::get_timestamp() {
+            return Err("invite code has expired".to_string());
+        }
+
+        // get receiving administrator
+        let receiver;
+        if admin_id.len() > 0 {
+            match PeerId::from_bytes(admin_id) {
+                Ok(id) => receiver = id,
+                Err(_) => return Err("invalid administrator id".to_string()),
+            }
+        } else {
+            match PeerId::from_bytes(&code.admin_id) {
+                Ok(id) => receiver = id,
+                Err(_) => return Err("invalid administrator id in invite code".to_string()),
+            }
+        }
+
+        // send code to administrator
+        let proto_message = proto_net::GroupContainer {
+            message: Some(proto_net::group_container::Message::RedeemCode(
+                proto_net::RedeemInviteCode {
+                    invite_code: signed_code.encode_to_vec(),
+                },
+            )),
+        };
+        Group::send_notify_message(&user_account, &receiver, proto_message.encode_to_vec());
+
+        Ok(code)
+    }
+
+    /// process a redeemed invite code from the network
+    ///
+    /// If the code is valid, the sender is invited to the group.
+    /// The use of the code is counted in the new revision of the
+    /// invitation.
+    /// Returns the id of the group, whose updated group info
+    /// needs to be posted to all members.
+    pub fn on_redeem(
+        sender_id: &PeerId,
+        account_id: &PeerId,
+        message: &proto_net::RedeemInviteCode,
+    ) -> Result<Vec<u8>, String> {
+        let signed_code;
+        match proto_net::SignedInviteCode::decode(&message.invite_code[..]) {
+            Ok(my_code) => signed_code = my_code,
+            Err(e) => return Err(format!("invite code decoding error {}", e)),
+        }
+        let code = Self::validate(&signed_code)?;
+
+        let group;
+        match GroupStorage::get_group(account_id.to_owned(), code.group_id.clone()) {
+            Some(my_group) => group = my_group,
+            None => return Err("group not found".to_string()),
+        }
+
+        if group.is_direct_chat {
+            return Err("direct chat room does not allow this action".to_string());
+        }
+        if group.status == super::proto_rpc::GroupStatus::Deactivated as i32 {
+            return Err("group is deactivated".to_string());
+        }
+        if !group.is_administrator(&account_id.to_bytes()) {
+            return Err("we are not administrator of this group".to_string());
+        }
+
+        // the creator of the code needs to be an administrator
+        if !group.is_administrator(&code.admin_id) {
+            return Err("invite code creator is not administrator".to_string());
+        }
+
+        // check expiry
+        if code.expires_at > 0 && code.expires_at < Timestamp::get_timestamp() {
+            return Err("invite code has expired".to_string());
+        }
+
+        // check uses
+        let uses = match group.invite_code_uses.get(&code.code_id) {
+            Some(uses) => *uses,
+            None => 0,
+        };
+        if code.max_uses > 0 && uses >= code.max_uses {
+            return Err("invite code has been used up".to_string());
+        }
+
+        // invite sender
+        Member::invite(account_id, &code.group_id, sender_id)?;
+
+        // count use
+        if let Some(mut group) =
+            GroupStorage::get_group(account_id.to_owned(), code.group_id.clone())
+        {
+            group.invite_code_uses.insert(code.code_id, uses + 1);
+            GroupStorage::save_group(account_id.to_owned(), group);
+        }
+
+        Ok(code.group_id)
+    }
+
+    /// decode and validate an invite code string
+    fn decode(
+        code_string: &str,
+    ) -> Result<(proto_net::SignedInviteCode, proto_net::InviteCode), String> {
+        let data;
+        match bs58::decode(code_string).into_vec() {
+            Ok(my_data) => data = my_data,
+            Err(_) => return Err("invalid invite code".to_string()),
+        }
+
+        let signed_code;
+        match proto_net::SignedInviteCode::decode(&data[..]) {
+            Ok(my_code) => signed_code = my_code,
+            Err(_) => return Err("invalid invite code".to_string()),
+        }
+
+        let code = Self::validate(&signed_code)?;
+
+        Ok((signed_code, code))
+    }
+
+    /// validate the signature of an invite code
+    ///
+    /// Returns the decoded invite code.
+    fn validate(
+        signed_code: &proto_net::SignedInviteCode,
+    ) -> Result<proto_net::InviteCode, String> {
+        let code;
+        match proto_net::InviteCode::decode(&signed_code.code[..]) {
+            Ok(my_code) => code = my_code,
+            Err(_) => return Err("invalid invite code".to_string()),
+        }
+
+        let admin_id;
+        match PeerId::from_bytes(&code.admin_id) {
+            Ok(id) => admin_id = id,
+            Err(_) => return Err("invalid administrator id in invite code".to_string()),
+        }
+
+        // get the embedded public key of the administrator
+        let key;
+        match PublicKey::from_protobuf_encoding(&code.admin_key) {
+            Ok(pub_key) => key = pub_key,
+            Err(_) => return Err("invalid administrator key in invite code".to_string()),
+        }
+        if PeerId::from_public_key(&key) != admin_id {
+            return Err("administrator key doesn't match administrator id".to_string());
+        }
+
+        if !key.verify(&signed_code.code, &signed_code.signature) {
+            return Err("invalid invite code signature".to_string());
+        }
+
+        Ok(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::identity::Keypair;
+
+    fn signed_code(keys: &Keypair, admin_id: &PeerId) -> proto_net::SignedInviteCode {
+        let code = proto_net::InviteCode {
+            group_id: vec![1; 16],
+            group_name: "group".to_string(),
+            admin_id: admin_id.to_bytes(),
+            code_id: 7,
+            created_at: 1,
+            expires_at: 0,
+            max_uses: 3,
+            admin_key: keys.public().to_protobuf_encoding(),
+        }
+        .encode_to_vec();
+        let signature = keys.sign(&code).unwrap();
+
+        proto_net::SignedInviteCode { code, signature }
+    }
+
+    #[test]
+    fn validate_accepts_signed_code() {
+        let keys = Keypair::generate_ed25519();
+        let signed = signed_code(&keys, &keys.public().to_peer_id());
+
+        let code = InviteCode::validate(&signed).unwrap();
+        assert_eq!(code.code_id, 7);
+        assert_eq!(code.max_uses, 3);
+    }
+
+    #[test]
+    fn validate_refuses_modified_code() {
+        let keys = Keypair::generate_ed25519();
+        let mut signed = signed_code(&keys, &keys.public().to_peer_id());
+        let mut code = proto_net::InviteCode::decode(&signed.code[..]).unwrap();
+        code.max_uses = 0;
+        signed.code = code.encode_to_vec();
+
+        assert!(InviteCode::validate(&signed).is_err());
+    }
+
+    #[test]
+    fn validate_refuses_foreign_key() {
+        let keys = Keypair::generate_ed25519();
+        let admin = Keypair::generate_ed25519();
+        let signed = signed_code(&keys, &admin.public().to_peer_id());
+
+        assert!(InviteCode::validate(&signed).is_err());
+    }
+
+    #[test]
+    fn decode_refuses_invalid_strings() {
+        assert!(InviteCode::decode("not a code").is_err());
+        assert!(InviteCode::decode("").is_err());
+    }
+}
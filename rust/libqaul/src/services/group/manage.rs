@@ -4,13 +4,21 @@
 //! # Group Management
 
 use libp2p::PeerId;
+use prost::Message;
 use std::collections::BTreeMap;
 
 use super::group_id::GroupId;
-use super::{Group, GroupStorage};
+use super::{Group, GroupSettings, GroupStorage};
+use crate::node::user_accounts::UserAccounts;
 use crate::services::chat::{self, Chat, ChatStorage};
 use crate::utilities::timestamp::Timestamp;
 
+/// maximal size of a group avatar image in bytes
+pub const MAX_AVATAR_SIZE: usize = 16 * 1024;
+
+/// maximal length of a group description in bytes
+pub const MAX_DESCRIPTION_LENGTH: usize = 1024;
+
 /// Group Manage Structure
 pub struct GroupManage {}
 impl GroupManage {
@@ -66,6 +74,7 @@ impl GroupManage {
                     .try_into()
                     .unwrap(),
                 last_message_index: 0,
+                last_read_index: 0,
             },
         );
         group.members.insert(
@@ -78,6 +87,7 @@ impl GroupManage {
                     .try_into()
                     .unwrap(),
                 last_message_index: 0,
+                last_read_index: 0,
             },
         );
 
@@ -104,6 +114,7 @@ impl GroupManage {
                 joined_at: Timestamp::get_timestamp(),
                 state: super::proto_rpc::GroupMemberState::Activated as i32,
                 last_message_index: 0,
+                last_read_index: 0,
             },
         );
 
@@ -145,10 +156,10 @@ impl GroupManage {
     ) -> Result<(), String> {
         if let Some(mut group) = GroupStorage::get_group(account_id.to_owned(), group_id.to_owned())
         {
-            // check if administrator
+            // check if administrator or allowed member
             if let Some(member) = group.get_member(&account_id.to_bytes()) {
                 // check permission
-                if member.role != 255 {
+                if member.role != 255 && !(group.settings.members_can_rename && member.state > 0) {
                     return Err("you don't have the permissions to rename this group".to_string());
                 }
             } else {
@@ -170,6 +181,262 @@ impl GroupManage {
         Err("can not find group".to_string())
     }
 
+    /// set the message retention time of a group from RPC command
+    ///
+    /// `account_id` the user account ID
+    /// `retention` the retention time in seconds, 0 = keep forever
+    pub fn set_retention(
+        account_id: &PeerId,
+        group_id: &Vec<u8>,
+        retention: u64,
+    ) -> Result<(), String> {
+        if let Some(mut group) = GroupStorage::get_group(account_id.to_owned(), group_id.to_owned())
+        {
+            // check if administrator
+            if let Some(member) = group.get_member(&account_id.to_bytes()) {
+                // check permission
+                if member.role != 255 {
+                    return Err(
+                        "you don't have the permissions to change the retention of this group"
+                            .to_string(),
+                    );
+                }
+            } else {
+                return Err("you are not a member for this group".to_string());
+            }
+
+            // set retention
+            group.retention = retention;
+
+            // update revision
+            group.revision = group.revision + 1;
+
+            // save group
+            GroupStorage::save_group(account_id.to_owned(), group);
+
+            return Ok(());
+        }
+
+        Err("can not find group".to_string())
+    }
+
+    /// update the description, avatar or settings of a group from RPC command
+    ///
+    /// `account_id` the user account ID
+    pub fn update_group(
+        account_id: &PeerId,
+        group_id: &Vec<u8>,
+        update: &Option<super::proto_rpc::group_update_request::Update>,
+    ) -> Result<(), String> {
+        let mut group;
+        match GroupStorage::get_group(account_id.to_owned(), group_id.to_owned()) {
+            Some(my_group) => group = my_group,
+            None => return Err("can not find group".to_string()),
+        }
+
+        // check it's direct chat room
+        if group.is_direct_chat {
+            return Err("direct chat room does not allow this action".to_string());
+        }
+
+        // check if administrator
+        if !group.is_member(&account_id.to_bytes()) {
+            return Err("you are not a member for this group".to_string());
+        }
+        if !group.is_administrator(&account_id.to_bytes()) {
+            return Err("you don't have the permissions to update this group".to_string());
+        }
+
+        // update group
+        match update {
+            Some(super::proto_rpc::group_update_request::Update::Description(description)) => {
+                if description.len() > MAX_DESCRIPTION_LENGTH {
+                    return Err("group description is too long".to_string());
+                }
+                group.description = description.clone();
+            }
+            Some(super::proto_rpc::group_update_request::Update::Avatar(avatar)) => {
+                if avatar.len() > MAX_AVATAR_SIZE {
+                    return Err(format!(
+                        "group avatar is too big, maximal size is {} bytes",
+                        MAX_AVATAR_SIZE
+                    ));
+                }
+                group.avatar = avatar.clone();
+            }
+            Some(super::proto_rpc::group_update_request::Update::Settings(settings)) => {
+                group.settings = GroupSettings::from_rpc(settings);
+            }
+            None => return Err("no group update".to_string()),
+        }
+
+        // update revision
+        group.revision = group.revision + 1;
+
+        // save group
+        GroupStorage::save_group(account_id.to_owned(), group);
+
+        Ok(())
+    }
+
+    /// disband group from RPC command
+    ///
+    /// All members are notified and the group is deactivated.
+    ///
+    /// `account_id` the user account ID
+    pub fn disband_group(account_id: &PeerId, group_id: &Vec<u8>) -> Result<(), String> {
+        // get user account from node
+        let user_account;
+        match UserAccounts::get_by_id(*account_id) {
+            Some(my_account) => user_account = my_account,
+            None => return Err("user account has problem".to_string()),
+        }
+
+        let mut group;
+        match GroupStorage::get_group(account_id.to_owned(), group_id.to_owned()) {
+            Some(my_group) => group = my_group,
+            None => return Err("can not find group".to_string()),
+        }
+
+        // check it's direct chat room
+        if group.is_direct_chat {
+            return Err("direct chat room does not allow this action".to_string());
+        }
+
+        // check group status
+        if group.status == super::proto_rpc::GroupStatus::Deactivated as i32 {
+            return Err("group is deactivated".to_string());
+        }
+
+        // check if administrator
+        if !group.is_member(&account_id.to_bytes()) {
+            return Err("you are not a member for this group".to_string());
+        }
+        if !group.is_administrator(&account_id.to_bytes()) {
+            return Err("you don't have the permissions to disband this group".to_string());
+        }
+
+        // notify all members
+        let proto_message = super::proto_net::GroupContainer {
+            message: Some(super::proto_net::group_container::Message::Closed(
+                super::proto_net::GroupClosed {
+                    group_id: group_id.clone(),
+                },
+            )),
+        };
+        for user_id in group.members.keys() {
+            if *user_id == account_id.to_bytes() {
+                continue;
+            }
+            if let Ok(receiver) = PeerId::from_bytes(user_id) {
+                Group::send_notify_message(&user_account, &receiver, proto_message.encode_to_vec());
+            }
+        }
+
+        // deactivate group
+        group.status = super::proto_rpc::GroupStatus::Deactivated as i32;
+        GroupStorage::save_group(account_id.to_owned(), group);
+
+        // save group closed event
+        Self::save_closed_event(account_id, group_id, account_id);
+
+        Ok(())
+    }
+
+    /// set the role of a group member from RPC command
+    ///
+    /// Promotes a member to administrator or demotes an
+    /// administrator to a normal user.
+    /// A group always needs to have at least one administrator.
+    ///
+    /// `account_id` the user account ID
+    pub fn set_role(
+        account_id: &PeerId,
+        group_id: &Vec<u8>,
+        user_id: &Vec<u8>,
+        role: i32,
+    ) -> Result<(), String> {
+        // check role
+        if super::proto_rpc::GroupMemberRole::from_i32(role).is_none() {
+            return Err("invalid member role".to_string());
+        }
+
+        let mut group;
+        match GroupStorage::get_group(account_id.to_owned(), group_id.to_owned()) {
+            Some(my_group) => group = my_group,
+            None => return Err("can not find group".to_string()),
+        }
+
+        // check it's direct chat room
+        if group.is_direct_chat {
+            return Err("direct chat room does not allow this action".to_string());
+        }
+
+        // check if administrator
+        if !group.is_member(&account_id.to_bytes()) {
+            return Err("you are not a member for this group".to_string());
+        }
+        if !group.is_administrator(&account_id.to_bytes()) {
+            return Err("you don't have the permissions to change member roles".to_string());
+        }
+
+        // get member
+        let mut member;
+        match group.get_member(user_id) {
+            Some(my_member) => member = my_member.clone(),
+            None => return Err("this user is not member of this group".to_string()),
+        }
+        if member.state == super::proto_rpc::GroupMemberState::Invited as i32 {
+            return Err("this user has not joined the group yet".to_string());
+        }
+        if member.role == role {
+            return Ok(());
+        }
+
+        // set role
+        member.role = role;
+        group.members.insert(user_id.clone(), member);
+
+        // check that an administrator remains
+        let has_admin = group
+            .members
+            .values()
+            .any(|m| m.role == super::proto_rpc::GroupMemberRole::Admin as i32 && m.state > 0);
+        if !has_admin {
+            return Err("a group needs at least one administrator".to_string());
+        }
+
+        // update revision
+        group.revision = group.revision + 1;
+
+        // save group
+        GroupStorage::save_group(account_id.to_owned(), group);
+
+        Ok(())
+    }
+
+    /// save group closed event to the chat conversation
+    fn save_closed_event(account_id: &PeerId, group_id: &Vec<u8>, sender_id: &PeerId) {
+        let event = chat::rpc_proto::ChatContentMessage {
+            message: Some(chat::rpc_proto::chat_content_message::Message::GroupEvent(
+                chat::rpc_proto::GroupEvent {
+                    event_type: chat::rpc_proto::GroupEventType::Closed as i32,
+                    user_id: sender_id.to_bytes(),
+                },
+            )),
+        };
+
+        ChatStorage::save_message(
+            account_id,
+            &GroupId::from_bytes(group_id).unwrap(),
+            sender_id,
+            &Vec::new(),
+            Timestamp::get_timestamp(),
+            event,
+            chat::rpc_proto::MessageStatus::Received,
+        );
+    }
+
     /// get a new message ID
     pub fn get_new_message_id(account_id: &PeerId, group_id: &Vec<u8>) -> Vec<u8> {
         if let Some(mut group) = GroupStorage::get_group(account_id.to_owned(), group_id.to_owned())
@@ -213,6 +480,7 @@ impl GroupManage {
                 joined_at: m.joined_at,
                 state: m.state,
                 last_message_index: m.last_message_index,
+                last_read_index: m.last_read_index,
             };
             members.push(member);
         }
@@ -229,6 +497,10 @@ impl GroupManage {
             last_message_at: group.last_message_at,
             last_message: group.last_message_data,
             last_message_sender_id: group.last_message_sender_id,
+            retention: group.retention,
+            description: group.description,
+            avatar: group.avatar,
+            settings: Some(group.settings.to_rpc()),
         };
         Ok(res)
     }
@@ -252,6 +524,7 @@ impl GroupManage {
                             joined_at: m.joined_at,
                             state: m.state,
                             last_message_index: m.last_message_index,
+                            last_read_index: m.last_read_index,
                         };
                         members.push(member);
                     }
@@ -268,6 +541,10 @@ impl GroupManage {
                         last_message_at: group.last_message_at,
                         last_message: group.last_message_data,
                         last_message_sender_id: group.last_message_sender_id,
+                        retention: group.retention,
+                        description: group.description,
+                        avatar: group.avatar,
+                        settings: Some(group.settings.to_rpc()),
                     };
                     res.groups.push(grp);
                 }
@@ -294,6 +571,7 @@ impl GroupManage {
                             joined_at: member.joined_at,
                             state: member.state,
                             last_message_index: member.last_message_index,
+                            last_read_index: member.last_read_index,
                         });
                     }
 
@@ -312,6 +590,10 @@ impl GroupManage {
                             last_message_at: 0,
                             last_message: Vec::new(),
                             last_message_sender_id: Vec::new(),
+                            retention: invite.group.retention,
+                            description: invite.group.description,
+                            avatar: invite.group.avatar,
+                            settings: Some(invite.group.settings.to_rpc()),
                         }),
                     };
 
@@ -323,11 +605,138 @@ impl GroupManage {
         res
     }
 
+    /// process group closed message from network
+    ///
+    /// The group was disbanded by an administrator.
+    pub fn on_group_closed(
+        sender_id: &PeerId,
+        account_id: &PeerId,
+        message: &super::proto_net::GroupClosed,
+    ) -> Result<(), String> {
+        let mut group;
+        match GroupStorage::get_group(account_id.to_owned(), message.group_id.clone()) {
+            Some(my_group) => group = my_group,
+            None => return Err("group not found".to_string()),
+        }
+
+        // check it's direct chat room
+        if group.is_direct_chat {
+            return Err("direct chat room does not allow this action".to_string());
+        }
+
+        // check if sender is administrator
+        if !group.is_administrator(&sender_id.to_bytes()) {
+            return Err("sender is not administrator".to_string());
+        }
+
+        // deactivate group
+        group.status = super::proto_rpc::GroupStatus::Deactivated as i32;
+        GroupStorage::save_group(account_id.to_owned(), group);
+
+        // save group closed event
+        Self::save_closed_event(account_id, &message.group_id, sender_id);
+
+        Ok(())
+    }
+
+    /// check if the sender of a group update is allowed
+    /// to make the changes it contains
+    ///
+    /// Administrators are allowed to make all changes.
+    /// Members are only allowed to invite new members and to
+    /// rename the group, if the group settings permit it.
+    /// Members they invited are activated, once they accepted
+    /// the invitation.
+    fn check_update_permission(
+        group: &Group,
+        sender_id: &PeerId,
+        notify: &super::proto_net::GroupInfo,
+    ) -> Result<(), String> {
+        let sender;
+        match group.get_member(&sender_id.to_bytes()) {
+            Some(member) => sender = member,
+            None => return Err("sender is not member of this group".to_string()),
+        }
+        if sender.state == 0 {
+            return Err("sender has not joined this group".to_string());
+        }
+        if sender.role == super::proto_rpc::GroupMemberRole::Admin as i32 {
+            return Ok(());
+        }
+
+        // members can't remove members or change their roles
+        for member in group.members.values() {
+            if member.state == 0 {
+                continue;
+            }
+            match notify.members.iter().find(|m| m.user_id == member.user_id) {
+                Some(m) => {
+                    if m.role != member.role {
+                        return Err("only administrators can change member roles".to_string());
+                    }
+                }
+                None => return Err("only administrators can remove members".to_string()),
+            }
+        }
+
+        // check new members
+        for m in &notify.members {
+            match group.get_member(&m.user_id) {
+                Some(member) => {
+                    if member.state > 0 {
+                        continue;
+                    }
+                }
+                None => {
+                    if m.state != super::proto_net::GroupMemberState::Invited as i32 {
+                        return Err("members can only add invited members".to_string());
+                    }
+                }
+            }
+            if !group.settings.members_can_invite {
+                return Err("members are not allowed to invite".to_string());
+            }
+            if m.role == super::proto_net::GroupMemberRole::Admin as i32 {
+                return Err("only administrators can add administrators".to_string());
+            }
+        }
+
+        // check group name
+        if notify.group_name != group.name && !group.settings.members_can_rename {
+            return Err("members are not allowed to rename this group".to_string());
+        }
+
+        // check group settings
+        if notify.retention != group.retention
+            || notify.description != group.description
+            || notify.avatar != group.avatar
+            || GroupSettings::from_net(&notify.settings) != group.settings
+        {
+            return Err("only administrators can change the group settings".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// get the hash of the group info of a
+    /// protobuf encoded `SignedGroupInfo` message
+    fn signed_info_hash(signed_info: &Vec<u8>) -> Vec<u8> {
+        match super::proto_net::SignedGroupInfo::decode(&signed_info[..]) {
+            Ok(signed) => Group::hash_group_info(&signed.group_info),
+            Err(_) => Vec::new(),
+        }
+    }
+
     /// process group notify message from network
+    ///
+    /// `sender_id` is the member who created this group info.
+    /// `signed_info` is the protobuf encoded `SignedGroupInfo` message,
+    /// which is forwarded to members with an older group revision.
     pub fn on_group_notify(
         sender_id: PeerId,
         account_id: PeerId,
         notify: &super::proto_net::GroupInfo,
+        signed_info: Vec<u8>,
     ) {
         // check for valid group ID
         let group_id;
@@ -349,48 +758,123 @@ impl GroupManage {
             Some(my_group) => {
                 group = my_group;
 
+                // a deactivated group doesn't accept updates anymore
+                if group.status == super::proto_rpc::GroupStatus::Deactivated as i32 {
+                    log::warn!(
+                        "group update: group {} is deactivated",
+                        group_id.to_string()
+                    );
+                    return;
+                }
+
                 // check if the sent revision is higher then the one we already have
                 // return otherwise
-                if group.revision >= notify.revision {
+                if group.revision > notify.revision {
                     log::warn!("group update: got a smaller revision");
                     return;
                 }
 
-                // check if sender is administrator, otherwise return
-                let mut sender_is_admin = false;
-                for (member_id, member) in &group.members {
-                    orign_members.insert(member_id.clone(), true);
-
-                    if member.user_id == sender_id.to_bytes() && member.role == 255 {
-                        sender_is_admin = true;
-                    }
+                // diverging group infos of the same revision:
+                // the group info with the higher hash wins
+                if group.revision == notify.revision
+                    && Self::signed_info_hash(&signed_info) <= group.info_hash()
+                {
+                    log::trace!("group update: revision {} already known", notify.revision);
+                    return;
                 }
 
-                if !sender_is_admin {
+                // check if sender is allowed to make these changes, otherwise return
+                if let Err(e) = Self::check_update_permission(&group, &sender_id, notify) {
                     log::error!(
-                        "illegitimate update from user {} for group {}",
+                        "illegitimate update from user {} for group {}: {}",
                         sender_id.to_base58(),
                         group_id.to_string(),
+                        e
                     );
                     return;
                 }
+
+                for (member_id, member) in &group.members {
+                    // pending invites haven't joined yet
+                    if member.state > 0 {
+                        orign_members.insert(member_id.clone(), true);
+                    }
+                }
             }
             None => {
                 first_join = true;
 
+                // the sender of a new group needs to be an administrator of it
+                let sender_is_admin = notify.members.iter().any(|m| {
+                    m.user_id == sender_id.to_bytes()
+                        && m.role == super::proto_net::GroupMemberRole::Admin as i32
+                });
+                if !sender_is_admin {
+                    log::error!(
+                        "illegitimate group info from user {} for group {}",
+                        sender_id.to_base58(),
+                        group_id.to_string(),
+                    );
+                    return;
+                }
+
+                // ignore groups we are not a member of,
+                // pending invitations need to be accepted first
+                if !notify
+                    .members
+                    .iter()
+                    .any(|m| m.user_id == account_id.to_bytes() && m.state > 0)
+                {
+                    log::warn!(
+                        "group update: not a member of group {}",
+                        group_id.to_string()
+                    );
+                    return;
+                }
+
                 group = Group::new();
             }
         }
 
+        // a group always needs an administrator
+        if !notify
+            .members
+            .iter()
+            .any(|m| m.role == super::proto_net::GroupMemberRole::Admin as i32)
+        {
+            log::error!(
+                "group update without administrator from user {} for group {}",
+                sender_id.to_base58(),
+                group_id.to_string(),
+            );
+            return;
+        }
+
+        // check avatar size
+        if notify.avatar.len() > MAX_AVATAR_SIZE {
+            log::error!(
+                "group update with oversized avatar from user {} for group {}",
+                sender_id.to_base58(),
+                group_id.to_string(),
+            );
+            return;
+        }
+
         // check for new members
         let mut members: BTreeMap<Vec<u8>, super::GroupMember> = BTreeMap::new();
         for m in &notify.members {
             if orign_members.contains_key(&m.user_id) {
                 orign_members.remove(&m.user_id);
-            } else {
+            } else if m.state > 0 {
                 new_members.push(m.user_id.clone());
             }
 
+            // the read index is local information, keep it
+            let mut last_read_index = 0;
+            if let Some(member) = group.members.get(&m.user_id) {
+                last_read_index = member.last_read_index;
+            }
+
             members.insert(
                 m.user_id.clone(),
                 super::GroupMember {
@@ -399,22 +883,46 @@ impl GroupManage {
                     joined_at: m.joined_at,
                     state: m.state,
                     last_message_index: m.last_message_index,
+                    last_read_index,
                 },
             );
         }
 
+        // keep our pending invites
+        for (member_id, member) in &group.members {
+            if member.state == 0 && !members.contains_key(member_id) {
+                members.insert(member_id.clone(), member.clone());
+            }
+        }
+
         // update group
         group.id = notify.group_id.clone();
         group.name = notify.group_name.clone();
         group.created_at = notify.created_at;
         group.revision = notify.revision;
         group.members = members;
+        group.retention = notify.retention;
+        group.description = notify.description.clone();
+        group.avatar = notify.avatar.clone();
+        group.settings = GroupSettings::from_net(&notify.settings);
+        group.signed_info = signed_info;
+        for code_use in &notify.invite_code_uses {
+            let uses = group.invite_code_uses.entry(code_use.code_id).or_insert(0);
+            if code_use.uses > *uses {
+                *uses = code_use.uses;
+            }
+        }
 
         // activate group after invite accept
         if group.status == super::proto_rpc::GroupStatus::InviteAccepted as i32 {
             group.status = super::proto_rpc::GroupStatus::Active as i32;
         }
 
+        // deactivate group if we are not a member anymore
+        if !group.is_member(&account_id.to_bytes()) {
+            group.status = super::proto_rpc::GroupStatus::Deactivated as i32;
+        }
+
         // save group
         GroupStorage::save_group(account_id, group);
 
@@ -5,13 +5,14 @@
 //!
 //! Invite new group members.
 //! Accept or reject invitations.
+//! Leave groups.
 
 use libp2p::PeerId;
 use prost::Message;
 
 use super::chat::{self, ChatStorage};
 use super::group_id::GroupId;
-use super::{Group, GroupMember, GroupStorage};
+use super::{Group, GroupMember, GroupSettings, GroupStorage};
 use crate::utilities::timestamp::Timestamp;
 use crate::{node::user_accounts::UserAccounts, utilities::timestamp};
 
@@ -20,6 +21,9 @@ pub struct Member {}
 
 impl Member {
     /// invite member from rpc command
+    ///
+    /// The invited member is part of the group info,
+    /// the group revision is increased.
     pub fn invite(
         account_id: &PeerId,
         group_id: &Vec<u8>,
@@ -37,10 +41,10 @@ impl Member {
             return Err("direct chat room does not allow this action".to_string());
         }
 
-        // check admin permission
+        // check invite permission
         if let Some(member) = group.get_member(&account_id.to_bytes()) {
-            if member.role != 255 {
-                return Err("you haven't permission for remove member".to_string());
+            if member.role != 255 && !(group.settings.members_can_invite && member.state > 0) {
+                return Err("you don't have the permissions to invite members".to_string());
             }
         } else {
             return Err("you are not member in this group".to_string());
@@ -76,6 +80,11 @@ impl Member {
                         created_at: group.created_at,
                         revision: group.revision,
                         members,
+                        retention: group.retention,
+                        invite_code_uses: group.get_invite_code_uses(),
+                        description: group.description.clone(),
+                        avatar: group.avatar.clone(),
+                        settings: Some(group.settings.to_net()),
                     }),
                 },
             )),
@@ -93,10 +102,14 @@ impl Member {
                     .try_into()
                     .unwrap(),
                 last_message_index: 0,
+                last_read_index: 0,
             };
 
             group.members.insert(user_id.to_bytes(), member);
 
+            // update revision
+            group.revision = group.revision + 1;
+
             GroupStorage::save_group(user_account.id, group);
         } else {
             return Err("user account problem".to_string());
@@ -253,6 +266,112 @@ impl Member {
         Ok(true)
     }
 
+    /// leave a group from rpc command
+    ///
+    /// All other group members are notified and the group
+    /// is deactivated for this user account.
+    /// The last administrator can only leave the group,
+    /// if there are no other members left in the group.
+    pub fn leave(account_id: &PeerId, group_id: &Vec<u8>) -> Result<bool, String> {
+        // get user account from node
+        let user_account;
+        match UserAccounts::get_by_id(*account_id) {
+            Some(my_account) => user_account = my_account,
+            None => return Err("user account has problem".to_string()),
+        }
+
+        // get group from data base
+        let mut group;
+        match GroupStorage::get_group(account_id.to_owned(), group_id.to_owned()) {
+            Some(my_group) => group = my_group,
+            None => return Err("group not found".to_string()),
+        }
+
+        // check it's direct chat room
+        if group.is_direct_chat {
+            return Err("direct chat room does not allow this action".to_string());
+        }
+
+        // check group status
+        if group.status == super::proto_rpc::GroupStatus::Deactivated as i32 {
+            return Err("group is deactivated".to_string());
+        }
+
+        // check membership
+        if !group.is_member(&account_id.to_bytes()) {
+            return Err("you are not member in this group".to_string());
+        }
+
+        // the last administrator needs to hand over the group
+        if group.is_administrator(&account_id.to_bytes()) {
+            let mut other_members = 0;
+            let mut other_admins = 0;
+            for member in group.members.values() {
+                if member.user_id == account_id.to_bytes() || member.state == 0 {
+                    continue;
+                }
+                other_members = other_members + 1;
+                if member.role == super::proto_rpc::GroupMemberRole::Admin as i32 {
+                    other_admins = other_admins + 1;
+                }
+            }
+            if other_members > 0 && other_admins == 0 {
+                return Err(
+                    "you are the last administrator, promote another member or disband the group"
+                        .to_string(),
+                );
+            }
+        }
+
+        // notify all other members
+        let proto_message = super::proto_net::GroupContainer {
+            message: Some(super::proto_net::group_container::Message::Left(
+                super::proto_net::LeftGroup {
+                    group_id: group_id.clone(),
+                },
+            )),
+        };
+        for user_id in group.members.keys() {
+            if *user_id == account_id.to_bytes() {
+                continue;
+            }
+            if let Ok(receiver) = PeerId::from_bytes(user_id) {
+                Group::send_notify_message(&user_account, &receiver, proto_message.encode_to_vec());
+            }
+        }
+
+        // remove self from group
+        group.members.remove(&account_id.to_bytes());
+
+        // set group deactivation status
+        group.status = super::proto_rpc::GroupStatus::Deactivated as i32;
+
+        // save group
+        GroupStorage::save_group(account_id.to_owned(), group);
+
+        // save group event
+        let event = chat::rpc_proto::ChatContentMessage {
+            message: Some(chat::rpc_proto::chat_content_message::Message::GroupEvent(
+                chat::rpc_proto::GroupEvent {
+                    event_type: chat::rpc_proto::GroupEventType::Left as i32,
+                    user_id: account_id.to_bytes(),
+                },
+            )),
+        };
+
+        ChatStorage::save_message(
+            account_id,
+            &GroupId::from_bytes(group_id).unwrap(),
+            account_id,
+            &Vec::new(),
+            Timestamp::get_timestamp(),
+            event,
+            chat::rpc_proto::MessageStatus::Received,
+        );
+
+        Ok(true)
+    }
+
     /// process group invite message from network
     pub fn on_be_invited(
         sender_id: &PeerId,
@@ -280,6 +399,7 @@ impl Member {
                     joined_at: member.joined_at,
                     state: member.state,
                     last_message_index: member.last_message_index,
+                    last_read_index: 0,
                 },
             );
         }
@@ -289,6 +409,10 @@ impl Member {
         group.created_at = group_info.created_at;
         group.status = super::proto_rpc::GroupStatus::InviteAccepted as i32;
         group.revision = group_info.revision;
+        group.retention = group_info.retention;
+        group.description = group_info.description.clone();
+        group.avatar = group_info.avatar.clone();
+        group.settings = GroupSettings::from_net(&group_info.settings);
 
         let invited = super::GroupInvited {
             sender_id: sender_id.to_bytes(),
@@ -469,4 +593,67 @@ impl Member {
 
         Ok(true)
     }
+
+    /// a member has left the group
+    ///
+    /// Returns true if we are an administrator of the group.
+    /// In this case the member is removed, the group revision is
+    /// increased and the group update needs to be posted to all members.
+    /// The other members only record the event and receive the
+    /// new member list with the administrator's signed group update.
+    pub fn on_left(
+        sender_id: &PeerId,
+        account_id: &PeerId,
+        message: &super::proto_net::LeftGroup,
+    ) -> Result<bool, String> {
+        // get group from data base
+        let mut group;
+        match GroupStorage::get_group(account_id.to_owned(), message.group_id.clone()) {
+            Some(my_group) => group = my_group,
+            None => return Err("group not found".to_string()),
+        }
+
+        // check it's direct chat room
+        if group.is_direct_chat {
+            return Err("direct chat room does not allow this action".to_string());
+        }
+
+        // check if sender is in group
+        if !group.is_member(&sender_id.to_bytes()) {
+            return Err("sender is not in group".to_string());
+        }
+
+        // only the administrator removes the sender and updates the revision,
+        // the other members wait for the administrator's signed group update
+        let is_admin = group.is_administrator(&account_id.to_bytes());
+        if is_admin {
+            group.members.remove(&sender_id.to_bytes());
+            group.revision = group.revision + 1;
+
+            // save group
+            GroupStorage::save_group(account_id.to_owned(), group.clone());
+        }
+
+        // save event
+        let event = chat::rpc_proto::ChatContentMessage {
+            message: Some(chat::rpc_proto::chat_content_message::Message::GroupEvent(
+                chat::rpc_proto::GroupEvent {
+                    event_type: chat::rpc_proto::GroupEventType::Left as i32,
+                    user_id: sender_id.to_bytes(),
+                },
+            )),
+        };
+
+        ChatStorage::save_message(
+            account_id,
+            &GroupId::from_bytes(&group.id).unwrap(),
+            sender_id,
+            &Vec::new(),
+            Timestamp::get_timestamp(),
+            event,
+            chat::rpc_proto::MessageStatus::Received,
+        );
+
+        Ok(is_admin)
+    }
 }
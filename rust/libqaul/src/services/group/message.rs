@@ -4,6 +4,7 @@
 //! # Group Management Message Handling
 //!
 //! This file processes an incoming group management message.
+//! The group revision of the sender is compared with ours.
 
 use libp2p::PeerId;
 
@@ -19,6 +20,8 @@ impl GroupMessage {
         account_id: &PeerId,
         group_id: &Vec<u8>,
         message_id: &Vec<u8>,
+        revision: u32,
+        info_hash: &Vec<u8>,
     ) -> Result<bool, String> {
         let group;
         match GroupStorage::get_group(account_id.to_owned(), group_id.to_owned()) {
@@ -64,6 +67,9 @@ impl GroupMessage {
             super::Group::update_group_member(&account_id, group_id, &sender);
         }
 
+        // compare group revisions
+        super::GroupSync::on_revision(sender_id, account_id, &group, revision, info_hash);
+
         Ok(true)
     }
 }
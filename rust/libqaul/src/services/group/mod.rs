@@ -10,6 +10,7 @@
 use libp2p::PeerId;
 use prost::Message;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::BTreeMap;
 
 use self::proto_net::GroupMemberRole;
@@ -21,16 +22,22 @@ use crate::rpc::Rpc;
 use crate::utilities::timestamp::Timestamp;
 
 pub mod group_id;
+mod invite_code;
 mod manage;
 mod member;
 mod message;
+mod sender_key;
 pub mod storage;
+mod sync;
 
 pub use group_id::GroupId;
+use invite_code::InviteCode;
 pub use manage::GroupManage;
 use member::Member;
 pub use message::GroupMessage;
+pub use sender_key::GroupSenderKey;
 pub use storage::GroupStorage;
+use sync::GroupSync;
 
 /// Import protobuf message definition generated by
 /// the rust module prost-build.
@@ -54,6 +61,63 @@ pub struct GroupMember {
     pub state: i32,
     // last message index
     pub last_message_index: u32,
+    // index of the last of our messages this member has read
+    pub last_read_index: u32,
+}
+
+/// Group permission settings
+///
+/// The default settings allow only administrators
+/// to invite and rename, and all members to post.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct GroupSettings {
+    /// members are allowed to invite new members
+    pub members_can_invite: bool,
+    /// only administrators are allowed to post messages
+    pub admins_only_post: bool,
+    /// members are allowed to rename the group
+    pub members_can_rename: bool,
+}
+
+impl GroupSettings {
+    /// create settings from the network format
+    pub fn from_net(settings: &Option<proto_net::GroupSettings>) -> GroupSettings {
+        match settings {
+            Some(settings) => GroupSettings {
+                members_can_invite: settings.members_can_invite,
+                admins_only_post: settings.admins_only_post,
+                members_can_rename: settings.members_can_rename,
+            },
+            None => GroupSettings::default(),
+        }
+    }
+
+    /// create settings from the RPC format
+    pub fn from_rpc(settings: &proto_rpc::GroupSettings) -> GroupSettings {
+        GroupSettings {
+            members_can_invite: settings.members_can_invite,
+            admins_only_post: settings.admins_only_post,
+            members_can_rename: settings.members_can_rename,
+        }
+    }
+
+    /// convert settings to the network format
+    pub fn to_net(&self) -> proto_net::GroupSettings {
+        proto_net::GroupSettings {
+            members_can_invite: self.members_can_invite,
+            admins_only_post: self.admins_only_post,
+            members_can_rename: self.members_can_rename,
+        }
+    }
+
+    /// convert settings to the RPC format
+    pub fn to_rpc(&self) -> proto_rpc::GroupSettings {
+        proto_rpc::GroupSettings {
+            members_can_invite: self.members_can_invite,
+            admins_only_post: self.admins_only_post,
+            members_can_rename: self.members_can_rename,
+        }
+    }
 }
 
 /// Structure of Group
@@ -98,6 +162,27 @@ pub struct Group {
     pub last_message_data: Vec<u8>,
     /// last message sender id
     pub last_message_sender_id: Vec<u8>,
+    /// message retention time in seconds
+    ///
+    /// messages older than this time are deleted,
+    /// 0 = messages are kept forever
+    pub retention: u64,
+    /// group description
+    pub description: String,
+    /// group avatar image
+    pub avatar: Vec<u8>,
+    /// group permission settings
+    pub settings: GroupSettings,
+    /// signed group info of the current revision
+    ///
+    /// This field contains the data of the
+    /// qaul.net.group message `SignedGroupInfo`.
+    /// It is empty if we haven't received a signed group info yet.
+    pub signed_info: Vec<u8>,
+    /// number of uses of the group's invite codes
+    ///
+    /// code_id => uses
+    pub invite_code_uses: BTreeMap<u32, u32>,
 }
 
 /// Group module to process transfer, receive and RPC commands
@@ -106,6 +191,12 @@ impl Group {
     pub fn init() {
         // initialize group storage
         GroupStorage::init();
+
+        // initialize group synchronisation
+        GroupSync::init();
+
+        // initialize group sender keys
+        GroupSenderKey::init();
     }
 
     /// creates a new empty group
@@ -124,6 +215,12 @@ impl Group {
             last_message_at: 0,
             last_message_data: Vec::new(),
             last_message_sender_id: Vec::new(),
+            retention: 0,
+            description: "".to_string(),
+            avatar: Vec::new(),
+            settings: GroupSettings::default(),
+            signed_info: Vec::new(),
+            invite_code_uses: BTreeMap::new(),
         }
     }
 
@@ -136,7 +233,6 @@ impl Group {
     }
 
     /// Verify if a user is the administrator of the group
-    #[allow(dead_code)]
     pub fn is_administrator(&self, user_id: &Vec<u8>) -> bool {
         if let Some(member) = self.members.get(user_id) {
             if member.role == GroupMemberRole::Admin as i32 {
@@ -146,11 +242,56 @@ impl Group {
         false
     }
 
+    /// Verify if a user is allowed to post messages into the group
+    pub fn can_post(&self, user_id: &Vec<u8>) -> bool {
+        if self.settings.admins_only_post {
+            return self.is_administrator(user_id);
+        }
+        self.is_member(user_id)
+    }
+
+    /// get the invite code uses in network format
+    pub fn get_invite_code_uses(&self) -> Vec<proto_net::InviteCodeUse> {
+        self.invite_code_uses
+            .iter()
+            .map(|(code_id, uses)| proto_net::InviteCodeUse {
+                code_id: *code_id,
+                uses: *uses,
+            })
+            .collect()
+    }
+
     /// Verify if a user is a member of the group
     pub fn is_member(&self, user_id: &Vec<u8>) -> bool {
         self.members.contains_key(user_id)
     }
 
+    /// get the hash of the signed group info of the current revision
+    ///
+    /// The hash decides between diverging group infos of the
+    /// same revision. It is empty if we don't have a signed
+    /// group info of the current revision.
+    pub fn info_hash(&self) -> Vec<u8> {
+        if self.signed_info.len() == 0 {
+            return Vec::new();
+        }
+
+        if let Ok(signed) = proto_net::SignedGroupInfo::decode(&self.signed_info[..]) {
+            if let Ok(info) = proto_net::GroupInfo::decode(&signed.group_info[..]) {
+                if info.revision == self.revision {
+                    return Self::hash_group_info(&signed.group_info);
+                }
+            }
+        }
+
+        Vec::new()
+    }
+
+    /// hash of a protobuf encoded `GroupInfo` message
+    pub fn hash_group_info(group_info: &Vec<u8>) -> Vec<u8> {
+        Sha256::digest(group_info).to_vec()
+    }
+
     /// Verify if both user_id's are members of the group
     ///
     /// This is a convenient function to verify incoming messages.
@@ -225,6 +366,12 @@ impl Group {
             }
         }
 
+        // check post permission
+        if !group.can_post(&user_account.id.to_bytes()) {
+            log::error!("only administrators can post into this group");
+            return;
+        }
+
         let last_index = my_member.last_message_index + 1;
         let message_id = Chat::generate_message_id(&group.id, &user_account.id, last_index);
         let common_message = proto::CommonMessage {
@@ -236,6 +383,8 @@ impl Group {
                     content: data.clone(),
                 },
             )),
+            group_revision: group.revision,
+            group_info_hash: group.info_hash(),
         };
 
         let send_message = proto::Messaging {
@@ -264,65 +413,96 @@ impl Group {
         }
     }
 
+    /// Create the group info of the current revision
+    /// and sign it with the keys of the user account
+    ///
+    /// Returns the protobuf encoded `SignedGroupInfo` message.
+    fn sign_group_info(user_account: &UserAccount, group: &Group) -> Option<Vec<u8>> {
+        // pending invites are part of the group info,
+        // so that invitations of members can be verified
+        let mut members: Vec<proto_net::GroupMember> = vec![];
+        for m in group.members.values() {
+            members.push(proto_net::GroupMember {
+                user_id: m.user_id.clone(),
+                role: m.role,
+                state: m.state,
+                joined_at: m.joined_at,
+                last_message_index: m.last_message_index,
+            });
+        }
+
+        let group_info = proto_net::GroupInfo {
+            group_id: group.id.clone(),
+            group_name: group.name.clone(),
+            created_at: group.created_at,
+            revision: group.revision,
+            members,
+            retention: group.retention,
+            invite_code_uses: group.get_invite_code_uses(),
+            description: group.description.clone(),
+            avatar: group.avatar.clone(),
+            settings: Some(group.settings.to_net()),
+        }
+        .encode_to_vec();
+
+        match user_account.keys.sign(&group_info) {
+            Ok(signature) => Some(
+                proto_net::SignedGroupInfo {
+                    group_info,
+                    admin_id: user_account.id.to_bytes(),
+                    signature,
+                }
+                .encode_to_vec(),
+            ),
+            Err(e) => {
+                log::error!("group info signing error {}", e);
+                None
+            }
+        }
+    }
+
     /// Send group updated to all members
+    ///
+    /// The group info is signed by us and
+    /// saved as the signed info of the current revision.
     fn post_group_update(account_id: &PeerId, group_id: &Vec<u8>) {
-        let group;
+        let mut group;
         match GroupStorage::get_group(account_id.to_owned(), group_id.to_owned()) {
             Some(my_group) => group = my_group,
             None => return,
         }
 
-        // create group notify message and post to all members
-        let mut members: Vec<proto_net::GroupMember> = vec![];
-        for m in group.members.values() {
-            if m.state > 0 {
-                members.push(proto_net::GroupMember {
-                    user_id: m.user_id.clone(),
-                    role: m.role,
-                    state: m.state,
-                    joined_at: m.joined_at,
-                    last_message_index: m.last_message_index,
-                });
-            }
+        let user_account;
+        match UserAccounts::get_by_id(*account_id) {
+            Some(my_account) => user_account = my_account,
+            None => return,
         }
 
-        let notify = proto_net::GroupInfo {
-            group_id: group_id.clone(),
-            group_name: group.name.clone(),
-            created_at: group.created_at,
-            revision: group.revision,
-            members,
-        };
+        // create signed group info
+        match Self::sign_group_info(&user_account, &group) {
+            Some(signed_info) => group.signed_info = signed_info,
+            None => return,
+        }
+        GroupStorage::save_group(account_id.to_owned(), group.clone());
 
-        let container = proto_net::GroupContainer {
-            message: Some(proto_net::group_container::Message::GroupInfo(notify)),
-        };
+        let signed;
+        match proto_net::SignedGroupInfo::decode(&group.signed_info[..]) {
+            Ok(info) => signed = info,
+            Err(e) => {
+                log::error!("signed group info decoding error {}", e);
+                return;
+            }
+        }
 
-        let send_message = proto::Messaging {
-            message: Some(proto::messaging::Message::GroupInviteMessage(
-                proto::GroupInviteMessage {
-                    content: container.encode_to_vec(),
-                },
-            )),
+        let container = proto_net::GroupContainer {
+            message: Some(proto_net::group_container::Message::SignedInfo(signed)),
         };
 
         // send to all group members
-        if let Some(user_account) = UserAccounts::get_by_id(*account_id) {
-            for user_id in group.members.keys() {
-                let receiver = PeerId::from_bytes(&user_id.clone()).unwrap();
-                if receiver != *account_id {
-                    let message_id: Vec<u8> = Vec::new();
-                    if let Err(error) = Messaging::pack_and_send_message(
-                        &user_account,
-                        &receiver,
-                        send_message.encode_to_vec(),
-                        MessagingServiceType::Group,
-                        &message_id,
-                        true,
-                    ) {
-                        log::error!("send group notify error {}", error);
-                    }
-                }
+        for user_id in group.members.keys() {
+            let receiver = PeerId::from_bytes(&user_id.clone()).unwrap();
+            if receiver != *account_id {
+                Self::send_notify_message(&user_account, &receiver, container.encode_to_vec());
             }
         }
     }
@@ -354,6 +534,24 @@ impl Group {
                         log::error!("group on_removed error {}", error);
                     }
                 }
+                Some(proto_net::group_container::Message::Left(left)) => {
+                    log::trace!("group::on_left");
+                    // remove the leaving member from the group
+                    match Member::on_left(&sender_id, &receiver_id, &left) {
+                        Ok(true) => Self::post_group_update(&user.id, &left.group_id),
+                        Ok(false) => {}
+                        Err(error) => log::error!("group on_left error {}", error),
+                    }
+                }
+                Some(proto_net::group_container::Message::Closed(closed)) => {
+                    log::trace!("group::on_group_closed");
+                    // deactivate group
+                    if let Err(error) =
+                        GroupManage::on_group_closed(&sender_id, &receiver_id, &closed)
+                    {
+                        log::error!("group on_group_closed error {}", error);
+                    }
+                }
                 Some(proto_net::group_container::Message::ReplyInvite(reply_invite)) => {
                     log::trace!("group::on_answered for invite");
                     if let Err(error) =
@@ -372,8 +570,40 @@ impl Group {
                         sender_id.to_owned(),
                         receiver_id.to_owned(),
                         &group_info,
+                        Vec::new(),
                     );
                 }
+                Some(proto_net::group_container::Message::InfoRequest(info_request)) => {
+                    log::trace!("group info request arrived");
+                    if let Err(error) =
+                        GroupSync::on_info_request(sender_id, receiver_id, &info_request)
+                    {
+                        log::error!("group on_info_request error {}", error);
+                    }
+                }
+                Some(proto_net::group_container::Message::SenderKey(sender_key)) => {
+                    log::trace!("group sender key arrived");
+                    if let Err(error) =
+                        GroupSenderKey::on_sender_key(sender_id, receiver_id, &sender_key)
+                    {
+                        log::error!("group on_sender_key error {}", error);
+                    }
+                }
+                Some(proto_net::group_container::Message::RedeemCode(redeem_code)) => {
+                    log::trace!("group invite code arrived");
+                    match InviteCode::on_redeem(sender_id, receiver_id, &redeem_code) {
+                        Ok(group_id) => Self::post_group_update(&user.id, &group_id),
+                        Err(error) => log::error!("group on_redeem error {}", error),
+                    }
+                }
+                Some(proto_net::group_container::Message::SignedInfo(signed_info)) => {
+                    log::trace!("signed group info arrived");
+                    if let Err(error) =
+                        GroupSync::on_signed_info(sender_id, receiver_id, &signed_info)
+                    {
+                        log::error!("group on_signed_info error {}", error);
+                    }
+                }
                 None => {
                     log::error!("group message from {} was empty", sender_id.to_base58())
                 }
@@ -508,14 +738,17 @@ impl Group {
                     Some(proto_rpc::group::Message::GroupInviteMemberRequest(invite_req)) => {
                         let mut status = true;
                         let mut message: String = "".to_string();
-                        if let Err(err) = Member::invite(
+                        match Member::invite(
                             &my_user_id,
                             &invite_req.group_id,
                             &PeerId::from_bytes(&invite_req.user_id).unwrap(),
                         ) {
-                            status = false;
-                            message = err.clone();
-                            log::error!("Get group info error, {}", err);
+                            Ok(_) => Self::post_group_update(&my_user_id, &invite_req.group_id),
+                            Err(err) => {
+                                status = false;
+                                message = err.clone();
+                                log::error!("Get group info error, {}", err);
+                            }
                         }
                         let proto_message = proto_rpc::Group {
                             message: Some(proto_rpc::group::Message::GroupInviteMemberResponse(
@@ -599,6 +832,243 @@ impl Group {
                             Self::post_group_update(&my_user_id, &remove_req.group_id);
                         }
                     }
+                    Some(proto_rpc::group::Message::GroupRetentionRequest(retention_req)) => {
+                        let mut status = true;
+                        let mut message: String = "".to_string();
+                        if let Err(err) = GroupManage::set_retention(
+                            &my_user_id,
+                            &retention_req.group_id,
+                            retention_req.retention,
+                        ) {
+                            status = false;
+                            message = err.clone();
+                        }
+
+                        let proto_message = proto_rpc::Group {
+                            message: Some(proto_rpc::group::Message::GroupRetentionResponse(
+                                proto_rpc::GroupRetentionResponse {
+                                    group_id: retention_req.group_id.clone(),
+                                    retention: retention_req.retention,
+                                    result: Some(proto_rpc::GroupResult { status, message }),
+                                },
+                            )),
+                        };
+
+                        // send message
+                        Rpc::send_message(
+                            proto_message.encode_to_vec(),
+                            crate::rpc::proto::Modules::Group.into(),
+                            "".to_string(),
+                            Vec::new(),
+                        );
+
+                        // post updates
+                        if status {
+                            Self::post_group_update(&my_user_id, &retention_req.group_id);
+                        }
+                    }
+                    Some(proto_rpc::group::Message::GroupLeaveRequest(leave_req)) => {
+                        let mut status = true;
+                        let mut message: String = "".to_string();
+                        if let Err(err) = Member::leave(&my_user_id, &leave_req.group_id) {
+                            status = false;
+                            message = err.clone();
+                        }
+
+                        let proto_message = proto_rpc::Group {
+                            message: Some(proto_rpc::group::Message::GroupLeaveResponse(
+                                proto_rpc::GroupLeaveResponse {
+                                    group_id: leave_req.group_id.clone(),
+                                    result: Some(proto_rpc::GroupResult { status, message }),
+                                },
+                            )),
+                        };
+
+                        // send message
+                        Rpc::send_message(
+                            proto_message.encode_to_vec(),
+                            crate::rpc::proto::Modules::Group.into(),
+                            "".to_string(),
+                            Vec::new(),
+                        );
+                    }
+                    Some(proto_rpc::group::Message::GroupDisbandRequest(disband_req)) => {
+                        let mut status = true;
+                        let mut message: String = "".to_string();
+                        if let Err(err) =
+                            GroupManage::disband_group(&my_user_id, &disband_req.group_id)
+                        {
+                            status = false;
+                            message = err.clone();
+                        }
+
+                        let proto_message = proto_rpc::Group {
+                            message: Some(proto_rpc::group::Message::GroupDisbandResponse(
+                                proto_rpc::GroupDisbandResponse {
+                                    group_id: disband_req.group_id.clone(),
+                                    result: Some(proto_rpc::GroupResult { status, message }),
+                                },
+                            )),
+                        };
+
+                        // send message
+                        Rpc::send_message(
+                            proto_message.encode_to_vec(),
+                            crate::rpc::proto::Modules::Group.into(),
+                            "".to_string(),
+                            Vec::new(),
+                        );
+                    }
+                    Some(proto_rpc::group::Message::GroupSetRoleRequest(role_req)) => {
+                        let mut status = true;
+                        let mut message: String = "".to_string();
+                        if let Err(err) = GroupManage::set_role(
+                            &my_user_id,
+                            &role_req.group_id,
+                            &role_req.user_id,
+                            role_req.role,
+                        ) {
+                            status = false;
+                            message = err.clone();
+                        }
+
+                        let proto_message = proto_rpc::Group {
+                            message: Some(proto_rpc::group::Message::GroupSetRoleResponse(
+                                proto_rpc::GroupSetRoleResponse {
+                                    group_id: role_req.group_id.clone(),
+                                    user_id: role_req.user_id.clone(),
+                                    role: role_req.role,
+                                    result: Some(proto_rpc::GroupResult { status, message }),
+                                },
+                            )),
+                        };
+
+                        // send message
+                        Rpc::send_message(
+                            proto_message.encode_to_vec(),
+                            crate::rpc::proto::Modules::Group.into(),
+                            "".to_string(),
+                            Vec::new(),
+                        );
+
+                        // post updates
+                        if status {
+                            Self::post_group_update(&my_user_id, &role_req.group_id);
+                        }
+                    }
+                    Some(proto_rpc::group::Message::GroupInviteCodeRequest(code_req)) => {
+                        let mut status = true;
+                        let mut message: String = "".to_string();
+                        let mut code = "".to_string();
+                        let mut expires_at = 0;
+                        match InviteCode::create(
+                            &my_user_id,
+                            &code_req.group_id,
+                            code_req.expires_in,
+                            code_req.max_uses,
+                        ) {
+                            Ok((my_code, my_expires_at)) => {
+                                code = my_code;
+                                expires_at = my_expires_at;
+                            }
+                            Err(err) => {
+                                status = false;
+                                message = err;
+                            }
+                        }
+
+                        let proto_message = proto_rpc::Group {
+                            message: Some(proto_rpc::group::Message::GroupInviteCodeResponse(
+                                proto_rpc::GroupInviteCodeResponse {
+                                    group_id: code_req.group_id.clone(),
+                                    code,
+                                    expires_at,
+                                    max_uses: code_req.max_uses,
+                                    result: Some(proto_rpc::GroupResult { status, message }),
+                                },
+                            )),
+                        };
+
+                        // send message
+                        Rpc::send_message(
+                            proto_message.encode_to_vec(),
+                            crate::rpc::proto::Modules::Group.into(),
+                            "".to_string(),
+                            Vec::new(),
+                        );
+                    }
+                    Some(proto_rpc::group::Message::GroupRedeemCodeRequest(redeem_req)) => {
+                        let mut status = true;
+                        let mut message: String = "".to_string();
+                        let mut group_id = Vec::new();
+                        let mut group_name = "".to_string();
+                        match InviteCode::redeem(
+                            &my_user_id,
+                            &redeem_req.code,
+                            &redeem_req.admin_id,
+                        ) {
+                            Ok(code) => {
+                                group_id = code.group_id;
+                                group_name = code.group_name;
+                            }
+                            Err(err) => {
+                                status = false;
+                                message = err;
+                            }
+                        }
+
+                        let proto_message = proto_rpc::Group {
+                            message: Some(proto_rpc::group::Message::GroupRedeemCodeResponse(
+                                proto_rpc::GroupRedeemCodeResponse {
+                                    group_id,
+                                    group_name,
+                                    result: Some(proto_rpc::GroupResult { status, message }),
+                                },
+                            )),
+                        };
+
+                        // send message
+                        Rpc::send_message(
+                            proto_message.encode_to_vec(),
+                            crate::rpc::proto::Modules::Group.into(),
+                            "".to_string(),
+                            Vec::new(),
+                        );
+                    }
+                    Some(proto_rpc::group::Message::GroupUpdateRequest(update_req)) => {
+                        let mut status = true;
+                        let mut message: String = "".to_string();
+                        if let Err(err) = GroupManage::update_group(
+                            &my_user_id,
+                            &update_req.group_id,
+                            &update_req.update,
+                        ) {
+                            status = false;
+                            message = err.clone();
+                        }
+
+                        let proto_message = proto_rpc::Group {
+                            message: Some(proto_rpc::group::Message::GroupUpdateResponse(
+                                proto_rpc::GroupUpdateResponse {
+                                    group_id: update_req.group_id.clone(),
+                                    result: Some(proto_rpc::GroupResult { status, message }),
+                                },
+                            )),
+                        };
+
+                        // send message
+                        Rpc::send_message(
+                            proto_message.encode_to_vec(),
+                            crate::rpc::proto::Modules::Group.into(),
+                            "".to_string(),
+                            Vec::new(),
+                        );
+
+                        // post updates
+                        if status {
+                            Self::post_group_update(&my_user_id, &update_req.group_id);
+                        }
+                    }
                     _ => {
                         log::error!("Unhandled Protobuf Group chat message");
                     }
@@ -2,7 +2,7 @@
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Group {
     /// message type
-    #[prost(oneof="group::Message", tags="1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16")]
+    #[prost(oneof="group::Message", tags="1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30")]
     pub message: ::core::option::Option<group::Message>,
 }
 /// Nested message and enum types in `Group`.
@@ -58,6 +58,48 @@ pub mod group {
         /// group invited response
         #[prost(message, tag="16")]
         GroupInvitedResponse(super::GroupInvitedResponse),
+        /// group retention request
+        #[prost(message, tag="17")]
+        GroupRetentionRequest(super::GroupRetentionRequest),
+        /// group retention response
+        #[prost(message, tag="18")]
+        GroupRetentionResponse(super::GroupRetentionResponse),
+        /// group leave request
+        #[prost(message, tag="19")]
+        GroupLeaveRequest(super::GroupLeaveRequest),
+        /// group leave response
+        #[prost(message, tag="20")]
+        GroupLeaveResponse(super::GroupLeaveResponse),
+        /// group disband request
+        #[prost(message, tag="21")]
+        GroupDisbandRequest(super::GroupDisbandRequest),
+        /// group disband response
+        #[prost(message, tag="22")]
+        GroupDisbandResponse(super::GroupDisbandResponse),
+        /// set member role request
+        #[prost(message, tag="23")]
+        GroupSetRoleRequest(super::GroupSetRoleRequest),
+        /// set member role response
+        #[prost(message, tag="24")]
+        GroupSetRoleResponse(super::GroupSetRoleResponse),
+        /// create invite code request
+        #[prost(message, tag="25")]
+        GroupInviteCodeRequest(super::GroupInviteCodeRequest),
+        /// create invite code response
+        #[prost(message, tag="26")]
+        GroupInviteCodeResponse(super::GroupInviteCodeResponse),
+        /// redeem invite code request
+        #[prost(message, tag="27")]
+        GroupRedeemCodeRequest(super::GroupRedeemCodeRequest),
+        /// redeem invite code response
+        #[prost(message, tag="28")]
+        GroupRedeemCodeResponse(super::GroupRedeemCodeResponse),
+        /// update group description, avatar or settings
+        #[prost(message, tag="29")]
+        GroupUpdateRequest(super::GroupUpdateRequest),
+        /// update group response
+        #[prost(message, tag="30")]
+        GroupUpdateResponse(super::GroupUpdateResponse),
     }
 }
 /// Group Result
@@ -208,6 +250,14 @@ pub struct GroupMember {
     /// last message index
     #[prost(uint32, tag="5")]
     pub last_message_index: u32,
+    /// last read index
+    ///
+    /// index of the last of our messages
+    /// this member has read.
+    /// This value is only updated if the member
+    /// sends read receipts.
+    #[prost(uint32, tag="6")]
+    pub last_read_index: u32,
 }
 /// Group info response
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -246,6 +296,33 @@ pub struct GroupInfo {
     /// sender of the last message
     #[prost(bytes="vec", tag="11")]
     pub last_message_sender_id: ::prost::alloc::vec::Vec<u8>,
+    /// message retention time in seconds
+    ///
+    /// 0 = messages are kept forever
+    #[prost(uint64, tag="12")]
+    pub retention: u64,
+    /// group description
+    #[prost(string, tag="13")]
+    pub description: ::prost::alloc::string::String,
+    /// group avatar image
+    #[prost(bytes="vec", tag="14")]
+    pub avatar: ::prost::alloc::vec::Vec<u8>,
+    /// group permission settings
+    #[prost(message, optional, tag="15")]
+    pub settings: ::core::option::Option<GroupSettings>,
+}
+/// Group permission settings
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GroupSettings {
+    /// members are allowed to invite new members
+    #[prost(bool, tag="1")]
+    pub members_can_invite: bool,
+    /// only administrators are allowed to post messages
+    #[prost(bool, tag="2")]
+    pub admins_only_post: bool,
+    /// members are allowed to rename the group
+    #[prost(bool, tag="3")]
+    pub members_can_rename: bool,
 }
 /// Group list request
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -281,6 +358,217 @@ pub struct GroupInvitedResponse {
     #[prost(message, repeated, tag="1")]
     pub invited: ::prost::alloc::vec::Vec<GroupInvited>,
 }
+/// Set the message retention time of a group
+///
+/// Only group administrators can change the
+/// retention time.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GroupRetentionRequest {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// message retention time in seconds
+    ///
+    /// 0 = messages are kept forever
+    #[prost(uint64, tag="2")]
+    pub retention: u64,
+}
+/// Group retention response
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GroupRetentionResponse {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// message retention time in seconds
+    #[prost(uint64, tag="2")]
+    pub retention: u64,
+    /// result
+    #[prost(message, optional, tag="3")]
+    pub result: ::core::option::Option<GroupResult>,
+}
+/// Leave a group
+///
+/// The last administrator of a group can only leave
+/// the group, if there are no other members left.
+/// Otherwise another member needs to be promoted to
+/// administrator first, or the group needs to be disbanded.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GroupLeaveRequest {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+}
+/// Group leave response
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GroupLeaveResponse {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// result
+    #[prost(message, optional, tag="2")]
+    pub result: ::core::option::Option<GroupResult>,
+}
+/// Disband a group
+///
+/// Only group administrators can disband a group.
+/// All members are notified and the group is
+/// deactivated for everybody.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GroupDisbandRequest {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+}
+/// Group disband response
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GroupDisbandResponse {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// result
+    #[prost(message, optional, tag="2")]
+    pub result: ::core::option::Option<GroupResult>,
+}
+/// Set the role of a group member
+///
+/// Promote a member to administrator or demote
+/// an administrator to a normal user.
+/// Only group administrators can change roles.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GroupSetRoleRequest {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// user id
+    #[prost(bytes="vec", tag="2")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+    /// new role of the member
+    #[prost(enumeration="GroupMemberRole", tag="3")]
+    pub role: i32,
+}
+/// Set member role response
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GroupSetRoleResponse {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// user id
+    #[prost(bytes="vec", tag="2")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+    /// role
+    #[prost(enumeration="GroupMemberRole", tag="3")]
+    pub role: i32,
+    /// result
+    #[prost(message, optional, tag="4")]
+    pub result: ::core::option::Option<GroupResult>,
+}
+/// Create a group invite code
+///
+/// Only group administrators can create invite codes.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GroupInviteCodeRequest {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// validity of the code in seconds
+    ///
+    /// 0 = the code doesn't expire
+    #[prost(uint64, tag="2")]
+    pub expires_in: u64,
+    /// maximal number of uses
+    ///
+    /// 0 = unlimited
+    #[prost(uint32, tag="3")]
+    pub max_uses: u32,
+}
+/// Group invite code response
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GroupInviteCodeResponse {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// the invite code string
+    #[prost(string, tag="2")]
+    pub code: ::prost::alloc::string::String,
+    /// expiry time, 0 = the code doesn't expire
+    #[prost(uint64, tag="3")]
+    pub expires_at: u64,
+    /// maximal number of uses, 0 = unlimited
+    #[prost(uint32, tag="4")]
+    pub max_uses: u32,
+    /// result
+    #[prost(message, optional, tag="5")]
+    pub result: ::core::option::Option<GroupResult>,
+}
+/// Redeem a group invite code
+///
+/// The code is sent to a group administrator, who
+/// verifies it and sends us a group invitation.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GroupRedeemCodeRequest {
+    /// the invite code string
+    #[prost(string, tag="1")]
+    pub code: ::prost::alloc::string::String,
+    /// user id of the administrator to send the code to
+    ///
+    /// If empty, the code is sent to the administrator
+    /// who created it.
+    #[prost(bytes="vec", tag="2")]
+    pub admin_id: ::prost::alloc::vec::Vec<u8>,
+}
+/// Redeem invite code response
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GroupRedeemCodeResponse {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// group name
+    #[prost(string, tag="2")]
+    pub group_name: ::prost::alloc::string::String,
+    /// result
+    #[prost(message, optional, tag="3")]
+    pub result: ::core::option::Option<GroupResult>,
+}
+/// Update group description, avatar or settings
+///
+/// Only group administrators are allowed to update the group.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GroupUpdateRequest {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// the update
+    #[prost(oneof="group_update_request::Update", tags="2, 3, 4")]
+    pub update: ::core::option::Option<group_update_request::Update>,
+}
+/// Nested message and enum types in `GroupUpdateRequest`.
+pub mod group_update_request {
+    /// the update
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Update {
+        /// new group description
+        #[prost(string, tag="2")]
+        Description(::prost::alloc::string::String),
+        /// new group avatar image
+        ///
+        /// an empty avatar removes the avatar
+        #[prost(bytes, tag="3")]
+        Avatar(::prost::alloc::vec::Vec<u8>),
+        /// new group permission settings
+        #[prost(message, tag="4")]
+        Settings(super::GroupSettings),
+    }
+}
+/// Update group response
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GroupUpdateResponse {
+    /// group id
+    #[prost(bytes="vec", tag="1")]
+    pub group_id: ::prost::alloc::vec::Vec<u8>,
+    /// result
+    #[prost(message, optional, tag="2")]
+    pub result: ::core::option::Option<GroupResult>,
+}
 /// Group member state
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
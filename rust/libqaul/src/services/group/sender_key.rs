@@ -0,0 +1,552 @@
+// Copyright (c) 2023 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Group Sender Keys
+//!
+//! Group messages are encrypted once with the sender key of
+//! the sending member, and the same encrypted data is delivered
+//! to all group members.
+//!
+//! Every member creates it's own symmetric sender key per group
+//! and distributes it to all other members via the end to end
+//! encrypted direct messaging channel.
+//! Whenever the members of a group change, a new sender key is
+//! created and distributed before the next message is sent.
+//!
+//! Sender keys are only accepted from members of the group.
+//! The nonces of the received messages are tracked per sender key,
+//! replayed messages are refused.
+//!
+//! Group messages that arrive before the sender key are cached
+//! and processed once the sender key has been received.
+//! The cache is limited in size and cached messages expire.
+
+use libp2p::PeerId;
+use noise_protocol::CipherState;
+use noise_rust_crypto::ChaCha20Poly1305;
+use prost::Message;
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+use sled_extensions::{bincode::Tree, DbExt};
+use state::Storage;
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use super::{proto_net, Group, GroupStorage};
+use crate::node::user_accounts::{UserAccount, UserAccounts};
+use crate::services::messaging::{process::MessagingProcess, proto};
+use crate::storage::database::DataBase;
+use crate::utilities::timestamp::Timestamp;
+
+/// mutable state of the sender key storage
+static SENDERKEYS: Storage<RwLock<GroupSenderKey>> = Storage::new();
+
+/// maximal number of cached messages per user account
+const PENDING_MAX: usize = 1000;
+
+/// time after which cached messages expire in milliseconds
+const PENDING_TIMEOUT: u64 = 3 * 24 * 60 * 60 * 1000;
+
+/// number of nonces tracked by the replay window
+const REPLAY_WINDOW: u64 = 1024;
+
+/// Our own sender key of a group
+#[derive(Serialize, Deserialize, Clone)]
+pub struct OwnSenderKey {
+    /// id of the sender key
+    pub key_id: u32,
+    /// symmetric key
+    pub key: Vec<u8>,
+    /// nonce of the next message
+    pub nonce: u64,
+    /// user ids of the members this key was distributed to
+    pub members: Vec<Vec<u8>>,
+    /// creation time
+    pub created_at: u64,
+}
+
+/// Sender key received from a group member
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SenderKey {
+    /// id of the sender key
+    pub key_id: u32,
+    /// symmetric key
+    pub key: Vec<u8>,
+    /// time when the key was received
+    pub received_at: u64,
+    /// nonces of the received messages
+    pub replay: ReplayWindow,
+}
+
+/// Window of the received message nonces of a sender key
+///
+/// Nonces that were already received, or that are older
+/// than the window, are refused.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct ReplayWindow {
+    /// highest nonce received
+    pub highest: u64,
+    /// bitmap of the received nonces
+    ///
+    /// bit n represents the nonce `highest - n`
+    pub bitmap: Vec<u64>,
+}
+
+impl ReplayWindow {
+    /// check if a nonce was already received
+    pub fn is_replayed(&self, nonce: u64) -> bool {
+        if self.bitmap.is_empty() || nonce > self.highest {
+            return false;
+        }
+
+        let offset = self.highest - nonce;
+        if offset >= REPLAY_WINDOW {
+            return true;
+        }
+        (self.bitmap[(offset / 64) as usize] >> (offset % 64)) & 1 == 1
+    }
+
+    /// record a received nonce
+    pub fn insert(&mut self, nonce: u64) {
+        if self.bitmap.is_empty() {
+            self.bitmap = vec![0; (REPLAY_WINDOW / 64) as usize];
+            self.highest = nonce;
+        } else if nonce > self.highest {
+            self.shift(nonce - self.highest);
+            self.highest = nonce;
+        }
+
+        let offset = self.highest - nonce;
+        if offset < REPLAY_WINDOW {
+            self.bitmap[(offset / 64) as usize] |= 1 << (offset % 64);
+        }
+    }
+
+    /// move all recorded nonces further away from the highest nonce
+    fn shift(&mut self, by: u64) {
+        if by >= REPLAY_WINDOW {
+            self.bitmap.iter_mut().for_each(|word| *word = 0);
+            return;
+        }
+
+        let words = (by / 64) as usize;
+        let bits = by % 64;
+        for index in (0..self.bitmap.len()).rev() {
+            let mut word = 0;
+            if index >= words {
+                word = self.bitmap[index - words] << bits;
+                if bits > 0 && index > words {
+                    word |= self.bitmap[index - words - 1] >> (64 - bits);
+                }
+            }
+            self.bitmap[index] = word;
+        }
+    }
+}
+
+/// Group message waiting for it's sender key
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PendingMessage {
+    /// time when the message was received
+    pub received_at: u64,
+    /// encoded messaging container
+    pub container: Vec<u8>,
+}
+
+/// Sender key DB links for user account
+#[derive(Clone)]
+pub struct SenderKeyAccountDb {
+    /// our own sender keys
+    ///
+    /// group_id => OwnSenderKey
+    pub own: Tree<OwnSenderKey>,
+    /// sender keys of other members
+    ///
+    /// {group_id}{sender_id}{key_id} => SenderKey
+    pub received: Tree<SenderKey>,
+    /// messages waiting for their sender key
+    ///
+    /// {group_id}{sender_id}{key_id}{signature} => PendingMessage
+    pub pending: Tree<PendingMessage>,
+}
+
+/// Group Sender Keys
+pub struct GroupSenderKey {
+    /// data base tree references accessible
+    /// by user account
+    db_ref: BTreeMap<Vec<u8>, SenderKeyAccountDb>,
+}
+
+impl GroupSenderKey {
+    /// initialize sender key storage
+    pub fn init() {
+        let sender_keys = GroupSenderKey {
+            db_ref: BTreeMap::new(),
+        };
+        SENDERKEYS.set(RwLock::new(sender_keys));
+    }
+
+    /// get DB refs for user account
+    fn get_db_ref(account_id: &PeerId) -> SenderKeyAccountDb {
+        {
+            let sender_keys = SENDERKEYS.get().read().unwrap();
+            if let Some(db_ref) = sender_keys.db_ref.get(&account_id.to_bytes()) {
+                return db_ref.clone();
+            }
+        }
+
+        // open trees
+        let db = DataBase::get_user_db(account_id.to_owned());
+        let db_ref = SenderKeyAccountDb {
+            own: db.open_bincode_tree("group_sender_key_own").unwrap(),
+            received: db.open_bincode_tree("group_sender_key_received").unwrap(),
+            pending: db.open_bincode_tree("group_sender_key_pending").unwrap(),
+        };
+
+        let mut sender_keys = SENDERKEYS.get().write().unwrap();
+        sender_keys
+            .db_ref
+            .insert(account_id.to_bytes(), db_ref.clone());
+
+        db_ref
+    }
+
+    /// create the data base key of a received sender key
+    ///
+    /// {group_id}{sender_id}{key_id}
+    fn create_key(group_id: &Vec<u8>, sender_id: &PeerId, key_id: u32) -> Vec<u8> {
+        let mut key = group_id.clone();
+        key.extend(sender_id.to_bytes());
+        key.extend(key_id.to_be_bytes());
+        key
+    }
+
+    /// get the user ids of all group members we send our messages to
+    pub fn get_receivers(account_id: &PeerId, group: &Group) -> Vec<Vec<u8>> {
+        group
+            .members
+            .values()
+            .filter(|member| member.user_id != account_id.to_bytes() && member.state > 0)
+            .map(|member| member.user_id.clone())
+            .collect()
+    }
+
+    /// Encrypt a group message with our sender key
+    ///
+    /// A new sender key is created and distributed to all
+    /// receivers, if the group members have changed since
+    /// the last key was created.
+    pub fn encrypt(
+        user_account: &UserAccount,
+        group: &Group,
+        data: Vec<u8>,
+    ) -> Result<proto::GroupEncrypted, String> {
+        let db_ref = Self::get_db_ref(&user_account.id);
+        let receivers = Self::get_receivers(&user_account.id, group);
+
+        // get our current sender key
+        let mut own_key = None;
+        match db_ref.own.get(&group.id) {
+            Ok(Some(key)) => {
+                if key.members == receivers {
+                    own_key = Some(key);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => log::error!("sender key data base error {}", e),
+        }
+
+        // rotate the sender key on membership changes
+        let mut own_key = match own_key {
+            Some(key) => key,
+            None => Self::rotate(user_account, group, receivers),
+        };
+
+        // encrypt message
+        let nonce = own_key.nonce;
+        let mut cipher: CipherState<ChaCha20Poly1305> =
+            CipherState::new(own_key.key.as_slice(), nonce);
+        let encrypted = cipher.encrypt_vec(data.as_slice());
+
+        // save the nonce of the next message
+        own_key.nonce = nonce + 1;
+        let key_id = own_key.key_id;
+        if let Err(e) = db_ref.own.insert(group.id.clone(), own_key) {
+            return Err(format!("sender key data base error {}", e));
+        }
+        if let Err(e) = db_ref.own.flush() {
+            log::error!("sender key flush error {}", e);
+        }
+
+        Ok(proto::GroupEncrypted {
+            group_id: group.id.clone(),
+            key_id,
+            nonce,
+            data: encrypted,
+        })
+    }
+
+    /// Create a new sender key and distribute it to all receivers
+    fn rotate(user_account: &UserAccount, group: &Group, receivers: Vec<Vec<u8>>) -> OwnSenderKey {
+        let mut rng = thread_rng();
+        let key: [u8; 32] = rng.gen();
+
+        let own_key = OwnSenderKey {
+            key_id: rng.gen(),
+            key: key.to_vec(),
+            nonce: 0,
+            members: receivers,
+            created_at: Timestamp::get_timestamp(),
+        };
+
+        log::trace!(
+            "new sender key {} for group {}",
+            own_key.key_id,
+            bs58::encode(&group.id).into_string()
+        );
+
+        // distribute key via direct messages
+        let proto_message = proto_net::GroupContainer {
+            message: Some(proto_net::group_container::Message::SenderKey(
+                proto_net::SenderKeyDistribution {
+                    group_id: group.id.clone(),
+                    key_id: own_key.key_id,
+                    key: own_key.key.clone(),
+                },
+            )),
+        };
+        for user_id in &own_key.members {
+            if let Ok(receiver) = PeerId::from_bytes(user_id) {
+                Group::send_notify_message(user_account, &receiver, proto_message.encode_to_vec());
+            }
+        }
+
+        own_key
+    }
+
+    /// Decrypt a group message with the sender key of the sender
+    ///
+    /// If we haven't received the sender key yet, the message
+    /// container is cached and `None` is returned.
+    pub fn decrypt(
+        account_id: &PeerId,
+        sender_id: &PeerId,
+        encrypted: &proto::GroupEncrypted,
+        container: &proto::Container,
+    ) -> Option<Vec<u8>> {
+        let db_ref = Self::get_db_ref(account_id);
+        let key = Self::create_key(&encrypted.group_id, sender_id, encrypted.key_id);
+
+        let mut sender_key;
+        match db_ref.received.get(&key) {
+            Ok(Some(my_key)) => sender_key = my_key,
+            Ok(None) => {
+                log::trace!(
+                    "sender key {} of {} missing, cache message",
+                    encrypted.key_id,
+                    sender_id.to_base58()
+                );
+
+                // cache message until the sender key arrives
+                let mut pending_key = key;
+                pending_key.extend(container.signature.clone());
+                Self::cache_pending(&db_ref, pending_key, container);
+                return None;
+            }
+            Err(e) => {
+                log::error!("sender key data base error {}", e);
+                return None;
+            }
+        }
+
+        let mut cipher: CipherState<ChaCha20Poly1305> =
+            CipherState::new(sender_key.key.as_slice(), encrypted.nonce);
+        match cipher.decrypt_vec(encrypted.data.as_slice()) {
+            Ok(decrypted) => {
+                // remember the nonce to refuse replays
+                sender_key.replay.insert(encrypted.nonce);
+                if let Err(e) = db_ref.received.insert(key, sender_key) {
+                    log::error!("sender key data base error {}", e);
+                }
+                if let Err(e) = db_ref.received.flush() {
+                    log::error!("sender key flush error {}", e);
+                }
+                Some(decrypted)
+            }
+            Err(_) => {
+                log::error!("group message decryption error");
+                None
+            }
+        }
+    }
+
+    /// Check if a group message was already received
+    pub fn is_replayed(
+        account_id: &PeerId,
+        sender_id: &PeerId,
+        encrypted: &proto::GroupEncrypted,
+    ) -> bool {
+        let db_ref = Self::get_db_ref(account_id);
+        let key = Self::create_key(&encrypted.group_id, sender_id, encrypted.key_id);
+
+        match db_ref.received.get(&key) {
+            Ok(Some(sender_key)) => sender_key.replay.is_replayed(encrypted.nonce),
+            _ => false,
+        }
+    }
+
+    /// cache a message until it's sender key arrives
+    ///
+    /// Expired messages are removed from the cache and the
+    /// oldest messages are dropped when the cache is full.
+    fn cache_pending(
+        db_ref: &SenderKeyAccountDb,
+        pending_key: Vec<u8>,
+        container: &proto::Container,
+    ) {
+        let now = Timestamp::get_timestamp();
+
+        let mut entries: Vec<(u64, Vec<u8>)> = Vec::new();
+        for entry in db_ref.pending.iter() {
+            if let Ok((key, pending)) = entry {
+                if pending.received_at + PENDING_TIMEOUT < now {
+                    if let Err(e) = db_ref.pending.remove(key) {
+                        log::error!("sender key pending cache error {}", e);
+                    }
+                } else {
+                    entries.push((pending.received_at, key.to_vec()));
+                }
+            }
+        }
+
+        // drop the oldest messages
+        if entries.len() >= PENDING_MAX {
+            log::warn!("sender key pending cache full");
+            entries.sort_by_key(|(received_at, _)| *received_at);
+            for (_, key) in entries.iter().take(entries.len() + 1 - PENDING_MAX) {
+                if let Err(e) = db_ref.pending.remove(key.to_owned()) {
+                    log::error!("sender key pending cache error {}", e);
+                }
+            }
+        }
+
+        let pending = PendingMessage {
+            received_at: now,
+            container: container.encode_to_vec(),
+        };
+        if let Err(e) = db_ref.pending.insert(pending_key, pending) {
+            log::error!("sender key pending cache error {}", e);
+        }
+        if let Err(e) = db_ref.pending.flush() {
+            log::error!("sender key flush error {}", e);
+        }
+    }
+
+    /// process a received sender key
+    ///
+    /// The key is saved and all cached messages
+    /// of this key are processed.
+    pub fn on_sender_key(
+        sender_id: &PeerId,
+        account_id: &PeerId,
+        message: &proto_net::SenderKeyDistribution,
+    ) -> Result<(), String> {
+        // check if the group exists
+        let group;
+        match GroupStorage::get_group(account_id.to_owned(), message.group_id.clone()) {
+            Some(my_group) => group = my_group,
+            None => return Err("group not found".to_string()),
+        }
+        if group.is_direct_chat {
+            return Err("direct chat room does not allow this action".to_string());
+        }
+        if !group.is_member(&sender_id.to_bytes()) {
+            return Err("sender is not a member of the group".to_string());
+        }
+        if message.key.len() != 32 {
+            return Err("invalid sender key".to_string());
+        }
+
+        // save sender key
+        let db_ref = Self::get_db_ref(account_id);
+        let key = Self::create_key(&message.group_id, sender_id, message.key_id);
+
+        // keep the received nonces of a known key
+        let replay = match db_ref.received.get(&key) {
+            Ok(Some(known)) if known.key == message.key => known.replay,
+            _ => ReplayWindow::default(),
+        };
+        let sender_key = SenderKey {
+            key_id: message.key_id,
+            key: message.key.clone(),
+            received_at: Timestamp::get_timestamp(),
+            replay,
+        };
+        if let Err(e) = db_ref.received.insert(key.clone(), sender_key) {
+            return Err(format!("sender key data base error {}", e));
+        }
+        if let Err(e) = db_ref.received.flush() {
+            log::error!("sender key flush error {}", e);
+        }
+
+        // process cached messages
+        let user_account;
+        match UserAccounts::get_by_id(*account_id) {
+            Some(my_account) => user_account = my_account,
+            None => return Err("user account has problem".to_string()),
+        }
+        for entry in db_ref.pending.scan_prefix(&key) {
+            if let Ok((pending_key, pending)) = entry {
+                if let Err(e) = db_ref.pending.remove(pending_key) {
+                    log::error!("sender key pending cache error {}", e);
+                }
+
+                match proto::Container::decode(&pending.container[..]) {
+                    Ok(container) => {
+                        MessagingProcess::process_received_message(user_account.clone(), container)
+                    }
+                    Err(e) => log::error!("cached container decoding error {}", e),
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_window_refuses_received_nonces() {
+        let mut window = ReplayWindow::default();
+        assert!(!window.is_replayed(0));
+        window.insert(0);
+        assert!(window.is_replayed(0));
+        assert!(!window.is_replayed(1));
+    }
+
+    #[test]
+    fn replay_window_accepts_out_of_order_nonces() {
+        let mut window = ReplayWindow::default();
+        window.insert(5);
+        window.insert(70);
+        assert!(!window.is_replayed(3));
+        window.insert(3);
+        assert!(window.is_replayed(3));
+        assert!(window.is_replayed(5));
+        assert!(window.is_replayed(70));
+        assert!(!window.is_replayed(69));
+    }
+
+    #[test]
+    fn replay_window_refuses_nonces_older_than_window() {
+        let mut window = ReplayWindow::default();
+        window.insert(10);
+        window.insert(10 + REPLAY_WINDOW);
+        assert!(window.is_replayed(10));
+        assert!(window.is_replayed(9));
+        assert!(!window.is_replayed(11));
+    }
+}
@@ -171,18 +171,28 @@ impl GroupStorage {
     }
 
     /// Clear Unread Message Counter
-    pub fn group_clear_unread(account_id: PeerId, group_id: Vec<u8>) {
+    ///
+    /// Returns true if there were unread messages in the group.
+    pub fn group_clear_unread(account_id: PeerId, group_id: Vec<u8>) -> bool {
         log::debug!("group_clear_unread");
 
         if let Some(mut group) = Self::get_group(account_id, group_id) {
+            if group.unread_messages == 0 {
+                return false;
+            }
+
             // clear unread value
             group.unread_messages = 0;
 
             // save group
             Self::save_group(account_id, group);
+
+            return true;
         } else {
             log::error!("group_clear_unread group not found");
         }
+
+        false
     }
 
     /// get invite
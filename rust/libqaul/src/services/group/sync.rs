@@ -0,0 +1,267 @@
+// Copyright (c) 2023 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Group State Synchronisation
+//!
+//! Every change of the group members or of the group settings
+//! increases the group revision.
+//! The member who made the change, usually an administrator,
+//! signs the new group info and sends it to all members.
+//! Each member stores the signed group info of it's current
+//! revision.
+//!
+//! Members attach their group revision to every group message.
+//! When a member receives a message with a newer revision,
+//! it requests the signed group info from the sender.
+//! When it receives a message with an older revision, it sends
+//! the signed group info to the sender.
+//! As the group info is signed, it can be forwarded by every member.
+//! The receiver checks the permissions of the signing member.
+//!
+//! Two administrators can change the group at the same time,
+//! creating two different group infos of the same revision.
+//! Members therefore attach the hash of their signed group info
+//! to their messages as well. Of two group infos with the same
+//! revision, the one with the higher hash wins. Every member
+//! ends up with the same group info, the changes of the other
+//! group info are discarded.
+
+use libp2p::PeerId;
+use prost::Message;
+use state::Storage;
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use super::{proto_net, Group, GroupManage, GroupStorage};
+use crate::node::user_accounts::UserAccounts;
+use crate::router::users::Users;
+use crate::utilities::timestamp::Timestamp;
+
+/// mutable state of the group synchronisation
+static GROUPSYNC: Storage<RwLock<GroupSync>> = Storage::new();
+
+/// minimal interval between two synchronisations
+/// of the same group with the same member in milliseconds
+const SYNC_INTERVAL: u64 = 60 * 1000;
+
+/// Group State Synchronisation
+pub struct GroupSync {
+    /// time of the last synchronisation
+    ///
+    /// The key is a combination of the account id,
+    /// the group id and the user id of the member.
+    last_sync: BTreeMap<Vec<u8>, u64>,
+}
+
+impl GroupSync {
+    /// initialize group synchronisation state
+    pub fn init() {
+        let sync = GroupSync {
+            last_sync: BTreeMap::new(),
+        };
+        GROUPSYNC.set(RwLock::new(sync));
+    }
+
+    /// compare the group revision of a received group message
+    ///
+    /// Requests the group info from the sender if the sender has a
+    /// newer revision, and sends our signed group info to the sender
+    /// if the sender's revision is outdated.
+    /// If both have the same revision but different group infos,
+    /// the group info with the higher hash is synchronised.
+    pub fn on_revision(
+        sender_id: &PeerId,
+        account_id: &PeerId,
+        group: &Group,
+        revision: u32,
+        info_hash: &Vec<u8>,
+    ) {
+        if group.is_direct_chat {
+            return;
+        }
+
+        let our_hash = group.info_hash();
+        let newer = match revision == group.revision {
+            true => {
+                if info_hash.len() == 0 || *info_hash == our_hash {
+                    return;
+                }
+                *info_hash > our_hash
+            }
+            false => revision > group.revision,
+        };
+
+        if !Self::check_interval(account_id, &group.id, sender_id) {
+            return;
+        }
+
+        let user_account;
+        match UserAccounts::get_by_id(*account_id) {
+            Some(my_account) => user_account = my_account,
+            None => return,
+        }
+
+        if newer {
+            log::trace!(
+                "group {} is outdated, request revision {}",
+                bs58::encode(&group.id).into_string(),
+                revision
+            );
+
+            let proto_message = proto_net::GroupContainer {
+                message: Some(proto_net::group_container::Message::InfoRequest(
+                    proto_net::GroupInfoRequest {
+                        group_id: group.id.clone(),
+                        revision: group.revision,
+                        info_hash: our_hash,
+                    },
+                )),
+            };
+            Group::send_notify_message(&user_account, sender_id, proto_message.encode_to_vec());
+        } else {
+            Self::send_signed_info(&user_account.id, sender_id, group);
+        }
+    }
+
+    /// process a group info request from the network
+    pub fn on_info_request(
+        sender_id: &PeerId,
+        account_id: &PeerId,
+        request: &proto_net::GroupInfoRequest,
+    ) -> Result<(), String> {
+        let group;
+        match GroupStorage::get_group(account_id.to_owned(), request.group_id.clone()) {
+            Some(my_group) => group = my_group,
+            None => return Err("group not found".to_string()),
+        }
+
+        if group.is_direct_chat {
+            return Err("direct chat room does not allow this action".to_string());
+        }
+
+        if !group.is_member(&sender_id.to_bytes()) {
+            return Err("sender is not member in this group".to_string());
+        }
+
+        if group.revision < request.revision {
+            return Ok(());
+        }
+        if group.revision == request.revision && group.info_hash() <= request.info_hash {
+            return Ok(());
+        }
+
+        Self::send_signed_info(account_id, sender_id, &group);
+
+        Ok(())
+    }
+
+    /// process a signed group info from the network
+    ///
+    /// The signature is validated before the group info
+    /// is handed over to the group manager.
+    pub fn on_signed_info(
+        sender_id: &PeerId,
+        account_id: &PeerId,
+        signed_info: &proto_net::SignedGroupInfo,
+    ) -> Result<(), String> {
+        let admin_id;
+        match PeerId::from_bytes(&signed_info.admin_id) {
+            Ok(id) => admin_id = id,
+            Err(_) => return Err("invalid administrator id".to_string()),
+        }
+
+        // validate signature
+        match Users::get_pub_key(&admin_id) {
+            Some(key) => {
+                if !key.verify(&signed_info.group_info, &signed_info.signature) {
+                    return Err("invalid group info signature".to_string());
+                }
+            }
+            None => return Err("unknown group administrator".to_string()),
+        }
+
+        let group_info;
+        match proto_net::GroupInfo::decode(&signed_info.group_info[..]) {
+            Ok(info) => group_info = info,
+            Err(e) => return Err(format!("group info decoding error {}", e)),
+        }
+
+        log::trace!(
+            "signed group info revision {} from {} by {}",
+            group_info.revision,
+            sender_id.to_base58(),
+            admin_id.to_base58()
+        );
+
+        GroupManage::on_group_notify(
+            admin_id,
+            account_id.to_owned(),
+            &group_info,
+            signed_info.encode_to_vec(),
+        );
+
+        Ok(())
+    }
+
+    /// send our signed group info to a member
+    ///
+    /// If we don't have a signed group info for the current revision
+    /// and we are an administrator, the group info is signed by us.
+    fn send_signed_info(account_id: &PeerId, receiver: &PeerId, group: &Group) {
+        let user_account;
+        match UserAccounts::get_by_id(*account_id) {
+            Some(my_account) => user_account = my_account,
+            None => return,
+        }
+
+        let mut signed_info = group.signed_info.clone();
+        if signed_info.len() == 0 {
+            if !group.is_administrator(&account_id.to_bytes()) {
+                return;
+            }
+            match Group::sign_group_info(&user_account, group) {
+                Some(info) => signed_info = info,
+                None => return,
+            }
+            let mut updated_group = group.clone();
+            updated_group.signed_info = signed_info.clone();
+            GroupStorage::save_group(account_id.to_owned(), updated_group);
+        }
+
+        let signed;
+        match proto_net::SignedGroupInfo::decode(&signed_info[..]) {
+            Ok(info) => signed = info,
+            Err(e) => {
+                log::error!("signed group info decoding error {}", e);
+                return;
+            }
+        }
+
+        let proto_message = proto_net::GroupContainer {
+            message: Some(proto_net::group_container::Message::SignedInfo(signed)),
+        };
+        Group::send_notify_message(&user_account, receiver, proto_message.encode_to_vec());
+    }
+
+    /// check if the last synchronisation with this member
+    /// is older than the synchronisation interval
+    ///
+    /// Returns true and updates the synchronisation time if the
+    /// synchronisation can take place.
+    fn check_interval(account_id: &PeerId, group_id: &Vec<u8>, user_id: &PeerId) -> bool {
+        let mut key = account_id.to_bytes();
+        key.extend(group_id);
+        key.extend(user_id.to_bytes());
+
+        let now = Timestamp::get_timestamp();
+        let mut sync = GROUPSYNC.get().write().unwrap();
+        if let Some(last_sync) = sync.last_sync.get(&key) {
+            if now < last_sync + SYNC_INTERVAL {
+                return false;
+            }
+        }
+        sync.last_sync.insert(key, now);
+
+        true
+    }
+}
@@ -11,7 +11,7 @@ use prost::Message;
 use serde::{Deserialize, Serialize};
 use sled_extensions::{bincode::Tree, DbExt};
 use state::Storage;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::sync::RwLock;
 
 #[cfg(emulate)]
@@ -22,9 +22,13 @@ pub mod retransmit;
 
 use super::chat::{ChatFile, ChatStorage};
 use super::crypto::Crypto;
-use crate::connections::ConnectionModule;
+use super::group::{Group, GroupSenderKey};
+use crate::connections::{bandwidth::Bandwidth, ConnectionModule};
 use crate::node::user_accounts::{UserAccount, UserAccounts};
-use crate::router::table::RoutingTable;
+use crate::router::{
+    neighbours::Neighbours,
+    table::{RoutingConnectionEntry, RoutingTable},
+};
 use crate::storage::database::DataBase;
 use crate::utilities::timestamp::Timestamp;
 use process::MessagingProcess;
@@ -41,7 +45,11 @@ pub static MESSAGING: Storage<RwLock<Messaging>> = Storage::new();
 
 /// Messaging Scheduling Structure
 pub struct ScheduledMessage {
-    receiver: PeerId,
+    /// receivers of the message
+    ///
+    /// Group containers can have several receivers,
+    /// they are fanned out by the scheduler.
+    receivers: Vec<PeerId>,
     container: proto::Container,
     is_common: bool,
     is_forward: bool,
@@ -75,6 +83,8 @@ pub struct UnConfirmedMessage {
     pub scheduled_dtn: bool,
     // flag that indicate DTN message
     pub is_dtn: bool,
+    // retransmissions via routes, whose neighbours were not connected
+    pub stale_routes: u32,
 }
 
 /// Unconfirmed Message Type
@@ -99,16 +109,45 @@ pub enum MessagingServiceType {
     Rtc,
 }
 
+impl MessagingServiceType {
+    /// check if messages of this service have a high priority
+    ///
+    /// High priority messages are sent redundantly via two
+    /// connection modules, if configured.
+    pub fn is_high_priority(&self) -> bool {
+        match self {
+            MessagingServiceType::Crypto
+            | MessagingServiceType::Group
+            | MessagingServiceType::Chat => true,
+            _ => false,
+        }
+    }
+}
+
 /// Unconfirmed Messages Structure
 pub struct UnConfirmedMessages {
     /// signature => UnConfirmedMessage
     pub unconfirmed: Tree<UnConfirmedMessage>,
 }
 
+/// Routes an unconfirmed message was already sent on
+pub struct RoutesTried {
+    /// [(module, neighbour node id)]
+    pub routes: Vec<(ConnectionModule, PeerId)>,
+    /// routes the message was last sent on
+    pub last: Vec<(ConnectionModule, PeerId)>,
+    /// time when the message was last sent
+    pub last_tried: u64,
+}
+
 /// Qaul Messaging Structure
 pub struct Messaging {
     /// ring buffer of messages scheduled for sending
     pub to_send: VecDeque<ScheduledMessage>,
+    /// routes an unconfirmed message was already sent on
+    ///
+    /// signature => routes tried
+    pub routes_tried: HashMap<Vec<u8>, RoutesTried>,
 }
 
 /// Qaul Failed Message Structure
@@ -123,6 +162,14 @@ pub struct FailedMessage {
 }
 
 impl Messaging {
+    /// time after which the routes tried of a message are forgotten
+    const ROUTES_TRIED_TIMEOUT: u64 = 5 * 60 * 1000;
+
+    /// number of retransmissions of a message via routes,
+    /// whose neighbours are not connected, before
+    /// the message is sent via DTN
+    const STALE_ROUTES_RETRIES: u32 = 5;
+
     /// Initialize messaging and create the ring buffer.
     pub fn init() {
         #[cfg(emulate)]
@@ -131,6 +178,7 @@ impl Messaging {
 
         let messaging = Messaging {
             to_send: VecDeque::new(),
+            routes_tried: HashMap::new(),
         };
         MESSAGING.set(RwLock::new(messaging));
 
@@ -160,13 +208,14 @@ impl Messaging {
             scheduled: false,
             scheduled_dtn: false,
             is_dtn,
+            stale_routes: 0,
         };
         let unconfirmed = UNCONFIRMED.get().write().unwrap();
 
         // insert message to data base
         if let Err(e) = unconfirmed
             .unconfirmed
-            .insert(container.signature.clone(), new_entry)
+            .insert(Self::unconfirmed_key(container, receiver), new_entry)
         {
             log::error!("{}", e);
         }
@@ -176,6 +225,26 @@ impl Messaging {
         }
     }
 
+    /// Save a group container to the data base, to wait for
+    /// the confirmation of every receiver
+    fn save_unconfirmed_group_message(
+        receivers: &Vec<PeerId>,
+        container: &proto::Container,
+        message_type: &MessagingServiceType,
+        message_id: &Vec<u8>,
+    ) {
+        for receiver in receivers {
+            log::trace!("send group message to {}", receiver.to_base58());
+            Self::save_unconfirmed_message(
+                message_type.clone(),
+                message_id,
+                receiver,
+                container,
+                false,
+            );
+        }
+    }
+
     /// process confirmation message and return (sender_id, message_id)
     pub fn on_confirmed_message(
         signature: &Vec<u8>,
@@ -185,10 +254,35 @@ impl Messaging {
     ) {
         log::trace!("message confirmed");
 
+        // group containers are confirmed by every receiver
+        let mut key = signature.to_owned();
+        key.extend(sender_id.to_bytes());
+        {
+            let unconfirmed = UNCONFIRMED.get().read().unwrap();
+            if !unconfirmed.unconfirmed.contains_key(&key).unwrap_or(false) {
+                key = signature.to_owned();
+            }
+        }
+
+        // forget the routes tried
+        let routes_tried;
+        {
+            let mut messaging = MESSAGING.get().write().unwrap();
+            routes_tried = messaging.routes_tried.remove(&key);
+        }
+
+        // record the successful delivery via the neighbour,
+        // if the message was last sent via a single neighbour
+        if let Some(routes_tried) = routes_tried {
+            if let [(module, node_id)] = routes_tried.last[..] {
+                Neighbours::record_delivery(module, node_id, true);
+            }
+        }
+
         let unconfirmed = UNCONFIRMED.get().write().unwrap();
 
         // check and remove unconfirmed from DB
-        match unconfirmed.unconfirmed.remove(signature) {
+        match unconfirmed.unconfirmed.remove(&key) {
             Ok(v) => {
                 if let Err(e) = unconfirmed.unconfirmed.flush() {
                     log::error!("Error unconfirmed table flush: {}", e);
@@ -260,15 +354,67 @@ impl Messaging {
         }
     }
 
-    fn on_scheduled_message(signature: &Vec<u8>) {
+    /// get the key of a message in the unconfirmed table
+    ///
+    /// Group containers are sent to several receivers with the
+    /// same signature, their key is {signature}{receiver_id}.
+    pub fn unconfirmed_key(container: &proto::Container, receiver: &PeerId) -> Vec<u8> {
+        let mut key = container.signature.clone();
+        if Self::is_group_container(container) {
+            key.extend(receiver.to_bytes());
+        }
+        key
+    }
+
+    /// check if the container is a group container,
+    /// addressed to several receivers
+    pub fn is_group_container(container: &proto::Container) -> bool {
+        match &container.envelope {
+            Some(envelope) => !envelope.receiver_ids.is_empty(),
+            None => false,
+        }
+    }
+
+    /// get the receivers of a message container
+    ///
+    /// Group containers are addressed to all receivers of the
+    /// envelope, restricted to the receivers they are forwarded
+    /// to on this route.
+    pub fn get_receivers(container: &proto::Container) -> Vec<PeerId> {
+        let envelope;
+        match &container.envelope {
+            Some(my_envelope) => envelope = my_envelope,
+            None => return Vec::new(),
+        }
+
+        if envelope.receiver_ids.is_empty() {
+            return PeerId::from_bytes(&envelope.receiver_id)
+                .into_iter()
+                .collect();
+        }
+
+        envelope
+            .receiver_ids
+            .iter()
+            .filter(|id| container.forward_to.is_empty() || container.forward_to.contains(id))
+            .filter_map(|id| PeerId::from_bytes(id).ok())
+            .collect()
+    }
+
+    /// set the scheduled flag of an unconfirmed message
+    ///
+    /// Returns the service type of the message, if the message
+    /// is waiting for a confirmation.
+    fn on_scheduled_message(signature: &Vec<u8>) -> Option<MessagingServiceType> {
         let unconfirmed = UNCONFIRMED.get().write().unwrap();
         if !unconfirmed.unconfirmed.contains_key(signature).unwrap() {
-            return;
+            return None;
         }
 
         let mut unconfirmed_message = unconfirmed.unconfirmed.get(signature).unwrap().unwrap();
+        let message_type = unconfirmed_message.message_type.clone();
         if unconfirmed_message.scheduled {
-            return;
+            return Some(message_type);
         }
 
         unconfirmed_message.scheduled = true;
@@ -282,6 +428,103 @@ impl Messaging {
                 log::error!("error updating unconfirmed table");
             }
         }
+
+        Some(message_type)
+    }
+
+    /// count a retransmission of an unconfirmed message, for
+    /// which the routes exist but their neighbours are not connected
+    ///
+    /// Returns true when the routes failed too often and the
+    /// message shall be sent via DTN.
+    fn on_stale_routes(signature: &Vec<u8>) -> bool {
+        let unconfirmed = UNCONFIRMED.get().write().unwrap();
+        let mut unconfirmed_message;
+        match unconfirmed.unconfirmed.get(signature) {
+            Ok(Some(my_unconfirmed_message)) => unconfirmed_message = my_unconfirmed_message,
+            _ => return true,
+        }
+
+        if unconfirmed_message.stale_routes >= Self::STALE_ROUTES_RETRIES {
+            return true;
+        }
+
+        unconfirmed_message.stale_routes += 1;
+        if let Err(_e) = unconfirmed
+            .unconfirmed
+            .insert(signature.clone(), unconfirmed_message)
+        {
+            log::error!("error updating unconfirmed table");
+        } else {
+            if let Err(_e) = unconfirmed.unconfirmed.flush() {
+                log::error!("error updating unconfirmed table");
+            }
+        }
+
+        false
+    }
+
+    /// un-record a message dropped by the bandwidth limit
+    ///
+    /// The route is removed from the routes tried of the message.
+    /// If no other route of the message was sent, the message is
+    /// not marked as scheduled anymore and retransmitted soon.
+    pub fn on_dropped(neighbour_id: &PeerId, module: ConnectionModule, data: &Vec<u8>) {
+        let container;
+        match proto::Container::decode(&data[..]) {
+            Ok(my_container) => container = my_container,
+            Err(_) => return,
+        }
+
+        for receiver in Self::get_receivers(&container) {
+            let key = Self::unconfirmed_key(&container, &receiver);
+
+            let sent = {
+                let mut messaging = MESSAGING.get().write().unwrap();
+                match messaging.routes_tried.get_mut(&key) {
+                    Some(routes_tried) => {
+                        routes_tried
+                            .routes
+                            .retain(|route| *route != (module, *neighbour_id));
+                        routes_tried
+                            .last
+                            .retain(|route| *route != (module, *neighbour_id));
+                        routes_tried.routes.len() > 0
+                    }
+                    None => false,
+                }
+            };
+
+            if !sent {
+                Self::on_unscheduled_message(&key);
+            }
+        }
+    }
+
+    /// reset the scheduled flag of an unconfirmed message
+    fn on_unscheduled_message(signature: &Vec<u8>) {
+        let unconfirmed = UNCONFIRMED.get().write().unwrap();
+        let mut unconfirmed_message;
+        match unconfirmed.unconfirmed.get(signature) {
+            Ok(Some(my_unconfirmed_message)) => unconfirmed_message = my_unconfirmed_message,
+            _ => return,
+        }
+
+        if !unconfirmed_message.scheduled {
+            return;
+        }
+
+        unconfirmed_message.scheduled = false;
+        if let Err(_e) = unconfirmed
+            .unconfirmed
+            .insert(signature.clone(), unconfirmed_message)
+        {
+            log::error!("error updating unconfirmed table");
+        } else {
+            if let Err(_e) = unconfirmed.unconfirmed.flush() {
+                log::error!("error updating unconfirmed table");
+            }
+        }
     }
 
     fn on_scheduled_as_dtn_message(signature: &Vec<u8>) {
@@ -313,7 +556,7 @@ impl Messaging {
         user_account: &UserAccount,
         receiver: &PeerId,
         data: Vec<u8>,
-        _message_type: MessagingServiceType,
+        message_type: MessagingServiceType,
         message_id: &Vec<u8>,
         is_common_message: bool,
     ) -> Result<Vec<u8>, String> {
@@ -342,11 +585,99 @@ impl Messaging {
             )),
         };
 
+        let signature = Self::sign_and_schedule(
+            user_account,
+            receiver,
+            envelop_payload.encode_to_vec(),
+            message_id,
+            is_common_message,
+        )?;
+
+        // remember the service for the bandwidth statistics
+        Bandwidth::tag_message(&signature, &message_type);
+
+        Ok(signature)
+    }
+
+    /// pack, sign and schedule a group message for sending
+    ///
+    /// The message is encrypted once with our sender key of the group
+    /// and the same encrypted payload is sent to all group members.
+    pub fn pack_and_send_group_message(
+        user_account: &UserAccount,
+        group: &Group,
+        data: Vec<u8>,
+        message_type: MessagingServiceType,
+        message_id: &Vec<u8>,
+    ) -> Result<(), String> {
+        // encrypt data
+        let encrypted_message = GroupSenderKey::encrypt(user_account, group, data)?;
+
+        let envelop_payload = proto::EnvelopPayload {
+            payload: Some(proto::envelop_payload::Payload::GroupEncrypted(
+                encrypted_message,
+            )),
+        }
+        .encode_to_vec();
+
+        let receivers: Vec<PeerId> = GroupSenderKey::get_receivers(&user_account.id, group)
+            .iter()
+            .filter_map(|user_id| PeerId::from_bytes(user_id).ok())
+            .collect();
+        if receivers.is_empty() {
+            return Ok(());
+        }
+
+        // create one envelope, addressed to all group members
+        let envelope = proto::Envelope {
+            sender_id: user_account.id.to_bytes(),
+            receiver_id: group.id.clone(),
+            payload: envelop_payload,
+            receiver_ids: receivers
+                .iter()
+                .map(|receiver| receiver.to_bytes())
+                .collect(),
+        };
+
+        // sign message
+        let signature;
+        match user_account.keys.sign(&envelope.encode_to_vec()) {
+            Ok(my_signature) => signature = my_signature,
+            Err(_) => return Err("messaging signing error".to_string()),
+        }
+        let container = proto::Container {
+            signature: signature.clone(),
+            envelope: Some(envelope),
+            forward_to: Vec::new(),
+        };
+
+        // wait for the confirmation of every receiver
+        Self::save_unconfirmed_group_message(&receivers, &container, &message_type, message_id);
+
+        // remember the service for the bandwidth statistics
+        Bandwidth::tag_message(&signature, &message_type);
+
+        // the container is fanned out to the receivers by the scheduler
+        Self::schedule_group_message(receivers, container, false);
+
+        Ok(())
+    }
+
+    /// pack the payload into an envelope, sign it
+    /// and schedule it for sending
+    fn sign_and_schedule(
+        user_account: &UserAccount,
+        receiver: &PeerId,
+        payload: Vec<u8>,
+        message_id: &Vec<u8>,
+        is_common_message: bool,
+    ) -> Result<Vec<u8>, String> {
         // create envelope
         let envelope = proto::Envelope {
             sender_id: user_account.id.to_bytes(),
             receiver_id: receiver.to_bytes(),
-            payload: envelop_payload.encode_to_vec(),
+            payload,
+            receiver_ids: Vec::new(),
         };
 
         // encode envelope
@@ -361,6 +692,7 @@ impl Messaging {
             let container = proto::Container {
                 signature: signature.clone(),
                 envelope: Some(envelope),
+                forward_to: Vec::new(),
             };
 
             // in common message case, save into unconfirmed table
@@ -407,6 +739,7 @@ impl Messaging {
             sender_id: user_account.id.to_bytes(),
             receiver_id: storage_node_id.to_bytes(),
             payload: dtn_payload.encode_to_vec(),
+            receiver_ids: Vec::new(),
         };
 
         if let Ok(signature_dtn) = user_account.keys.sign(&envelope_dtn.encode_to_vec()) {
@@ -414,6 +747,7 @@ impl Messaging {
             let container_dtn = proto::Container {
                 signature: signature_dtn.clone(),
                 envelope: Some(envelope_dtn),
+                forward_to: Vec::new(),
             };
 
             // in common message case, save into unconfirmed table
@@ -456,24 +790,45 @@ impl Messaging {
         scheduled_dtn: bool,
         is_dtn: bool,
     ) {
+        Self::schedule(ScheduledMessage {
+            receivers: vec![receiver],
+            container,
+            is_common,
+            is_forward,
+            scheduled_dtn,
+            is_dtn,
+        });
+    }
+
+    /// schedule a group container
+    ///
+    /// The container is fanned out to all receivers by the scheduler.
+    pub fn schedule_group_message(
+        receivers: Vec<PeerId>,
+        container: proto::Container,
+        is_forward: bool,
+    ) {
+        Self::schedule(ScheduledMessage {
+            receivers,
+            container,
+            is_common: true,
+            is_forward,
+            scheduled_dtn: false,
+            is_dtn: false,
+        });
+    }
+
+    /// add a message to the sending queue
+    fn schedule(msg: ScheduledMessage) {
         #[cfg(emulate)]
         if network_emul::NetworkEmulator::is_lost() {
             log::error!(
                 "drop message, signature: {}",
-                bs58::encode(container.signature.clone()).into_string()
+                bs58::encode(msg.container.signature.clone()).into_string()
             );
             return;
         }
 
-        let msg = ScheduledMessage {
-            receiver,
-            container,
-            is_common,
-            is_forward,
-            scheduled_dtn,
-            is_dtn,
-        };
-
         // add it to sending queue
         let mut messaging = MESSAGING.get().write().unwrap();
         messaging.to_send.push_back(msg);
@@ -483,7 +838,14 @@ impl Messaging {
     ///
     /// Check if there is a message scheduled for sending.
     ///
-    pub fn check_scheduler() -> Option<(PeerId, ConnectionModule, Vec<u8>)> {
+    /// Returns the neighbours and connection modules to send
+    /// the message to. Messages waiting for a confirmation fail over
+    /// to the next route, when they are retransmitted.
+    /// If redundant sending is configured, messages of high priority
+    /// services are sent via two different connection modules.
+    ///
+    pub fn check_scheduler() -> Vec<(PeerId, ConnectionModule, Vec<u8>)> {
+        let mut result: Vec<(PeerId, ConnectionModule, Vec<u8>)> = Vec::new();
         let message_item: Option<ScheduledMessage>;
 
         // get scheduled messaging buffer
@@ -493,18 +855,55 @@ impl Messaging {
         }
 
         if let Some(message) = message_item {
-            // check for route
-            if let Some(route) = RoutingTable::get_route_to_user(message.receiver) {
+            // fan out group containers
+            if message.receivers.len() > 1 {
+                return Self::fan_out(message);
+            }
+
+            let receiver;
+            match message.receivers.first() {
+                Some(my_receiver) => receiver = my_receiver.to_owned(),
+                None => return result,
+            }
+
+            // a group container is only forwarded to this receiver
+            let mut container = message.container;
+            if Self::is_group_container(&container) {
+                container.forward_to = vec![receiver.to_bytes()];
+            }
+            let key = Self::unconfirmed_key(&container, &receiver);
+
+            // check for routes
+            let routes = RoutingTable::get_routes_to_user(receiver);
+            if routes.len() > 0 {
                 // update unconfirmed table set scheduled flag.
-                Self::on_scheduled_message(&message.container.signature);
+                let message_type = Self::on_scheduled_message(&key);
+
+                // select routes
+                let selected;
+                if let Some(message_type) = message_type {
+                    let redundant = message_type.is_high_priority()
+                        && crate::router::Router::get_configuration().redundant_sending
+                        && !message.is_forward
+                        && !message.is_dtn;
+                    selected = Self::select_routes(&key, &routes, redundant);
+                } else {
+                    selected = vec![routes[0].clone()];
+                }
 
                 // create binary message
-                let data = message.container.encode_to_vec();
+                let data = container.encode_to_vec();
 
-                // return information
-                return Some((route.node, route.module, data));
+                for route in selected {
+                    result.push((route.node, route.module, data.clone()));
+                }
+            } else if RoutingTable::has_routes_to_user(receiver) && !Self::on_stale_routes(&key) {
+                // the neighbours of the routes are not connected (yet),
+                // the message will be retransmitted
+                log::debug!("no connected route to {}", receiver.to_base58());
             } else {
-                // user is offline we schedule through DTN service
+                // user is offline or the routes to the user failed,
+                // we schedule through DTN service
                 if !message.is_forward
                     && !message.is_dtn
                     && !message.scheduled_dtn
@@ -512,7 +911,7 @@ impl Messaging {
                 {
                     // get storage node id
                     if let Ok(my_user_id) =
-                        PeerId::from_bytes(&message.container.envelope.as_ref().unwrap().sender_id)
+                        PeerId::from_bytes(&container.envelope.as_ref().unwrap().sender_id)
                     {
                         if let Some(storage_node_id) =
                             super::dtn::Dtn::get_storage_user(&my_user_id)
@@ -521,13 +920,13 @@ impl Messaging {
                                 if let Err(_e) = Self::send_dtn_message(
                                     &user_account,
                                     &storage_node_id,
-                                    &message.container,
+                                    &container,
                                 ) {
                                     log::error!("DTN scheduling error!");
                                 } else {
                                     log::error!("DTN scheduled...");
                                     // update unconfirmed table
-                                    Self::on_scheduled_as_dtn_message(&message.container.signature);
+                                    Self::on_scheduled_as_dtn_message(&key);
                                 }
                             }
                         }
@@ -536,7 +935,136 @@ impl Messaging {
             }
         }
 
-        None
+        result
+    }
+
+    /// fan out a group container to its receivers
+    ///
+    /// The receivers are grouped by the neighbour of their best route.
+    /// The container is sent once to every neighbour, together with
+    /// the receivers it shall be forwarded to.
+    /// Receivers without a connected route are scheduled individually.
+    fn fan_out(message: ScheduledMessage) -> Vec<(PeerId, ConnectionModule, Vec<u8>)> {
+        let mut hops: Vec<(RoutingConnectionEntry, Vec<PeerId>)> = Vec::new();
+
+        for receiver in &message.receivers {
+            let routes = RoutingTable::get_routes_to_user(receiver.to_owned());
+            match routes.first() {
+                Some(route) => {
+                    match hops
+                        .iter_mut()
+                        .find(|(hop, _)| hop.module == route.module && hop.node == route.node)
+                    {
+                        Some((_, receivers)) => receivers.push(receiver.to_owned()),
+                        None => hops.push((route.to_owned(), vec![receiver.to_owned()])),
+                    }
+                }
+                None => Self::schedule_message(
+                    receiver.to_owned(),
+                    message.container.clone(),
+                    message.is_common,
+                    message.is_forward,
+                    message.scheduled_dtn,
+                    message.is_dtn,
+                ),
+            }
+        }
+
+        let mut result: Vec<(PeerId, ConnectionModule, Vec<u8>)> = Vec::new();
+        for (route, receivers) in hops {
+            let mut container = message.container.clone();
+            container.forward_to = receivers
+                .iter()
+                .map(|receiver| receiver.to_bytes())
+                .collect();
+
+            for receiver in &receivers {
+                Self::on_scheduled_message(&Self::unconfirmed_key(&container, receiver));
+            }
+
+            result.push((route.node, route.module, container.encode_to_vec()));
+        }
+
+        result
+    }
+
+    /// select the routes to send a message on
+    ///
+    /// The first route, that was not yet tried for this message, is
+    /// chosen. When all routes were tried, it starts again with
+    /// the best route.
+    /// For redundant sending, a second route via another connection
+    /// module is added.
+    fn select_routes(
+        signature: &Vec<u8>,
+        routes: &Vec<RoutingConnectionEntry>,
+        redundant: bool,
+    ) -> Vec<RoutingConnectionEntry> {
+        let mut messaging = MESSAGING.get().write().unwrap();
+        let routes_tried = messaging
+            .routes_tried
+            .entry(signature.to_owned())
+            .or_insert(RoutesTried {
+                routes: Vec::new(),
+                last: Vec::new(),
+                last_tried: 0,
+            });
+        routes_tried.last_tried = Timestamp::get_timestamp();
+        let tried = &mut routes_tried.routes;
+
+        // find first untried route
+        let mut first = routes
+            .iter()
+            .find(|route| !tried.contains(&(route.module, route.node)));
+        if first.is_none() {
+            tried.clear();
+            first = routes.first();
+        }
+
+        let mut selected: Vec<RoutingConnectionEntry> = Vec::new();
+        if let Some(route) = first {
+            selected.push(route.to_owned());
+
+            // add a route via another module
+            if redundant {
+                if let Some(second) = routes.iter().find(|second| second.module != route.module) {
+                    selected.push(second.to_owned());
+                }
+            }
+        }
+
+        for route in &selected {
+            if !tried.contains(&(route.module, route.node)) {
+                tried.push((route.module, route.node));
+            }
+        }
+        routes_tried.last = selected
+            .iter()
+            .map(|route| (route.module, route.node))
+            .collect();
+
+        selected
+    }
+
+    /// expire the routes tried of unconfirmed messages
+    ///
+    /// The routes are forgotten, when the message is no longer
+    /// waiting for a confirmation, when it was handed over to
+    /// the DTN service or when it wasn't sent for a long time.
+    pub fn expire_routes_tried(unconfirmed: &Tree<UnConfirmedMessage>) {
+        let now = Timestamp::get_timestamp();
+        let mut messaging = MESSAGING.get().write().unwrap();
+
+        messaging.routes_tried.retain(|key, routes_tried| {
+            if now > routes_tried.last_tried + Self::ROUTES_TRIED_TIMEOUT {
+                return false;
+            }
+
+            match unconfirmed.get(key) {
+                Ok(Some(unconfirmed_message)) => !unconfirmed_message.scheduled_dtn,
+                _ => false,
+            }
+        });
     }
 
     /// Send a confirmation message for a received message
@@ -585,6 +1113,11 @@ impl Messaging {
         // decode message container
         match proto::Container::decode(&received.data[..]) {
             Ok(container) => {
+                if Self::is_group_container(&container) {
+                    Self::received_group(container);
+                    return;
+                }
+
                 if let Some(envelope) = container.envelope.clone() {
                     match PeerId::from_bytes(&envelope.receiver_id) {
                         Ok(receiver_id) => {
@@ -619,4 +1152,105 @@ impl Messaging {
             Err(e) => log::error!("Messaging container decoding error: {}", e),
         }
     }
+
+    /// received a group container
+    ///
+    /// The container is processed for all receivers that are
+    /// local user accounts, and forwarded to all other receivers.
+    fn received_group(container: proto::Container) {
+        let mut forward: Vec<PeerId> = Vec::new();
+        for receiver in Self::get_receivers(&container) {
+            match UserAccounts::get_by_id(receiver) {
+                Some(user_account) => {
+                    MessagingProcess::process_received_message(user_account, container.clone())
+                }
+                None => forward.push(receiver),
+            }
+        }
+
+        if forward.len() > 0 {
+            Self::schedule_group_message(forward, container, true);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn init() {
+        // the storage can only be set once per test binary
+        let db = sled_extensions::Config::default()
+            .temporary(true)
+            .open()
+            .unwrap();
+        let unconfirmed: Tree<UnConfirmedMessage> = db.open_bincode_tree("unconfirmed").unwrap();
+        UNCONFIRMED.set(RwLock::new(UnConfirmedMessages { unconfirmed }));
+    }
+
+    #[test]
+    fn group_file_container_is_stored_as_chat_file() {
+        init();
+        let receivers = vec![PeerId::random(), PeerId::random()];
+        let container = proto::Container {
+            signature: vec![1; 64],
+            envelope: Some(proto::Envelope {
+                sender_id: PeerId::random().to_bytes(),
+                receiver_id: vec![2; 16],
+                payload: Vec::new(),
+                receiver_ids: receivers
+                    .iter()
+                    .map(|receiver| receiver.to_bytes())
+                    .collect(),
+            }),
+            forward_to: Vec::new(),
+        };
+
+        Messaging::save_unconfirmed_group_message(
+            &receivers,
+            &container,
+            &MessagingServiceType::ChatFile,
+            &vec![3; 16],
+        );
+
+        let unconfirmed = UNCONFIRMED.get().read().unwrap();
+        for receiver in &receivers {
+            let key = Messaging::unconfirmed_key(&container, receiver);
+            let message = unconfirmed.unconfirmed.get(&key).unwrap().unwrap();
+            assert!(matches!(
+                message.message_type,
+                MessagingServiceType::ChatFile
+            ));
+            assert_eq!(message.receiver_id, receiver.to_bytes());
+        }
+    }
+
+    #[test]
+    fn stale_routes_are_counted_apart_from_retransmissions() {
+        init();
+        let receiver = PeerId::random();
+        let container = proto::Container {
+            signature: vec![4; 64],
+            envelope: None,
+            forward_to: Vec::new(),
+        };
+        Messaging::save_unconfirmed_message(
+            MessagingServiceType::Chat,
+            &vec![5; 16],
+            &receiver,
+            &container,
+            false,
+        );
+        let key = Messaging::unconfirmed_key(&container, &receiver);
+
+        for _ in 0..Messaging::STALE_ROUTES_RETRIES {
+            assert!(!Messaging::on_stale_routes(&key));
+        }
+        assert!(Messaging::on_stale_routes(&key));
+
+        let unconfirmed = UNCONFIRMED.get().read().unwrap();
+        let message = unconfirmed.unconfirmed.get(&key).unwrap().unwrap();
+        assert_eq!(message.retry, 1);
+        assert_eq!(message.stale_routes, Messaging::STALE_ROUTES_RETRIES);
+    }
 }
@@ -11,10 +11,10 @@ use prost::Message;
 
 use crate::node::user_accounts::UserAccount;
 use crate::router;
-use crate::services::chat::{self, rpc_proto, ChatFile, ChatStorage};
+use crate::services::chat::{self, rpc_proto, ChatFile, ChatPoll, ChatStorage};
 use crate::services::crypto::Crypto;
 use crate::services::dtn;
-use crate::services::group::{self, Group, GroupId};
+use crate::services::group::{self, Group, GroupId, GroupSenderKey};
 use crate::services::rtc;
 use crate::utilities::timestamp::Timestamp;
 
@@ -83,6 +83,14 @@ impl MessagingProcess {
                 );
             }
             Some(super::proto::messaging::Message::RtcStreamMessage(_rtc_stream)) => {}
+            Some(super::proto::messaging::Message::ReadReceipt(read_receipt)) => {
+                // update the read state of the group member
+                if let Err(e) =
+                    chat::ChatMessage::on_read_receipt(sender_id, &user_account.id, &read_receipt)
+                {
+                    log::error!("read receipt processing error {}", e);
+                }
+            }
             Some(super::proto::messaging::Message::GroupInviteMessage(group_invite)) => {
                 // TODO: pass on user_account
                 group::Group::net(sender_id, &user_account.id, &group_invite.content);
@@ -122,6 +130,22 @@ impl MessagingProcess {
                         if !group.are_members(&user_account.id.to_bytes(), &sender_id.to_bytes()) {
                             return;
                         }
+
+                        // Check if the sender is allowed to post chat messages.
+                        match common.payload {
+                            Some(super::proto::common_message::Payload::ChatMessage(_))
+                            | Some(super::proto::common_message::Payload::FileMessage(_))
+                            | Some(super::proto::common_message::Payload::PollMessage(_)) => {
+                                if !group.can_post(&sender_id.to_bytes()) {
+                                    log::warn!(
+                                        "received message from {} without post permission",
+                                        sender_id
+                                    );
+                                    return;
+                                }
+                            }
+                            _ => {}
+                        }
                     }
                     None => {
                         log::warn!("received message from {} for unexisting group", sender_id);
@@ -136,6 +160,13 @@ impl MessagingProcess {
                             message: Some(rpc_proto::chat_content_message::Message::ChatContent(
                                 rpc_proto::ChatContent {
                                     text: chat_message.content.clone(),
+                                    reply: chat_message.reply.clone().map(|reply| {
+                                        rpc_proto::ChatReply {
+                                            message_id: reply.message_id,
+                                            sender_id: reply.sender_id,
+                                            preview: reply.preview,
+                                        }
+                                    }),
                                 },
                             )),
                         };
@@ -160,6 +191,24 @@ impl MessagingProcess {
                             &file_message.content,
                         );
                     }
+                    Some(super::proto::common_message::Payload::PollMessage(ref poll_message)) => {
+                        ChatPoll::on_poll(
+                            sender_id,
+                            &user_account.id,
+                            &group_id,
+                            &common,
+                            poll_message,
+                        );
+                    }
+                    Some(super::proto::common_message::Payload::VoteMessage(ref vote_message)) => {
+                        ChatPoll::on_vote(
+                            sender_id,
+                            &user_account.id,
+                            &group_id,
+                            &common,
+                            vote_message,
+                        );
+                    }
                     Some(super::proto::common_message::Payload::GroupMessage(
                         ref group_message,
                     )) => {
@@ -190,6 +239,8 @@ impl MessagingProcess {
                     &user_account.id,
                     &group_id.to_bytes(),
                     &common.message_id,
+                    common.group_revision,
+                    &common.group_info_hash,
                 ) {
                     log::error!("group status processing error {}", e);
                 }
@@ -213,7 +264,7 @@ impl MessagingProcess {
     pub fn process_received_message(user_account: UserAccount, container: super::proto::Container) {
         // check envelop
         let envelope;
-        match container.envelope {
+        match container.envelope.clone() {
             Some(v) => envelope = v,
             _ => {
                 log::error!("No Envelope in Message Container");
@@ -257,14 +308,23 @@ impl MessagingProcess {
 
         // check receiver_id
         let receiver_id;
-        match PeerId::from_bytes(&envelope.receiver_id) {
-            Ok(v) => {
-                receiver_id = v;
+        if envelope.receiver_ids.is_empty() {
+            match PeerId::from_bytes(&envelope.receiver_id) {
+                Ok(v) => {
+                    receiver_id = v;
+                }
+                _ => {
+                    log::error!("Error retrieving PeerId");
+                    return;
+                }
             }
-            _ => {
-                log::error!("Error retrieving PeerId");
+        } else {
+            // group containers need to be addressed to us
+            if !envelope.receiver_ids.contains(&user_account.id.to_bytes()) {
+                log::error!("group message not addressed to {}", user_account.id);
                 return;
             }
+            receiver_id = user_account.id;
         }
 
         match super::proto::EnvelopPayload::decode(&envelope.payload[..]) {
@@ -288,6 +348,44 @@ impl MessagingProcess {
                             &container.signature,
                         );
                     }
+                    Some(super::proto::envelop_payload::Payload::GroupEncrypted(encrypted)) => {
+                        // refuse replayed messages, but confirm them again,
+                        // as the first confirmation might have been lost
+                        if GroupSenderKey::is_replayed(&user_account.id, &sender_id, &encrypted) {
+                            log::trace!(
+                                "group message {} already received",
+                                bs58::encode(&container.signature).into_string()
+                            );
+                            if let Err(e) = super::Messaging::send_confirmation(
+                                &user_account.id,
+                                &sender_id,
+                                &container.signature,
+                            ) {
+                                log::error!("send confirmation failed {}", e);
+                            }
+                            return;
+                        }
+
+                        // decrypt data with the sender key
+                        // the message is cached if the sender key is missing
+                        let decrypted: Vec<u8>;
+                        match GroupSenderKey::decrypt(
+                            &user_account.id,
+                            &sender_id,
+                            &encrypted,
+                            &container,
+                        ) {
+                            Some(decryption_result) => decrypted = decryption_result,
+                            None => return,
+                        }
+
+                        Self::on_decrypted_message(
+                            &sender_id,
+                            user_account,
+                            &decrypted,
+                            &container.signature,
+                        );
+                    }
                     Some(super::proto::envelop_payload::Payload::Dtn(dtn)) => {
                         dtn::Dtn::net(&receiver_id, &sender_id, &container.signature, &dtn);
                     }
@@ -20,6 +20,10 @@ impl MessagingRetransmit {
     pub fn process() {
         // get unconfirmed table
         let unconfirmed = super::UNCONFIRMED.get().write().unwrap();
+
+        // forget the routes of messages that are no longer retransmitted
+        super::Messaging::expire_routes_tried(&unconfirmed.unconfirmed);
+
         if unconfirmed.unconfirmed.len() == 0 {
             // there are no message to retrans
             return;
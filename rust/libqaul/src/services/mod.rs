@@ -11,6 +11,7 @@
 //! * Messaging: sends and receives direct messages in qaul network.
 //!   It can be accessed by other services.
 //! * Chat: Sends and receives direct chat messages via messaging.
+//! * Search: Full text search over the chat messages and the feed.
 
 pub mod chat;
 pub mod crypto;
@@ -19,6 +20,7 @@ pub mod feed;
 pub mod group;
 pub mod messaging;
 pub mod rtc;
+pub mod search;
 
 /// qaul Services
 pub struct Services {}
@@ -32,6 +34,7 @@ impl Services {
         feed::Feed::init();
         messaging::Messaging::init();
         chat::Chat::init();
+        search::Search::init();
         group::Group::init();
         rtc::Rtc::init();
         dtn::Dtn::init();
@@ -132,6 +132,8 @@ impl Rtc {
                     content: data.clone(),
                 },
             )),
+            group_revision: group.revision,
+            group_info_hash: group.info_hash(),
         };
         let send_message = proto::Messaging {
             message: Some(proto::messaging::Message::CommonMessage(
@@ -0,0 +1,627 @@
+// Copyright (c) 2022 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Qaul Search Service
+//!
+//! Local full text search over the chat messages, the descriptions
+//! of chat files and the public feed messages.
+//!
+//! The search service maintains an inverted index in the data base.
+//! Each word of a message is saved as a key, which references the
+//! indexed message:
+//!
+//! `{word}0x00{document key}` => `{document key}`
+//!
+//! The chat messages are indexed per user account,
+//! the feed messages are indexed once per node, as the feed is
+//! shared by all user accounts of a node.
+
+use libp2p::PeerId;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use sled_extensions::{bincode::Tree, DbExt};
+use state::Storage;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::RwLock;
+
+use super::chat::{rpc_proto as chat_proto, ChatStorage};
+use super::feed::{Feed, FeedMessageData};
+use crate::rpc::Rpc;
+use crate::storage::database::DataBase;
+
+/// Import protobuf message definition generated by
+/// the rust module prost-build.
+pub mod proto_rpc {
+    include!("qaul.rpc.search.rs");
+}
+
+/// mutable state of the search indexes
+static SEARCH: Storage<RwLock<Search>> = Storage::new();
+
+/// minimal character length of an indexed word
+const MIN_TERM_LENGTH: usize = 2;
+
+/// default number of results returned per request
+const DEFAULT_LIMIT: u32 = 20;
+
+/// number of characters displayed before and after
+/// the search term in a snippet
+const SNIPPET_CONTEXT: usize = 40;
+
+/// Indexed Document
+///
+/// Contains the message reference and the indexed text.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SearchDocument {
+    /// type of the document
+    ///
+    /// the values of `proto_rpc::SearchResultType`
+    pub document_type: i32,
+    /// group id of the chat conversation
+    ///
+    /// empty for feed messages
+    pub group_id: Vec<u8>,
+    /// chat or feed message id
+    pub message_id: Vec<u8>,
+    /// index of the message in the conversation or feed
+    pub index: u64,
+    /// id of the sending user
+    pub sender_id: Vec<u8>,
+    /// time when the message was sent
+    pub sent_at: u64,
+    /// indexed text
+    pub text: String,
+}
+
+/// Search index data base trees
+#[derive(Clone)]
+pub struct SearchDb {
+    /// inverted index: word + document key => document key
+    pub terms: Tree<Vec<u8>>,
+    /// document key => document
+    pub documents: Tree<SearchDocument>,
+}
+
+/// qaul Search Service
+pub struct Search {
+    /// search index of the feed messages
+    feed: SearchDb,
+    /// chat search indexes per user account
+    db_ref: BTreeMap<Vec<u8>, SearchDb>,
+}
+
+impl Search {
+    /// initialize search service
+    ///
+    /// Needs to be called after the feed and the chat
+    /// services have been initialized.
+    pub fn init() {
+        // open feed index trees
+        let db = DataBase::get_node_db();
+        let terms: Tree<Vec<u8>> = db.open_bincode_tree("feed_search_terms").unwrap();
+        let documents: Tree<SearchDocument> =
+            db.open_bincode_tree("feed_search_documents").unwrap();
+        let feed = SearchDb { terms, documents };
+
+        // index the existing feed messages on first startup
+        if feed.documents.len() == 0 {
+            for message_data in Feed::get_message_data() {
+                Self::index_feed_document(&feed, &message_data);
+            }
+            Self::flush(&feed);
+        }
+
+        let search = Search {
+            feed,
+            db_ref: BTreeMap::new(),
+        };
+        SEARCH.set(RwLock::new(search));
+    }
+
+    /// index a chat message of a user account
+    ///
+    /// Text messages and file messages are indexed,
+    /// all other chat messages are ignored.
+    pub fn index_chat_message(account_id: &PeerId, message: &chat_proto::ChatMessage) {
+        let db_ref = Self::get_db_ref(account_id);
+        if Self::index_chat_document(&db_ref, message) {
+            Self::flush(&db_ref);
+        }
+    }
+
+    /// index a feed message
+    pub fn index_feed_message(message: &FeedMessageData) {
+        let feed;
+        {
+            let search = SEARCH.get().read().unwrap();
+            feed = search.feed.clone();
+        }
+
+        Self::index_feed_document(&feed, message);
+        Self::flush(&feed);
+    }
+
+    /// remove a chat message from the search index of a user account
+    pub fn remove_chat_message(account_id: &PeerId, message: &chat_proto::ChatMessage) {
+        let db_ref = Self::get_db_ref(account_id);
+
+        let mut key = message.group_id.clone();
+        key.extend(message.index.to_be_bytes());
+
+        if Self::remove_document(&db_ref, key) {
+            Self::flush(&db_ref);
+        }
+    }
+
+    /// remove a document and it's words from the search index
+    ///
+    /// Returns true if the document was found.
+    fn remove_document(db_ref: &SearchDb, key: Vec<u8>) -> bool {
+        let document;
+        match db_ref.documents.get(key.clone()) {
+            Ok(Some(doc)) => document = doc,
+            Ok(None) => return false,
+            Err(e) => {
+                log::error!("search document error: {}", e);
+                return false;
+            }
+        }
+
+        for term in Self::tokenize(&document.text) {
+            let mut term_key = term.into_bytes();
+            term_key.push(0);
+            term_key.extend(key.clone());
+
+            if let Err(e) = db_ref.terms.remove(term_key) {
+                log::error!("Error removing search term from data base: {}", e);
+            }
+        }
+
+        if let Err(e) = db_ref.documents.remove(key) {
+            log::error!("Error removing search document from data base: {}", e);
+        }
+
+        true
+    }
+
+    /// create document from a chat message and add it to the index
+    ///
+    /// Returns true if the message was indexed.
+    fn index_chat_document(db_ref: &SearchDb, message: &chat_proto::ChatMessage) -> bool {
+        let document_type;
+        let text;
+        match chat_proto::ChatContentMessage::decode(&message.content[..]) {
+            Ok(content) => match content.message {
+                Some(chat_proto::chat_content_message::Message::ChatContent(chat_content)) => {
+                    document_type = proto_rpc::SearchResultType::ChatMessage;
+                    text = chat_content.text;
+                }
+                Some(chat_proto::chat_content_message::Message::FileContent(file_content)) => {
+                    document_type = proto_rpc::SearchResultType::ChatFile;
+                    text = file_content.file_name + " " + &file_content.file_description;
+                }
+                Some(chat_proto::chat_content_message::Message::PollContent(poll_content)) => {
+                    document_type = proto_rpc::SearchResultType::ChatMessage;
+                    text = poll_content.question + " " + &poll_content.options.join(" ");
+                }
+                _ => return false,
+            },
+            Err(e) => {
+                log::error!("search: chat content decoding error {}", e);
+                return false;
+            }
+        }
+
+        let document = SearchDocument {
+            document_type: document_type as i32,
+            group_id: message.group_id.clone(),
+            message_id: message.message_id.clone(),
+            index: message.index,
+            sender_id: message.sender_id.clone(),
+            sent_at: message.sent_at,
+            text,
+        };
+
+        let mut key = message.group_id.clone();
+        key.extend(message.index.to_be_bytes());
+
+        Self::index_document(db_ref, key, document);
+        true
+    }
+
+    /// create document from a feed message and add it to the index
+    fn index_feed_document(feed: &SearchDb, message: &FeedMessageData) {
+        let document = SearchDocument {
+            document_type: proto_rpc::SearchResultType::FeedMessage as i32,
+            group_id: Vec::new(),
+            message_id: message.message_id.clone(),
+            index: message.index,
+            sender_id: message.sender_id.clone(),
+            sent_at: message.timestamp_sent,
+            text: message.content.clone(),
+        };
+
+        Self::index_document(feed, message.index.to_be_bytes().to_vec(), document);
+    }
+
+    /// add a document to the search index
+    fn index_document(db_ref: &SearchDb, key: Vec<u8>, document: SearchDocument) {
+        for term in Self::tokenize(&document.text) {
+            let mut term_key = term.into_bytes();
+            term_key.push(0);
+            term_key.extend(key.clone());
+
+            if let Err(e) = db_ref.terms.insert(term_key, key.clone()) {
+                log::error!("Error saving search term to data base: {}", e);
+            }
+        }
+
+        if let Err(e) = db_ref.documents.insert(key, document) {
+            log::error!("Error saving search document to data base: {}", e);
+        }
+    }
+
+    /// flush search index trees to disk
+    fn flush(db_ref: &SearchDb) {
+        if let Err(e) = db_ref.terms.flush() {
+            log::error!("Error search terms flush: {}", e);
+        }
+        if let Err(e) = db_ref.documents.flush() {
+            log::error!("Error search documents flush: {}", e);
+        }
+    }
+
+    /// split a text into it's lower case words
+    fn tokenize(text: &str) -> BTreeSet<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| word.chars().count() >= MIN_TERM_LENGTH)
+            .map(|word| word.to_lowercase())
+            .collect()
+    }
+
+    /// get all document keys containing a word starting with the term
+    fn find_term(db_ref: &SearchDb, term: &String) -> BTreeSet<Vec<u8>> {
+        let mut keys = BTreeSet::new();
+
+        for res in db_ref.terms.scan_prefix(term.as_bytes()) {
+            match res {
+                Ok((_term_key, key)) => {
+                    keys.insert(key);
+                }
+                Err(e) => log::error!("search term error: {}", e),
+            }
+        }
+
+        keys
+    }
+
+    /// find all documents of an index containing all terms
+    fn find_documents(db_ref: &SearchDb, terms: &BTreeSet<String>) -> Vec<SearchDocument> {
+        let mut result: Option<BTreeSet<Vec<u8>>> = None;
+
+        for term in terms {
+            let keys = Self::find_term(db_ref, term);
+            result = match result {
+                Some(previous) => Some(previous.intersection(&keys).cloned().collect()),
+                None => Some(keys),
+            };
+        }
+
+        let mut documents = Vec::new();
+        for key in result.unwrap_or_default() {
+            match db_ref.documents.get(key) {
+                Ok(Some(document)) => documents.push(document),
+                Ok(None) => {}
+                Err(e) => log::error!("search document error: {}", e),
+            }
+        }
+
+        documents
+    }
+
+    /// check if a document passes the filters of the request
+    fn filter(document: &SearchDocument, request: &proto_rpc::SearchRequest) -> bool {
+        if request.group_id.len() > 0 && document.group_id != request.group_id {
+            return false;
+        }
+        if request.sender_id.len() > 0 && document.sender_id != request.sender_id {
+            return false;
+        }
+        if request.sent_after > 0 && document.sent_at < request.sent_after {
+            return false;
+        }
+        if request.sent_before > 0 && document.sent_at > request.sent_before {
+            return false;
+        }
+
+        true
+    }
+
+    /// create a text snippet around the first occurrence of the term
+    fn create_snippet(text: &String, term: &String) -> String {
+        let chars: Vec<char> = text.chars().collect();
+        let lower: Vec<char> = chars
+            .iter()
+            .map(|c| c.to_lowercase().next().unwrap_or(*c))
+            .collect();
+        let term_chars: Vec<char> = term.chars().collect();
+
+        // find position of the term
+        let mut position = 0;
+        if term_chars.len() > 0 && lower.len() >= term_chars.len() {
+            for i in 0..=(lower.len() - term_chars.len()) {
+                if lower[i..i + term_chars.len()] == term_chars[..] {
+                    position = i;
+                    break;
+                }
+            }
+        }
+
+        let start = position.saturating_sub(SNIPPET_CONTEXT);
+        let end = std::cmp::min(chars.len(), position + term_chars.len() + SNIPPET_CONTEXT);
+
+        let mut snippet = String::new();
+        if start > 0 {
+            snippet.push_str("...");
+        }
+        snippet.extend(chars[start..end].iter());
+        if end < chars.len() {
+            snippet.push_str("...");
+        }
+
+        snippet
+    }
+
+    /// search the chat messages of a user account and the feed
+    pub fn search(
+        account_id: &PeerId,
+        request: &proto_rpc::SearchRequest,
+    ) -> proto_rpc::SearchResponse {
+        let terms = Self::tokenize(&request.query);
+
+        let mut documents: Vec<SearchDocument> = Vec::new();
+        if terms.len() > 0 {
+            let source = proto_rpc::SearchSource::from_i32(request.source)
+                .unwrap_or(proto_rpc::SearchSource::All);
+
+            // search chat messages
+            if source != proto_rpc::SearchSource::Feed {
+                let db_ref = Self::get_db_ref(account_id);
+                documents.extend(Self::find_documents(&db_ref, &terms));
+            }
+
+            // search feed messages
+            if source != proto_rpc::SearchSource::Chat && request.group_id.len() == 0 {
+                let feed;
+                {
+                    let search = SEARCH.get().read().unwrap();
+                    feed = search.feed.clone();
+                }
+                documents.extend(Self::find_documents(&feed, &terms));
+            }
+        }
+
+        // filter & sort results, newest first
+        documents.retain(|document| Self::filter(document, request));
+        documents.sort_by(|a, b| b.sent_at.cmp(&a.sent_at));
+
+        let total = documents.len() as u32;
+        let mut limit = request.limit;
+        if limit == 0 {
+            limit = DEFAULT_LIMIT;
+        }
+
+        // create the snippets around the first search term
+        let first_term = terms.iter().next().cloned().unwrap_or_default();
+        let results = documents
+            .into_iter()
+            .skip(request.offset as usize)
+            .take(limit as usize)
+            .map(|document| proto_rpc::SearchResult {
+                result_type: document.document_type,
+                group_id: document.group_id,
+                message_id: document.message_id,
+                index: document.index,
+                sender_id: document.sender_id,
+                sent_at: document.sent_at,
+                snippet: Self::create_snippet(&document.text, &first_term),
+            })
+            .collect();
+
+        proto_rpc::SearchResponse {
+            query: request.query.clone(),
+            total,
+            offset: request.offset,
+            results,
+        }
+    }
+
+    /// get the search index of a user account
+    fn get_db_ref(account_id: &PeerId) -> SearchDb {
+        // check if user account data exists
+        {
+            let search = SEARCH.get().read().unwrap();
+
+            if let Some(db_ref) = search.db_ref.get(&account_id.to_bytes()) {
+                return db_ref.clone();
+            }
+        }
+
+        // create search index if it does not exist
+        Self::create_search_db(account_id)
+    }
+
+    /// create the search index of a user account
+    ///
+    /// When the index is empty, all existing chat messages
+    /// of the user account are indexed.
+    fn create_search_db(account_id: &PeerId) -> SearchDb {
+        // get user data base
+        let db = DataBase::get_user_db(account_id.to_owned());
+
+        // open trees
+        let terms: Tree<Vec<u8>> = db.open_bincode_tree("search_terms").unwrap();
+        let documents: Tree<SearchDocument> = db.open_bincode_tree("search_documents").unwrap();
+        let db_ref = SearchDb { terms, documents };
+
+        // add user to state
+        {
+            let mut search = SEARCH.get().write().unwrap();
+            search.db_ref.insert(account_id.to_bytes(), db_ref.clone());
+        }
+
+        // index the existing chat messages
+        if db_ref.documents.len() == 0 {
+            let chat_db = ChatStorage::get_db_ref(account_id.to_owned());
+            for res in chat_db.messages.iter() {
+                if let Ok((_key, message)) = res {
+                    Self::index_chat_document(&db_ref, &message);
+                }
+            }
+            Self::flush(&db_ref);
+        }
+
+        db_ref
+    }
+
+    /// Process incoming RPC request messages for search module
+    pub fn rpc(data: Vec<u8>, user_id: Vec<u8>) {
+        let account_id;
+        match PeerId::from_bytes(&user_id) {
+            Ok(id) => account_id = id,
+            Err(e) => {
+                log::error!("invalid user id: {}", e);
+                return;
+            }
+        }
+
+        match proto_rpc::Search::decode(&data[..]) {
+            Ok(search) => match search.message {
+                Some(proto_rpc::search::Message::SearchRequest(request)) => {
+                    let response = Self::search(&account_id, &request);
+
+                    let proto_message = proto_rpc::Search {
+                        message: Some(proto_rpc::search::Message::SearchResponse(response)),
+                    };
+
+                    // send message
+                    Rpc::send_message(
+                        proto_message.encode_to_vec(),
+                        crate::rpc::proto::Modules::Search.into(),
+                        "".to_string(),
+                        Vec::new(),
+                    );
+                }
+                _ => {
+                    log::error!("Unhandled Protobuf Search Message");
+                }
+            },
+            Err(error) => {
+                log::error!("{:?}", error);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(group_id: Vec<u8>, sender_id: Vec<u8>, sent_at: u64) -> SearchDocument {
+        SearchDocument {
+            document_type: 0,
+            group_id,
+            message_id: Vec::new(),
+            index: 1,
+            sender_id,
+            sent_at,
+            text: String::from(""),
+        }
+    }
+
+    #[test]
+    fn tokenize_splits_and_lowercases_words() {
+        let terms = Search::tokenize("Hello, World! hello-world");
+        let expected: BTreeSet<String> = ["hello", "world"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(terms, expected);
+    }
+
+    #[test]
+    fn tokenize_drops_short_words() {
+        let terms = Search::tokenize("a b cd e");
+        assert_eq!(terms.len(), 1);
+        assert!(terms.contains("cd"));
+    }
+
+    #[test]
+    fn tokenize_keeps_unicode_words() {
+        let terms = Search::tokenize("Grüße aus Zürich");
+        assert!(terms.contains("grüße"));
+        assert!(terms.contains("zürich"));
+        assert!(terms.contains("aus"));
+    }
+
+    #[test]
+    fn snippet_of_short_text_is_the_text() {
+        let text = String::from("Meeting at the Station");
+        let snippet = Search::create_snippet(&text, &String::from("station"));
+        assert_eq!(snippet, text);
+    }
+
+    #[test]
+    fn snippet_is_cut_around_the_term() {
+        let text = format!("{}needle{}", "a".repeat(100), "b".repeat(100));
+        let snippet = Search::create_snippet(&text, &String::from("needle"));
+        assert_eq!(
+            snippet,
+            format!(
+                "...{}needle{}...",
+                "a".repeat(SNIPPET_CONTEXT),
+                "b".repeat(SNIPPET_CONTEXT)
+            )
+        );
+    }
+
+    #[test]
+    fn snippet_without_term_starts_at_the_beginning() {
+        let text = "x".repeat(100);
+        let snippet = Search::create_snippet(&text, &String::from("needle"));
+        assert_eq!(
+            snippet,
+            format!("{}...", "x".repeat(SNIPPET_CONTEXT + "needle".len()))
+        );
+    }
+
+    #[test]
+    fn snippet_counts_characters_not_bytes() {
+        let text = format!("{}ende", "ü".repeat(60));
+        let snippet = Search::create_snippet(&text, &String::from("ende"));
+        assert_eq!(snippet, format!("...{}ende", "ü".repeat(SNIPPET_CONTEXT)));
+    }
+
+    #[test]
+    fn filter_checks_group_sender_and_time() {
+        let document = document(vec![1], vec![2], 1000);
+
+        let mut request = proto_rpc::SearchRequest::default();
+        assert!(Search::filter(&document, &request));
+
+        request.group_id = vec![3];
+        assert!(!Search::filter(&document, &request));
+        request.group_id = vec![1];
+        assert!(Search::filter(&document, &request));
+
+        request.sender_id = vec![3];
+        assert!(!Search::filter(&document, &request));
+        request.sender_id = vec![2];
+        assert!(Search::filter(&document, &request));
+
+        request.sent_after = 1001;
+        assert!(!Search::filter(&document, &request));
+        request.sent_after = 1000;
+        request.sent_before = 999;
+        assert!(!Search::filter(&document, &request));
+        request.sent_before = 1000;
+        assert!(Search::filter(&document, &request));
+    }
+}
@@ -29,6 +29,12 @@ pub struct Node {
     pub initialized: u8,
     pub id: String,
     pub keys: String,
+    /// pre-shared key of a private mesh network
+    ///
+    /// 32 bytes, base64 encoded.
+    /// Leave it empty to join the public qaul network.
+    #[serde(default)]
+    pub network_key: String,
 }
 
 impl Default for Node {
@@ -37,6 +43,7 @@ impl Default for Node {
             initialized: 0,
             id: String::from(""),
             keys: String::from(""),
+            network_key: String::from(""),
         }
     }
 }
@@ -46,6 +53,19 @@ impl Default for Node {
 pub struct Lan {
     pub active: bool,
     pub listen: String,
+    /// additional multi addresses to listen on,
+    /// e.g. `/ip6/::/tcp/0` for IPv6
+    #[serde(default)]
+    pub listen_extra: Vec<String>,
+    /// mDNS discovery of other nodes
+    #[serde(default)]
+    pub mdns: LanMdns,
+    /// network interfaces to listen on
+    #[serde(default)]
+    pub interfaces: LanInterfaces,
+    /// static LAN peers to connect to
+    #[serde(default)]
+    pub peers: Vec<String>,
 }
 
 impl Default for Lan {
@@ -53,10 +73,51 @@ impl Default for Lan {
         Lan {
             active: true,
             listen: String::from("/ip4/0.0.0.0/tcp/0"),
+            listen_extra: Vec::new(),
+            mdns: LanMdns::default(),
+            interfaces: LanInterfaces::default(),
+            peers: Vec::new(),
         }
     }
 }
 
+/// mDNS Discovery of the LAN Module
+#[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(default)]
+pub struct LanMdns {
+    /// discover and advertise nodes via mDNS
+    pub active: bool,
+    /// query interval in seconds
+    pub query_interval: u64,
+    /// time to live of discovered nodes in seconds
+    pub ttl: u64,
+    /// discover nodes via IPv6 instead of IPv4
+    pub ipv6: bool,
+}
+
+impl Default for LanMdns {
+    fn default() -> Self {
+        LanMdns {
+            active: true,
+            query_interval: 30,
+            ttl: 300,
+            ipv6: false,
+        }
+    }
+}
+
+/// Interface Selection of the LAN Module
+///
+/// If the allow list is empty, all interfaces are allowed.
+/// Interfaces in the deny list are never used.
+#[derive(Debug, Deserialize, Clone, Serialize, Default)]
+pub struct LanInterfaces {
+    /// names of the interfaces to listen on
+    pub allow: Vec<String>,
+    /// names of the interfaces not to listen on
+    pub deny: Vec<String>,
+}
+
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct InternetPeer {
     pub address: String,
@@ -71,6 +132,16 @@ pub struct Internet {
     pub peers: Vec<InternetPeer>,
     pub do_listen: bool,
     pub listen: String,
+    /// additional multi addresses to listen on,
+    /// e.g. for WebSocket or QUIC connections
+    #[serde(default)]
+    pub listen_extra: Vec<String>,
+    /// circuit relay & hole punching
+    #[serde(default)]
+    pub relay: InternetRelay,
+    /// outbound proxy for all dialed connections
+    #[serde(default)]
+    pub proxy: InternetProxy,
 }
 
 impl Default for Internet {
@@ -99,6 +170,126 @@ impl Default for Internet {
             listen: String::from("/ip4/0.0.0.0/tcp/9229"),
             #[cfg(not(any(target_os = "android", target_os = "ios")))]
             listen: listen_str.clone(),
+            listen_extra: Vec::new(),
+            relay: InternetRelay::default(),
+            proxy: InternetProxy::default(),
+        }
+    }
+}
+
+/// Circuit Relay & Hole Punching of the Internet Module
+///
+/// Nodes behind a NAT can be reached via a relay node.
+/// Relayed connections are upgraded to direct connections
+/// via hole punching whenever possible.
+#[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(default)]
+pub struct InternetRelay {
+    /// act as a relay for other nodes
+    pub active: bool,
+    /// maximal number of reservations of nodes listening via this relay
+    pub max_reservations: usize,
+    /// maximal number of simultaneously relayed connections
+    pub max_circuits: usize,
+    /// maximal duration of a relayed connection in seconds
+    pub max_circuit_duration: u64,
+    /// maximal number of bytes relayed per connection and direction
+    pub max_circuit_bytes: u64,
+    /// relay nodes to listen via
+    ///
+    /// The multi addresses need to contain the node id of the relay,
+    /// e.g. `/ip4/144.91.74.192/tcp/9229/p2p/12D3KooW...`
+    pub listen_via: Vec<String>,
+    /// upgrade relayed connections to direct connections
+    pub hole_punching: bool,
+}
+
+impl Default for InternetRelay {
+    fn default() -> Self {
+        InternetRelay {
+            active: false,
+            max_reservations: 128,
+            max_circuits: 16,
+            max_circuit_duration: 600,
+            max_circuit_bytes: 10 * 1024 * 1024,
+            listen_via: Vec::new(),
+            hole_punching: true,
+        }
+    }
+}
+
+/// Outbound Proxy of the Internet Module
+#[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(default)]
+pub struct InternetProxy {
+    /// dial all internet connections via the proxy
+    pub active: bool,
+    /// proxy protocol: `socks5` or `http`
+    pub kind: String,
+    /// socket address of the proxy, e.g. `127.0.0.1:9050`
+    pub address: String,
+    /// optional user name for the proxy authentication
+    pub username: String,
+    /// optional password for the proxy authentication
+    pub password: String,
+}
+
+impl Default for InternetProxy {
+    fn default() -> Self {
+        InternetProxy {
+            active: false,
+            kind: String::from("socks5"),
+            address: String::from("127.0.0.1:9050"),
+            username: String::new(),
+            password: String::new(),
+        }
+    }
+}
+
+/// Stream interface
+///
+/// A byte stream link to a single neighbour node.
+///
+/// * `kind`: `serial`, `tcp`, `tcp_listen` or `pipe`
+/// * `address`: device path of the serial device or pipe,
+///   socket address of the TCP connection
+/// * `framing`: `kiss` or `slip`
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct StreamInterface {
+    pub name: String,
+    pub kind: String,
+    pub address: String,
+    #[serde(default = "StreamInterface::default_baud_rate")]
+    pub baud_rate: u32,
+    #[serde(default = "StreamInterface::default_framing")]
+    pub framing: String,
+    pub enabled: bool,
+}
+
+impl StreamInterface {
+    fn default_baud_rate() -> u32 {
+        9600
+    }
+    fn default_framing() -> String {
+        String::from("kiss")
+    }
+}
+
+/// Stream Connection Module
+///
+/// Connects to neighbour nodes via serial lines,
+/// radio modems and other point-to-point byte streams.
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct Stream {
+    pub active: bool,
+    pub interfaces: Vec<StreamInterface>,
+}
+
+impl Default for Stream {
+    fn default() -> Self {
+        Stream {
+            active: true,
+            interfaces: Vec::new(),
         }
     }
 }
@@ -110,6 +301,8 @@ pub struct UserAccount {
     pub id: String,
     pub keys: String,
     pub storage: StorageOptions,
+    #[serde(default)]
+    pub chat: ChatOptions,
 }
 
 impl Default for UserAccount {
@@ -119,6 +312,7 @@ impl Default for UserAccount {
             id: String::from(""),
             keys: String::from(""),
             storage: StorageOptions::default(),
+            chat: ChatOptions::default(),
         }
     }
 }
@@ -145,6 +339,7 @@ impl Default for DebugOption {
 /// All units are second
 /// because rtt is measured as micro seconds
 /// * routing options
+/// * link quality weights per connection module, in percent
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct RoutingOptions {
     //Sending the table every 10 seconds to direct neighbours.
@@ -155,6 +350,67 @@ pub struct RoutingOptions {
     pub hop_count_penalty: u64,
     //How long a route is stored until it is removed.
     pub maintain_period_limit: u64,
+    //Penalty per expected retransmission (ETX) of a neighbour link.
+    #[serde(default = "RoutingOptions::default_etx_penalty")]
+    pub etx_penalty: u64,
+    //Number of recent deliveries used to calculate the delivery ratio.
+    #[serde(default = "RoutingOptions::default_delivery_window")]
+    pub delivery_window: u32,
+    //Link quality weight of the LAN module in percent.
+    #[serde(default = "RoutingOptions::default_module_weight")]
+    pub lq_weight_lan: u32,
+    //Link quality weight of the Internet module in percent.
+    #[serde(default = "RoutingOptions::default_module_weight")]
+    pub lq_weight_internet: u32,
+    //Link quality weight of the BLE module in percent.
+    #[serde(default = "RoutingOptions::default_module_weight")]
+    pub lq_weight_ble: u32,
+    //Link quality weight of the stream module in percent.
+    #[serde(default = "RoutingOptions::default_module_weight")]
+    pub lq_weight_stream: u32,
+    //Number of ranked routes per user kept for each connection module.
+    #[serde(default = "RoutingOptions::default_max_routes_per_module")]
+    pub max_routes_per_module: u32,
+    //Send our high priority messages (crypto, group and chat messages)
+    //redundantly via two different connection modules.
+    #[serde(default)]
+    pub redundant_sending: bool,
+    //Sending a full routing table snapshot every 60 seconds, deltas in between.
+    #[serde(default = "RoutingOptions::default_full_table_period")]
+    pub full_table_period: u64,
+    //Maximal number of hops a broadcast message travels.
+    #[serde(default = "RoutingOptions::default_broadcast_ttl")]
+    pub broadcast_ttl: u32,
+    //Maximal number of broadcast messages forwarded per neighbour and minute.
+    #[serde(default = "RoutingOptions::default_broadcast_rate_limit")]
+    pub broadcast_rate_limit: u32,
+    //Announce broadcast message ids to neighbours instead of forwarding the messages.
+    #[serde(default)]
+    pub broadcast_lazy: bool,
+}
+
+impl RoutingOptions {
+    fn default_etx_penalty() -> u64 {
+        1
+    }
+    fn default_delivery_window() -> u32 {
+        20
+    }
+    fn default_module_weight() -> u32 {
+        100
+    }
+    fn default_max_routes_per_module() -> u32 {
+        3
+    }
+    fn default_full_table_period() -> u64 {
+        60
+    }
+    fn default_broadcast_ttl() -> u32 {
+        16
+    }
+    fn default_broadcast_rate_limit() -> u32 {
+        120
+    }
 }
 
 impl Default for RoutingOptions {
@@ -164,6 +420,18 @@ impl Default for RoutingOptions {
             ping_neighbour_period: 5,   //5  seconds, unit: seconds
             hop_count_penalty: 10,      //10 seconds, unit: second
             maintain_period_limit: 300, //5min, unit: second
+            etx_penalty: 1,             //1 second per expected retransmission
+            delivery_window: 20,        //last 20 deliveries
+            lq_weight_lan: 100,         //100 %
+            lq_weight_internet: 100,    //100 %
+            lq_weight_ble: 100,         //100 %
+            lq_weight_stream: 100,      //100 %
+            max_routes_per_module: 3,   //3 routes
+            redundant_sending: false,
+            full_table_period: 60,     //60 seconds, unit: second
+            broadcast_ttl: 16,         //16 hops
+            broadcast_rate_limit: 120, //120 messages per minute
+            broadcast_lazy: false,
         }
     }
 }
@@ -190,6 +458,49 @@ impl Default for StorageOptions {
     }
 }
 
+/// Chat Configuration Options
+///
+/// The following options can be configured:
+///
+/// * send read receipts to the group members
+/// * default message retention time of the groups
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct ChatOptions {
+    /// inform group members when we have read their messages
+    pub read_receipts: bool,
+    /// default message retention time in seconds, 0 = keep forever
+    #[serde(default)]
+    pub retention: u64,
+}
+
+impl Default for ChatOptions {
+    fn default() -> Self {
+        ChatOptions {
+            read_receipts: true,
+            retention: 0,
+        }
+    }
+}
+
+/// Bandwidth Limits
+///
+/// Maximal sending bandwidth per connection module,
+/// in bytes per second. 0 means unlimited.
+///
+/// Messages exceeding the limit of a module are dropped
+/// and counted in the bandwidth statistics.
+#[derive(Debug, Deserialize, Clone, Serialize, Default)]
+pub struct BandwidthLimits {
+    #[serde(default)]
+    pub lan: u64,
+    #[serde(default)]
+    pub internet: u64,
+    #[serde(default)]
+    pub ble: u64,
+    #[serde(default)]
+    pub stream: u64,
+}
+
 /// Configuration Structure of libqaul
 ///
 /// This structure contains the entire configuration of libqaul.
@@ -198,9 +509,13 @@ pub struct Configuration {
     pub node: Node,
     pub lan: Lan,
     pub internet: Internet,
+    #[serde(default)]
+    pub stream: Stream,
     pub user_accounts: Vec<UserAccount>,
     pub debug: DebugOption,
     pub routing: RoutingOptions,
+    #[serde(default)]
+    pub bandwidth: BandwidthLimits,
 }
 
 impl Default for Configuration {
@@ -209,9 +524,11 @@ impl Default for Configuration {
             node: Node::default(),
             lan: Lan::default(),
             internet: Internet::default(),
+            stream: Stream::default(),
             user_accounts: Vec::new(),
             debug: DebugOption::default(),
             routing: RoutingOptions::default(),
+            bandwidth: BandwidthLimits::default(),
         }
     }
 }
@@ -0,0 +1,78 @@
+// Copyright (c) 2023 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Data Compression
+//!
+//! Compress and decompress binary data with deflate.
+
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression as Level};
+use std::io::{Read, Write};
+
+/// maximal size of decompressed data
+///
+/// This protects against decompression bombs.
+const MAX_INFLATED_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Compression utility
+pub struct Compression {}
+
+impl Compression {
+    /// compress data with deflate
+    pub fn deflate(data: &[u8]) -> Result<Vec<u8>, String> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Level::default());
+        if let Err(e) = encoder.write_all(data) {
+            return Err(format!("deflate error: {}", e));
+        }
+        match encoder.finish() {
+            Ok(compressed) => Ok(compressed),
+            Err(e) => Err(format!("deflate error: {}", e)),
+        }
+    }
+
+    /// decompress deflate compressed data
+    pub fn inflate(data: &[u8]) -> Result<Vec<u8>, String> {
+        let mut decoder = DeflateDecoder::new(data).take(MAX_INFLATED_SIZE + 1);
+        let mut inflated = Vec::new();
+        if let Err(e) = decoder.read_to_end(&mut inflated) {
+            return Err(format!("inflate error: {}", e));
+        }
+        if inflated.len() as u64 > MAX_INFLATED_SIZE {
+            return Err("inflated data exceeds maximal size".to_string());
+        }
+        Ok(inflated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deflate_inflate_round_trip() {
+        let data = b"qaul routing information qaul routing information".to_vec();
+        let compressed = Compression::deflate(&data).unwrap();
+
+        assert_eq!(Compression::inflate(&compressed).unwrap(), data);
+    }
+
+    #[test]
+    fn inflate_accepts_maximal_size() {
+        let data = vec![0; MAX_INFLATED_SIZE as usize];
+        let compressed = Compression::deflate(&data).unwrap();
+
+        assert_eq!(Compression::inflate(&compressed).unwrap().len(), data.len());
+    }
+
+    #[test]
+    fn inflate_refuses_oversized_data() {
+        let data = vec![0; MAX_INFLATED_SIZE as usize + 1];
+        let compressed = Compression::deflate(&data).unwrap();
+
+        assert!(Compression::inflate(&compressed).is_err());
+    }
+
+    #[test]
+    fn inflate_refuses_invalid_data() {
+        assert!(Compression::inflate(&[0xff; 16]).is_err());
+    }
+}
@@ -9,7 +9,9 @@
 //!
 //! * qaul ID conversions
 //! * timestamp handling
+//! * data compression
 
+pub mod compression;
 pub mod filelogger;
 pub mod qaul_id;
 pub mod timestamp;
@@ -0,0 +1,198 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Upgrade to new version 2.0.0-beta.13
+//!
+//! Breaking changes that need to be upgraded:
+//!
+//! * user account data bases: the bincode encoded `Group` and
+//!   `GroupMember` structures of the trees `groups` and `invited`
+//!   have new fields for read receipts, retention, group info
+//!   signatures, invite code uses, description, avatar & settings.
+//! * node data base: the bincode encoded `UnConfirmedMessage`
+//!   structure of the tree `unconfirmed` has a new field counting
+//!   the retransmissions via stale routes.
+
+use bincode::Options;
+use serde::{de::DeserializeOwned, Serialize};
+use std::path::{Path, PathBuf};
+
+mod old_group;
+mod old_unconfirmed;
+
+use super::backup;
+
+/// # Version Upgrade Logic
+pub struct VersionUpgrade {}
+impl VersionUpgrade {
+    /// Upgrade to new Version
+    ///
+    /// Returns a result, containing a tuple with ( new_version, new_path )
+    pub fn upgrade(storage_path: &Path, old_path: &Path) -> Result<(String, PathBuf), String> {
+        let version = "2.0.0-beta.13";
+        println!("upgrade to version {}", version);
+        let new_path = storage_path.join("backup").join(version);
+
+        // cleanup dest
+        backup::Backup::remove_folder(&new_path);
+
+        // create dest
+        if let Err(_) = std::fs::create_dir(&new_path) {
+            return Err("failed to create destinaton folder".to_string());
+        }
+
+        // move contents
+        println!("move content");
+        if Self::move_content(Path::new(old_path), &new_path) == false {
+            return Err("Error moving content".to_string());
+        }
+
+        // create new version file
+        println!("create version file");
+        let path = Path::new(new_path.to_str().unwrap()).join("version");
+        if let Err(_) = std::fs::write(path, version) {
+            println!("failed to create version file!");
+        }
+
+        // upgrade the user account data bases
+        println!("upgrade group data bases");
+        for entry_res in std::fs::read_dir(&new_path).unwrap() {
+            let entry = entry_res.unwrap();
+            let file_name_buf = entry.file_name();
+            let file_name = file_name_buf.to_str().unwrap();
+
+            if entry.file_type().unwrap().is_dir() && file_name.starts_with("12D3KooW") {
+                let db_path = entry.path().join("user.db");
+                if db_path.exists() {
+                    Self::upgrade_groups(&db_path)?;
+                }
+            }
+        }
+
+        // upgrade the node data base
+        println!("upgrade unconfirmed messages");
+        let db_path = new_path.join("node.db");
+        if db_path.exists() {
+            Self::upgrade_unconfirmed(&db_path)?;
+        }
+
+        // remove old backup
+        backup::Backup::remove_folder(old_path);
+
+        Ok((version.to_string(), new_path))
+    }
+
+    /// upgrade the group trees of a user account data base
+    fn upgrade_groups(db_path: &Path) -> Result<(), String> {
+        let db = match sled::open(db_path) {
+            Ok(db) => db,
+            Err(e) => return Err(format!("failed to open {:?}: {}", db_path, e)),
+        };
+
+        Self::upgrade_tree(&db, "groups", |group: old_group::Group| group.upgrade())?;
+        Self::upgrade_tree(&db, "invited", |invited: old_group::GroupInvited| {
+            invited.upgrade()
+        })?;
+
+        if let Err(e) = db.flush() {
+            return Err(format!("failed to flush {:?}: {}", db_path, e));
+        }
+
+        Ok(())
+    }
+
+    /// upgrade the unconfirmed messages tree of the node data base
+    fn upgrade_unconfirmed(db_path: &Path) -> Result<(), String> {
+        let db = match sled::open(db_path) {
+            Ok(db) => db,
+            Err(e) => return Err(format!("failed to open {:?}: {}", db_path, e)),
+        };
+
+        Self::upgrade_tree(
+            &db,
+            "unconfirmed",
+            |unconfirmed: old_unconfirmed::UnConfirmedMessage| unconfirmed.upgrade(),
+        )?;
+
+        if let Err(e) = db.flush() {
+            return Err(format!("failed to flush {:?}: {}", db_path, e));
+        }
+
+        Ok(())
+    }
+
+    /// re-encode all entries of a bincode tree
+    ///
+    /// Entries that can't be decoded in the old format are
+    /// left untouched, they are already in the new format.
+    fn upgrade_tree<O, N, F>(db: &sled::Db, name: &str, convert: F) -> Result<(), String>
+    where
+        O: DeserializeOwned,
+        N: Serialize,
+        F: Fn(O) -> N,
+    {
+        let tree = match db.open_tree(name) {
+            Ok(tree) => tree,
+            Err(e) => return Err(format!("failed to open tree {}: {}", name, e)),
+        };
+
+        // the old entries need to be decoded exactly
+        let old_options = bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .reject_trailing_bytes();
+
+        for entry in tree.iter() {
+            let (key, value) = match entry {
+                Ok(entry) => entry,
+                Err(e) => return Err(format!("failed to read tree {}: {}", name, e)),
+            };
+
+            let old: O = match old_options.deserialize(&value) {
+                Ok(old) => old,
+                Err(_) => continue,
+            };
+
+            let new_value = match bincode::serialize(&convert(old)) {
+                Ok(new_value) => new_value,
+                Err(e) => return Err(format!("failed to encode {} entry: {}", name, e)),
+            };
+
+            if let Err(e) = tree.insert(key, new_value) {
+                return Err(format!("failed to save {} entry: {}", name, e));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// move the content to the new version folder
+    fn move_content(old_path: &Path, new_path: &Path) -> bool {
+        let mut files: Vec<String> = vec![];
+        let mut folders: Vec<String> = vec![];
+
+        for entry_res in std::fs::read_dir(old_path).unwrap() {
+            let entry = entry_res.unwrap();
+            let file_name_buf = entry.file_name();
+            let file_name = file_name_buf.to_str().unwrap();
+
+            if entry.file_type().unwrap().is_dir() {
+                if file_name.starts_with(".") {
+                    continue;
+                }
+                let path = String::from(file_name);
+                folders.push(path);
+            } else {
+                if file_name == "version" {
+                    continue;
+                }
+                let path = String::from(file_name);
+                files.push(path);
+            }
+        }
+
+        if super::backup::Backup::move_files(&files, old_path, new_path) == false {
+            return false;
+        }
+        super::backup::Backup::move_folders(&folders, old_path, new_path)
+    }
+}
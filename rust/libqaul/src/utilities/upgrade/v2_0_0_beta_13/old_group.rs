@@ -0,0 +1,100 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Group Structures from Version 2.0.0-beta.12
+//!
+//! These structures are needed to load the existing groups
+//! from the user account data bases.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::services::group::{self, GroupSettings};
+
+/// Structure of group member
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GroupMember {
+    pub user_id: Vec<u8>,
+    pub role: i32,
+    pub joined_at: u64,
+    pub state: i32,
+    pub last_message_index: u32,
+}
+
+/// Structure of invited group
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GroupInvited {
+    pub sender_id: Vec<u8>,
+    pub received_at: u64,
+    pub group: Group,
+}
+
+/// Structure of Group
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Group {
+    pub id: Vec<u8>,
+    pub name: String,
+    pub is_direct_chat: bool,
+    pub created_at: u64,
+    pub status: i32,
+    pub revision: u32,
+    pub members: BTreeMap<Vec<u8>, GroupMember>,
+    pub unread_messages: u32,
+    pub last_message_at: u64,
+    pub last_message_data: Vec<u8>,
+    pub last_message_sender_id: Vec<u8>,
+}
+
+impl Group {
+    /// convert the group to the new structure
+    pub fn upgrade(self) -> group::Group {
+        let members = self
+            .members
+            .into_iter()
+            .map(|(id, member)| {
+                (
+                    id,
+                    group::GroupMember {
+                        user_id: member.user_id,
+                        role: member.role,
+                        joined_at: member.joined_at,
+                        state: member.state,
+                        last_message_index: member.last_message_index,
+                        last_read_index: 0,
+                    },
+                )
+            })
+            .collect();
+
+        group::Group {
+            id: self.id,
+            name: self.name,
+            is_direct_chat: self.is_direct_chat,
+            created_at: self.created_at,
+            status: self.status,
+            revision: self.revision,
+            members,
+            unread_messages: self.unread_messages,
+            last_message_at: self.last_message_at,
+            last_message_data: self.last_message_data,
+            last_message_sender_id: self.last_message_sender_id,
+            retention: 0,
+            description: String::from(""),
+            avatar: Vec::new(),
+            settings: GroupSettings::default(),
+            signed_info: Vec::new(),
+            invite_code_uses: BTreeMap::new(),
+        }
+    }
+}
+
+impl GroupInvited {
+    /// convert the invitation to the new structure
+    pub fn upgrade(self) -> group::GroupInvited {
+        group::GroupInvited {
+            sender_id: self.sender_id,
+            received_at: self.received_at,
+            group: self.group.upgrade(),
+        }
+    }
+}
@@ -0,0 +1,43 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Unconfirmed Message Structure from Version 2.0.0-beta.12
+//!
+//! This structure is needed to load the unconfirmed messages
+//! from the node data base.
+
+use serde::{Deserialize, Serialize};
+
+use crate::services::messaging::{self, MessagingServiceType};
+
+/// Structure of unconfirmed message
+#[derive(Serialize, Deserialize, Clone)]
+pub struct UnConfirmedMessage {
+    pub receiver_id: Vec<u8>,
+    pub message_type: MessagingServiceType,
+    pub message_id: Vec<u8>,
+    pub container: Vec<u8>,
+    pub last_sent: u64,
+    pub retry: u32,
+    pub scheduled: bool,
+    pub scheduled_dtn: bool,
+    pub is_dtn: bool,
+}
+
+impl UnConfirmedMessage {
+    /// convert the unconfirmed message to the new structure
+    pub fn upgrade(self) -> messaging::UnConfirmedMessage {
+        messaging::UnConfirmedMessage {
+            receiver_id: self.receiver_id,
+            message_type: self.message_type,
+            message_id: self.message_id,
+            container: self.container,
+            last_sent: self.last_sent,
+            retry: self.retry,
+            scheduled: self.scheduled,
+            scheduled_dtn: self.scheduled_dtn,
+            is_dtn: self.is_dtn,
+            stale_routes: 0,
+        }
+    }
+}
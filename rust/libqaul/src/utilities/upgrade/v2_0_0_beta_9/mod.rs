@@ -68,10 +68,15 @@ impl VersionUpgrade {
                 initialized: old_cfg.node.initialized,
                 id: old_cfg.node.id.clone(),
                 keys: old_cfg.node.keys.clone(),
+                network_key: String::from(""),
             };
             let lan = crate::storage::configuration::Lan {
                 active: old_cfg.lan.active,
                 listen: old_cfg.lan.listen,
+                listen_extra: Vec::new(),
+                mdns: crate::storage::configuration::LanMdns::default(),
+                interfaces: crate::storage::configuration::LanInterfaces::default(),
+                peers: Vec::new(),
             };
 
             let mut peers: Vec<crate::storage::configuration::InternetPeer> = vec![];
@@ -87,6 +92,9 @@ impl VersionUpgrade {
                 peers,
                 do_listen: old_cfg.internet.do_listen,
                 listen: old_cfg.internet.listen,
+                listen_extra: Vec::new(),
+                relay: crate::storage::configuration::InternetRelay::default(),
+                proxy: crate::storage::configuration::InternetProxy::default(),
             };
 
             let mut user_accounts: Vec<crate::storage::configuration::UserAccount> = vec![];
@@ -99,6 +107,7 @@ impl VersionUpgrade {
                         users: user.storage.users.clone(),
                         size_total: user.storage.size_total,
                     },
+                    chat: crate::storage::configuration::ChatOptions::default(),
                 });
             }
 
@@ -110,15 +119,18 @@ impl VersionUpgrade {
                 ping_neighbour_period: old_cfg.routing.ping_neighbour_period,
                 hop_count_penalty: old_cfg.routing.hop_count_penalty,
                 maintain_period_limit: old_cfg.routing.maintain_period_limit,
+                ..Default::default()
             };
 
             let new_config = crate::storage::configuration::Configuration {
                 node,
                 lan,
                 internet,
+                stream: Default::default(),
                 user_accounts,
                 debug,
                 routing,
+                bandwidth: Default::default(),
             };
 
             if let Ok(yaml) = serde_yaml::to_string(&new_config) {